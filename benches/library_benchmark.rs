@@ -1,6 +1,6 @@
 use std::{fs::File, path::PathBuf};
 
-use criterion::{criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
 use flate2::read::GzDecoder;
 use ripasso::{crypto::CryptoImpl, pass};
 use tar::Archive;
@@ -60,10 +60,24 @@ fn criterion_benchmark_load_4_passwords(c: &mut Criterion) {
     )
     .unwrap();
 
+    // the first listing writes the metadata cache, so this measures listing with a warm cache
     c.bench_function("populate_password_list 4 passwords", |b| {
         b.iter(|| pop_list(password_dir.clone()))
     });
 
+    let cache_file = password_dir
+        .join(".git")
+        .join("ripasso-metadata-cache.json");
+    c.bench_function("populate_password_list 4 passwords without cache", |b| {
+        b.iter_batched(
+            || {
+                let _ = std::fs::remove_file(&cache_file);
+            },
+            |()| pop_list(password_dir.clone()),
+            BatchSize::SmallInput,
+        )
+    });
+
     cleanup(base_path, "populate_password_list_large_repo").unwrap();
 }
 
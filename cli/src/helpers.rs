@@ -0,0 +1,199 @@
+/*  Ripasso - a simple password manager
+    Copyright (C) 2019-2020 Joakim Lundborg, Alexander Kjäll
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, version 3 of the License.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, IsTerminal, Write},
+    path::{Component, Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use ripasso::{
+    crypto::PassphrasePrompt,
+    pass::{Error, Result},
+};
+
+/// A directory level in the tree printed by `ls` and `find`
+#[derive(Default)]
+struct Node {
+    children: BTreeMap<String, Node>,
+}
+
+impl Node {
+    fn insert(&mut self, name: &str) {
+        let mut node = self;
+        for part in name.split('/').filter(|p| !p.is_empty()) {
+            node = node.children.entry(part.to_owned()).or_default();
+        }
+    }
+
+    fn render(&self, prefix: &str, out: &mut String) {
+        let count = self.children.len();
+        for (i, (name, child)) in self.children.iter().enumerate() {
+            let last = i + 1 == count;
+            out.push_str(prefix);
+            out.push_str(if last { "└── " } else { "├── " });
+            out.push_str(name);
+            out.push('\n');
+            let child_prefix = format!("{prefix}{}", if last { "    " } else { "│   " });
+            child.render(&child_prefix, out);
+        }
+    }
+}
+
+/// Renders a list of entry names as a tree, in the same format as `tree` does for pass.
+pub fn render_tree(header: &str, names: &[String]) -> String {
+    let mut root = Node::default();
+    for name in names {
+        root.insert(name);
+    }
+
+    let mut out = format!("{header}\n");
+    root.render("", &mut out);
+    out
+}
+
+/// Returns the names of all entries in the store below `sub_dir`, relative to `sub_dir`,
+/// without the `extension` of the password files. The .git directory is skipped.
+pub fn entry_names(root: &Path, extension: &str, sub_dir: &Path) -> Result<Vec<String>> {
+    let base = root.join(sub_dir);
+    let mut to_visit = vec![base.clone()];
+    let mut names = vec![];
+    while let Some(dir) = to_visit.pop() {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                if path.file_name() != Some(std::ffi::OsStr::new(".git")) {
+                    to_visit.push(path);
+                }
+            } else if path.extension() == Some(std::ffi::OsStr::new(extension)) {
+                let relpath = path.strip_prefix(&base)?.with_extension("");
+                names.push(relpath.to_string_lossy().to_string());
+            }
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Returns the paths of all files below `sub_dir`, relative to `root`, including the `.gpg-id`
+/// files and their signatures. The .git directory is skipped.
+pub fn files_below(root: &Path, sub_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut to_visit = vec![root.join(sub_dir)];
+    let mut files = vec![];
+    while let Some(dir) = to_visit.pop() {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                if path.file_name() != Some(std::ffi::OsStr::new(".git")) {
+                    to_visit.push(path);
+                }
+            } else {
+                files.push(path.strip_prefix(root)?.to_path_buf());
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Returns an error if the path would point outside the password store.
+pub fn check_sneaky_paths(name: &str) -> Result<()> {
+    let path = PathBuf::from(name);
+    if path.is_absolute() || path.components().any(|c| c == Component::ParentDir) {
+        return Err(Error::Generic(
+            "You've attempted to pass a sneaky path to ripasso. Go home.",
+        ));
+    }
+    Ok(())
+}
+
+/// Asks a yes/no question on the terminal. When stdin isn't a terminal, the answer is
+/// always yes, the same as pass does, so that scripts don't hang.
+pub fn yesno(question: &str) -> bool {
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        return true;
+    }
+
+    eprint!("{question} [y/N] ");
+    let _ = std::io::stderr().flush();
+    let mut answer = String::new();
+    if stdin.lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim(), "y" | "Y" | "yes" | "Yes")
+}
+
+/// Reads one line from stdin, without the trailing newline. If stdin is a terminal and
+/// `echo` is false, the typed characters are hidden.
+pub fn read_line(prompt: &str, echo: bool) -> Result<String> {
+    let stdin = std::io::stdin();
+    let interactive = stdin.is_terminal();
+    if interactive {
+        eprint!("{prompt}");
+        let _ = std::io::stderr().flush();
+    }
+
+    let hide = interactive && !echo;
+    if hide {
+        set_terminal_echo(false);
+    }
+    let mut line = String::new();
+    let res = stdin.lock().read_line(&mut line);
+    if hide {
+        set_terminal_echo(true);
+        eprintln!();
+    }
+    if res? == 0 {
+        return Err(Error::Generic("no input"));
+    }
+
+    if line.ends_with('\n') {
+        line.pop();
+        if line.ends_with('\r') {
+            line.pop();
+        }
+    }
+    Ok(line)
+}
+
+/// Asks for the passphrases of locked secret keys on the terminal. When stdin isn't a
+/// terminal nothing is asked, as it might be the content of the entry.
+pub struct TerminalPassphrase;
+
+impl PassphrasePrompt for TerminalPassphrase {
+    fn passphrase(&self, key: &str, attempt: u32) -> Option<String> {
+        if !std::io::stdin().is_terminal() {
+            return None;
+        }
+        if attempt > 1 {
+            eprintln!("Wrong passphrase, try again.");
+        }
+        read_line(&format!("Passphrase for {key}: "), false).ok()
+    }
+}
+
+fn set_terminal_echo(on: bool) {
+    let _ = Command::new("stty")
+        .arg(if on { "echo" } else { "-echo" })
+        .stdin(Stdio::inherit())
+        .status();
+}
+
+#[cfg(test)]
+#[path = "tests/helpers.rs"]
+mod helpers_tests;
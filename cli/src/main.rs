@@ -0,0 +1,1108 @@
+/*  Ripasso - a simple password manager
+    Copyright (C) 2019-2020 Joakim Lundborg, Alexander Kjäll
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, version 3 of the License.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    process::{self, Stdio},
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+use hex::FromHex;
+use ripasso::{
+    clipboard::{ClipboardManager, DEFAULT_CLIPBOARD_TIMEOUT},
+    crypto::CryptoImpl,
+    export::{ExportFormat, ExportOptions, FieldSelection},
+    generate::{CharacterClasses, PasswordGenerator},
+    git::remove_and_commit,
+    import::{read_entries, ConflictStrategy, ImportFormat, ImportOptions},
+    pass,
+    pass::{Error, PasswordEntry, PasswordStore, Result},
+};
+use zeroize::Zeroize;
+
+mod helpers;
+
+use crate::helpers::{
+    check_sneaky_paths, entry_names, files_below, read_line, render_tree, yesno, TerminalPassphrase,
+};
+
+const PROGRAM: &str = "ripasso";
+const GENERATED_LENGTH: usize = 25;
+/// The hidden command that keeps a secret on the clipboard in the background, see `clip`
+const CLIP_COMMAND: &str = "__clip";
+
+/// The parsed command line of a subcommand, flags are kept in the order they were given.
+struct Args {
+    flags: Vec<String>,
+    positional: Vec<String>,
+}
+
+impl Args {
+    fn parse(args: &[String]) -> Self {
+        let mut flags = vec![];
+        let mut positional = vec![];
+        let mut only_positional = false;
+        for arg in args {
+            if only_positional || !arg.starts_with('-') || arg == "-" {
+                positional.push(arg.clone());
+            } else if arg == "--" {
+                only_positional = true;
+            } else {
+                flags.push(arg.clone());
+            }
+        }
+        Self { flags, positional }
+    }
+
+    /// Returns true if any of the flags matches the short or long form.
+    fn has(&self, short: &str, long: &str) -> bool {
+        self.flags.iter().any(|f| f == short || f == long)
+    }
+
+    /// Returns the value of a flag on the form `-c3` or `--clip=3`, `Some(None)` if the flag
+    /// was given without a value.
+    fn value(&self, short: &str, long: &str) -> Option<Option<String>> {
+        for f in &self.flags {
+            if f == short || f == long {
+                return Some(None);
+            }
+            if let Some(v) = f.strip_prefix(&format!("{long}=")) {
+                return Some(Some(v.to_owned()));
+            }
+            if let Some(v) = f.strip_prefix(short) {
+                if !f.starts_with("--") {
+                    return Some(Some(v.to_owned()));
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns true if there is a flag that isn't in the list of allowed flags.
+    fn has_unknown(&self, allowed: &[(&str, &str)]) -> bool {
+        self.flags.iter().any(|f| {
+            !allowed.iter().any(|(short, long)| {
+                f == short
+                    || f == long
+                    || f.starts_with(&format!("{long}="))
+                    || (short.len() == 2 && !f.starts_with("--") && f.starts_with(short))
+            })
+        })
+    }
+}
+
+fn usage(text: &str) -> Result<i32> {
+    eprintln!("Usage: {PROGRAM} {text}");
+    Ok(1)
+}
+
+fn not_in_store(name: &str) -> Error {
+    Error::GenericDyn(format!("{name} is not in the password store."))
+}
+
+fn help() {
+    let clip_time = DEFAULT_CLIPBOARD_TIMEOUT.as_secs();
+    println!(
+        "{PROGRAM} v{}
+A password manager that uses the file format of the standard unix password manager 'pass'
+
+Usage:
+    {PROGRAM} [ls] [subfolder]
+        List passwords.
+    {PROGRAM} find pass-names...
+        List passwords that match pass-names.
+    {PROGRAM} [show] [--clip[=line-number],-c[line-number]] pass-name
+        Show existing password and optionally put it on the clipboard.
+        If put on the clipboard, it will be cleared after the clipboard timeout of the
+        store, {clip_time} seconds unless configured.
+    {PROGRAM} grep [-i] search-string
+        Search for password files containing search-string when decrypted.
+    {PROGRAM} insert [--echo,-e | --multiline,-m] [--force,-f] pass-name
+        Insert new password. Optionally, echo the password back to the console
+        during entry. Or, optionally, the entry may be multiline. Prompt before
+        overwriting existing password unless forced.
+    {PROGRAM} edit pass-name
+        Insert a new password or edit an existing password using $EDITOR.
+    {PROGRAM} generate [--no-symbols,-n] [--clip,-c] [--in-place,-i | --force,-f] pass-name [pass-length]
+        Generate a new password of pass-length (or {GENERATED_LENGTH} if unspecified) with optionally no symbols.
+        Optionally put it on the clipboard and clear board after the clipboard timeout.
+        Prompt before overwriting existing password unless forced.
+        Optionally replace only the first line of an existing file with a new password.
+    {PROGRAM} rm [--recursive,-r] [--force,-f] pass-name
+        Remove existing password or directory, optionally forcefully.
+    {PROGRAM} mv [--force,-f] old-path new-path
+        Renames or moves old-path to new-path, optionally forcefully, selectively reencrypting.
+    {PROGRAM} cp [--force,-f] old-path new-path
+        Copies old-path to new-path, optionally forcefully, selectively reencrypting.
+    {PROGRAM} otp [code] [--clip,-c] pass-name
+        Generate a TOTP code from the otpauth:// url in pass-name.
+    {PROGRAM} import [--rename,-r] [--prefix=subfolder,-psubfolder] format export-file
+        Import passwords from an export file of another password manager, format is one of
+        kdbx, bitwarden, 1password or browser. Passwords that already exist are skipped,
+        or optionally imported with a number added to the name.
+    {PROGRAM} export [--recipient=key-id,-rkey-id | --plaintext=format,-pformat] [--fields=names,-Fnames] [--force,-f] [subfolder] export-file
+        Export the passwords in subfolder, or in the whole store, to a tar archive that is
+        encrypted to key-id, or unencrypted to a file of format csv or json. Optionally only
+        export the comma separated parts in names, for example password,login,otp,notes.
+        Warns before writing plaintext or inside a git repository, unless forced.
+    {PROGRAM} git git-command-args...
+        Execute git commands on the password store.
+    {PROGRAM} help
+        Show this text.
+    {PROGRAM} version
+        Show version information.",
+        env!("CARGO_PKG_VERSION")
+    );
+}
+
+fn get_stores(config: &config::Config, home: &Option<PathBuf>) -> Result<Vec<PasswordStore>> {
+    let mut final_stores: Vec<PasswordStore> = vec![];
+    let stores_res = config.get("stores");
+    if let Ok(stores) = stores_res {
+        let stores: HashMap<String, config::Value> = stores;
+
+        let mut store_names: Vec<&String> = stores.keys().collect();
+        store_names.sort();
+        for store_name in store_names {
+            let store: HashMap<String, config::Value> = stores[store_name].clone().into_table()?;
+
+            let password_store_dir_opt = store.get("path");
+            let valid_signing_keys_opt = store.get("valid_signing_keys");
+
+            if let Some(store_dir) = password_store_dir_opt {
+                let password_store_dir = Some(PathBuf::from(store_dir.clone().into_str()?));
+
+                let valid_signing_keys = match valid_signing_keys_opt {
+                    Some(k) => match k.clone().into_str() {
+                        Err(_) => None,
+                        Ok(key) => {
+                            if key == "-1" {
+                                None
+                            } else {
+                                Some(key)
+                            }
+                        }
+                    },
+                    None => None,
+                };
+
+                let pgp_impl = match store.get("pgp") {
+                    Some(pgp_str) => CryptoImpl::try_from(pgp_str.clone().into_str()?.as_str()),
+                    None => Ok(CryptoImpl::GpgMe),
+                }?;
+
+                let own_fingerprint = store
+                    .get("own_fingerprint")
+                    .and_then(|k| k.clone().into_str().ok())
+                    .and_then(|key| <[u8; 20]>::from_hex(key).ok());
+
+                final_stores.push(PasswordStore::new(
+                    store_name,
+                    &password_store_dir,
+                    &valid_signing_keys,
+                    home,
+                    &None,
+                    &pgp_impl,
+                    &own_fingerprint,
+                )?);
+            }
+        }
+    } else if final_stores.is_empty() && home.is_some() {
+        let default_path = home.clone().unwrap().join(".password_store");
+        if default_path.exists() {
+            final_stores.push(PasswordStore::new(
+                "default",
+                &Some(default_path),
+                &None,
+                home,
+                &None,
+                &CryptoImpl::GpgMe,
+                &None,
+            )?);
+        }
+    }
+
+    Ok(final_stores)
+}
+
+/// Opens the store named `default`, which is also the one that `PASSWORD_STORE_DIR` points
+/// to, or the first configured store if there is no default store.
+fn open_store() -> Result<PasswordStore> {
+    let home = std::env::var("HOME").ok().map(PathBuf::from);
+    let password_store_dir = std::env::var("PASSWORD_STORE_DIR").ok();
+    let password_store_signing_key = std::env::var("PASSWORD_STORE_SIGNING_KEY").ok();
+    let xdg_config_home = std::env::var("XDG_CONFIG_HOME").ok().map(PathBuf::from);
+
+    let (config, _) = pass::read_config(
+        &password_store_dir,
+        &password_store_signing_key,
+        &home,
+        &xdg_config_home,
+    )?;
+
+    let mut stores = get_stores(&config, &home)?;
+    let mut store = match stores.iter().position(|s| s.get_name() == "default") {
+        Some(i) => stores.swap_remove(i),
+        None if !stores.is_empty() => stores.swap_remove(0),
+        None => {
+            return Err(Error::Generic(
+                "password store is empty. Try \"pass init\".",
+            ))
+        }
+    };
+    store.set_passphrase_prompt(Arc::new(TerminalPassphrase));
+    Ok(store)
+}
+
+fn entry_path(store: &PasswordStore, name: &str) -> PathBuf {
+    store
+        .get_store_path()
+        .join(format!("{name}.{}", store.password_file_extension()))
+}
+
+fn load_entry(store: &PasswordStore, name: &str) -> PasswordEntry {
+    PasswordEntry::load_from_filesystem(
+        &store.get_store_path(),
+        &PathBuf::from(format!("{name}.{}", store.password_file_extension())),
+        store.password_file_extension(),
+    )
+}
+
+fn is_dir(store: &PasswordStore, name: &str) -> bool {
+    !name.is_empty() && store.get_store_path().join(name).is_dir()
+}
+
+/// How long a secret stays on the clipboard: `PASSWORD_STORE_CLIP_TIME` if it's set, like
+/// for pass, and otherwise the clipboard timeout of the store.
+fn clip_timeout(store: &PasswordStore) -> Duration {
+    std::env::var("PASSWORD_STORE_CLIP_TIME")
+        .ok()
+        .and_then(|t| t.parse().ok())
+        .map_or_else(|| store.get_clipboard_timeout(), Duration::from_secs)
+}
+
+/// Puts the content on the clipboard, and leaves it to a background process to clear it again
+/// after the clipboard timeout, so that the terminal can be used in the meantime. Some
+/// platforms only keep the clipboard content for as long as the process that owns it is
+/// alive, so the background process also keeps it until then.
+fn clip(store: &PasswordStore, content: &str, name: &str) -> Result<()> {
+    let timeout = clip_timeout(store);
+    let mut child = process::Command::new(std::env::current_exe()?)
+        .arg(CLIP_COMMAND)
+        .arg(timeout.as_secs().to_string())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    // the secret is handed over on stdin, so that it doesn't show up in the process list
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or(Error::Generic("can't write to the clipboard process"))?;
+    stdin.write_all(content.as_bytes())?;
+    drop(stdin);
+
+    // the process keeps running after it has answered, so only its first line is read
+    let mut status = String::new();
+    BufReader::new(
+        child
+            .stdout
+            .take()
+            .ok_or(Error::Generic("can't read from the clipboard process"))?,
+    )
+    .read_line(&mut status)?;
+    if status.trim() != "copied" {
+        let _ = child.wait();
+        return Err(Error::GenericDyn(format!(
+            "Couldn't copy {name} to clipboard: {}",
+            status.trim()
+        )));
+    }
+
+    println!(
+        "Copied {name} to clipboard. Will clear in {} seconds.",
+        timeout.as_secs()
+    );
+    Ok(())
+}
+
+/// Runs in the background process that `clip` starts: copies the secret from stdin with the
+/// clipboard manager of the library, tells `clip` how that went, and stays alive until the
+/// secret has been cleared.
+fn cmd_clip_in_background(args: &[String]) -> Result<i32> {
+    let timeout = args
+        .first()
+        .and_then(|t| t.parse().ok())
+        .map(Duration::from_secs)
+        .ok_or(Error::Generic("the clipboard timeout is missing"))?;
+
+    let mut secret = String::new();
+    std::io::stdin().read_to_string(&mut secret)?;
+    let res = ClipboardManager::new().and_then(|clipboard| {
+        clipboard.copy_secret(&secret, timeout)?;
+        Ok(clipboard)
+    });
+    secret.zeroize();
+
+    let clipboard = match res {
+        Ok(clipboard) => clipboard,
+        Err(err) => {
+            println!("{err}");
+            return Ok(1);
+        }
+    };
+    println!("copied");
+
+    thread::sleep(timeout);
+    clipboard.clear()?;
+    Ok(0)
+}
+
+fn cmd_ls(store: &PasswordStore, args: &[String]) -> Result<i32> {
+    let args = Args::parse(args);
+    if !args.flags.is_empty() || args.positional.len() > 1 {
+        return usage("ls [subfolder]");
+    }
+    let sub_dir = args
+        .positional
+        .first()
+        .map_or("", |s| s.trim_end_matches('/'));
+    check_sneaky_paths(sub_dir)?;
+
+    if !sub_dir.is_empty() && !is_dir(store, sub_dir) {
+        return Err(not_in_store(sub_dir));
+    }
+
+    let header = if sub_dir.is_empty() {
+        "Password Store"
+    } else {
+        sub_dir
+    };
+    let names = entry_names(
+        &store.get_store_path(),
+        store.password_file_extension(),
+        Path::new(sub_dir),
+    )?;
+    print!("{}", render_tree(header, &names));
+    Ok(0)
+}
+
+fn cmd_show(store: &PasswordStore, args: &[String]) -> Result<i32> {
+    const USAGE: &str = "show [--clip[=line-number],-c[line-number]] [pass-name]";
+
+    let args = Args::parse(args);
+    if args.has_unknown(&[("-c", "--clip")]) || args.positional.len() > 1 {
+        return usage(USAGE);
+    }
+    let clip_line = match args.value("-c", "--clip") {
+        None => None,
+        Some(None) => Some(1),
+        Some(Some(l)) => match l.parse::<usize>() {
+            Ok(l) if l > 0 => Some(l),
+            _ => return usage(USAGE),
+        },
+    };
+
+    let name = args.positional.first().map_or("", String::as_str);
+    check_sneaky_paths(name)?;
+
+    if !name.is_empty() && entry_path(store, name).is_file() {
+        let mut secret = load_entry(store, name).secret(store)?;
+        let res = match clip_line {
+            None => {
+                print!("{secret}");
+                if !secret.ends_with('\n') {
+                    println!();
+                }
+                Ok(0)
+            }
+            Some(l) => match secret.split('\n').nth(l - 1).filter(|s| !s.is_empty()) {
+                Some(line) => clip(store, line, name).map(|_| 0),
+                None => Err(Error::GenericDyn(format!(
+                    "There is no password to put on the clipboard at line {l}."
+                ))),
+            },
+        };
+        secret.zeroize();
+        res
+    } else if name.is_empty() || is_dir(store, name) {
+        cmd_ls(store, &args.positional)
+    } else {
+        Err(not_in_store(name))
+    }
+}
+
+fn cmd_find(store: &PasswordStore, args: &[String]) -> Result<i32> {
+    if args.is_empty() {
+        return usage("find pass-names...");
+    }
+    let terms: Vec<String> = args.iter().map(|t| t.to_lowercase()).collect();
+
+    let names = entry_names(
+        &store.get_store_path(),
+        store.password_file_extension(),
+        Path::new(""),
+    )?;
+    let matching: Vec<String> = names
+        .into_iter()
+        .filter(|name| {
+            name.to_lowercase()
+                .split('/')
+                .any(|segment| terms.iter().any(|t| segment.contains(t.as_str())))
+        })
+        .collect();
+
+    print!(
+        "{}",
+        render_tree(&format!("Search Terms: {}", args.join(",")), &matching)
+    );
+    Ok(0)
+}
+
+fn cmd_grep(store: &PasswordStore, args: &[String]) -> Result<i32> {
+    let args = Args::parse(args);
+    if args.has_unknown(&[("-i", "--ignore-case")]) || args.positional.len() != 1 {
+        return usage("grep [-i] search-string");
+    }
+    let ignore_case = args.has("-i", "--ignore-case");
+    let needle = if ignore_case {
+        args.positional[0].to_lowercase()
+    } else {
+        args.positional[0].clone()
+    };
+
+    for name in entry_names(
+        &store.get_store_path(),
+        store.password_file_extension(),
+        Path::new(""),
+    )? {
+        let mut secret = match load_entry(store, &name).secret(store) {
+            Ok(s) => s,
+            Err(err) => {
+                eprintln!("Error: {name}: {err}");
+                continue;
+            }
+        };
+        let matches: Vec<&str> = secret
+            .lines()
+            .filter(|l| {
+                if ignore_case {
+                    l.to_lowercase().contains(&needle)
+                } else {
+                    l.contains(&needle)
+                }
+            })
+            .collect();
+        if !matches.is_empty() {
+            println!("{name}:");
+            for line in matches {
+                println!("{line}");
+            }
+        }
+        secret.zeroize();
+    }
+    Ok(0)
+}
+
+/// Writes the content to a new password file, or overwrites the existing one.
+fn save_entry(store: &mut PasswordStore, name: &str, content: String) -> Result<()> {
+    if entry_path(store, name).is_file() {
+        load_entry(store, name).update(content, store)
+    } else {
+        let res = store.new_password_file(name, &content);
+        let mut content = content;
+        content.zeroize();
+        res.map(|_| ())
+    }
+}
+
+/// Returns false if the entry exists and the user didn't want to overwrite it.
+fn confirm_overwrite(store: &PasswordStore, name: &str, force: bool) -> bool {
+    force
+        || !entry_path(store, name).exists()
+        || yesno(&format!(
+            "An entry already exists for {name}. Overwrite it?"
+        ))
+}
+
+fn cmd_insert(store: &mut PasswordStore, args: &[String]) -> Result<i32> {
+    const USAGE: &str = "insert [--echo,-e | --multiline,-m] [--force,-f] pass-name";
+
+    let args = Args::parse(args);
+    if args.has_unknown(&[("-e", "--echo"), ("-m", "--multiline"), ("-f", "--force")])
+        || args.positional.len() != 1
+    {
+        return usage(USAGE);
+    }
+    let echo = args.has("-e", "--echo");
+    let multiline = args.has("-m", "--multiline");
+    if echo && multiline {
+        return usage(USAGE);
+    }
+    let name = &args.positional[0];
+    check_sneaky_paths(name)?;
+
+    if !confirm_overwrite(store, name, args.has("-f", "--force")) {
+        return Ok(1);
+    }
+
+    let content = if multiline {
+        println!("Enter contents of {name} and press Ctrl+D when finished:\n");
+        let mut content = String::new();
+        std::io::stdin().read_to_string(&mut content)?;
+        content
+    } else if echo {
+        let password = read_line(&format!("Enter password for {name}: "), true)?;
+        format!("{password}\n")
+    } else {
+        let mut password = read_line(&format!("Enter password for {name}: "), false)?;
+        let mut check = read_line(&format!("Retype password for {name}: "), false)?;
+        let same = password == check;
+        check.zeroize();
+        if !same {
+            password.zeroize();
+            return Err(Error::Generic("the entered passwords do not match."));
+        }
+        let content = format!("{password}\n");
+        password.zeroize();
+        content
+    };
+
+    save_entry(store, name, content)?;
+    Ok(0)
+}
+
+fn cmd_generate(store: &mut PasswordStore, args: &[String]) -> Result<i32> {
+    const USAGE: &str = "generate [--no-symbols,-n] [--clip,-c] [--in-place,-i | --force,-f] pass-name [pass-length]";
+
+    let args = Args::parse(args);
+    if args.has_unknown(&[
+        ("-n", "--no-symbols"),
+        ("-c", "--clip"),
+        ("-i", "--in-place"),
+        ("-f", "--force"),
+    ]) || args.positional.is_empty()
+        || args.positional.len() > 2
+    {
+        return usage(USAGE);
+    }
+    let in_place = args.has("-i", "--in-place");
+    let force = args.has("-f", "--force");
+    if in_place && force {
+        return usage(USAGE);
+    }
+    let name = &args.positional[0];
+    check_sneaky_paths(name)?;
+
+    let length = match args.positional.get(1) {
+        None => GENERATED_LENGTH,
+        Some(l) => match l.parse::<usize>() {
+            Ok(l) if l > 0 => l,
+            Ok(_) => return Err(Error::Generic("pass-length must be greater than zero.")),
+            Err(_) => {
+                return Err(Error::GenericDyn(format!(
+                    "pass-length \"{l}\" must be a number."
+                )))
+            }
+        },
+    };
+
+    let exists = entry_path(store, name).is_file();
+    if !in_place && !confirm_overwrite(store, name, force) {
+        return Ok(1);
+    }
+
+    // the same characters as pass uses, [:punct:][:alnum:] or only [:alnum:]
+    let generator = PasswordGenerator::Characters {
+        length,
+        classes: CharacterClasses {
+            symbols: !args.has("-n", "--no-symbols"),
+            ..CharacterClasses::default()
+        },
+    };
+    let generated = generator.generate()?;
+    let password = generated.password();
+
+    if in_place && exists {
+        let entry = load_entry(store, name);
+        let mut parsed = entry.parsed(store)?;
+        parsed.set_password(password);
+        entry.update(parsed.to_string(), store)?;
+        println!("Replaced the password for {name}.");
+    } else {
+        save_entry(store, name, format!("{password}\n"))?;
+    }
+
+    if args.value("-c", "--clip").is_some() {
+        clip(store, password, name)?;
+    } else {
+        println!("The generated password for {name} is:\n{password}");
+    }
+    Ok(0)
+}
+
+/// Creates a private file for the editor to work on, in memory backed storage if available.
+fn edit_file_path(name: &str) -> PathBuf {
+    let shm = PathBuf::from("/dev/shm");
+    let dir = if shm.is_dir() {
+        shm
+    } else {
+        std::env::temp_dir()
+    };
+    let file_name = format!("{PROGRAM}.{}-{}.txt", process::id(), name.replace('/', "-"));
+    dir.join(file_name)
+}
+
+fn write_private_file(path: &Path, content: &str) -> Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(content.as_bytes())?;
+    Ok(())
+}
+
+fn cmd_edit(store: &mut PasswordStore, args: &[String]) -> Result<i32> {
+    let args = Args::parse(args);
+    if !args.flags.is_empty() || args.positional.len() != 1 {
+        return usage("edit pass-name");
+    }
+    let name = &args.positional[0];
+    check_sneaky_paths(name)?;
+
+    let mut old = if entry_path(store, name).is_file() {
+        load_entry(store, name).secret(store)?
+    } else {
+        String::new()
+    };
+
+    let tmp_file = edit_file_path(name);
+    write_private_file(&tmp_file, &old)?;
+
+    let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_owned());
+    let status = process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("{editor} \"$1\""))
+        .arg(&editor)
+        .arg(&tmp_file)
+        .status();
+
+    let new = std::fs::read_to_string(&tmp_file);
+    let _ = std::fs::remove_file(&tmp_file);
+
+    if !status?.success() {
+        old.zeroize();
+        return Err(Error::Generic("the editor exited with an error."));
+    }
+    let mut new = new.map_err(|_| Error::Generic("New password not saved."))?;
+
+    let unchanged = new == old;
+    old.zeroize();
+    if unchanged {
+        new.zeroize();
+        return Err(Error::GenericDyn(format!("Password for {name} unchanged.")));
+    }
+
+    save_entry(store, name, new)?;
+    Ok(0)
+}
+
+/// Removes empty directories between the removed file and the root of the store.
+fn remove_empty_parents(root: &Path, path: &Path) {
+    let mut dir = path.parent();
+    while let Some(d) = dir {
+        if d == root || std::fs::remove_dir(d).is_err() {
+            break;
+        }
+        dir = d.parent();
+    }
+}
+
+fn cmd_rm(store: &mut PasswordStore, args: &[String]) -> Result<i32> {
+    let args = Args::parse(args);
+    if args.has_unknown(&[("-r", "--recursive"), ("-f", "--force")]) || args.positional.len() != 1 {
+        return usage("rm [--recursive,-r] [--force,-f] pass-name");
+    }
+    let name = args.positional[0].trim_end_matches('/');
+    check_sneaky_paths(name)?;
+
+    let root = store.get_store_path();
+    let is_entry = entry_path(store, name).is_file();
+    let paths = if is_entry {
+        vec![PathBuf::from(format!(
+            "{name}.{}",
+            store.password_file_extension()
+        ))]
+    } else if is_dir(store, name) {
+        if !args.has("-r", "--recursive") {
+            return Err(Error::GenericDyn(format!(
+                "{name}/ is a directory, use --recursive to remove it."
+            )));
+        }
+        files_below(&root, Path::new(name))?
+    } else {
+        return Err(not_in_store(name));
+    };
+
+    if !args.has("-f", "--force")
+        && !yesno(&format!("Are you sure you would like to delete {name}?"))
+    {
+        return Ok(1);
+    }
+
+    if is_entry {
+        let path = root.join(&paths[0]);
+        std::fs::remove_file(&path)?;
+        println!("removed '{}'", path.display());
+    } else {
+        std::fs::remove_dir_all(root.join(name))?;
+        println!("removed '{}'", root.join(name).display());
+    }
+    remove_empty_parents(&root, &root.join(name));
+
+    if store.repo().is_ok() {
+        remove_and_commit(store, &paths, &format!("Remove {name} from store."))?;
+    }
+    Ok(0)
+}
+
+/// What a `mv` or `cp` works on.
+enum Transfer {
+    /// A single password entry, with the name of the entry and the name of the target.
+    Entry(String, String),
+    /// A directory, with the name of the directory and the name of the target directory.
+    Dir(String, String),
+}
+
+/// Resolves the source and target of a `mv` or `cp`, using the same rules as pass: a target
+/// that ends with a slash or is an existing directory means that the source should be put
+/// inside of it.
+fn transfer_target(store: &PasswordStore, from: &str, to: &str) -> Result<Transfer> {
+    check_sneaky_paths(from)?;
+    check_sneaky_paths(to)?;
+
+    let from_trimmed = from.trim_end_matches('/');
+    let into_dir = to.ends_with('/') || is_dir(store, to);
+    let to = to.trim_end_matches('/');
+    let base_name = from_trimmed.rsplit('/').next().unwrap_or(from_trimmed);
+
+    if !from.ends_with('/') && entry_path(store, from).is_file() {
+        let target = if into_dir {
+            format!("{to}/{base_name}")
+        } else {
+            to.to_owned()
+        };
+        Ok(Transfer::Entry(from.to_owned(), target))
+    } else if is_dir(store, from_trimmed) {
+        let target_dir = if is_dir(store, to) {
+            format!("{to}/{base_name}")
+        } else {
+            to.to_owned()
+        };
+        Ok(Transfer::Dir(from_trimmed.to_owned(), target_dir))
+    } else {
+        Err(not_in_store(from))
+    }
+}
+
+fn cmd_copy_or_move(store: &mut PasswordStore, args: &[String], is_move: bool) -> Result<i32> {
+    let command = if is_move { "mv" } else { "cp" };
+
+    let args = Args::parse(args);
+    if args.has_unknown(&[("-f", "--force")]) || args.positional.len() != 2 {
+        return usage(&format!("{command} [--force,-f] old-path new-path"));
+    }
+    let force = args.has("-f", "--force");
+
+    match transfer_target(store, &args.positional[0], &args.positional[1])? {
+        Transfer::Entry(from, to) => {
+            if from == to {
+                return Ok(0);
+            }
+            if !confirm_overwrite(store, &to, force) {
+                return Ok(1);
+            }
+            if entry_path(store, &to).is_file() {
+                std::fs::remove_file(entry_path(store, &to))?;
+            }
+            if is_move {
+                store.rename_file(&from, &to)?;
+                remove_empty_parents(&store.get_store_path(), &entry_path(store, &from));
+            } else {
+                store.copy_file(&from, &to)?;
+            }
+        }
+        Transfer::Dir(from, to) => {
+            if from == to {
+                return Ok(0);
+            }
+            let overwritten: Vec<String> = entry_names(
+                &store.get_store_path(),
+                store.password_file_extension(),
+                Path::new(&from),
+            )?
+            .into_iter()
+            .map(|n| format!("{to}/{n}"))
+            .filter(|n| entry_path(store, n).is_file())
+            .collect();
+            for n in &overwritten {
+                if !confirm_overwrite(store, n, force) {
+                    return Ok(1);
+                }
+            }
+            for n in &overwritten {
+                std::fs::remove_file(entry_path(store, n))?;
+            }
+            if is_move {
+                store.rename_dir(&from, &to)?;
+            } else {
+                store.copy_dir(&from, &to)?;
+            }
+        }
+    }
+    Ok(0)
+}
+
+fn cmd_otp(store: &PasswordStore, args: &[String]) -> Result<i32> {
+    const USAGE: &str = "otp [code] [--clip,-c] pass-name";
+
+    let args = Args::parse(args);
+    let positional: Vec<&String> = match args.positional.first().map(String::as_str) {
+        Some("code") => args.positional.iter().skip(1).collect(),
+        _ => args.positional.iter().collect(),
+    };
+    if args.has_unknown(&[("-c", "--clip")]) || positional.len() != 1 {
+        return usage(USAGE);
+    }
+    let name = positional[0];
+    check_sneaky_paths(name)?;
+
+    if !entry_path(store, name).is_file() {
+        return Err(not_in_store(name));
+    }
+
+    let code = load_entry(store, name).mfa(store)?;
+    if args.has("-c", "--clip") {
+        clip(store, &code, name)?;
+    } else {
+        println!("{code}");
+    }
+    Ok(0)
+}
+
+fn cmd_import(store: &mut PasswordStore, args: &[String]) -> Result<i32> {
+    const USAGE: &str = "import [--rename,-r] [--prefix=subfolder,-psubfolder] format export-file";
+
+    let args = Args::parse(args);
+    if args.has_unknown(&[("-r", "--rename"), ("-p", "--prefix")]) || args.positional.len() != 2 {
+        return usage(USAGE);
+    }
+    let Some(format) = ImportFormat::from_name(&args.positional[0]) else {
+        return usage(USAGE);
+    };
+    let prefix = match args.value("-p", "--prefix") {
+        None => String::new(),
+        Some(Some(prefix)) => prefix,
+        Some(None) => return usage(USAGE),
+    };
+    check_sneaky_paths(&prefix)?;
+
+    let mut password = match format {
+        ImportFormat::Kdbx => Some(read_line(
+            "Enter password for the KeePass database: ",
+            false,
+        )?),
+        _ => None,
+    };
+    let entries = read_entries(format, Path::new(&args.positional[1]), password.as_deref());
+    password.zeroize();
+    let entries = entries?;
+
+    let options = ImportOptions {
+        prefix,
+        on_conflict: if args.has("-r", "--rename") {
+            ConflictStrategy::Rename
+        } else {
+            ConflictStrategy::Skip
+        },
+    };
+    let report = store.import(&entries, &options)?;
+
+    for (name, new_name) in &report.renamed {
+        println!("{name} already exists, imported as {new_name}");
+    }
+    for (name, reason) in &report.skipped {
+        println!("Skipped {name}: {reason}");
+    }
+    println!("Imported {} passwords.", report.imported.len());
+    Ok(0)
+}
+
+fn cmd_export(store: &mut PasswordStore, args: &[String]) -> Result<i32> {
+    const USAGE: &str = "export [--recipient=key-id,-rkey-id | --plaintext=format,-pformat] [--fields=names,-Fnames] [--force,-f] [subfolder] export-file";
+
+    let args = Args::parse(args);
+    if args.has_unknown(&[
+        ("-r", "--recipient"),
+        ("-p", "--plaintext"),
+        ("-F", "--fields"),
+        ("-f", "--force"),
+    ]) || args.positional.is_empty()
+        || args.positional.len() > 2
+    {
+        return usage(USAGE);
+    }
+    let (subtree, path) = match args.positional.as_slice() {
+        [path] => (String::new(), Path::new(path)),
+        [subtree, path] => (subtree.clone(), Path::new(path)),
+        _ => return usage(USAGE),
+    };
+    check_sneaky_paths(&subtree)?;
+    if !subtree.is_empty() && !is_dir(store, subtree.trim_end_matches('/')) {
+        return Err(not_in_store(&subtree));
+    }
+
+    let selection = match args.value("-F", "--fields") {
+        None => FieldSelection::default(),
+        Some(Some(names)) => {
+            let names: Vec<String> = names.split(',').map(|n| n.trim().to_owned()).collect();
+            let has = |part: &str| names.iter().any(|n| n.eq_ignore_ascii_case(part));
+            FieldSelection {
+                password: has("password"),
+                otp: has("otp"),
+                notes: has("notes"),
+                fields: Some(
+                    names
+                        .iter()
+                        .filter(|n| !["password", "otp", "notes"].contains(&n.as_str()))
+                        .cloned()
+                        .collect(),
+                ),
+            }
+        }
+        Some(None) => return usage(USAGE),
+    };
+    let options = ExportOptions { subtree, selection };
+
+    let format = match args.value("-p", "--plaintext") {
+        None => None,
+        Some(Some(f)) if f.eq_ignore_ascii_case("csv") => Some(ExportFormat::Csv),
+        Some(Some(f)) if f.eq_ignore_ascii_case("json") => Some(ExportFormat::Json),
+        Some(_) => return usage(USAGE),
+    };
+    let recipient = match args.value("-r", "--recipient") {
+        None => None,
+        Some(Some(key_id)) => Some(store.recipient_from(&key_id, &[], None)?),
+        Some(None) => return usage(USAGE),
+    };
+    if format.is_some() == recipient.is_some() {
+        return usage(USAGE);
+    }
+
+    let warnings = store.export_warnings(path, format.is_none());
+    if !warnings.is_empty() && !args.has("-f", "--force") {
+        for warning in &warnings {
+            eprintln!("Warning: {warning}.");
+        }
+        if !yesno("Are you sure you would like to export?") {
+            return Ok(1);
+        }
+    }
+
+    store.reload_password_list()?;
+    let report = match (format, recipient) {
+        (Some(format), _) => store.export_plaintext(path, format, &options)?,
+        (None, Some(recipient)) => store.export_archive(path, &[recipient], &options)?,
+        (None, None) => return usage(USAGE),
+    };
+    println!(
+        "Exported {} passwords to {}.",
+        report.exported.len(),
+        path.display()
+    );
+    Ok(0)
+}
+
+fn cmd_git(store: &PasswordStore, args: &[String]) -> Result<i32> {
+    let status = process::Command::new("git")
+        .arg("-C")
+        .arg(store.get_store_path())
+        .args(args)
+        .status()?;
+
+    Ok(status.code().unwrap_or(1))
+}
+
+fn run(args: &[String]) -> Result<i32> {
+    let (command, rest) = match args.split_first() {
+        None => ("ls", &args[..0]),
+        Some((c, rest)) => (c.as_str(), rest),
+    };
+
+    match command {
+        CLIP_COMMAND => return cmd_clip_in_background(rest),
+        "help" | "--help" | "-h" => {
+            help();
+            return Ok(0);
+        }
+        "version" | "--version" | "-v" => {
+            println!("{PROGRAM} v{}", env!("CARGO_PKG_VERSION"));
+            return Ok(0);
+        }
+        _ => {}
+    }
+
+    let mut store = open_store()?;
+
+    match command {
+        "ls" | "list" => cmd_ls(&store, rest),
+        "show" => cmd_show(&store, rest),
+        "find" | "search" => cmd_find(&store, rest),
+        "grep" => cmd_grep(&store, rest),
+        "insert" | "add" => cmd_insert(&mut store, rest),
+        "edit" => cmd_edit(&mut store, rest),
+        "generate" => cmd_generate(&mut store, rest),
+        "delete" | "rm" | "remove" => cmd_rm(&mut store, rest),
+        "rename" | "mv" => cmd_copy_or_move(&mut store, rest, true),
+        "copy" | "cp" => cmd_copy_or_move(&mut store, rest, false),
+        "otp" => cmd_otp(&store, rest),
+        "import" => cmd_import(&mut store, rest),
+        "export" => cmd_export(&mut store, rest),
+        "git" => cmd_git(&store, rest),
+        _ => cmd_show(&store, args),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match run(&args) {
+        Ok(code) => process::exit(code),
+        Err(err) => {
+            eprintln!("Error: {err}");
+            process::exit(1);
+        }
+    }
+}
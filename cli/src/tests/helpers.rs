@@ -0,0 +1,82 @@
+use std::path::{Path, PathBuf};
+
+use tempfile::tempdir;
+
+use super::*;
+
+#[test]
+fn render_tree_nested() {
+    let names = vec![
+        "email/personal".to_owned(),
+        "email/work".to_owned(),
+        "web/example.com".to_owned(),
+        "zzz".to_owned(),
+    ];
+
+    let tree = render_tree("Password Store", &names);
+
+    assert_eq!(
+        "Password Store
+├── email
+│   ├── personal
+│   └── work
+├── web
+│   └── example.com
+└── zzz
+",
+        tree
+    );
+}
+
+#[test]
+fn render_tree_empty() {
+    assert_eq!("Password Store\n", render_tree("Password Store", &[]));
+}
+
+#[test]
+fn entry_names_skips_git_and_other_files() {
+    let dir = tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join(".git")).unwrap();
+    std::fs::create_dir_all(dir.path().join("work/servers")).unwrap();
+    std::fs::write(dir.path().join(".git/config.gpg"), "").unwrap();
+    std::fs::write(dir.path().join(".gpg-id"), "").unwrap();
+    std::fs::write(dir.path().join("bank.gpg"), "").unwrap();
+    std::fs::write(dir.path().join("work/example.com.gpg"), "").unwrap();
+    std::fs::write(dir.path().join("work/servers/db.gpg"), "").unwrap();
+
+    assert_eq!(
+        vec!["bank", "work/example.com", "work/servers/db"],
+        entry_names(dir.path(), "gpg", Path::new("")).unwrap()
+    );
+    assert_eq!(
+        vec!["example.com", "servers/db"],
+        entry_names(dir.path(), "gpg", Path::new("work")).unwrap()
+    );
+}
+
+#[test]
+fn files_below_includes_recipients_files() {
+    let dir = tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("work/servers")).unwrap();
+    std::fs::write(dir.path().join("bank.gpg"), "").unwrap();
+    std::fs::write(dir.path().join("work/.gpg-id"), "").unwrap();
+    std::fs::write(dir.path().join("work/.gpg-id.sig"), "").unwrap();
+    std::fs::write(dir.path().join("work/servers/db.gpg"), "").unwrap();
+
+    assert_eq!(
+        vec![
+            PathBuf::from("work/.gpg-id"),
+            PathBuf::from("work/.gpg-id.sig"),
+            PathBuf::from("work/servers/db.gpg"),
+        ],
+        files_below(dir.path(), Path::new("work")).unwrap()
+    );
+}
+
+#[test]
+fn check_sneaky_paths_rejects_traversal() {
+    assert!(check_sneaky_paths("work/example.com").is_ok());
+    assert!(check_sneaky_paths("../outside").is_err());
+    assert!(check_sneaky_paths("work/../../outside").is_err());
+    assert!(check_sneaky_paths("/etc/passwd").is_err());
+}
@@ -0,0 +1,184 @@
+/*  Ripasso - a simple password manager
+    Copyright (C) 2019-2020 Joakim Lundborg, Alexander Kjäll
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, version 3 of the License.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::sync::Arc;
+
+use cursive::{
+    event::Key,
+    traits::*,
+    views::{Dialog, EditView, LinearLayout, OnEventView, TextView},
+    Cursive,
+};
+use lazy_static::lazy_static;
+use pass::Result;
+use ripasso::{
+    credentials::{AnsweredCredentials, AnsweredPassphrases, Attempt, Question},
+    pass,
+};
+
+use crate::{helpers, helpers::get_value_from_input};
+
+lazy_static! {
+    /// The passphrases for the secret keys, shared by all the stores.
+    pub static ref KEY_PASSPHRASES: Arc<AnsweredPassphrases> = Arc::new(AnsweredPassphrases::default());
+}
+
+type Operation<T> = Arc<dyn Fn(&AnsweredCredentials) -> Result<T> + Send + Sync>;
+type Done<T> = Arc<dyn Fn(&mut Cursive, Result<T>) + Send + Sync>;
+
+/// Runs a git operation, and if it failed because it needed a credential that the user hasn't
+/// given yet, asks for it in a dialog and runs the operation again. `done` gets the result of
+/// the last run.
+pub fn run_with_credentials<T: 'static>(
+    ui: &mut Cursive,
+    operation: impl Fn(&AnsweredCredentials) -> Result<T> + Send + Sync + 'static,
+    done: impl Fn(&mut Cursive, Result<T>) + Send + Sync + 'static,
+) {
+    run(
+        ui,
+        AnsweredCredentials::default(),
+        Arc::new(operation),
+        Arc::new(done),
+    );
+}
+
+fn run<T: 'static>(
+    ui: &mut Cursive,
+    credentials: AnsweredCredentials,
+    operation: Operation<T>,
+    done: Done<T>,
+) {
+    match credentials.attempt(&*operation) {
+        Attempt::Ask(question) => credential_dialog(ui, credentials, question, operation, done),
+        Attempt::Done(res) => done(ui, res),
+    }
+}
+
+fn credential_dialog<T: 'static>(
+    ui: &mut Cursive,
+    credentials: AnsweredCredentials,
+    question: Question,
+    operation: Operation<T>,
+    done: Done<T>,
+) {
+    let mut fields = LinearLayout::vertical();
+    let title = match &question {
+        Question::Passphrase(key_file) => {
+            fields.add_child(TextView::new(
+                super::CATALOG
+                    .gettext("Passphrase for {}:")
+                    .replace("{}", &key_file.to_string_lossy()),
+            ));
+            super::CATALOG.gettext("SSH Key Passphrase")
+        }
+        Question::UserAndToken { url, username } => {
+            fields.add_child(TextView::new(
+                super::CATALOG
+                    .gettext("User name and token for {}:")
+                    .replace("{}", url),
+            ));
+            fields.add_child(
+                EditView::new()
+                    .content(username.clone().unwrap_or_default())
+                    .with_name("credential_user_input")
+                    .fixed_size((50_usize, 1_usize)),
+            );
+            super::CATALOG.gettext("Git Credentials")
+        }
+    };
+    fields.add_child(
+        EditView::new()
+            .secret()
+            .with_name("credential_secret_input")
+            .fixed_size((50_usize, 1_usize)),
+    );
+
+    let d = Dialog::around(fields)
+        .title(title)
+        .button(super::CATALOG.gettext("Ok"), move |s| {
+            let secret = get_value_from_input(s, "credential_secret_input")
+                .map(|secret| secret.to_string())
+                .unwrap_or_default();
+            let user = get_value_from_input(s, "credential_user_input")
+                .map(|user| user.to_string())
+                .unwrap_or_default();
+            let mut credentials = credentials.clone();
+            credentials.answer(&question, user, secret);
+            s.pop_layer();
+
+            run(s, credentials, operation.clone(), done.clone());
+        })
+        .dismiss_button(super::CATALOG.gettext("Cancel"));
+
+    let ev = OnEventView::new(d).on_event(Key::Esc, |s| {
+        s.pop_layer();
+    });
+
+    ui.add_layer(ev);
+}
+
+/// Shows the error of a decryption, unless it failed because a secret key needs a passphrase,
+/// then the passphrase is asked for and `retry` is called once the user has answered.
+pub fn errorbox_or_ask_passphrase(
+    ui: &mut Cursive,
+    err: &pass::Error,
+    retry: impl Fn(&mut Cursive) + Send + Sync + 'static,
+) {
+    let key = match KEY_PASSPHRASES.take_unanswered() {
+        Ok(key) => key,
+        Err(lock_err) => {
+            helpers::errorbox(ui, &lock_err);
+            return;
+        }
+    };
+    let Some(key) = key else {
+        helpers::errorbox(ui, err);
+        return;
+    };
+
+    let fields = LinearLayout::vertical()
+        .child(TextView::new(
+            super::CATALOG
+                .gettext("Passphrase for {}:")
+                .replace("{}", &key),
+        ))
+        .child(
+            EditView::new()
+                .secret()
+                .with_name("key_passphrase_input")
+                .fixed_size((50_usize, 1_usize)),
+        );
+
+    let d = Dialog::around(fields)
+        .title(super::CATALOG.gettext("Secret Key Passphrase"))
+        .button(super::CATALOG.gettext("Ok"), move |s| {
+            let passphrase = get_value_from_input(s, "key_passphrase_input")
+                .map(|passphrase| passphrase.to_string())
+                .unwrap_or_default();
+            s.pop_layer();
+            match KEY_PASSPHRASES.answer(key.clone(), passphrase) {
+                Ok(()) => retry(s),
+                Err(err) => helpers::errorbox(s, &err),
+            }
+        })
+        .dismiss_button(super::CATALOG.gettext("Cancel"));
+
+    let ev = OnEventView::new(d).on_event(Key::Esc, |s| {
+        s.pop_layer();
+    });
+
+    ui.add_layer(ev);
+}
@@ -14,9 +14,8 @@
     along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-use arboard::Clipboard;
 use cursive::{
     event::Key,
     views::{Checkbox, Dialog, EditView, OnEventView, RadioButton, TextView},
@@ -24,10 +23,10 @@ use cursive::{
 };
 use lazy_static::lazy_static;
 use pass::Result;
-use ripasso::{crypto::CryptoImpl, pass};
+use ripasso::{clipboard::ClipboardManager, crypto::CryptoImpl, pass};
 
 lazy_static! {
-    static ref CLIPBOARD: Arc<Mutex<Clipboard>> = Arc::new(Mutex::new(Clipboard::new().unwrap()));
+    static ref CLIPBOARD: ClipboardManager = ClipboardManager::new().unwrap();
 }
 
 /// Displays an error in a cursive dialog
@@ -51,9 +50,19 @@ pub fn errorbox(ui: &mut Cursive, err: &pass::Error) {
     ui.add_layer(ev);
 }
 
-/// Copies content to the clipboard.
-pub fn set_clipboard(content: &String) -> Result<()> {
-    Ok(CLIPBOARD.lock().unwrap().set_text(content)?)
+/// Copies content that isn't secret to the clipboard.
+pub fn set_clipboard(content: &str) -> Result<()> {
+    CLIPBOARD.copy_text(content)
+}
+
+/// Copies a secret to the clipboard, it's cleared after `timeout` if it's still there.
+pub fn copy_secret(secret: &str, timeout: Duration) -> Result<()> {
+    CLIPBOARD.copy_secret(secret, timeout)
+}
+
+/// Clears the clipboard if it still holds the last copied secret.
+pub fn clear_clipboard() -> Result<bool> {
+    CLIPBOARD.clear()
 }
 
 pub fn get_value_from_input(s: &mut Cursive, input_name: &str) -> Option<std::sync::Arc<String>> {
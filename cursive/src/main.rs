@@ -18,46 +18,68 @@ use std::{
     collections::HashMap,
     path::{Path, PathBuf},
     process,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
     thread, time,
 };
 
+use chrono::Local;
 use cursive::{
     direction::Orientation,
     event::{Event, Key},
     menu::Tree,
+    theme::{BaseColor, Color},
     traits::*,
+    utils::markup::StyledString,
     views::{
         Checkbox, CircularFocus, Dialog, EditView, LinearLayout, NamedView, OnEventView,
         RadioGroup, ResizedView, ScrollView, SelectView, TextArea, TextView,
     },
-    Cursive, CursiveExt,
+    CbSink, Cursive, CursiveExt,
 };
 use hex::FromHex;
 use pass::Result;
 use ripasso::{
+    audit::{AuditIssue, AuditOptions, AuditReport},
     crypto::CryptoImpl,
-    git::{pull, push},
+    discovery::KeySource,
+    generate::{Capitalization, CharacterClasses, PasswordGenerator, Strength},
+    git::{self, pull, push, MergeConflict, SyncRemote},
     pass,
     pass::{
-        all_recipients_from_stores, OwnerTrustLevel, PasswordStore, Recipient, SignatureStatus,
+        all_recipients_from_stores, parse_fingerprints, DiffLine, KeyWarning, OtpCode,
+        OwnerTrustLevel, ParsedEntry, PasswordStore, Recipient, SignatureStatus,
     },
+    sync::{SyncEvent, SyncService},
+    watch::{PasswordChange, StoreWatcher},
 };
 use unic_langid::LanguageIdentifier;
 
+mod credentials;
 mod helpers;
 mod wizard;
 
 use lazy_static::lazy_static;
 use zeroize::Zeroize;
 
-use crate::helpers::{get_value_from_input, is_checkbox_checked, is_radio_button_selected};
+use crate::{
+    credentials::{errorbox_or_ask_passphrase, run_with_credentials, KEY_PASSPHRASES},
+    helpers::{get_value_from_input, is_checkbox_checked, is_radio_button_selected},
+};
 
 /// The 'pointer' to the current PasswordStore is of this convoluted type.
 type PasswordStoreType = Arc<Mutex<Arc<Mutex<PasswordStore>>>>;
 /// The list of stores that the user have.
 type StoreListType = Arc<Mutex<Vec<Arc<Mutex<PasswordStore>>>>>;
 
+/// MFA codes that are valid for fewer seconds than this aren't copied, instead the next code
+/// is copied as soon as it becomes valid.
+const MFA_MIN_VALIDITY: u64 = 5;
+/// Increased every time a MFA code is copied, so that the countdown of an earlier code stops.
+static MFA_COUNTDOWN: AtomicUsize = AtomicUsize::new(0);
+
 lazy_static! {
     static ref CATALOG: gettext::Catalog = get_translation_catalog();
     static ref DEFAULT_TERMINAL_SIZE: (usize, usize) = match terminal_size::terminal_size() {
@@ -140,22 +162,29 @@ fn copy(ui: &mut Cursive, store: PasswordStoreType) {
     if sel.is_none() {
         return;
     }
-    if let Err(err) = || -> pass::Result<()> {
-        let mut secret: String = sel.unwrap().secret(&*store.lock()?.lock()?)?;
-        helpers::set_clipboard(&secret)?;
+    let timeout = match || -> pass::Result<time::Duration> {
+        let store = store.lock()?;
+        let store = store.lock()?;
+        let mut secret: String = sel.unwrap().secret(&store)?;
+        let res = helpers::copy_secret(&secret, store.get_clipboard_timeout());
         secret.zeroize();
-        Ok(())
+        res?;
+        Ok(store.get_clipboard_timeout())
     }() {
-        helpers::errorbox(ui, &err);
-        return;
-    }
+        Ok(timeout) => timeout,
+        Err(err) => {
+            let store = store.clone();
+            errorbox_or_ask_passphrase(ui, &err, move |s| copy(s, store.clone()));
+            return;
+        }
+    };
 
-    thread::spawn(|| {
-        thread::sleep(time::Duration::from_secs(40));
-        helpers::set_clipboard(&String::new()).unwrap();
-    });
     ui.call_on_name("status_bar", |l: &mut TextView| {
-        l.set_content(CATALOG.gettext("Copied password to copy buffer for 40 seconds"));
+        l.set_content(
+            CATALOG
+                .gettext("Copied password to copy buffer for {} seconds")
+                .replace("{}", &timeout.as_secs().to_string()),
+        );
     });
 }
 
@@ -168,23 +197,28 @@ fn copy_first_line(ui: &mut Cursive, store: PasswordStoreType) {
     if sel.is_none() {
         return;
     }
-    if let Err(err) = || -> pass::Result<()> {
-        let mut secret = sel.unwrap().password(&*store.lock()?.lock()?)?;
-        helpers::set_clipboard(&secret)?;
+    let timeout = match || -> pass::Result<time::Duration> {
+        let store = store.lock()?;
+        let store = store.lock()?;
+        let mut secret = sel.unwrap().password(&store)?;
+        let res = helpers::copy_secret(&secret, store.get_clipboard_timeout());
         secret.zeroize();
-        Ok(())
+        res?;
+        Ok(store.get_clipboard_timeout())
     }() {
-        helpers::errorbox(ui, &err);
-        return;
-    }
+        Ok(timeout) => timeout,
+        Err(err) => {
+            let store = store.clone();
+            errorbox_or_ask_passphrase(ui, &err, move |s| copy_first_line(s, store.clone()));
+            return;
+        }
+    };
 
-    thread::spawn(|| {
-        thread::sleep(time::Duration::from_secs(40));
-        helpers::set_clipboard(&String::new()).unwrap();
-    });
     ui.call_on_name("status_bar", |l: &mut TextView| {
         l.set_content(
-            CATALOG.gettext("Copied first line of password to copy buffer for 40 seconds"),
+            CATALOG
+                .gettext("Copied first line of password to copy buffer for {} seconds")
+                .replace("{}", &timeout.as_secs().to_string()),
         );
     });
 }
@@ -198,19 +232,148 @@ fn copy_mfa(ui: &mut Cursive, store: PasswordStoreType) {
     if sel.is_none() {
         return;
     }
-    if let Err(err) = || -> pass::Result<()> {
-        let mut secret = sel.unwrap().mfa(&*store.lock()?.lock()?)?;
-        helpers::set_clipboard(&secret)?;
-        secret.zeroize();
-        Ok(())
+    let sel = sel.unwrap();
+
+    let accounts = match || -> pass::Result<Vec<pass::OtpAccount>> {
+        sel.otp_accounts(&*store.lock()?.lock()?)
     }() {
-        helpers::errorbox(ui, &err);
+        Ok(accounts) => accounts,
+        Err(err) => {
+            helpers::errorbox(ui, &err);
+            return;
+        }
+    };
+
+    if accounts.len() < 2 {
+        copy_mfa_code(ui, &store, &sel, 0);
         return;
     }
 
-    ui.call_on_name("status_bar", |l: &mut TextView| {
-        l.set_content(CATALOG.gettext("Copied MFA code to copy buffer"));
+    let mut accounts_view = SelectView::<usize>::new().h_align(cursive::align::HAlign::Left);
+    for (index, account) in accounts.iter().enumerate() {
+        accounts_view.add_item(account.label(), index);
+    }
+    accounts_view.set_on_submit(move |ui: &mut Cursive, index: &usize| {
+        ui.pop_layer();
+        copy_mfa_code(ui, &store, &sel, *index);
     });
+
+    let d = Dialog::around(accounts_view)
+        .title(CATALOG.gettext("Choose MFA account"))
+        .dismiss_button(CATALOG.gettext("Cancel"));
+
+    let ev = OnEventView::new(d).on_event(Key::Esc, |s| {
+        s.pop_layer();
+    });
+
+    ui.add_layer(ev);
+}
+
+fn copy_mfa_code(
+    ui: &mut Cursive,
+    store: &PasswordStoreType,
+    entry: &pass::PasswordEntry,
+    index: usize,
+) {
+    let (code, timeout) = match || -> pass::Result<(OtpCode, time::Duration)> {
+        let store = store.lock()?;
+        let store = store.lock()?;
+        Ok((
+            entry.mfa_code_for(&store, index)?,
+            store.get_clipboard_timeout(),
+        ))
+    }() {
+        Ok(code) => code,
+        Err(err) => {
+            helpers::errorbox(ui, &err);
+            return;
+        }
+    };
+
+    let generation = MFA_COUNTDOWN.fetch_add(1, Ordering::SeqCst) + 1;
+    let cb_sink = ui.cb_sink().clone();
+
+    match (code.seconds_remaining(), code.next_code(), code.period()) {
+        (Some(remaining), Some(next), Some(period)) if code.expires_within(MFA_MIN_VALIDITY) => {
+            let mut next = next.to_owned();
+            thread::spawn(move || {
+                thread::sleep(time::Duration::from_secs(remaining));
+                if MFA_COUNTDOWN.load(Ordering::SeqCst) != generation {
+                    next.zeroize();
+                    return;
+                }
+                let res = helpers::copy_secret(&next, timeout);
+                next.zeroize();
+                if let Err(err) = res {
+                    let err = pass::Error::GenericDyn(err.to_string());
+                    let _ = cb_sink.send(Box::new(move |ui: &mut Cursive| {
+                        helpers::errorbox(ui, &err);
+                    }));
+                    return;
+                }
+                mfa_countdown(&cb_sink, generation, period);
+            });
+
+            ui.call_on_name("status_bar", |l: &mut TextView| {
+                l.set_content(
+                    CATALOG
+                        .gettext("Waiting {} seconds for a fresh MFA code")
+                        .replace("{}", &remaining.to_string()),
+                );
+            });
+        }
+        (remaining, _, _) => {
+            let mut secret = code.code().to_owned();
+            let res = helpers::copy_secret(&secret, timeout);
+            secret.zeroize();
+            if let Err(err) = res {
+                helpers::errorbox(ui, &err);
+                return;
+            }
+
+            match remaining {
+                Some(remaining) => {
+                    thread::spawn(move || mfa_countdown(&cb_sink, generation, remaining));
+                }
+                None => {
+                    ui.call_on_name("status_bar", |l: &mut TextView| {
+                        l.set_content(CATALOG.gettext("Copied MFA code to copy buffer"));
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Shows how many seconds the copied MFA code is valid for in the status bar, until it
+/// expires or another code is copied.
+fn mfa_countdown(cb_sink: &CbSink, generation: usize, mut remaining: u64) {
+    while remaining > 0 {
+        if MFA_COUNTDOWN.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        let text = CATALOG
+            .gettext("Copied MFA code to copy buffer, valid for {} seconds")
+            .replace("{}", &remaining.to_string());
+        let res = cb_sink.send(Box::new(move |ui: &mut Cursive| {
+            ui.call_on_name("status_bar", |l: &mut TextView| {
+                l.set_content(text);
+            });
+        }));
+        if res.is_err() {
+            return;
+        }
+        thread::sleep(time::Duration::from_secs(1));
+        remaining -= 1;
+    }
+
+    if MFA_COUNTDOWN.load(Ordering::SeqCst) == generation {
+        let _ = cb_sink.send(Box::new(|ui: &mut Cursive| {
+            ui.call_on_name("status_bar", |l: &mut TextView| {
+                l.set_content(CATALOG.gettext("The copied MFA code has expired"));
+            });
+        }));
+    }
 }
 
 fn copy_name(ui: &mut Cursive) {
@@ -226,7 +389,7 @@ fn copy_name(ui: &mut Cursive) {
 
     if let Err(err) = || -> pass::Result<()> {
         let name = sel.name.split('/').next_back();
-        helpers::set_clipboard(&name.unwrap_or("").to_string())?;
+        helpers::set_clipboard(name.unwrap_or(""))?;
         Ok(())
     }() {
         helpers::errorbox(ui, &err);
@@ -301,10 +464,19 @@ fn show_file_history(ui: &mut Cursive, store: PasswordStoreType) -> Result<()> {
     if password_entry_opt.is_none() {
         return Ok(());
     }
-    let password_entry = password_entry_opt.unwrap();
+    let password_entry = Arc::new(password_entry_opt.unwrap());
 
     let mut file_history_view = SelectView::<pass::GitLogLine>::new()
         .h_align(cursive::align::HAlign::Left)
+        .on_submit({
+            let store = store.clone();
+            let password_entry = password_entry.clone();
+            move |ui: &mut Cursive, line: &pass::GitLogLine| {
+                if let Err(err) = show_version(ui, &store, &password_entry, line) {
+                    helpers::errorbox(ui, &err);
+                }
+            }
+        })
         .with_name("file_history");
 
     let history = password_entry.get_history(&*store.lock()?.lock()?)?;
@@ -331,6 +503,33 @@ fn show_file_history(ui: &mut Cursive, store: PasswordStoreType) -> Result<()> {
 
     let d = Dialog::around(file_history_view)
         .title(CATALOG.gettext("File History"))
+        .button(CATALOG.gettext("Show"), {
+            let store = store.clone();
+            let password_entry = password_entry.clone();
+            move |ui: &mut Cursive| {
+                if let Some(line) = selected_history_line(ui) {
+                    if let Err(err) = show_version(ui, &store, &password_entry, &line) {
+                        helpers::errorbox(ui, &err);
+                    }
+                }
+            }
+        })
+        .button(CATALOG.gettext("Diff with Latest"), {
+            let store = store.clone();
+            let password_entry = password_entry.clone();
+            move |ui: &mut Cursive| {
+                if let Some(line) = selected_history_line(ui) {
+                    if let Err(err) = show_diff(ui, &store, &password_entry, &line) {
+                        helpers::errorbox(ui, &err);
+                    }
+                }
+            }
+        })
+        .button(CATALOG.gettext("Restore"), move |ui: &mut Cursive| {
+            if let Some(line) = selected_history_line(ui) {
+                restore_version_dialog(ui, store.clone(), password_entry.clone(), line);
+            }
+        })
         .dismiss_button(CATALOG.gettext("Ok"));
 
     let file_history_event = OnEventView::new(d).on_event(Key::Esc, |s| {
@@ -342,6 +541,122 @@ fn show_file_history(ui: &mut Cursive, store: PasswordStoreType) -> Result<()> {
     Ok(())
 }
 
+fn selected_history_line(ui: &mut Cursive) -> Option<Arc<pass::GitLogLine>> {
+    ui.find_name::<SelectView<pass::GitLogLine>>("file_history")
+        .and_then(|l| l.selection())
+}
+
+/// Shows the content that the entry had at a commit of its history.
+fn show_version(
+    ui: &mut Cursive,
+    store: &PasswordStoreType,
+    password_entry: &pass::PasswordEntry,
+    line: &pass::GitLogLine,
+) -> Result<()> {
+    let mut secret = password_entry.secret_at(&*store.lock()?.lock()?, line.commit_id)?;
+    let d = Dialog::around(TextView::new(&secret))
+        .title(
+            CATALOG
+                .gettext("Version from {}")
+                .replace("{}", &line.commit_time.to_string()),
+        )
+        .dismiss_button(CATALOG.gettext("Ok"));
+    secret.zeroize();
+
+    let ev = OnEventView::new(d).on_event(Key::Esc, |s| {
+        s.pop_layer();
+    });
+    ui.add_layer(ev);
+    Ok(())
+}
+
+/// Shows what changed in the entry between a commit of its history and the latest commit.
+fn show_diff(
+    ui: &mut Cursive,
+    store: &PasswordStoreType,
+    password_entry: &pass::PasswordEntry,
+    line: &pass::GitLogLine,
+) -> Result<()> {
+    let diff = {
+        let store = store.lock()?;
+        let store = store.lock()?;
+        let history = password_entry.get_history(&store)?;
+        match history.first() {
+            Some(latest) => password_entry.diff(&store, line.commit_id, latest.commit_id)?,
+            None => vec![],
+        }
+    };
+
+    let mut text = StyledString::new();
+    for diff_line in &diff {
+        match diff_line {
+            DiffLine::Removed(l) => {
+                text.append_styled(format!("- {l}\n"), Color::Dark(BaseColor::Red));
+            }
+            DiffLine::Added(l) => {
+                text.append_styled(format!("+ {l}\n"), Color::Dark(BaseColor::Green));
+            }
+            _ => text.append_plain(format!("  {}\n", diff_line.text())),
+        }
+    }
+
+    let d = Dialog::around(ScrollView::new(TextView::new(text)))
+        .title(
+            CATALOG
+                .gettext("Changes since {}")
+                .replace("{}", &line.commit_time.to_string()),
+        )
+        .dismiss_button(CATALOG.gettext("Ok"));
+
+    let ev = OnEventView::new(d).on_event(Key::Esc, |s| {
+        s.pop_layer();
+    });
+    ui.add_layer(ev);
+    Ok(())
+}
+
+fn restore_version(
+    store: &PasswordStoreType,
+    password_entry: &pass::PasswordEntry,
+    line: &pass::GitLogLine,
+) -> Result<()> {
+    let store = store.lock()?;
+    let res = password_entry.restore(&*store.lock()?, line.commit_id);
+    res
+}
+
+fn restore_version_dialog(
+    ui: &mut Cursive,
+    store: PasswordStoreType,
+    password_entry: Arc<pass::PasswordEntry>,
+    line: Arc<pass::GitLogLine>,
+) {
+    let d = Dialog::around(TextView::new(
+        CATALOG
+            .gettext("Restore the version from {}?")
+            .replace("{}", &line.commit_time.to_string()),
+    ))
+    .button(CATALOG.gettext("Yes"), move |ui: &mut Cursive| {
+        match restore_version(&store, &password_entry, &line) {
+            Err(err) => helpers::errorbox(ui, &err),
+            Ok(()) => {
+                // the confirmation and the history
+                ui.pop_layer();
+                ui.pop_layer();
+                ui.call_on_name("status_bar", |l: &mut TextView| {
+                    l.set_content(CATALOG.gettext("Restored the old version"));
+                });
+            }
+        }
+    })
+    .dismiss_button(CATALOG.gettext("Cancel"));
+
+    let ev = OnEventView::new(d).on_event(Key::Esc, |s| {
+        s.pop_layer();
+    });
+    ui.add_layer(ev);
+}
+
 fn do_show_file_history(ui: &mut Cursive, store: PasswordStoreType) {
     let res = show_file_history(ui, store);
 
@@ -350,6 +665,113 @@ fn do_show_file_history(ui: &mut Cursive, store: PasswordStoreType) {
     }
 }
 
+fn audit_dialog(ui: &mut Cursive, store: PasswordStoreType) {
+    let mut fields = LinearLayout::horizontal();
+    fields.add_child(
+        TextView::new(CATALOG.gettext("Breached passwords list (optional): "))
+            .fixed_size((36_usize, 1_usize)),
+    );
+    fields.add_child(
+        EditView::new()
+            .with_name("audit_breach_list")
+            .fixed_size((50_usize, 1_usize)),
+    );
+
+    let store2 = store.clone();
+    let d = Dialog::around(fields)
+        .title(CATALOG.gettext("Audit Passwords"))
+        .button(CATALOG.gettext("Audit"), move |ui: &mut Cursive| {
+            do_audit(ui, store.clone());
+        })
+        .dismiss_button(CATALOG.gettext("Cancel"));
+
+    let ev = OnEventView::new(d)
+        .on_event(Key::Esc, |s| {
+            s.pop_layer();
+        })
+        .on_event(Key::Enter, move |ui: &mut Cursive| {
+            do_audit(ui, store2.clone());
+        });
+
+    ui.add_layer(ev);
+}
+
+fn audit_issue_text(issue: &AuditIssue) -> String {
+    match issue {
+        AuditIssue::Reused { by } => CATALOG
+            .gettext("reused by {}")
+            .replace("{}", &by.join(", ")),
+        AuditIssue::Weak { strength, .. } => CATALOG
+            .gettext("weak ({})")
+            .replace("{}", &strength.to_string()),
+        AuditIssue::NotRotated { days } => CATALOG
+            .gettext("not changed in {} days")
+            .replace("{}", &days.to_string()),
+        AuditIssue::Breached { count } => CATALOG
+            .gettext("seen {} times in breaches")
+            .replace("{}", &count.to_string()),
+        AuditIssue::Unreadable(reason) => CATALOG
+            .gettext("couldn't be decrypted: {}")
+            .replace("{}", reason),
+    }
+}
+
+fn do_audit(ui: &mut Cursive, store: PasswordStoreType) {
+    let breach_list = get_value_from_input(ui, "audit_breach_list")
+        .map(|p| p.trim().to_owned())
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from);
+    let options = AuditOptions {
+        breach_list,
+        ..AuditOptions::default()
+    };
+
+    let report = match || -> Result<AuditReport> { store.lock()?.lock()?.audit(&options) }() {
+        Ok(report) => report,
+        Err(err) => {
+            helpers::errorbox(ui, &err);
+            return;
+        }
+    };
+    ui.pop_layer();
+
+    let mut issues_view = SelectView::<String>::new().h_align(cursive::align::HAlign::Left);
+    for entry in &report.entries {
+        let issues: Vec<String> = entry.issues.iter().map(audit_issue_text).collect();
+        issues_view.add_item(
+            format!("{}: {}", entry.name, issues.join(", ")),
+            entry.name.clone(),
+        );
+    }
+    // selecting an entry in the report shows it in the password list
+    issues_view.set_on_submit(move |ui: &mut Cursive, name: &String| {
+        ui.pop_layer();
+        ui.call_on_name("search_box", |e: &mut EditView| {
+            e.set_content(name.clone());
+        });
+        do_search(&store, ui, name);
+    });
+
+    let summary = CATALOG
+        .gettext("{} of {} passwords have issues")
+        .replacen("{}", &report.entries.len().to_string(), 1)
+        .replacen("{}", &report.audited.to_string(), 1);
+
+    let d = Dialog::around(
+        LinearLayout::vertical()
+            .child(TextView::new(summary))
+            .child(ScrollView::new(issues_view)),
+    )
+    .title(CATALOG.gettext("Audit Report"))
+    .dismiss_button(CATALOG.gettext("Ok"));
+
+    let ev = OnEventView::new(d).on_event(Key::Esc, |s| {
+        s.pop_layer();
+    });
+
+    ui.add_layer(ev);
+}
+
 fn do_password_save(ui: &mut Cursive, password: &str, store: PasswordStoreType, do_pop: bool) {
     let res = password_save(ui, password, store, do_pop);
     if let Err(err) = res {
@@ -407,7 +829,8 @@ fn open(ui: &mut Cursive, store: PasswordStoreType) -> Result<()> {
         match password_entry.secret(&*store.lock()?.lock()?) {
             Ok(p) => p,
             Err(err) => {
-                helpers::errorbox(ui, &err);
+                let store = store.clone();
+                errorbox_or_ask_passphrase(ui, &err, move |s| do_open(s, store.clone()));
                 return Ok(());
             }
         }
@@ -441,11 +864,14 @@ fn open(ui: &mut Cursive, store: PasswordStoreType) -> Result<()> {
 
         })
         .button(CATALOG.gettext("Generate"), move |s| {
-            let mut new_password = ripasso::words::generate_password(6);
-            s.call_on_name("editbox", |e: &mut TextArea| {
-                e.set_content(&new_password);
+            generate_dialog(s, |s, new_password| {
+                s.call_on_name("editbox", |e: &mut TextArea| {
+                    // only replace the password, keep the rest of the entry
+                    let mut parsed = ParsedEntry::parse(e.get_content());
+                    parsed.set_password(new_password);
+                    e.set_content(parsed.to_string());
+                });
             });
-            new_password.zeroize();
         })
         .dismiss_button(CATALOG.gettext("Close"));
 
@@ -458,37 +884,80 @@ fn open(ui: &mut Cursive, store: PasswordStoreType) -> Result<()> {
     Ok(())
 }
 
-fn do_rename_file(ui: &mut Cursive, store: PasswordStoreType) -> Result<()> {
-    let old_name = ui
-        .find_name::<TextView>("old_name_input")
-        .unwrap()
-        .get_content();
+/// What the rename dialog does with the selected entry, or with its directory
+#[derive(Clone, Copy)]
+enum Transfer {
+    RenameFile,
+    CopyFile,
+    RenameDir,
+    CopyDir,
+}
+
+impl Transfer {
+    fn is_dir(self) -> bool {
+        matches!(self, Self::RenameDir | Self::CopyDir)
+    }
+
+    /// Returns the indices of the new entries in the password list.
+    fn run(self, store: &mut PasswordStore, old_name: &str, new_name: &str) -> Result<Vec<usize>> {
+        match self {
+            Self::RenameFile => store.rename_file(old_name, new_name).map(|i| vec![i]),
+            Self::CopyFile => store.copy_file(old_name, new_name).map(|i| vec![i]),
+            Self::RenameDir => store.rename_dir(old_name, new_name),
+            Self::CopyDir => store.copy_dir(old_name, new_name),
+        }
+    }
+}
+
+fn do_transfer(ui: &mut Cursive, store: PasswordStoreType, transfer: Transfer) -> Result<()> {
+    let old_name = if transfer.is_dir() {
+        ui.find_name::<EditView>("old_name_input")
+            .unwrap()
+            .get_content()
+            .to_string()
+    } else {
+        ui.find_name::<TextView>("old_name_input")
+            .unwrap()
+            .get_content()
+            .source()
+            .to_owned()
+    };
 
     let new_name = ui
         .find_name::<EditView>("new_name_input")
         .unwrap()
         .get_content();
 
-    let res = store
-        .lock()?
-        .lock()?
-        .rename_file(old_name.source(), &new_name);
+    let res = transfer.run(&mut store.lock()?.lock()?, &old_name, &new_name);
     match res {
         Err(err) => {
             helpers::errorbox(ui, &err);
         }
-        Ok(index) => {
+        Ok(indices) if transfer.is_dir() => {
+            ui.pop_layer();
+            redo_search(ui, &store);
+            let message = match transfer {
+                Transfer::RenameDir => CATALOG.gettext("Moved {} passwords"),
+                _ => CATALOG.gettext("Copied {} passwords"),
+            };
+            ui.call_on_name("status_bar", |l: &mut TextView| {
+                l.set_content(message.replace("{}", &indices.len().to_string()));
+            });
+        }
+        Ok(indices) => {
             let mut l = ui
                 .find_name::<SelectView<pass::PasswordEntry>>("results")
                 .unwrap();
 
-            if let Some(delete_id) = l.selected_id() {
-                l.remove_item(delete_id);
+            if let Transfer::RenameFile = transfer {
+                if let Some(delete_id) = l.selected_id() {
+                    l.remove_item(delete_id);
+                }
             }
 
             let col = screen_width(ui);
             let store = store.lock()?;
-            let entry = &store.lock()?.passwords[index];
+            let entry = &store.lock()?.passwords[indices[0]];
             l.add_item(create_label(entry, col), entry.clone());
             l.sort_by_label();
 
@@ -499,7 +968,7 @@ fn do_rename_file(ui: &mut Cursive, store: PasswordStoreType) -> Result<()> {
     Ok(())
 }
 
-fn rename_file_dialog(ui: &mut Cursive, store: PasswordStoreType) {
+fn transfer_dialog(ui: &mut Cursive, store: PasswordStoreType, transfer: Transfer) {
     let sel = ui
         .find_name::<SelectView<pass::PasswordEntry>>("results")
         .unwrap()
@@ -511,22 +980,60 @@ fn rename_file_dialog(ui: &mut Cursive, store: PasswordStoreType) {
     let sel = sel.unwrap();
     let old_name = sel.name.clone();
 
+    let (title, button, old_label, new_label) = match transfer {
+        Transfer::RenameFile => (
+            CATALOG.gettext("Rename File"),
+            CATALOG.gettext("Rename"),
+            CATALOG.gettext("Old file name: "),
+            CATALOG.gettext("New file name: "),
+        ),
+        Transfer::CopyFile => (
+            CATALOG.gettext("Copy File"),
+            CATALOG.gettext("Copy"),
+            CATALOG.gettext("Old file name: "),
+            CATALOG.gettext("New file name: "),
+        ),
+        Transfer::RenameDir => (
+            CATALOG.gettext("Move Directory"),
+            CATALOG.gettext("Move"),
+            CATALOG.gettext("Old directory: "),
+            CATALOG.gettext("New directory: "),
+        ),
+        Transfer::CopyDir => (
+            CATALOG.gettext("Copy Directory"),
+            CATALOG.gettext("Copy"),
+            CATALOG.gettext("Old directory: "),
+            CATALOG.gettext("New directory: "),
+        ),
+    };
+
     let mut fields = LinearLayout::vertical();
     let mut old_name_fields = LinearLayout::horizontal();
     let mut new_name_fields = LinearLayout::horizontal();
 
     old_name_fields.add_child(
-        TextView::new(CATALOG.gettext("Old file name: "))
+        TextView::new(old_label)
             .with_name("old_name_name")
             .fixed_size((10_usize, 1_usize)),
     );
-    old_name_fields.add_child(
-        TextView::new(old_name)
-            .with_name("old_name_input")
-            .fixed_size((50_usize, 1_usize)),
-    );
+    if transfer.is_dir() {
+        // the directory of the selected entry
+        let old_dir = old_name.rsplit_once('/').map_or("", |(dir, _)| dir);
+        old_name_fields.add_child(
+            EditView::new()
+                .content(old_dir)
+                .with_name("old_name_input")
+                .fixed_size((50_usize, 1_usize)),
+        );
+    } else {
+        old_name_fields.add_child(
+            TextView::new(old_name)
+                .with_name("old_name_input")
+                .fixed_size((50_usize, 1_usize)),
+        );
+    }
     new_name_fields.add_child(
-        TextView::new(CATALOG.gettext("New file name: "))
+        TextView::new(new_label)
             .with_name("new_name_name")
             .fixed_size((10_usize, 1_usize)),
     );
@@ -541,9 +1048,9 @@ fn rename_file_dialog(ui: &mut Cursive, store: PasswordStoreType) {
     let store2 = store.clone();
 
     let d = Dialog::around(fields)
-        .title(CATALOG.gettext("Rename File"))
-        .button(CATALOG.gettext("Rename"), move |ui: &mut Cursive| {
-            if let Err(e) = do_rename_file(ui, store.clone()) {
+        .title(title)
+        .button(button, move |ui: &mut Cursive| {
+            if let Err(e) = do_transfer(ui, store.clone(), transfer) {
                 helpers::errorbox(ui, &e);
             }
         })
@@ -554,7 +1061,7 @@ fn rename_file_dialog(ui: &mut Cursive, store: PasswordStoreType) {
             s.pop_layer();
         })
         .on_event(Key::Enter, move |ui: &mut Cursive| {
-            if let Err(e) = do_rename_file(ui, store2.clone()) {
+            if let Err(e) = do_transfer(ui, store2.clone(), transfer) {
                 helpers::errorbox(ui, &e);
             }
         });
@@ -608,7 +1115,177 @@ fn new_password_save(
         }
     }
 
-    Ok(())
+    Ok(())
+}
+
+fn strength_text(strength: Strength) -> String {
+    match strength {
+        Strength::VeryWeak => CATALOG.gettext("Very weak"),
+        Strength::Weak => CATALOG.gettext("Weak"),
+        Strength::Reasonable => CATALOG.gettext("Reasonable"),
+        Strength::Strong => CATALOG.gettext("Strong"),
+        Strength::VeryStrong => CATALOG.gettext("Very strong"),
+    }
+    .to_string()
+}
+
+fn generator_from_dialog(ui: &mut Cursive) -> Result<PasswordGenerator> {
+    let length = get_value_from_input(ui, "generate_length_input")
+        .and_then(|l| l.trim().parse::<usize>().ok())
+        .ok_or(pass::Error::Generic("the length must be a number"))?;
+
+    if is_checkbox_checked(ui, "generate_words_input") {
+        let separator = get_value_from_input(ui, "generate_separator_input")
+            .map(|s| s.to_string())
+            .unwrap_or_default();
+        let capitalization = ui
+            .call_on_name(
+                "generate_capitalization_input",
+                |l: &mut SelectView<Capitalization>| l.selection().map(|c| *c),
+            )
+            .flatten()
+            .unwrap_or_default();
+
+        Ok(PasswordGenerator::Words {
+            number_of_words: length,
+            separator,
+            capitalization,
+        })
+    } else {
+        Ok(PasswordGenerator::Characters {
+            length,
+            classes: CharacterClasses {
+                lowercase: is_checkbox_checked(ui, "generate_lowercase_input"),
+                uppercase: is_checkbox_checked(ui, "generate_uppercase_input"),
+                digits: is_checkbox_checked(ui, "generate_digits_input"),
+                symbols: is_checkbox_checked(ui, "generate_symbols_input"),
+                exclude_ambiguous: is_checkbox_checked(ui, "generate_ambiguous_input"),
+            },
+        })
+    }
+}
+
+fn generate_preview(ui: &mut Cursive) {
+    let generated = generator_from_dialog(ui).and_then(|g| g.generate());
+
+    let (password, strength) = match generated {
+        Ok(generated) => (
+            generated.password().to_owned(),
+            format!(
+                "{} ({:.0} {})",
+                strength_text(generated.strength()),
+                generated.entropy(),
+                CATALOG.gettext("bits of entropy")
+            ),
+        ),
+        Err(err) => (String::new(), err.to_string()),
+    };
+
+    ui.call_on_name("generate_preview", |l: &mut TextView| {
+        l.set_content(password);
+    });
+    ui.call_on_name("generate_strength", |l: &mut TextView| {
+        l.set_content(strength);
+    });
+}
+
+/// Shows a dialog where the user can configure and try out the password generator,
+/// `on_use` is called with the generated password if the user chooses to use it.
+fn generate_dialog<F>(ui: &mut Cursive, on_use: F)
+where
+    F: Fn(&mut Cursive, &str) + Send + Sync + 'static,
+{
+    let labeled = |label: &str, view: Box<dyn View>| {
+        LinearLayout::horizontal()
+            .child(TextView::new(label).fixed_size((20_usize, 1_usize)))
+            .child(view)
+    };
+    let checkbox = |name: &str, checked: bool| {
+        let mut c = Checkbox::new();
+        c.set_checked(checked);
+        Box::new(c.with_name(name)) as Box<dyn View>
+    };
+
+    let mut capitalization = SelectView::new().popup();
+    capitalization.add_item(CATALOG.gettext("lowercase"), Capitalization::Lowercase);
+    capitalization.add_item(CATALOG.gettext("Capitalized"), Capitalization::Capitalized);
+    capitalization.add_item(CATALOG.gettext("UPPERCASE"), Capitalization::Uppercase);
+    capitalization.add_item(CATALOG.gettext("Random"), Capitalization::Random);
+
+    let fields = LinearLayout::vertical()
+        .child(labeled(
+            CATALOG.gettext("Length: "),
+            Box::new(
+                EditView::new()
+                    .content("20")
+                    .on_edit(|ui, _, _| generate_preview(ui))
+                    .with_name("generate_length_input")
+                    .fixed_size((10_usize, 1_usize)),
+            ),
+        ))
+        .child(labeled(
+            CATALOG.gettext("Lowercase: "),
+            checkbox("generate_lowercase_input", true),
+        ))
+        .child(labeled(
+            CATALOG.gettext("Uppercase: "),
+            checkbox("generate_uppercase_input", true),
+        ))
+        .child(labeled(
+            CATALOG.gettext("Digits: "),
+            checkbox("generate_digits_input", true),
+        ))
+        .child(labeled(
+            CATALOG.gettext("Symbols: "),
+            checkbox("generate_symbols_input", true),
+        ))
+        .child(labeled(
+            CATALOG.gettext("No ambiguous: "),
+            checkbox("generate_ambiguous_input", false),
+        ))
+        .child(labeled(
+            CATALOG.gettext("Use words: "),
+            checkbox("generate_words_input", false),
+        ))
+        .child(labeled(
+            CATALOG.gettext("Separator: "),
+            Box::new(
+                EditView::new()
+                    .content("-")
+                    .with_name("generate_separator_input")
+                    .fixed_size((10_usize, 1_usize)),
+            ),
+        ))
+        .child(labeled(
+            CATALOG.gettext("Capitalization: "),
+            Box::new(capitalization.with_name("generate_capitalization_input")),
+        ))
+        .child(TextView::new("").with_name("generate_preview"))
+        .child(TextView::new("").with_name("generate_strength"));
+
+    let d = Dialog::around(fields)
+        .title(CATALOG.gettext("Generate password"))
+        .button(CATALOG.gettext("Generate"), generate_preview)
+        .button(CATALOG.gettext("Use"), move |ui| {
+            let password = ui
+                .call_on_name("generate_preview", |l: &mut TextView| {
+                    l.get_content().source().to_owned()
+                })
+                .unwrap_or_default();
+            if password.is_empty() {
+                return;
+            }
+            ui.pop_layer();
+            on_use(ui, &password);
+        })
+        .dismiss_button(CATALOG.gettext("Cancel"));
+
+    let ev = OnEventView::new(d).on_event(Key::Esc, |s| {
+        s.pop_layer();
+    });
+
+    ui.add_layer(ev);
+    generate_preview(ui);
 }
 
 fn create_save(s: &mut Cursive, store: PasswordStoreType) {
@@ -693,9 +1370,10 @@ fn create(ui: &mut Cursive, store: PasswordStoreType) {
     let d = Dialog::around(fields)
         .title(CATALOG.gettext("Add new password"))
         .button(CATALOG.gettext("Generate"), move |s| {
-            let new_password = ripasso::words::generate_password(6);
-            s.call_on_name("new_password_input", |e: &mut EditView| {
-                e.set_content(new_password);
+            generate_dialog(s, |s, new_password| {
+                s.call_on_name("new_password_input", |e: &mut EditView| {
+                    e.set_content(new_password);
+                });
             });
         })
         .button(CATALOG.gettext("Save"), move |ui: &mut Cursive| {
@@ -742,20 +1420,46 @@ fn delete_recipient(ui: &mut Cursive, store: PasswordStoreType) -> Result<()> {
     }
 }
 
+/// Returns the number of passwords that will be re-encrypted if the selected recipient is removed
+fn delete_recipient_reencrypt_count(ui: &mut Cursive, store: &PasswordStoreType) -> Result<usize> {
+    let l = ui
+        .find_name::<SelectView<Option<(PathBuf, pass::Recipient)>>>("recipients")
+        .unwrap();
+    let sel = l.selection();
+
+    let Some(Some((path, _))) = sel.as_deref() else {
+        return Err(crate::pass::Error::Generic("Selection is empty"));
+    };
+
+    let store = store.lock()?;
+    let store = store.lock()?;
+    Ok(store.remove_recipient_dry_run(path)?.len())
+}
+
 fn delete_recipient_verification(ui: &mut Cursive, store: PasswordStoreType) {
+    let mut question = CATALOG
+        .gettext("Are you sure you want to remove this person?")
+        .to_string();
+    if let Ok(count) = delete_recipient_reencrypt_count(ui, &store) {
+        question.push('\n');
+        question.push_str(
+            &CATALOG
+                .gettext("This will re-encrypt {} passwords.")
+                .replace("{}", &count.to_string()),
+        );
+    }
+
     ui.add_layer(CircularFocus::new(
-        Dialog::around(TextView::new(
-            CATALOG.gettext("Are you sure you want to remove this person?"),
-        ))
-        .button(CATALOG.gettext("Yes"), move |ui: &mut Cursive| {
-            let res = delete_recipient(ui, store.clone());
-            if let Err(err) = res {
-                helpers::errorbox(ui, &err)
-            } else {
-                ui.pop_layer();
-            }
-        })
-        .dismiss_button(CATALOG.gettext("Cancel")),
+        Dialog::around(TextView::new(question))
+            .button(CATALOG.gettext("Yes"), move |ui: &mut Cursive| {
+                let res = delete_recipient(ui, store.clone());
+                if let Err(err) = res {
+                    helpers::errorbox(ui, &err)
+                } else {
+                    ui.pop_layer();
+                }
+            })
+            .dismiss_button(CATALOG.gettext("Cancel")),
     ));
 }
 
@@ -769,9 +1473,23 @@ fn add_recipient(ui: &mut Cursive, store: PasswordStoreType, config_path: &Path)
     match recipient_from_res {
         Err(err) => helpers::errorbox(ui, &err),
         Ok(recipient) => {
-            if recipient.trust_level != OwnerTrustLevel::Ultimate {
-                helpers::errorbox(ui, &pass::Error::Generic(CATALOG.gettext("Can't import team member due to that the GPG trust relationship level isn't Ultimate")));
-                return Ok(());
+            match store.get_crypto().implementation() {
+                CryptoImpl::GpgMe if recipient.trust_level != OwnerTrustLevel::Ultimate => {
+                    helpers::errorbox(ui, &pass::Error::Generic(CATALOG.gettext("Can't import team member due to that the GPG trust relationship level isn't Ultimate")));
+                    return Ok(());
+                }
+                // sequoia computes the validity from the web of trust, the key must be
+                // certified by you or by enough trusted introducers
+                CryptoImpl::Sequoia
+                    if !matches!(
+                        recipient.trust_level,
+                        OwnerTrustLevel::Ultimate | OwnerTrustLevel::Full
+                    ) =>
+                {
+                    helpers::errorbox(ui, &pass::Error::Generic(CATALOG.gettext("Can't import team member due to that the key isn't certified by you or by trusted introducers")));
+                    return Ok(());
+                }
+                _ => {}
             }
 
             let dir_path = std::path::PathBuf::from(dir);
@@ -800,7 +1518,12 @@ fn add_recipient(ui: &mut Cursive, store: PasswordStoreType, config_path: &Path)
                                 )
                                 .unwrap();
                             recipients_view.add_item(
-                                render_recipient_label(&recipient, max_width_key, max_width_name),
+                                render_recipient_label(
+                                    &recipient,
+                                    store.get_key_expiry_warning_days(),
+                                    max_width_key,
+                                    max_width_name,
+                                ),
                                 Some((dir_path, recipient)),
                             );
 
@@ -869,13 +1592,44 @@ fn add_recipient_dialog(ui: &mut Cursive, store: PasswordStoreType, config_path:
     ui.add_layer(ev);
 }
 
+fn render_key_warning(warning: &KeyWarning) -> String {
+    match warning {
+        KeyWarning::Revoked(reason) => format!("{} {reason}", CATALOG.gettext("Revoked:")),
+        KeyWarning::Expired => CATALOG.gettext("Expired").to_owned(),
+        KeyWarning::NoEncryptionSubkey => CATALOG.gettext("No encryption subkey").to_owned(),
+        KeyWarning::ExpiresSoon(time) => {
+            format!("{} {}", CATALOG.gettext("Expires"), time.format("%Y-%m-%d"))
+        }
+        _ => warning.to_string(),
+    }
+}
+
+/// One line for every recipient in the store that can't, or soon can't, be encrypted to.
+fn render_recipient_warnings(warnings: &[(Recipient, KeyWarning)]) -> String {
+    warnings
+        .iter()
+        .map(|(recipient, warning)| {
+            format!(
+                "⚠️  {} {}: {}",
+                &recipient.key_id,
+                &recipient.name,
+                render_key_warning(warning)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
 fn render_recipient_label(
     recipient: &pass::Recipient,
+    key_expiry_warning_days: u32,
     max_width_key: usize,
     max_width_name: usize,
 ) -> String {
-    let symbol = match &recipient.key_ring_status {
-        pass::KeyRingStatus::NotInKeyRing => "⚠️ ",
+    let warning = recipient.key_warning(Local::now(), key_expiry_warning_days);
+
+    let symbol = match (&recipient.key_ring_status, &warning) {
+        (pass::KeyRingStatus::NotInKeyRing, _) | (_, Some(_)) => "⚠️ ",
         _ => "  ️",
     };
 
@@ -901,10 +1655,10 @@ fn render_recipient_label(
         },
         width_key = max_width_key,
         width_name = max_width_name
-    )
+    ) + &warning.map_or(String::new(), |w| render_key_warning(&w))
 }
 
-fn get_sub_dirs(dir: &PathBuf) -> Result<Vec<PathBuf>> {
+fn get_sub_dirs(dir: &PathBuf, recipients_file_name: &str) -> Result<Vec<PathBuf>> {
     let mut to_visit = vec![dir.clone()];
     let mut all = vec![PathBuf::from("./")];
     while let Some(d) = to_visit.pop() {
@@ -913,7 +1667,7 @@ fn get_sub_dirs(dir: &PathBuf) -> Result<Vec<PathBuf>> {
             let path = entry.path();
             if path.is_dir() && path.file_name() != Some(std::ffi::OsStr::new(".git")) {
                 to_visit.push(path.clone());
-                if path.join(".gpg-id").exists() {
+                if path.join(recipients_file_name).exists() {
                     all.push(path.strip_prefix(dir)?.to_path_buf());
                 }
             }
@@ -924,7 +1678,11 @@ fn get_sub_dirs(dir: &PathBuf) -> Result<Vec<PathBuf>> {
 }
 
 fn view_recipients(ui: &mut Cursive, store: PasswordStoreType, config_path: &Path) -> Result<()> {
-    let sub_dirs = get_sub_dirs(&store.lock()?.lock()?.get_store_path());
+    let sub_dirs = {
+        let store = store.lock()?;
+        let store = store.lock()?;
+        get_sub_dirs(&store.get_store_path(), store.recipients_file_name())
+    };
     if let Err(err) = sub_dirs {
         helpers::errorbox(ui, &err);
         return Ok(());
@@ -945,7 +1703,7 @@ fn view_recipients(ui: &mut Cursive, store: PasswordStoreType, config_path: &Pat
                 path_to_recipients.insert(dir.clone(), recipients_res.unwrap());
             }
 
-            view_recipients_for_many_dirs(ui, store, path_to_recipients, config_path);
+            view_recipients_for_many_dirs(ui, store, path_to_recipients, config_path)?;
         }
         std::cmp::Ordering::Equal => {
             do_view_recipients_for_dir(ui, store, sub_dirs[0].clone(), config_path);
@@ -970,7 +1728,10 @@ fn view_recipients_for_many_dirs(
     store: PasswordStoreType,
     path_to_recipients: HashMap<PathBuf, Vec<Recipient>>,
     config_path: &Path,
-) {
+) -> Result<()> {
+    let days = store.lock()?.lock()?.get_key_expiry_warning_days();
+    let warnings = store.lock()?.lock()?.recipient_warnings()?;
+
     let mut recipients_view = SelectView::<Option<(PathBuf, pass::Recipient)>>::new()
         .h_align(cursive::align::HAlign::Left)
         .with_name("recipients");
@@ -991,7 +1752,7 @@ fn view_recipients_for_many_dirs(
         }
         for recipient in recipients {
             recipients_view.get_mut().add_item(
-                render_recipient_label(recipient, max_width_key, max_width_name),
+                render_recipient_label(recipient, days, max_width_key, max_width_name),
                 Some((path.to_path_buf(), recipient.clone())),
             );
         }
@@ -1000,7 +1761,11 @@ fn view_recipients_for_many_dirs(
         .title(CATALOG.gettext("Team Members"))
         .dismiss_button(CATALOG.gettext("Ok"));
 
-    let ll = LinearLayout::new(Orientation::Vertical).child(d).child(
+    let mut ll = LinearLayout::new(Orientation::Vertical).child(d);
+    if !warnings.is_empty() {
+        ll.add_child(TextView::new(render_recipient_warnings(&warnings)));
+    }
+    ll.add_child(
         LinearLayout::new(Orientation::Horizontal)
             .child(TextView::new(CATALOG.gettext("ins: Add | ")))
             .child(TextView::new(CATALOG.gettext("del: Remove"))),
@@ -1021,6 +1786,8 @@ fn view_recipients_for_many_dirs(
         });
 
     ui.add_layer(recipients_event);
+
+    Ok(())
 }
 
 fn do_view_recipients_for_dir(
@@ -1049,6 +1816,8 @@ fn view_recipients_for_dir(
         return Ok(());
     }
     let recipients = recipients_res.unwrap();
+    let days = store.lock()?.lock()?.get_key_expiry_warning_days();
+    let warnings = store.lock()?.lock()?.recipient_warnings()?;
 
     let mut recipients_view = SelectView::<Option<(PathBuf, pass::Recipient)>>::new()
         .h_align(cursive::align::HAlign::Left)
@@ -1066,7 +1835,7 @@ fn view_recipients_for_dir(
     }
     for recipient in recipients {
         recipients_view.get_mut().add_item(
-            render_recipient_label(&recipient, max_width_key, max_width_name),
+            render_recipient_label(&recipient, days, max_width_key, max_width_name),
             Some((dir.clone(), recipient)),
         );
     }
@@ -1075,7 +1844,11 @@ fn view_recipients_for_dir(
         .title(CATALOG.gettext("Team Members"))
         .dismiss_button(CATALOG.gettext("Ok"));
 
-    let ll = LinearLayout::new(Orientation::Vertical).child(d).child(
+    let mut ll = LinearLayout::new(Orientation::Vertical).child(d);
+    if !warnings.is_empty() {
+        ll.add_child(TextView::new(render_recipient_warnings(&warnings)));
+    }
+    ll.add_child(
         LinearLayout::new(Orientation::Horizontal)
             .child(TextView::new(CATALOG.gettext("ins: Add | ")))
             .child(TextView::new(CATALOG.gettext("del: Remove"))),
@@ -1150,7 +1923,6 @@ fn search(store: &PasswordStoreType, ui: &mut Cursive, query: &str) -> Result<()
     for p in &r {
         l.add_item(create_label(p, col), p.clone());
     }
-    l.sort_by_label();
 
     Ok(())
 }
@@ -1167,40 +1939,206 @@ fn help() {
 }
 
 fn do_git_push(ui: &mut Cursive, store: PasswordStoreType) {
-    let res = git_push(ui, store);
-    if let Err(err) = res {
-        helpers::errorbox(ui, &err);
-    }
+    run_with_credentials(
+        ui,
+        move |credentials| push(&*store.lock()?.lock()?, credentials),
+        |ui, res| match res {
+            Err(err) => helpers::errorbox(ui, &err),
+            Ok(()) => {
+                ui.call_on_name("status_bar", |l: &mut TextView| {
+                    l.set_content(CATALOG.gettext("Pushed to remote git repository"));
+                });
+            }
+        },
+    );
 }
 
-fn git_push(ui: &mut Cursive, store: PasswordStoreType) -> Result<()> {
-    let push_result = push(&*store.lock()?.lock()?);
-    match push_result {
-        Err(err) => helpers::errorbox(ui, &err),
-        Ok(_) => {
-            ui.call_on_name("status_bar", |l: &mut TextView| {
-                l.set_content(CATALOG.gettext("Pushed to remote git repository"));
-            });
+fn do_git_push_all(ui: &mut Cursive, store: PasswordStoreType) {
+    run_with_credentials(
+        ui,
+        move |credentials| git::push_all(&*store.lock()?.lock()?, credentials),
+        |ui, res| match res {
+            Ok(statuses) => {
+                let lines: Vec<String> = statuses
+                    .iter()
+                    .map(|status| match &status.result {
+                        Ok(()) => format!("{}: {}", status.remote, CATALOG.gettext("pushed")),
+                        Err(err) => format!("{}: {err}", status.remote),
+                    })
+                    .collect();
+                remote_status_dialog(ui, CATALOG.gettext("Push to All Remotes"), &lines);
+            }
+            Err(err) => helpers::errorbox(ui, &err),
+        },
+    );
+}
+
+fn do_git_fetch_all(ui: &mut Cursive, store: PasswordStoreType) {
+    run_with_credentials(
+        ui,
+        move |credentials| git::fetch_all(&*store.lock()?.lock()?, credentials),
+        |ui, res| match res {
+            Ok(statuses) => {
+                let lines: Vec<String> = statuses
+                    .iter()
+                    .map(|status| match &status.result {
+                        Ok(0) => format!("{}: {}", status.remote, CATALOG.gettext("up to date")),
+                        Ok(new_commits) => format!(
+                            "{}: {}",
+                            status.remote,
+                            CATALOG
+                                .gettext("{} new commits")
+                                .replace("{}", &new_commits.to_string())
+                        ),
+                        Err(err) => format!("{}: {err}", status.remote),
+                    })
+                    .collect();
+                remote_status_dialog(ui, CATALOG.gettext("Fetch from All Remotes"), &lines);
+            }
+            Err(err) => helpers::errorbox(ui, &err),
+        },
+    );
+}
+
+/// Shows how syncing went with each of the remotes.
+fn remote_status_dialog(ui: &mut Cursive, title: &str, lines: &[String]) {
+    let d = Dialog::around(TextView::new(lines.join("\n")))
+        .dismiss_button(CATALOG.gettext("Ok"))
+        .title(title);
+
+    let ev = OnEventView::new(d).on_event(Key::Esc, |s| {
+        s.pop_layer();
+    });
+
+    ui.add_layer(ev);
+}
+
+fn do_git_pull(ui: &mut Cursive, store: PasswordStoreType) {
+    let pull_store = store.clone();
+    run_with_credentials(
+        ui,
+        move |credentials| pull(&*pull_store.lock()?.lock()?, credentials),
+        move |ui, res| {
+            if let Err(err) = git_pull_done(ui, store.clone(), res) {
+                helpers::errorbox(ui, &err);
+            }
+        },
+    );
+}
+
+fn git_pull_done(
+    ui: &mut Cursive,
+    store: PasswordStoreType,
+    res: Result<Vec<MergeConflict>>,
+) -> Result<()> {
+    match res {
+        Ok(conflicts) if !conflicts.is_empty() => {
+            merge_conflict_dialog(ui, store.clone(), Arc::new(Mutex::new(conflicts)), 0);
+            return Ok(());
         }
+        Ok(_) => {}
+        Err(err) => helpers::errorbox(ui, &err),
     }
-    Ok(())
+
+    reload_after_pull(ui, store)
 }
 
-fn do_git_pull(ui: &mut Cursive, store: PasswordStoreType) {
-    let res = git_pull(ui, store);
+/// Lets the user edit the entries that both sides of a pull changed, one at a time, and
+/// finishes the pull when the last one has been saved.
+fn merge_conflict_dialog(
+    ui: &mut Cursive,
+    store: PasswordStoreType,
+    conflicts: Arc<Mutex<Vec<MergeConflict>>>,
+    index: usize,
+) {
+    let (title, merged) = {
+        let conflicts = conflicts.lock().unwrap();
+        (
+            CATALOG
+                .gettext("Merge conflict in {}")
+                .replace("{}", &conflicts[index].path.to_string_lossy()),
+            conflicts[index].merged.clone(),
+        )
+    };
+
+    let fields = LinearLayout::vertical()
+        .child(TextView::new(CATALOG.gettext(
+            "Both you and the remote repository changed this entry, edit it to what it should contain:",
+        )))
+        .child(
+            TextArea::new()
+                .content(merged)
+                .with_name("merge_conflict_text_area")
+                .min_size((50, 10)),
+        );
+
+    let abort_store = store.clone();
+    let esc_store = store.clone();
+    let d = Dialog::around(fields)
+        .title(title)
+        .button(CATALOG.gettext("Save"), move |s| {
+            let content = s
+                .call_on_name("merge_conflict_text_area", |e: &mut TextArea| {
+                    e.get_content().to_owned()
+                })
+                .unwrap();
+            s.pop_layer();
+
+            let remaining = {
+                let mut conflicts = conflicts.lock().unwrap();
+                conflicts[index].merged.zeroize();
+                conflicts[index].merged = content;
+                conflicts.len() - index - 1
+            };
+            if remaining > 0 {
+                merge_conflict_dialog(s, store.clone(), conflicts.clone(), index + 1);
+                return;
+            }
+
+            let res = || -> Result<()> {
+                git::resolve_conflicts(&*store.lock()?.lock()?, &conflicts.lock().unwrap())?;
+                reload_after_pull(s, store.clone())
+            }();
+            if let Err(err) = res {
+                helpers::errorbox(s, &err);
+            }
+        })
+        .button(CATALOG.gettext("Abort Pull"), move |s| {
+            abort_pull(s, &abort_store);
+        });
+
+    let ev = OnEventView::new(d).on_event(Key::Esc, move |s| {
+        abort_pull(s, &esc_store);
+    });
+
+    ui.add_layer(ev);
+}
+
+fn abort_pull(ui: &mut Cursive, store: &PasswordStoreType) {
+    ui.pop_layer();
+    let res = || -> Result<()> { git::abort_merge(&*store.lock()?.lock()?) }();
     if let Err(err) = res {
         helpers::errorbox(ui, &err);
     }
 }
 
-fn git_pull(ui: &mut Cursive, store: PasswordStoreType) -> Result<()> {
-    let _ = pull(&*store.lock()?.lock()?).map_err(|err| helpers::errorbox(ui, &err));
+fn reload_after_pull(ui: &mut Cursive, store: PasswordStoreType) -> Result<()> {
     let _ = store
         .lock()?
         .lock()?
         .reload_password_list()
         .map_err(|err| helpers::errorbox(ui, &err));
 
+    show_password_list(ui, &store);
+    ui.call_on_name("status_bar", |l: &mut TextView| {
+        l.set_content(CATALOG.gettext("Pulled from remote git repository"));
+    });
+
+    Ok(())
+}
+
+/// Fills the password list with the passwords of the store.
+fn show_password_list(ui: &mut Cursive, store: &PasswordStoreType) {
     let col = screen_width(ui);
 
     ui.call_on_name(
@@ -1214,11 +2152,223 @@ fn git_pull(ui: &mut Cursive, store: PasswordStoreType) -> Result<()> {
             Ok(())
         },
     );
+}
+
+/// The sync services and the watchers of the stores. They are kept in the user data of the
+/// ui, so that the ones of a store can be restarted when the store is edited or added, and
+/// they stop when they are dropped.
+struct StoreServices {
+    current: PasswordStoreType,
+    sync_services: Vec<(Arc<Mutex<PasswordStore>>, SyncService)>,
+    watchers: Vec<(Arc<Mutex<PasswordStore>>, StoreWatcher)>,
+}
+
+/// Starts a sync service and a watcher for every store, see `start_sync_service` and
+/// `start_watcher`.
+fn start_store_services(
+    ui: &mut Cursive,
+    stores: &StoreListType,
+    current: &PasswordStoreType,
+) -> Result<StoreServices> {
+    let mut services = StoreServices {
+        current: current.clone(),
+        sync_services: vec![],
+        watchers: vec![],
+    };
+    #[allow(clippy::significant_drop_in_scrutinee)]
+    for store in stores.lock()?.iter() {
+        if let Some(service) = start_sync_service(ui, store, current)? {
+            services.sync_services.push((store.clone(), service));
+        }
+        services
+            .watchers
+            .push((store.clone(), start_watcher(ui, store, current)?));
+    }
+    Ok(services)
+}
+
+/// Stops the sync service and the watcher of `store`, if it has any, and starts new ones for
+/// what the store is now, like after its directory has been changed. This is also how a store
+/// that was added gets them.
+fn restart_store_services(ui: &mut Cursive, store: &Arc<Mutex<PasswordStore>>) -> Result<()> {
+    let Some(services) = ui.user_data::<StoreServices>() else {
+        return Ok(());
+    };
+    // the old ones have to be stopped first, since a sync service disconnects the store
+    // from itself when it stops
+    services
+        .sync_services
+        .retain(|(synced_store, _)| !Arc::ptr_eq(synced_store, store));
+    services
+        .watchers
+        .retain(|(watched_store, _)| !Arc::ptr_eq(watched_store, store));
+    let current = services.current.clone();
+
+    let service = start_sync_service(ui, store, &current)?;
+    let watcher = start_watcher(ui, store, &current)?;
+    if let Some(services) = ui.user_data::<StoreServices>() {
+        if let Some(service) = service {
+            services.sync_services.push((store.clone(), service));
+        }
+        services.watchers.push((store.clone(), watcher));
+    }
+
+    // the password list of a replaced store is read again, so the list that is shown has to
+    // follow if it's the current store
+    let is_current = Arc::ptr_eq(&*current.lock()?, store);
+    if is_current {
+        redo_search(ui, &current);
+    }
+    Ok(())
+}
+
+/// Starts a sync service for the store if it has a sync interval. What it does is shown on
+/// the status bar, and the password list is redrawn when the current store gets new commits.
+fn start_sync_service(
+    ui: &mut Cursive,
+    synced_store: &Arc<Mutex<PasswordStore>>,
+    current: &PasswordStoreType,
+) -> Result<Option<SyncService>> {
+    let Some(interval) = synced_store.lock()?.get_sync_interval() else {
+        return Ok(None);
+    };
+    let (service, events) = SyncService::start(synced_store.clone(), interval)?;
+
+    let cb_sink = ui.cb_sink().clone();
+    let synced_store = synced_store.clone();
+    let current = current.clone();
+    thread::spawn(move || {
+        for event in events {
+            let synced_store = synced_store.clone();
+            let current = current.clone();
+            let res = cb_sink.send(Box::new(move |ui: &mut Cursive| {
+                show_sync_event(ui, &current, &synced_store, event);
+            }));
+            if res.is_err() {
+                break;
+            }
+        }
+    });
+    Ok(Some(service))
+}
+
+fn show_sync_event(
+    ui: &mut Cursive,
+    current: &PasswordStoreType,
+    synced_store: &Arc<Mutex<PasswordStore>>,
+    event: SyncEvent,
+) {
+    let is_current = current
+        .lock()
+        .is_ok_and(|current| Arc::ptr_eq(&*current, synced_store));
+    if !is_current {
+        return;
+    }
+
+    let message = match event {
+        SyncEvent::Syncing => CATALOG
+            .gettext("Syncing with remote git repository")
+            .to_owned(),
+        SyncEvent::UpToDate => CATALOG
+            .gettext("Up to date with remote git repository")
+            .to_owned(),
+        SyncEvent::Updated => {
+            show_password_list(ui, current);
+            CATALOG
+                .gettext("Pulled from remote git repository")
+                .to_owned()
+        }
+        SyncEvent::Pushed => CATALOG
+            .gettext("Pushed to remote git repository")
+            .to_owned(),
+        SyncEvent::Conflicted => CATALOG
+            .gettext("Both you and the remote git repository changed the same entries, pull to resolve it")
+            .to_owned(),
+        SyncEvent::Failed(err) => CATALOG
+            .gettext("Syncing failed: {}")
+            .replace("{}", &err.to_string()),
+        _ => return,
+    };
     ui.call_on_name("status_bar", |l: &mut TextView| {
-        l.set_content(CATALOG.gettext("Pulled from remote git repository"));
+        l.set_content(message);
     });
+}
 
-    Ok(())
+/// Starts watching the directory of the store, so that its password list follows changes
+/// that are made outside of ripasso. The password list is updated when the current store
+/// changes.
+fn start_watcher(
+    ui: &mut Cursive,
+    watched_store: &Arc<Mutex<PasswordStore>>,
+    current: &PasswordStoreType,
+) -> Result<StoreWatcher> {
+    let (watcher, changes) = StoreWatcher::start(watched_store.clone())?;
+
+    let cb_sink = ui.cb_sink().clone();
+    let watched_store = watched_store.clone();
+    let current = current.clone();
+    thread::spawn(move || {
+        for change in changes {
+            let watched_store = watched_store.clone();
+            let current = current.clone();
+            let res = cb_sink.send(Box::new(move |ui: &mut Cursive| {
+                show_password_change(ui, &current, &watched_store, change);
+            }));
+            if res.is_err() {
+                break;
+            }
+        }
+    });
+    Ok(watcher)
+}
+
+fn show_password_change(
+    ui: &mut Cursive,
+    current: &PasswordStoreType,
+    watched_store: &Arc<Mutex<PasswordStore>>,
+    change: PasswordChange,
+) {
+    let is_current = current
+        .lock()
+        .is_ok_and(|current| Arc::ptr_eq(&*current, watched_store));
+    if !is_current {
+        return;
+    }
+
+    if let PasswordChange::Removed(entry) = change {
+        ui.call_on_name("results", |l: &mut SelectView<pass::PasswordEntry>| {
+            if let Some(id) = l.iter().position(|(_, p)| p.path == entry.path) {
+                l.remove_item(id);
+            }
+        });
+        return;
+    }
+
+    // the new entry is placed where the search puts it
+    redo_search(ui, current);
+}
+
+/// Runs the search of the search box again, after the password list has changed, and keeps
+/// the selected entry selected.
+fn redo_search(ui: &mut Cursive, store: &PasswordStoreType) {
+    let selected = ui
+        .find_name::<SelectView<pass::PasswordEntry>>("results")
+        .and_then(|l| l.selection())
+        .map(|p| p.path.clone());
+    let query = ui
+        .find_name::<EditView>("search_box")
+        .map(|e| e.get_content())
+        .unwrap_or_default();
+    do_search(store, ui, &query);
+
+    ui.call_on_name("results", |l: &mut SelectView<pass::PasswordEntry>| {
+        let id = l
+            .iter()
+            .position(|(_, p)| Some(&p.path) == selected.as_ref());
+        if let Some(id) = id {
+            l.set_selection(id);
+        }
+    });
 }
 
 fn do_gpg_import(ui: &mut Cursive, store: PasswordStoreType, config_path: &Path) -> Result<()> {
@@ -1286,7 +2436,7 @@ fn do_gpg_pull(ui: &mut Cursive, store: PasswordStoreType, config_path: &Path) -
 fn pgp_pull(ui: &mut Cursive, store: PasswordStoreType, config_path: &Path) {
     let config_path = config_path.to_owned();
     let d = Dialog::around(TextView::new(CATALOG.gettext(
-        "Download pgp data from keys.openpgp.org and import them into your key ring?",
+        "Download the pgp keys of the team members from the key sources of the store and import them into your key ring?",
     )))
     .dismiss_button(CATALOG.gettext("Cancel"))
     .button(CATALOG.gettext("Download"), move |ui| {
@@ -1428,7 +2578,24 @@ fn get_stores(config: &config::Config, home: &Option<PathBuf>) -> Result<Vec<Pas
                     },
                 };
 
-                final_stores.push(PasswordStore::new(
+                let clipboard_timeout = store
+                    .get("clipboard_timeout")
+                    .and_then(|t| t.clone().into_int().ok())
+                    .and_then(|t| u64::try_from(t).ok());
+                let key_cache_timeout = store
+                    .get("key_cache_timeout")
+                    .and_then(|t| t.clone().into_int().ok())
+                    .and_then(|t| u64::try_from(t).ok());
+                let key_expiry_warning_days = store
+                    .get("key_expiry_warning_days")
+                    .and_then(|t| t.clone().into_int().ok())
+                    .and_then(|t| u32::try_from(t).ok());
+                let sync_interval = store
+                    .get("sync_interval")
+                    .and_then(|t| t.clone().into_int().ok())
+                    .and_then(|t| u64::try_from(t).ok());
+
+                let mut password_store = PasswordStore::new(
                     store_name,
                     &password_store_dir,
                     &valid_signing_keys,
@@ -1436,7 +2603,29 @@ fn get_stores(config: &config::Config, home: &Option<PathBuf>) -> Result<Vec<Pas
                     &style_path_opt,
                     &pgp_impl,
                     &own_fingerprint,
-                )?);
+                )?;
+                password_store.set_clipboard_timeout(clipboard_timeout);
+                password_store.set_key_cache_timeout(key_cache_timeout);
+                password_store.set_key_expiry_warning_days(key_expiry_warning_days);
+                password_store.set_sync_interval(sync_interval);
+                if let Some(pull_strategy) = store.get("pull_strategy") {
+                    password_store.set_pull_strategy(pull_strategy.clone().into_str()?.parse()?);
+                }
+                if let Some(remotes) = store.get("remotes") {
+                    password_store
+                        .set_remotes(SyncRemote::parse_list(&remotes.clone().into_str()?)?);
+                }
+                if let Some(sources) = store.get("key_sources") {
+                    password_store
+                        .set_key_sources(KeySource::parse_list(&sources.clone().into_str()?)?);
+                }
+                if let Some(roots) = store.get("trust_roots") {
+                    // roots that aren't in the keyring are ignored, so that a missing key
+                    // doesn't make the whole store unusable
+                    let roots = parse_fingerprints(&roots.clone().into_str()?)?;
+                    password_store.set_trust_roots(roots);
+                }
+                final_stores.push(password_store);
             }
         }
     } else if final_stores.is_empty() && home.is_some() {
@@ -1478,9 +2667,11 @@ fn validate_stores_config(settings: &config::Config, home: &Option<PathBuf>) ->
 
             if let Some(p) = password_store_dir_opt {
                 let p_path = PathBuf::from(p.clone().into_str().unwrap());
-                let gpg_id = p_path.clone().join(".gpg-id");
+                let has_recipients = [CryptoImpl::GpgMe, CryptoImpl::Age]
+                    .iter()
+                    .any(|c| p_path.join(c.recipients_file_name()).exists());
 
-                if !p_path.exists() || !gpg_id.exists() {
+                if !p_path.exists() || !has_recipients {
                     incomplete_stores.push(PathBuf::from(p.clone().into_str().unwrap()));
                 }
             }
@@ -1515,8 +2706,10 @@ fn save_edit_config(
     let e_n = &*get_value_from_input(ui, "edit_name_input").unwrap();
     let e_d = &*get_value_from_input(ui, "edit_directory_input").unwrap();
     let e_k_bool = is_checkbox_checked(ui, "edit_keys_input");
-    let e_sequoia_selected = &is_radio_button_selected(ui, "edit_sequoia_button_name");
+    let e_sequoia_selected = is_radio_button_selected(ui, "edit_sequoia_button_name");
+    let e_age_selected = is_radio_button_selected(ui, "edit_age_button_name");
     let own_fingerprint = &*get_value_from_input(ui, "edit_own_fingerprint_input").unwrap();
+    let clipboard_timeout = &*get_value_from_input(ui, "edit_clipboard_timeout_input").unwrap();
 
     let e_k = if e_k_bool {
         let mut recipients: Vec<Recipient> = vec![];
@@ -1540,9 +2733,12 @@ fn save_edit_config(
         None
     };
 
-    let pgp_impl = match e_sequoia_selected {
-        true => CryptoImpl::Sequoia,
-        false => CryptoImpl::GpgMe,
+    let pgp_impl = if e_age_selected {
+        CryptoImpl::Age
+    } else if e_sequoia_selected {
+        CryptoImpl::Sequoia
+    } else {
+        CryptoImpl::GpgMe
     };
 
     let own_fingerprint = match <[u8; 20]>::from_hex(own_fingerprint) {
@@ -1550,6 +2746,13 @@ fn save_edit_config(
         Ok(fp) => Some(fp),
     };
 
+    let clipboard_timeout = match clipboard_timeout.trim() {
+        "" => None,
+        t => Some(t.parse::<u64>().map_err(|_| {
+            pass::Error::Generic("the clipboard timeout must be a number of seconds")
+        })?),
+    };
+
     let new_store = PasswordStore::new(
         e_n,
         &Some(PathBuf::from(e_d.clone())),
@@ -1563,22 +2766,51 @@ fn save_edit_config(
         helpers::errorbox(ui, &err);
         return Ok(());
     }
-    let new_store = new_store.unwrap();
+    let mut new_store = new_store.unwrap();
+    new_store.set_clipboard_timeout(clipboard_timeout);
+    new_store.set_passphrase_prompt(KEY_PASSPHRASES.clone());
 
     let l = ui.find_name::<SelectView<String>>("stores").unwrap();
 
     let sel = l.selection();
 
+    let mut edited_store = None;
     if sel.is_some() {
-        let mut stores_borrowed = stores.lock()?;
         #[allow(clippy::significant_drop_in_scrutinee)]
-        for (i, store) in stores_borrowed.iter().enumerate() {
-            if store.lock()?.get_name() == name {
-                stores_borrowed[i] = Arc::new(Mutex::new(new_store));
+        for store in stores.lock()?.iter() {
+            let mut store_borrowed = store.lock()?;
+            if store_borrowed.get_name() == name {
+                // the pull strategy, remotes, sync interval, key cache timeout, key expiry
+                // warning days, trust roots and key sources can only be changed in the config file
+                new_store.set_pull_strategy(store_borrowed.get_pull_strategy());
+                new_store.set_key_cache_timeout(store_borrowed.get_key_cache_timeout_setting());
+                new_store.set_key_expiry_warning_days(
+                    store_borrowed.get_key_expiry_warning_days_setting(),
+                );
+                new_store.set_remotes(store_borrowed.get_remotes().to_vec());
+                new_store.set_trust_roots(store_borrowed.get_trust_roots().to_vec());
+                new_store.set_key_sources(store_borrowed.get_key_sources().to_vec());
+                new_store.set_sync_interval(
+                    store_borrowed
+                        .get_sync_interval()
+                        .map(|interval| interval.as_secs()),
+                );
+                if let Err(err) = new_store.reload_password_list() {
+                    drop(store_borrowed);
+                    helpers::errorbox(ui, &err);
+                    return Ok(());
+                }
+                // the store is replaced in place, so that everything that holds on to it, like
+                // the current store, its sync service and its watcher, sees the new one
+                *store_borrowed = new_store;
+                edited_store = Some(store.clone());
                 break;
             }
         }
     }
+    if let Some(store) = edited_store {
+        restart_store_services(ui, &store)?;
+    }
 
     let save_res = pass::save_config(stores, config_file_location);
     if let Err(err) = save_res {
@@ -1619,10 +2851,9 @@ fn save_new_config(
         &None,
     )?;
 
-    {
-        let mut stores_borrowed = stores.lock()?;
-        stores_borrowed.push(Arc::new(Mutex::new(new_store)));
-    }
+    let new_store = Arc::new(Mutex::new(new_store));
+    stores.lock()?.push(new_store.clone());
+    restart_store_services(ui, &new_store)?;
 
     pass::save_config(stores, config_file_location)?;
 
@@ -1675,6 +2906,7 @@ fn edit_store_in_config(
     let mut keys_fields = LinearLayout::horizontal();
     let mut pgp_fields = LinearLayout::horizontal();
     let mut fingerprint_fields = LinearLayout::horizontal();
+    let mut clipboard_fields = LinearLayout::horizontal();
     name_fields.add_child(
         TextView::new(CATALOG.gettext("Name: "))
             .with_name("name_name")
@@ -1720,8 +2952,12 @@ fn edit_store_in_config(
     let sequoia_button = pgp_radio
         .button(CryptoImpl::Sequoia, "Sequoia")
         .with_name("edit_sequoia_button_name");
+    let age_button = pgp_radio
+        .button(CryptoImpl::Age, "age")
+        .with_name("edit_age_button_name");
     pgp_fields.add_child(gpg_me_button);
     pgp_fields.add_child(sequoia_button);
+    pgp_fields.add_child(age_button);
     fingerprint_fields.add_child(
         TextView::new(CATALOG.gettext("Own key fingerprint: "))
             .with_name("name_own_fingerprint")
@@ -1735,12 +2971,24 @@ fn edit_store_in_config(
             .with_name("edit_own_fingerprint_input")
             .fixed_size((50_usize, 1_usize)),
     );
+    clipboard_fields.add_child(
+        TextView::new(CATALOG.gettext("Clipboard timeout in seconds: "))
+            .with_name("name_clipboard_timeout")
+            .fixed_size((30_usize, 1_usize)),
+    );
+    clipboard_fields.add_child(
+        EditView::new()
+            .content(store.get_clipboard_timeout().as_secs().to_string())
+            .with_name("edit_clipboard_timeout_input")
+            .fixed_size((10_usize, 1_usize)),
+    );
 
     fields.add_child(name_fields);
     fields.add_child(directory_fields);
     fields.add_child(keys_fields);
     fields.add_child(pgp_fields);
     fields.add_child(fingerprint_fields);
+    fields.add_child(clipboard_fields);
 
     fields.add_child(
         TextView::new(CATALOG.gettext("Store Members: ")).fixed_size((30_usize, 1_usize)),
@@ -2108,9 +3356,13 @@ fn main() -> Result<()> {
         stores
             .unwrap()
             .into_iter()
-            .map(|s| Arc::new(Mutex::new(s)))
+            .map(|mut s| {
+                s.set_passphrase_prompt(KEY_PASSPHRASES.clone());
+                Arc::new(Mutex::new(s))
+            })
             .collect(),
     ));
+    let services_stores = stores.clone();
 
     if !config_file_location.exists() && stores.lock()?.len() == 1 {
         let mut config_file_dir = config_file_location.clone();
@@ -2209,7 +3461,7 @@ fn main() -> Result<()> {
     ui.add_global_callback(Event::CtrlChar('r'), {
         let store = store.clone();
         move |ui: &mut Cursive| {
-            rename_file_dialog(ui, store.clone());
+            transfer_dialog(ui, store.clone(), Transfer::RenameFile);
         }
     });
     ui.add_global_callback(Event::CtrlChar('f'), {
@@ -2320,7 +3572,25 @@ fn main() -> Result<()> {
             .leaf(CATALOG.gettext("Rename file (ctrl-r)"), {
                 let store = store.clone();
                 move |ui: &mut Cursive| {
-                    rename_file_dialog(ui, store.clone());
+                    transfer_dialog(ui, store.clone(), Transfer::RenameFile);
+                }
+            })
+            .leaf(CATALOG.gettext("Copy file"), {
+                let store = store.clone();
+                move |ui: &mut Cursive| {
+                    transfer_dialog(ui, store.clone(), Transfer::CopyFile);
+                }
+            })
+            .leaf(CATALOG.gettext("Move directory"), {
+                let store = store.clone();
+                move |ui: &mut Cursive| {
+                    transfer_dialog(ui, store.clone(), Transfer::RenameDir);
+                }
+            })
+            .leaf(CATALOG.gettext("Copy directory"), {
+                let store = store.clone();
+                move |ui: &mut Cursive| {
+                    transfer_dialog(ui, store.clone(), Transfer::CopyDir);
                 }
             })
             .leaf(CATALOG.gettext("Team Members (ctrl-v)"), {
@@ -2330,6 +3600,12 @@ fn main() -> Result<()> {
                     do_view_recipients(ui, store.clone(), &xdg_data_home);
                 }
             })
+            .leaf(CATALOG.gettext("Audit Passwords"), {
+                let store = store.clone();
+                move |ui: &mut Cursive| {
+                    audit_dialog(ui, store.clone());
+                }
+            })
             .delimiter()
             .leaf(CATALOG.gettext("Git Pull (ctrl-f)"), {
                 let store = store.clone();
@@ -2343,6 +3619,18 @@ fn main() -> Result<()> {
                     do_git_push(ui, store.clone());
                 }
             })
+            .leaf(CATALOG.gettext("Fetch from All Remotes"), {
+                let store = store.clone();
+                move |ui: &mut Cursive| {
+                    do_git_fetch_all(ui, store.clone());
+                }
+            })
+            .leaf(CATALOG.gettext("Push to All Remotes"), {
+                let store = store.clone();
+                move |ui: &mut Cursive| {
+                    do_git_push_all(ui, store.clone());
+                }
+            })
             .delimiter()
             .leaf(CATALOG.gettext("Pull PGP Certificates"), {
                 let store = store.clone();
@@ -2405,7 +3693,18 @@ fn main() -> Result<()> {
     thread::sleep(time::Duration::from_millis(200));
     do_search(&store, &mut ui, "");
 
+    match start_store_services(&mut ui, &services_stores, &store) {
+        Ok(services) => ui.set_user_data(services),
+        Err(err) => helpers::errorbox(&mut ui, &err),
+    }
+
     ui.run();
+
+    // the sync services and the watchers stop when they are dropped
+    drop(ui.take_user_data::<StoreServices>());
+
+    // don't leave a copied secret behind on the clipboard when the program exits
+    helpers::clear_clipboard()?;
     Ok(())
 }
 
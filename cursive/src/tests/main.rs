@@ -1,7 +1,7 @@
 use std::{fs::File, io::Write};
 
 use chrono::Local;
-use ripasso::pass::{PasswordEntry, RepositoryStatus};
+use ripasso::pass::{EncryptionSubkey, PasswordEntry, RepositoryStatus};
 use tempfile::tempdir;
 
 use super::*;
@@ -42,6 +42,7 @@ fn do_delete_normal() {
     store.passwords.push(PasswordEntry::new(
         &td.path().join(".password-store"),
         &PathBuf::from("file.gpg"),
+        "gpg",
         Ok(Local::now()),
         Ok(String::new()),
         Ok(SignatureStatus::Good),
@@ -95,6 +96,7 @@ fn get_selected_password_entry_some() {
         pass::PasswordEntry::new(
             &PathBuf::from("/tmp/"),
             &PathBuf::from("file.gpg"),
+            "gpg",
             Ok(Local::now()),
             Ok(String::new()),
             Ok(SignatureStatus::Good),
@@ -130,6 +132,7 @@ fn do_delete_one_entry() {
     store.passwords.push(PasswordEntry::new(
         &dir.path().join(".password-store"),
         &PathBuf::from("file.gpg"),
+        "gpg",
         Ok(Local::now()),
         Ok(String::new()),
         Ok(SignatureStatus::Good),
@@ -174,13 +177,69 @@ fn render_recipient_label_ultimate() {
         key_ring_status: ripasso::pass::KeyRingStatus::InKeyRing,
         trust_level: OwnerTrustLevel::Ultimate,
         not_usable: false,
+        created: None,
+        expires: None,
+        revocation_reason: None,
+        encryption_subkeys: vec![EncryptionSubkey {
+            fingerprint: "7E068070D5EF794B00C8A9D91D108E6C07CBC406".to_owned(),
+            created: None,
+            expires: None,
+        }],
     };
 
-    let result = render_recipient_label(&r, 20, 20);
+    let result = render_recipient_label(&r, 30, 20, 20);
 
     assert_eq!(String::from("  \u{fe0f} 1D108E6C07CBC406     Alexander Kjäll <alexander.kjall@gmail.com> Ultimate  Usable  "), result);
 }
 
+#[test]
+fn render_recipient_label_expires_soon() {
+    let expires = Local::now() + chrono::Duration::try_days(10).unwrap();
+    let r = Recipient {
+        name: "Alexander Kjäll <alexander.kjall@gmail.com>".to_owned(),
+        comment: ripasso::pass::Comment {
+            pre_comment: None,
+            post_comment: None,
+        },
+        key_id: "1D108E6C07CBC406".to_owned(),
+        fingerprint: Some(
+            <[u8; 20]>::from_hex("7E068070D5EF794B00C8A9D91D108E6C07CBC406").unwrap(),
+        ),
+        key_ring_status: ripasso::pass::KeyRingStatus::InKeyRing,
+        trust_level: OwnerTrustLevel::Ultimate,
+        not_usable: false,
+        created: None,
+        expires: None,
+        revocation_reason: None,
+        encryption_subkeys: vec![EncryptionSubkey {
+            fingerprint: "7E068070D5EF794B00C8A9D91D108E6C07CBC406".to_owned(),
+            created: None,
+            expires: Some(expires),
+        }],
+    };
+
+    let result = render_recipient_label(&r, 30, 20, 20);
+
+    assert_eq!(
+        format!(
+            "⚠️  1D108E6C07CBC406     Alexander Kjäll <alexander.kjall@gmail.com> Ultimate  Usable  Expires {}",
+            expires.format("%Y-%m-%d")
+        ),
+        result
+    );
+    assert_eq!(
+        String::from("  \u{fe0f} 1D108E6C07CBC406     Alexander Kjäll <alexander.kjall@gmail.com> Ultimate  Usable  "),
+        render_recipient_label(&r, 5, 20, 20)
+    );
+    assert_eq!(
+        "⚠️  1D108E6C07CBC406 Alexander Kjäll <alexander.kjall@gmail.com>: Revoked: Key is superseded",
+        render_recipient_warnings(&[(
+            r,
+            KeyWarning::Revoked("Key is superseded".to_owned())
+        )])
+    );
+}
+
 #[test]
 fn substr_wide_char() {
     assert_eq!(String::from("Können"), substr("Können", 0, 6));
@@ -198,6 +257,7 @@ fn create_label_basic() {
     let p = pass::PasswordEntry::new(
         &PathBuf::from("/tmp/"),
         &PathBuf::from("file.gpg"),
+        "gpg",
         Ok(chrono::DateTime::<Local>::from(
             chrono::DateTime::parse_from_str("2022-08-14 00:00:00+0000", "%Y-%m-%d %H:%M:%S%z")
                 .unwrap(),
@@ -216,7 +276,7 @@ fn create_label_basic() {
 fn get_sub_dirs_empty() {
     let dir = tempfile::tempdir().unwrap();
 
-    let dirs = get_sub_dirs(&dir.path().to_path_buf()).unwrap();
+    let dirs = get_sub_dirs(&dir.path().to_path_buf(), ".gpg-id").unwrap();
 
     assert_eq!(1, dirs.len());
     assert_eq!(PathBuf::from("./"), dirs[0]);
@@ -227,7 +287,7 @@ fn get_sub_dirs_one_dir() {
     let dir = tempfile::tempdir().unwrap();
     std::fs::create_dir(dir.path().join("one_dir")).unwrap();
 
-    let dirs = get_sub_dirs(&dir.path().to_path_buf()).unwrap();
+    let dirs = get_sub_dirs(&dir.path().to_path_buf(), ".gpg-id").unwrap();
 
     assert_eq!(1, dirs.len());
     assert_eq!(PathBuf::from("./"), dirs[0]);
@@ -239,7 +299,7 @@ fn get_sub_dirs_one_dir_with_pgpid() {
     std::fs::create_dir(dir.path().join("one_dir")).unwrap();
     std::fs::File::create(dir.path().join("one_dir").join(".gpg-id")).unwrap();
 
-    let dirs = get_sub_dirs(&dir.path().to_path_buf()).unwrap();
+    let dirs = get_sub_dirs(&dir.path().to_path_buf(), ".gpg-id").unwrap();
 
     assert_eq!(2, dirs.len());
     assert_eq!(PathBuf::from("./"), dirs[0]);
@@ -1,19 +1,31 @@
 mod imp;
 
 use std::{
+    cell::RefCell,
     path::Path,
+    rc::Rc,
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
 use adw::{
     prelude::{ListModelExtManual, *},
     subclass::prelude::*,
 };
-use glib::Object;
-use gtk::{gio, glib};
-use ripasso::pass::{PasswordEntry, PasswordStore};
+use chrono::Local;
+use glib::{clone, Object};
+use gtk::{
+    gio, glib, Dialog, DialogFlags, Label, Orientation, ResponseType, ScrolledWindow, TextView,
+};
+use ripasso::{
+    git::MergeConflict,
+    pass::{Error, KeyRingStatus, PasswordEntry, PasswordStore, Recipient},
+    sync::{SyncEvent, SyncService},
+    watch::{PasswordChange, StoreWatcher},
+};
 
 use crate::{
+    credentials::run_with_credentials,
     password_object::PasswordObject,
     utils::{error_dialog, PasswordStoreBoxed},
 };
@@ -52,20 +64,208 @@ impl CollectionObject {
             .clone()
     }
 
+    pub fn clipboard_timeout(&self) -> Duration {
+        self.imp()
+            .store
+            .borrow()
+            .lock()
+            .unwrap()
+            .get_clipboard_timeout()
+    }
+
     pub fn git_pull(&self, parent_window: &impl IsA<gtk::Window>) {
-        let res = ripasso::git::pull(&self.imp().store.borrow().as_ref().lock().unwrap());
+        let store = self.imp().store.borrow().clone();
+        let parent_window: gtk::Window = parent_window.clone().upcast();
 
-        if let Err(e) = res {
-            error_dialog(&e, parent_window);
-        }
+        run_with_credentials(
+            parent_window.clone(),
+            {
+                let store = store.clone();
+                move |credentials| ripasso::git::pull(&store.lock().unwrap(), credentials)
+            },
+            move |res| match res {
+                Ok(conflicts) if !conflicts.is_empty() => merge_conflict_dialog(
+                    store.clone(),
+                    Rc::new(RefCell::new(conflicts)),
+                    0,
+                    parent_window.clone(),
+                ),
+                Ok(_) => {}
+                Err(e) => error_dialog(&e, &parent_window),
+            },
+        );
     }
 
     pub fn git_push(&self, parent_window: &impl IsA<gtk::Window>) {
-        let res = ripasso::git::push(&self.imp().store.borrow().as_ref().lock().unwrap());
+        let store = self.imp().store.borrow().clone();
+        let parent_window: gtk::Window = parent_window.clone().upcast();
 
-        if let Err(e) = res {
-            error_dialog(&e, parent_window);
+        run_with_credentials(
+            parent_window.clone(),
+            move |credentials| ripasso::git::push(&store.lock().unwrap(), credentials),
+            move |res| {
+                if let Err(e) = res {
+                    error_dialog(&e, &parent_window);
+                }
+            },
+        );
+    }
+
+    /// Syncs the store in the background if it has a sync interval, and shows the passwords
+    /// again when new commits are pulled. The service stops when the collection is dropped.
+    pub fn start_sync(&self, parent_window: &impl IsA<gtk::Window>) {
+        let store = self.imp().store.borrow().clone();
+        let Some(interval) = store.lock().unwrap().get_sync_interval() else {
+            return;
+        };
+        let (service, events) = match SyncService::start(store, interval) {
+            Ok(started) => started,
+            Err(e) => {
+                error_dialog(&e, parent_window);
+                return;
+            }
+        };
+
+        let parent_window: gtk::Window = parent_window.clone().upcast();
+        // only the first of a row of failures is shown, they are retried at every interval
+        let mut failing = false;
+        glib::timeout_add_local(
+            Duration::from_millis(500),
+            clone!(@weak self as collection, @weak parent_window => @default-return glib::ControlFlow::Break, move || {
+                let _service = &service;
+                while let Ok(event) = events.try_recv() {
+                    let problem = match event {
+                        SyncEvent::Updated => {
+                            collection.reload_passwords();
+                            failing = false;
+                            continue;
+                        }
+                        SyncEvent::UpToDate | SyncEvent::Pushed => {
+                            failing = false;
+                            continue;
+                        }
+                        SyncEvent::Conflicted => Error::Generic(
+                            "Both you and the remote git repository changed the same entries, pull to resolve it",
+                        ),
+                        SyncEvent::Failed(e) => e,
+                        _ => continue,
+                    };
+                    if !failing {
+                        failing = true;
+                        error_dialog(&problem, &parent_window);
+                    }
+                }
+                glib::ControlFlow::Continue
+            }),
+        );
+    }
+
+    /// Watches the directory of the store, and updates the shown passwords when files are
+    /// added, removed or renamed outside of ripasso. The watcher stops when the collection is
+    /// dropped.
+    pub fn start_watch(&self, parent_window: &impl IsA<gtk::Window>) {
+        let store = self.imp().store.borrow().clone();
+        let (watcher, changes) = match StoreWatcher::start(store) {
+            Ok(started) => started,
+            Err(e) => {
+                error_dialog(&e, parent_window);
+                return;
+            }
+        };
+
+        glib::timeout_add_local(
+            Duration::from_millis(500),
+            clone!(@weak self as collection => @default-return glib::ControlFlow::Break, move || {
+                let _watcher = &watcher;
+                while let Ok(change) = changes.try_recv() {
+                    collection.show_password_change(change);
+                }
+                glib::ControlFlow::Continue
+            }),
+        );
+    }
+
+    fn show_password_change(&self, change: PasswordChange) {
+        let (removed, added) = match change {
+            PasswordChange::Added(entry) => (None, Some(entry)),
+            PasswordChange::Removed(entry) => (Some(entry), None),
+            PasswordChange::Renamed { from, to } => (Some(from), Some(to)),
+            _ => return,
+        };
+
+        let passwords = self.passwords();
+        if let Some(removed) = removed {
+            let position = passwords
+                .iter::<PasswordObject>()
+                .flatten()
+                .position(|p| p.password_entry().path == removed.path);
+            if let Some(position) = position {
+                passwords.remove(position as u32);
+            }
         }
+        // changes that were made in ripasso are already shown
+        let shown = |path: &Path| {
+            passwords
+                .iter::<PasswordObject>()
+                .flatten()
+                .any(|p| p.password_entry().path == path)
+        };
+        if let Some(added) = added.filter(|added| !shown(&added.path)) {
+            let store = self.imp().store.borrow().clone();
+            passwords.insert_sorted(
+                &PasswordObject::from_password_entry(added, store),
+                |a, b| {
+                    let name = |o: &glib::Object| {
+                        o.downcast_ref::<PasswordObject>()
+                            .map(|p| p.password_entry().name)
+                    };
+                    name(a).cmp(&name(b))
+                },
+            );
+        }
+    }
+
+    /// Moves or copies the entry `from`, or all the entries in the directory `from`, to `to`,
+    /// re-encrypted for the recipients of where they end up.
+    pub fn transfer(
+        &self,
+        parent_window: &impl IsA<gtk::Window>,
+        from: &str,
+        to: &str,
+        is_move: bool,
+    ) {
+        let store = self.imp().store.borrow().clone();
+        let mut store = store.lock().unwrap();
+        let is_file = store
+            .get_store_path()
+            .join(format!("{from}.{}", store.password_file_extension()))
+            .is_file();
+        let res = match (is_file, is_move) {
+            (true, true) => store.rename_file(from, to).map(|_| ()),
+            (true, false) => store.copy_file(from, to).map(|_| ()),
+            (false, true) => store.rename_dir(from, to).map(|_| ()),
+            (false, false) => store.copy_dir(from, to).map(|_| ()),
+        };
+        drop(store);
+
+        match res {
+            Ok(()) => self.reload_passwords(),
+            Err(e) => error_dialog(&e, parent_window),
+        }
+    }
+
+    /// Shows the passwords of the store again, after the list of the store was reloaded.
+    pub fn reload_passwords(&self) {
+        let store = self.imp().store.borrow().clone();
+        let mut entries = store.lock().unwrap().passwords.clone();
+        entries.sort_by(|a, b| a.name.partial_cmp(&b.name).unwrap());
+
+        let passwords: Vec<PasswordObject> = entries
+            .into_iter()
+            .map(|p| PasswordObject::from_password_entry(p, store.clone()))
+            .collect();
+        self.passwords().remove_all();
+        self.passwords().extend_from_slice(&passwords);
     }
 
     pub fn pgp_download(&self, parent_window: &impl IsA<gtk::Window>) {
@@ -79,6 +279,21 @@ impl CollectionObject {
         }
     }
 
+    pub fn show_recipients(&self, parent_window: &impl IsA<gtk::Window>) {
+        let res = {
+            let store = self.imp().store.borrow();
+            let store = store.lock().unwrap();
+            store
+                .all_recipients()
+                .map(|recipients| (recipients, store.get_key_expiry_warning_days()))
+        };
+
+        match res {
+            Ok((recipients, days)) => recipients_dialog(&recipients, days, parent_window),
+            Err(e) => error_dialog(&e, parent_window),
+        }
+    }
+
     pub fn to_collection_data(&self) -> CollectionData {
         let title = self.imp().title.borrow().clone();
         let passwords_data = self
@@ -123,3 +338,151 @@ pub struct CollectionData {
     pub title: String,
     pub passwords_data: Vec<PasswordEntry>,
 }
+
+/// Lets the user edit the entries that both sides of a pull changed, one at a time, and
+/// finishes the pull when the last one has been saved.
+fn merge_conflict_dialog(
+    store: Arc<Mutex<PasswordStore>>,
+    conflicts: Rc<RefCell<Vec<MergeConflict>>>,
+    index: usize,
+    parent_window: gtk::Window,
+) {
+    let title = format!(
+        "Merge conflict in {}",
+        conflicts.borrow()[index].path.display()
+    );
+    let dialog = Dialog::with_buttons(
+        Some(&title),
+        Some(&parent_window),
+        DialogFlags::MODAL | DialogFlags::DESTROY_WITH_PARENT | DialogFlags::USE_HEADER_BAR,
+        &[
+            ("Abort Pull", ResponseType::Cancel),
+            ("Save", ResponseType::Accept),
+        ],
+    );
+
+    let text_view = TextView::builder().monospace(true).build();
+    text_view
+        .buffer()
+        .set_text(&conflicts.borrow()[index].merged);
+    let scrolled_window = ScrolledWindow::builder()
+        .min_content_width(400)
+        .min_content_height(200)
+        .child(&text_view)
+        .build();
+    let content = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(6)
+        .margin_top(12)
+        .margin_bottom(12)
+        .margin_start(12)
+        .margin_end(12)
+        .build();
+    content.append(&Label::new(Some(
+        "Both you and the remote repository changed this entry, edit it to what it should contain:",
+    )));
+    content.append(&scrolled_window);
+    dialog.content_area().append(&content);
+
+    dialog.connect_response(move |dialog, response| {
+        dialog.destroy();
+
+        if response != ResponseType::Accept {
+            if let Err(e) = ripasso::git::abort_merge(&store.lock().unwrap()) {
+                error_dialog(&e, &parent_window);
+            }
+            return;
+        }
+
+        let buffer = text_view.buffer();
+        let text = buffer.text(&buffer.start_iter(), &buffer.end_iter(), false);
+        conflicts.borrow_mut()[index].merged = text.to_string();
+
+        if index + 1 < conflicts.borrow().len() {
+            merge_conflict_dialog(
+                store.clone(),
+                conflicts.clone(),
+                index + 1,
+                parent_window.clone(),
+            );
+            return;
+        }
+
+        let res = ripasso::git::resolve_conflicts(&store.lock().unwrap(), &conflicts.borrow());
+        if let Err(e) = res {
+            error_dialog(&e, &parent_window);
+        }
+    });
+    dialog.present();
+}
+
+/// Lists the team members of the store, with a warning for the ones whose keys are revoked,
+/// can't be encrypted to, or can't be encrypted to within `days` days.
+fn recipients_dialog(recipients: &[Recipient], days: u32, parent_window: &impl IsA<gtk::Window>) {
+    let dialog = Dialog::with_buttons(
+        Some("Team Members"),
+        Some(parent_window),
+        DialogFlags::MODAL | DialogFlags::DESTROY_WITH_PARENT | DialogFlags::USE_HEADER_BAR,
+        &[("Close", ResponseType::Close)],
+    );
+
+    let now = Local::now();
+    let mut warnings = 0;
+    let list = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(12)
+        .build();
+    for recipient in recipients {
+        let details = match (&recipient.key_ring_status, recipient.key_warning(now, days)) {
+            (KeyRingStatus::NotInKeyRing, _) => "⚠️ Not in the key ring".to_owned(),
+            (_, Some(warning)) => {
+                warnings += 1;
+                format!("⚠️ {warning}")
+            }
+            (_, None) => match recipient.encryption_expires() {
+                Some(expires) => format!("Expires {}", expires.format("%Y-%m-%d")),
+                None => "Never expires".to_owned(),
+            },
+        };
+
+        list.append(
+            &Label::builder()
+                .label(format!(
+                    "{}\n{} {}",
+                    recipient.name, recipient.key_id, details
+                ))
+                .xalign(0.0)
+                .selectable(true)
+                .build(),
+        );
+    }
+    let scrolled_window = ScrolledWindow::builder()
+        .min_content_width(400)
+        .min_content_height(200)
+        .child(&list)
+        .build();
+
+    let content = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(6)
+        .margin_top(12)
+        .margin_bottom(12)
+        .margin_start(12)
+        .margin_end(12)
+        .build();
+    if warnings > 0 {
+        content.append(
+            &Label::builder()
+                .label(format!(
+                    "{warnings} team members can't be encrypted to, or can't be within {days} days"
+                ))
+                .css_classes(["error"])
+                .build(),
+        );
+    }
+    content.append(&scrolled_window);
+    dialog.content_area().append(&content);
+
+    dialog.connect_response(|dialog, _| dialog.destroy());
+    dialog.present();
+}
@@ -0,0 +1,183 @@
+use std::{rc::Rc, sync::Arc};
+
+use adw::prelude::*;
+use gtk::{Dialog, DialogFlags, Entry, Label, Orientation, PasswordEntry, ResponseType};
+use once_cell::sync::Lazy;
+use ripasso::{
+    credentials::{AnsweredCredentials, AnsweredPassphrases, Attempt, Question},
+    pass::{Error, Result},
+};
+
+use crate::utils::error_dialog_standalone;
+
+/// The passphrases for the secret keys, shared by all the stores.
+pub static KEY_PASSPHRASES: Lazy<Arc<AnsweredPassphrases>> =
+    Lazy::new(|| Arc::new(AnsweredPassphrases::default()));
+
+type Operation<T> = Rc<dyn Fn(&AnsweredCredentials) -> Result<T>>;
+type Done<T> = Rc<dyn Fn(Result<T>)>;
+
+/// Runs a git operation, and if it failed because it needed a credential that the user hasn't
+/// given yet, asks for it in a dialog and runs the operation again. `done` gets the result of
+/// the last run.
+pub fn run_with_credentials<T: 'static>(
+    parent_window: gtk::Window,
+    operation: impl Fn(&AnsweredCredentials) -> Result<T> + 'static,
+    done: impl Fn(Result<T>) + 'static,
+) {
+    run(
+        parent_window,
+        AnsweredCredentials::default(),
+        Rc::new(operation),
+        Rc::new(done),
+    );
+}
+
+fn run<T: 'static>(
+    parent_window: gtk::Window,
+    credentials: AnsweredCredentials,
+    operation: Operation<T>,
+    done: Done<T>,
+) {
+    match credentials.attempt(&*operation) {
+        Attempt::Ask(question) => {
+            credential_dialog(parent_window, credentials, question, operation, done);
+        }
+        Attempt::Done(res) => done(res),
+    }
+}
+
+fn credential_dialog<T: 'static>(
+    parent_window: gtk::Window,
+    credentials: AnsweredCredentials,
+    question: Question,
+    operation: Operation<T>,
+    done: Done<T>,
+) {
+    let title = match &question {
+        Question::Passphrase(_) => "SSH Key Passphrase",
+        Question::UserAndToken { .. } => "Git Credentials",
+    };
+    let dialog = Dialog::with_buttons(
+        Some(title),
+        Some(&parent_window),
+        DialogFlags::MODAL | DialogFlags::DESTROY_WITH_PARENT | DialogFlags::USE_HEADER_BAR,
+        &[
+            ("Cancel", ResponseType::Cancel),
+            ("Ok", ResponseType::Accept),
+        ],
+    );
+
+    let content = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(6)
+        .margin_top(12)
+        .margin_bottom(12)
+        .margin_start(12)
+        .margin_end(12)
+        .build();
+    let user_entry = Entry::new();
+    let secret_entry = PasswordEntry::builder()
+        .show_peek_icon(true)
+        .activates_default(true)
+        .build();
+    match &question {
+        Question::Passphrase(key_file) => {
+            content.append(&Label::new(Some(&format!(
+                "Passphrase for {}:",
+                key_file.display()
+            ))));
+        }
+        Question::UserAndToken { url, username } => {
+            content.append(&Label::new(Some(&format!(
+                "User name and token for {url}:"
+            ))));
+            user_entry.set_text(username.as_deref().unwrap_or_default());
+            content.append(&user_entry);
+        }
+    }
+    content.append(&secret_entry);
+    dialog.content_area().append(&content);
+    dialog.set_default_response(ResponseType::Accept);
+
+    dialog.connect_response(move |dialog, response| {
+        dialog.destroy();
+
+        if response != ResponseType::Accept {
+            return;
+        }
+
+        let mut credentials = credentials.clone();
+        credentials.answer(
+            &question,
+            user_entry.text().to_string(),
+            secret_entry.text().to_string(),
+        );
+
+        run(
+            parent_window.clone(),
+            credentials,
+            operation.clone(),
+            done.clone(),
+        );
+    });
+    dialog.present();
+}
+
+/// Shows the error of a decryption, unless it failed because a secret key needs a passphrase,
+/// then the passphrase is asked for instead and the decryption is done again with `retry`.
+pub fn error_dialog_or_ask_passphrase(error: &Error, retry: impl Fn() + 'static) {
+    let key = match KEY_PASSPHRASES.take_unanswered() {
+        Ok(key) => key,
+        Err(lock_err) => {
+            error_dialog_standalone(&lock_err);
+            return;
+        }
+    };
+    let Some(key) = key else {
+        error_dialog_standalone(error);
+        return;
+    };
+
+    let dialog = Dialog::with_buttons(
+        Some("Secret Key Passphrase"),
+        None::<&gtk::Window>,
+        DialogFlags::MODAL | DialogFlags::USE_HEADER_BAR,
+        &[
+            ("Cancel", ResponseType::Cancel),
+            ("Ok", ResponseType::Accept),
+        ],
+    );
+
+    let content = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(6)
+        .margin_top(12)
+        .margin_bottom(12)
+        .margin_start(12)
+        .margin_end(12)
+        .build();
+    let secret_entry = PasswordEntry::builder()
+        .show_peek_icon(true)
+        .activates_default(true)
+        .build();
+    content.append(&Label::new(Some(&format!("Passphrase for {key}:"))));
+    content.append(&secret_entry);
+    dialog.content_area().append(&content);
+    dialog.set_default_response(ResponseType::Accept);
+
+    dialog.connect_response(move |dialog, response| {
+        dialog.destroy();
+
+        if response != ResponseType::Accept {
+            return;
+        }
+
+        let passphrase = secret_entry.text().to_string();
+        match KEY_PASSPHRASES.answer(key.clone(), passphrase) {
+            Ok(()) => retry(),
+            Err(err) => error_dialog_standalone(&err),
+        }
+    });
+    dialog.present();
+}
@@ -1,4 +1,5 @@
 mod collection_object;
+mod credentials;
 mod password_object;
 mod utils;
 mod window;
@@ -22,7 +23,12 @@ fn main() -> glib::ExitCode {
     app.connect_activate(build_ui);
 
     // Run the application
-    app.run()
+    let exit_code = app.run();
+
+    // don't leave a copied secret behind on the clipboard when the program exits
+    utils::clear_clipboard();
+
+    exit_code
 }
 
 fn setup_shortcuts(app: &adw::Application) {
@@ -9,7 +9,7 @@ use adw::subclass::prelude::*;
 use chrono::{DateTime, Local};
 use glib::Object;
 use gtk::glib;
-use ripasso::pass::{Error, PasswordEntry, PasswordStore};
+use ripasso::pass::{Error, PasswordEntry, PasswordStore, Result};
 
 use crate::utils::{error_dialog_standalone, PasswordStoreBoxed};
 
@@ -40,6 +40,16 @@ impl PasswordObject {
         self.imp().data.borrow().clone()
     }
 
+    /// Decrypts the password file, unlike the `secret` property it returns the error, so that
+    /// the caller can ask for the passphrase of a secret key and try again.
+    pub fn secret(&self) -> Result<String> {
+        let store = self.imp().store.borrow();
+        let store = store
+            .lock()
+            .map_err(|_| Error::Generic("problem locking the mutex"))?;
+        self.imp().data.borrow().secret(&store)
+    }
+
     pub fn from_password_entry(p_e: PasswordEntry, store: Arc<Mutex<PasswordStore>>) -> Self {
         let file_date: DateTime<Local> = match p_e.updated {
             Some(d) => d,
@@ -1,8 +1,20 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use adw::prelude::{DialogExt, GtkWindowExt, WidgetExt};
 use gtk::{prelude::IsA, MessageDialog};
-use ripasso::pass::{Error, PasswordStore};
+use once_cell::sync::Lazy;
+use ripasso::{
+    clipboard::ClipboardManager,
+    pass::{Error, PasswordStore},
+};
+
+// The clipboard manager has to live as long as the program, since on X11 and Wayland the
+// clipboard content is served by the process that set it
+static CLIPBOARD: Lazy<Result<ClipboardManager, String>> =
+    Lazy::new(|| ClipboardManager::new().map_err(|e| e.to_string()));
 
 #[derive(Clone, glib::SharedBoxed)]
 #[shared_boxed_type(name = "PasswordStoreBoxed")]
@@ -40,3 +52,22 @@ pub fn error_dialog_standalone(error: &Error) {
 
     dialog.show();
 }
+
+/// Puts the secret on the clipboard and clears it after `timeout`, if it's still there.
+pub fn copy_secret(secret: &str, timeout: Duration, transient_for: &impl IsA<gtk::Window>) {
+    let res = match &*CLIPBOARD {
+        Ok(clipboard) => clipboard.copy_secret(secret, timeout),
+        Err(e) => Err(Error::GenericDyn(e.clone())),
+    };
+
+    if let Err(e) = res {
+        error_dialog(&e, transient_for);
+    }
+}
+
+/// Clears the clipboard if it still holds the last copied secret.
+pub fn clear_clipboard() {
+    if let Ok(clipboard) = &*CLIPBOARD {
+        let _ = clipboard.clear();
+    }
+}
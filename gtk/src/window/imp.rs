@@ -4,7 +4,7 @@ use adw::{subclass::prelude::*, Leaflet};
 use glib::{subclass::InitializingObject, Propagation};
 use gtk::{
     gio, glib, glib::SignalHandlerId, Button, CompositeTemplate, Entry, FilterListModel, ListBox,
-    Stack,
+    SortListModel, Stack,
 };
 use once_cell::sync::OnceCell;
 
@@ -30,6 +30,7 @@ pub struct Window {
     pub collections: OnceCell<gio::ListStore>,
     pub current_collection: RefCell<Option<CollectionObject>>,
     pub current_filter_model: RefCell<Option<FilterListModel>>,
+    pub current_sort_model: RefCell<Option<SortListModel>>,
     pub passwords_changed_handler_id: RefCell<Option<SignalHandlerId>>,
     pub user_config_dir: RefCell<PathBuf>,
 }
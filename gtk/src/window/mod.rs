@@ -9,13 +9,25 @@ use std::{
 use adw::{prelude::*, subclass::prelude::*, ActionRow, NavigationDirection};
 use glib::{clone, Object};
 use gtk::{
-    gio, glib, glib::BindingFlags, pango, AboutDialog, CustomFilter, Dialog, DialogFlags, Entry,
-    FilterListModel, Label, ListBox, ListBoxRow, NoSelection, ResponseType, SelectionMode,
+    gio, glib, glib::BindingFlags, pango, AboutDialog, CheckButton, CustomFilter, CustomSorter,
+    Dialog, DialogFlags, DropDown, Entry, FilterListModel, Label, ListBox, ListBoxRow, NoSelection,
+    Orientation, ResponseType, SelectionMode, SortListModel, SpinButton,
 };
 use hex::FromHex;
-use ripasso::{crypto::CryptoImpl, pass::PasswordStore};
+use ripasso::{
+    crypto::CryptoImpl,
+    discovery::KeySource,
+    generate::{Capitalization, CharacterClasses, PasswordGenerator},
+    git::SyncRemote,
+    pass::{parse_fingerprints, PasswordStore, SearchQuery},
+};
 
-use crate::{collection_object::CollectionObject, password_object::PasswordObject};
+use crate::{
+    collection_object::CollectionObject,
+    credentials::{error_dialog_or_ask_passphrase, KEY_PASSPHRASES},
+    password_object::PasswordObject,
+    utils,
+};
 
 glib::wrapper! {
     pub struct Window(ObjectSubclass<imp::Window>)
@@ -57,19 +69,47 @@ impl Window {
             .clone()
             .expect("`current_filter_model` should be set in `set_current_collection`.")
             .set_filter(Some(&self.filter()));
+        self.imp()
+            .current_sort_model
+            .borrow()
+            .clone()
+            .expect("`current_sort_model` should be set in `set_current_collection`.")
+            .set_sorter(Some(&self.sorter()));
     }
 
     fn filter(&self) -> CustomFilter {
-        CustomFilter::new(clone!(@weak self as window => @default-panic, move |obj| {
-            let text = window.imp().entry.text();
+        let query = SearchQuery::parse(&self.imp().entry.text());
 
+        CustomFilter::new(move |obj| {
             // Get `PasswordObject` from `glib::Object`
             let password_object = obj
                 .downcast_ref::<PasswordObject>()
                 .expect("The object needs to be of type `PasswordObject`.");
 
-            password_object.property::<String>("name").contains(&text.to_string())
-        }))
+            query.score(&password_object.password_entry()).is_some()
+        })
+    }
+
+    fn sorter(&self) -> CustomSorter {
+        let query = SearchQuery::parse(&self.imp().entry.text());
+
+        // Best match first, the filter has already removed the entries that don't match
+        CustomSorter::new(move |obj1, obj2| {
+            let entry1 = obj1
+                .downcast_ref::<PasswordObject>()
+                .expect("The object needs to be of type `PasswordObject`.")
+                .password_entry();
+            let entry2 = obj2
+                .downcast_ref::<PasswordObject>()
+                .expect("The object needs to be of type `PasswordObject`.")
+                .password_entry();
+
+            query
+                .score(&entry2)
+                .cmp(&query.score(&entry1))
+                .then_with(|| entry1.name.cmp(&entry2.name))
+                .into()
+        })
     }
 
     fn setup_collections(&self) {
@@ -99,11 +139,18 @@ impl Window {
         // Convert `Vec<CollectionData>` to `Vec<CollectionObject>`
         let collections: Vec<CollectionObject> = stores
             .into_iter()
-            .map(|s| CollectionObject::from_store_data(s, &user_config_dir.clone().unwrap()))
+            .map(|mut s| {
+                s.set_passphrase_prompt(KEY_PASSPHRASES.clone());
+                CollectionObject::from_store_data(s, &user_config_dir.clone().unwrap())
+            })
             .collect();
 
         // Insert restored objects into model
         self.collections().extend_from_slice(&collections);
+        for collection in &collections {
+            collection.start_sync(self);
+            collection.start_watch(self);
+        }
 
         // Set first collection as current
         if let Some(first_collection) = collections.first() {
@@ -125,11 +172,27 @@ impl Window {
         ListBoxRow::builder().child(&label).build()
     }
 
+    /// Puts the secret of the password on the clipboard, after asking for the passphrase of the
+    /// secret key if it's needed.
+    fn copy_password(&self, password: PasswordObject) {
+        match password.secret() {
+            Ok(secret) => {
+                let timeout = self.current_collection().clipboard_timeout();
+                utils::copy_secret(&secret, timeout, self);
+            }
+            Err(err) => error_dialog_or_ask_passphrase(
+                &err,
+                clone!(@weak self as window => move || window.copy_password(password.clone())),
+            ),
+        }
+    }
+
     fn set_current_collection(&self, collection: CollectionObject) {
-        // Wrap model with filter and selection and pass it to the list box
+        // Wrap model with filter, sorter and selection and pass it to the list box
         let passwords = collection.passwords();
         let filter_model = FilterListModel::new(Some(passwords.clone()), Some(self.filter()));
-        let selection_model = NoSelection::new(Some(filter_model.clone()));
+        let sort_model = SortListModel::new(Some(filter_model.clone()), Some(self.sorter()));
+        let selection_model = NoSelection::new(Some(sort_model.clone()));
         self.imp().passwords_list.bind_model(
             Some(&selection_model),
             clone!(@weak self as window => @default-panic, move |obj| {
@@ -141,8 +204,9 @@ impl Window {
             }),
         );
 
-        // Store filter model
+        // Store filter and sort models
         self.imp().current_filter_model.replace(Some(filter_model));
+        self.imp().current_sort_model.replace(Some(sort_model));
 
         // If present, disconnect old `passwords_changed` handler
         if let Some(handler_id) = self.imp().passwords_changed_handler_id.take() {
@@ -264,16 +328,18 @@ impl Window {
             clone!(@weak self as window => move |_, row| {
                 let index = row.index();
 
-                let password = window.current_collection()
-                    .passwords()
+                // The rows are bound to the filtered and sorted model, not to the collection
+                let password = window.imp()
+                    .current_sort_model
+                    .borrow()
+                    .clone()
+                    .expect("`current_sort_model` should be set in `set_current_collection`.")
                     .item(index as u32)
                     .expect("There needs to be an object at this position.")
                     .downcast::<PasswordObject>()
                     .expect("The object needs to be a `PasswordObject`.");
 
-                let display = gtk::gdk::Display::default().unwrap();
-                let clipboard = display.clipboard();
-                clipboard.set_text(&password.property::<String>("secret"));
+                window.copy_password(password);
             }),
         );
 
@@ -332,6 +398,13 @@ impl Window {
         }));
         self.add_action(&action_pgp_download);
 
+        // Create action to show the team members of the current repository
+        let action_show_recipients = gio::SimpleAction::new("show-recipients", None);
+        action_show_recipients.connect_activate(clone!(@weak self as window => move |_, _| {
+            window.current_collection().show_recipients(&window);
+        }));
+        self.add_action(&action_show_recipients);
+
         // Create action to download pgp certificates for the current repository
         let action_about = gio::SimpleAction::new("about", None);
         action_about.connect_activate(clone!(@weak self as window => move |_, _| {
@@ -339,6 +412,26 @@ impl Window {
         }));
         self.add_action(&action_about);
 
+        // Create action to open the password generator
+        let action_generate_password = gio::SimpleAction::new("generate-password", None);
+        action_generate_password.connect_activate(clone!(@weak self as window => move |_, _| {
+            window.generate_password_dialog();
+        }));
+        self.add_action(&action_generate_password);
+
+        // Create actions to move or copy entries and directories in the current repository
+        let action_move_entries = gio::SimpleAction::new("move-entries", None);
+        action_move_entries.connect_activate(clone!(@weak self as window => move |_, _| {
+            window.transfer_dialog(true);
+        }));
+        self.add_action(&action_move_entries);
+
+        let action_copy_entries = gio::SimpleAction::new("copy-entries", None);
+        action_copy_entries.connect_activate(clone!(@weak self as window => move |_, _| {
+            window.transfer_dialog(false);
+        }));
+        self.add_action(&action_copy_entries);
+
         // Create action to create new collection and add to action group "win"
         let action_new_list = gio::SimpleAction::new("new-collection", None);
         action_new_list.connect_activate(clone!(@weak self as window => move |_, _| {
@@ -362,6 +455,112 @@ impl Window {
         about_dialog.present();
     }
 
+    fn generate_password_dialog(&self) {
+        let dialog = Dialog::with_buttons(
+            Some("Generate Password"),
+            Some(self),
+            DialogFlags::MODAL | DialogFlags::DESTROY_WITH_PARENT | DialogFlags::USE_HEADER_BAR,
+            &[
+                ("Cancel", ResponseType::Cancel),
+                ("Generate", ResponseType::Apply),
+                ("Copy", ResponseType::Accept),
+            ],
+        );
+        dialog.set_default_response(ResponseType::Apply);
+
+        let widgets = GeneratorWidgets::new();
+        let content = gtk::Box::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(6)
+            .margin_top(12)
+            .margin_bottom(12)
+            .margin_start(12)
+            .margin_end(12)
+            .build();
+        content.append(&widgets.length);
+        content.append(&widgets.lowercase);
+        content.append(&widgets.uppercase);
+        content.append(&widgets.digits);
+        content.append(&widgets.symbols);
+        content.append(&widgets.exclude_ambiguous);
+        content.append(&widgets.words);
+        content.append(&widgets.separator);
+        content.append(&widgets.capitalization);
+        content.append(&widgets.password);
+        content.append(&widgets.strength);
+        dialog.content_area().append(&content);
+
+        widgets.regenerate();
+
+        dialog.connect_response(
+            clone!(@weak self as window => move |dialog, response| match response {
+                ResponseType::Apply => widgets.regenerate(),
+                ResponseType::Accept => {
+                    let timeout = window.current_collection().clipboard_timeout();
+                    utils::copy_secret(&widgets.password.text(), timeout, &window);
+                    dialog.destroy();
+                }
+                _ => dialog.destroy(),
+            }),
+        );
+        dialog.present();
+    }
+
+    fn transfer_dialog(&self, is_move: bool) {
+        let (title, button) = if is_move {
+            ("Move Entry or Directory", "Move")
+        } else {
+            ("Copy Entry or Directory", "Copy")
+        };
+        let dialog = Dialog::with_buttons(
+            Some(title),
+            Some(self),
+            DialogFlags::MODAL | DialogFlags::DESTROY_WITH_PARENT | DialogFlags::USE_HEADER_BAR,
+            &[
+                ("Cancel", ResponseType::Cancel),
+                (button, ResponseType::Accept),
+            ],
+        );
+        dialog.set_default_response(ResponseType::Accept);
+
+        let content = gtk::Box::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(6)
+            .margin_top(12)
+            .margin_bottom(12)
+            .margin_start(12)
+            .margin_end(12)
+            .build();
+        let from_entry = Entry::builder()
+            .placeholder_text("Entry or directory")
+            .build();
+        let to_entry = Entry::builder()
+            .placeholder_text("New name")
+            .activates_default(true)
+            .build();
+        content.append(&from_entry);
+        content.append(&to_entry);
+        dialog.content_area().append(&content);
+
+        dialog.connect_response(
+            clone!(@weak self as window, @weak from_entry, @weak to_entry => move |dialog, response| {
+                dialog.destroy();
+
+                if response != ResponseType::Accept {
+                    return;
+                }
+
+                window.current_collection().transfer(
+                    &window,
+                    from_entry.text().trim_end_matches('/'),
+                    to_entry.text().trim_end_matches('/'),
+                    is_move,
+                );
+            }),
+        );
+        dialog.present();
+    }
+
     fn new_collection(&self) {
         // Create new Dialog
         let dialog = Dialog::with_buttons(
@@ -439,6 +638,102 @@ impl Window {
     }
 }
 
+/// The input fields of the password generator dialog
+struct GeneratorWidgets {
+    length: SpinButton,
+    lowercase: CheckButton,
+    uppercase: CheckButton,
+    digits: CheckButton,
+    symbols: CheckButton,
+    exclude_ambiguous: CheckButton,
+    words: CheckButton,
+    separator: Entry,
+    capitalization: DropDown,
+    password: Label,
+    strength: Label,
+}
+
+impl GeneratorWidgets {
+    fn new() -> Self {
+        let check =
+            |label: &str, active: bool| CheckButton::builder().label(label).active(active).build();
+
+        let length = SpinButton::with_range(1.0, 256.0, 1.0);
+        length.set_value(20.0);
+
+        Self {
+            length,
+            lowercase: check("Lowercase letters", true),
+            uppercase: check("Uppercase letters", true),
+            digits: check("Digits", true),
+            symbols: check("Symbols", true),
+            exclude_ambiguous: check("Exclude ambiguous characters", false),
+            words: check("Use words instead of characters", false),
+            separator: Entry::builder()
+                .text("-")
+                .placeholder_text("Word separator")
+                .build(),
+            capitalization: DropDown::from_strings(&[
+                "lowercase",
+                "Capitalized",
+                "UPPERCASE",
+                "Random",
+            ]),
+            password: Label::builder()
+                .selectable(true)
+                .wrap(true)
+                .wrap_mode(pango::WrapMode::Char)
+                .build(),
+            strength: Label::new(None),
+        }
+    }
+
+    fn generator(&self) -> PasswordGenerator {
+        let length = self.length.value_as_int().max(0) as usize;
+
+        if self.words.is_active() {
+            PasswordGenerator::Words {
+                number_of_words: length,
+                separator: self.separator.text().to_string(),
+                capitalization: match self.capitalization.selected() {
+                    1 => Capitalization::Capitalized,
+                    2 => Capitalization::Uppercase,
+                    3 => Capitalization::Random,
+                    _ => Capitalization::Lowercase,
+                },
+            }
+        } else {
+            PasswordGenerator::Characters {
+                length,
+                classes: CharacterClasses {
+                    lowercase: self.lowercase.is_active(),
+                    uppercase: self.uppercase.is_active(),
+                    digits: self.digits.is_active(),
+                    symbols: self.symbols.is_active(),
+                    exclude_ambiguous: self.exclude_ambiguous.is_active(),
+                },
+            }
+        }
+    }
+
+    fn regenerate(&self) {
+        match self.generator().generate() {
+            Ok(generated) => {
+                self.password.set_text(generated.password());
+                self.strength.set_text(&format!(
+                    "{}, {:.0} bits of entropy",
+                    generated.strength(),
+                    generated.entropy()
+                ));
+            }
+            Err(err) => {
+                self.password.set_text("");
+                self.strength.set_text(&err.to_string());
+            }
+        }
+    }
+}
+
 fn get_stores(
     config: &config::Config,
     home: &Option<PathBuf>,
@@ -500,7 +795,24 @@ fn get_stores(
                     },
                 };
 
-                final_stores.push(PasswordStore::new(
+                let clipboard_timeout = store
+                    .get("clipboard_timeout")
+                    .and_then(|t| t.clone().into_int().ok())
+                    .and_then(|t| u64::try_from(t).ok());
+                let key_cache_timeout = store
+                    .get("key_cache_timeout")
+                    .and_then(|t| t.clone().into_int().ok())
+                    .and_then(|t| u64::try_from(t).ok());
+                let key_expiry_warning_days = store
+                    .get("key_expiry_warning_days")
+                    .and_then(|t| t.clone().into_int().ok())
+                    .and_then(|t| u32::try_from(t).ok());
+                let sync_interval = store
+                    .get("sync_interval")
+                    .and_then(|t| t.clone().into_int().ok())
+                    .and_then(|t| u64::try_from(t).ok());
+
+                let mut password_store = PasswordStore::new(
                     store_name,
                     &password_store_dir,
                     &valid_signing_keys,
@@ -508,7 +820,29 @@ fn get_stores(
                     &style_path_opt,
                     &pgp_impl,
                     &own_fingerprint,
-                )?);
+                )?;
+                password_store.set_clipboard_timeout(clipboard_timeout);
+                password_store.set_key_cache_timeout(key_cache_timeout);
+                password_store.set_key_expiry_warning_days(key_expiry_warning_days);
+                password_store.set_sync_interval(sync_interval);
+                if let Some(pull_strategy) = store.get("pull_strategy") {
+                    password_store.set_pull_strategy(pull_strategy.clone().into_str()?.parse()?);
+                }
+                if let Some(remotes) = store.get("remotes") {
+                    password_store
+                        .set_remotes(SyncRemote::parse_list(&remotes.clone().into_str()?)?);
+                }
+                if let Some(sources) = store.get("key_sources") {
+                    password_store
+                        .set_key_sources(KeySource::parse_list(&sources.clone().into_str()?)?);
+                }
+                if let Some(roots) = store.get("trust_roots") {
+                    // roots that aren't in the keyring are ignored, so that a missing key
+                    // doesn't make the whole store unusable
+                    let roots = parse_fingerprints(&roots.clone().into_str()?)?;
+                    password_store.set_trust_roots(roots);
+                }
+                final_stores.push(password_store);
             }
         }
     } else if final_stores.is_empty() && home.is_some() {
@@ -0,0 +1,270 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local};
+use sha1::{Digest, Sha1};
+
+use crate::{
+    error::{Error, Result},
+    generate::{estimate_entropy, Strength},
+};
+
+/// Settings for a password audit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuditOptions {
+    /// Passwords with an estimated entropy below this number of bits are reported as weak
+    pub min_entropy: f64,
+    /// Passwords that haven't been changed in this many days are reported, `None` to not
+    /// check the age of the passwords
+    pub max_age_days: Option<i64>,
+    /// The pwned passwords list from haveibeenpwned.com, either a file with one `HASH:COUNT`
+    /// line per password sorted by hash, or a directory of range files named after the first
+    /// five characters of the hashes, with `SUFFIX:COUNT` lines. `None` to not check for
+    /// breached passwords
+    pub breach_list: Option<PathBuf>,
+}
+
+impl Default for AuditOptions {
+    fn default() -> Self {
+        Self {
+            min_entropy: 60.0,
+            max_age_days: Some(365),
+            breach_list: None,
+        }
+    }
+}
+
+/// A problem found with a password.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AuditIssue {
+    /// The same password is used by other entries
+    Reused { by: Vec<String> },
+    /// The password is easy to guess
+    Weak { entropy: f64, strength: Strength },
+    /// The password hasn't been changed in a long time
+    NotRotated { days: i64 },
+    /// The password is in the breach list, `count` is the number of times it has been seen
+    /// in breaches
+    Breached { count: u64 },
+    /// The entry couldn't be decrypted, so it wasn't audited
+    Unreadable(String),
+}
+
+/// The issues found with one password entry.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuditEntry {
+    pub name: String,
+    pub issues: Vec<AuditIssue>,
+}
+
+/// The result of an audit, only entries with issues are included.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AuditReport {
+    /// The number of entries that was audited
+    pub audited: usize,
+    /// The entries with issues, sorted by name
+    pub entries: Vec<AuditEntry>,
+}
+
+impl AuditReport {
+    /// Returns the issues of the entry with the name, if any.
+    pub fn issues_for(&self, name: &str) -> &[AuditIssue] {
+        self.entries
+            .iter()
+            .find(|e| e.name == name)
+            .map(|e| e.issues.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Returns the number of entries that has an issue for which `predicate` is true.
+    pub fn count(&self, predicate: impl Fn(&AuditIssue) -> bool) -> usize {
+        self.entries
+            .iter()
+            .filter(|e| e.issues.iter().any(&predicate))
+            .count()
+    }
+}
+
+/// What the audit needs to know about a decrypted password, the password itself is only kept
+/// as a SHA-1 hash.
+pub(crate) struct AuditedPassword {
+    name: String,
+    hash: [u8; 20],
+    entropy: f64,
+    updated: Option<DateTime<Local>>,
+}
+
+impl AuditedPassword {
+    pub(crate) fn new(name: &str, password: &str, updated: Option<DateTime<Local>>) -> Self {
+        Self {
+            name: name.to_owned(),
+            hash: Sha1::digest(password.as_bytes()).into(),
+            entropy: estimate_entropy(password),
+            updated,
+        }
+    }
+}
+
+/// Audits the passwords, `unreadable` are the names of the entries that couldn't be decrypted
+/// together with the reason.
+pub(crate) fn audit(
+    passwords: &[AuditedPassword],
+    unreadable: Vec<(String, String)>,
+    now: DateTime<Local>,
+    options: &AuditOptions,
+) -> Result<AuditReport> {
+    let mut by_hash: HashMap<[u8; 20], Vec<&str>> = HashMap::new();
+    for p in passwords {
+        by_hash.entry(p.hash).or_default().push(&p.name);
+    }
+
+    let breached = match &options.breach_list {
+        Some(path) => breach_counts(path, by_hash.keys())?,
+        None => HashMap::new(),
+    };
+
+    let mut entries = vec![];
+    for p in passwords {
+        let mut issues = vec![];
+
+        let others: Vec<String> = by_hash[&p.hash]
+            .iter()
+            .filter(|n| **n != p.name)
+            .map(|n| (*n).to_owned())
+            .collect();
+        if !others.is_empty() {
+            issues.push(AuditIssue::Reused { by: others });
+        }
+        if p.entropy < options.min_entropy {
+            issues.push(AuditIssue::Weak {
+                entropy: p.entropy,
+                strength: Strength::from_entropy(p.entropy),
+            });
+        }
+        if let (Some(max_age), Some(updated)) = (options.max_age_days, p.updated) {
+            let days = (now - updated).num_days();
+            if days > max_age {
+                issues.push(AuditIssue::NotRotated { days });
+            }
+        }
+        if let Some(count) = breached.get(&p.hash) {
+            issues.push(AuditIssue::Breached { count: *count });
+        }
+
+        if !issues.is_empty() {
+            entries.push(AuditEntry {
+                name: p.name.clone(),
+                issues,
+            });
+        }
+    }
+
+    let audited = passwords.len();
+    for (name, reason) in unreadable {
+        entries.push(AuditEntry {
+            name,
+            issues: vec![AuditIssue::Unreadable(reason)],
+        });
+    }
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(AuditReport { audited, entries })
+}
+
+/// Looks up the hashes in the breach list, and returns the counts of the ones that are in it.
+fn breach_counts<'a>(
+    path: &Path,
+    hashes: impl Iterator<Item = &'a [u8; 20]>,
+) -> Result<HashMap<[u8; 20], u64>> {
+    let mut counts = HashMap::new();
+    if path.is_dir() {
+        for hash in hashes {
+            let hex = hex::encode_upper(hash);
+            let (prefix, suffix) = hex.split_at(5);
+            let range_file = [format!("{prefix}.txt"), prefix.to_owned()]
+                .into_iter()
+                .map(|n| path.join(n))
+                .find(|p| p.is_file());
+            let Some(range_file) = range_file else {
+                continue;
+            };
+            for line in BufReader::new(File::open(range_file)?).lines() {
+                if let Some(count) = parse_breach_line(&line?, suffix) {
+                    counts.insert(*hash, count);
+                    break;
+                }
+            }
+        }
+    } else {
+        let mut reader = BufReader::new(File::open(path)?);
+        let len = reader.get_ref().metadata()?.len();
+        for hash in hashes {
+            if let Some(count) = search_sorted(&mut reader, len, &hex::encode_upper(hash))? {
+                counts.insert(*hash, count);
+            }
+        }
+    }
+    Ok(counts)
+}
+
+/// Returns the count if the line is `hash:count`, compared without regard to case.
+fn parse_breach_line(line: &str, hash: &str) -> Option<u64> {
+    let (h, count) = line.trim().split_once(':')?;
+    if h.eq_ignore_ascii_case(hash) {
+        count.trim().parse().ok()
+    } else {
+        None
+    }
+}
+
+/// Binary search for the line that starts with `hash` in a file of `HASH:COUNT` lines sorted
+/// by hash, the file can be far too large to read into memory.
+fn search_sorted(reader: &mut BufReader<File>, len: u64, hash: &str) -> Result<Option<u64>> {
+    // lo is always the start of a line, and the line we look for starts in lo..hi
+    let (mut lo, mut hi) = (0, len);
+    let mut line = String::new();
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+
+        // find the first line that starts at or after mid
+        let mut start = mid;
+        if mid > 0 {
+            reader.seek(SeekFrom::Start(mid - 1))?;
+            let mut skipped = vec![];
+            start = mid - 1 + reader.read_until(b'\n', &mut skipped)? as u64;
+        } else {
+            reader.seek(SeekFrom::Start(0))?;
+        }
+        if start >= hi {
+            hi = mid;
+            continue;
+        }
+
+        line.clear();
+        let read = reader.read_line(&mut line)?;
+        if read == 0 {
+            hi = mid;
+            continue;
+        }
+        let (line_hash, count) = line
+            .trim()
+            .split_once(':')
+            .ok_or(Error::Generic("malformed line in the breach list"))?;
+        match line_hash.to_ascii_uppercase().as_str().cmp(hash) {
+            std::cmp::Ordering::Equal => {
+                return Ok(count.trim().parse().ok());
+            }
+            std::cmp::Ordering::Less => lo = start + read as u64,
+            std::cmp::Ordering::Greater => hi = mid,
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+#[path = "tests/audit.rs"]
+mod audit_tests;
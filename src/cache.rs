@@ -0,0 +1,207 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, Result},
+    pass::{PasswordEntry, RepositoryStatus},
+    signature::SignatureStatus,
+};
+
+/// Increase this when the format of the cache changes, older caches are then thrown away.
+const CACHE_VERSION: u32 = 1;
+
+/// Name of the cache file, inside the `.git` directory of the store.
+pub const CACHE_FILE_NAME: &str = "ripasso-metadata-cache.json";
+
+/// The git metadata of one password, as found when walking the history.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedMetadata {
+    /// Commit time, as seconds since the epoch
+    pub updated: Option<i64>,
+    pub committed_by: Option<String>,
+    pub signature_status: Option<SignatureStatus>,
+    /// false if the file isn't in the repository
+    pub in_repo: bool,
+}
+
+impl CachedMetadata {
+    pub fn from_entry(entry: &PasswordEntry) -> Self {
+        Self {
+            updated: entry.updated.map(|t| t.timestamp()),
+            committed_by: entry.committed_by.clone(),
+            signature_status: entry.signature_status.clone(),
+            in_repo: entry.is_in_git == RepositoryStatus::InRepo,
+        }
+    }
+
+    pub fn to_entry(&self, base: &Path, relpath: &Path, extension: &str) -> PasswordEntry {
+        let updated: Option<DateTime<Local>> = self
+            .updated
+            .and_then(|t| Local.timestamp_opt(t, 0).single());
+        PasswordEntry::new(
+            base,
+            relpath,
+            extension,
+            updated.ok_or(Error::Generic("")),
+            self.committed_by.clone().ok_or(Error::Generic("")),
+            self.signature_status.clone().ok_or(Error::Generic("")),
+            if self.in_repo {
+                RepositoryStatus::InRepo
+            } else {
+                RepositoryStatus::NotInRepo
+            },
+        )
+    }
+}
+
+/// The metadata of all passwords in a store at a given HEAD commit. Finding out which commit
+/// last touched every file means walking the whole history, and verifying the signatures of
+/// those commits, which is slow in big stores, so the result is stored under `.git/` and only
+/// the files that changed are looked up again when HEAD moves forward.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MetadataCache {
+    version: u32,
+    /// The HEAD commit that the entries are valid for
+    head: String,
+    /// The signature statuses depends on which keys are allowed to sign
+    signing_keys: Vec<String>,
+    entries: HashMap<PathBuf, CachedMetadata>,
+    /// If the cache differs from the one on disk, either because HEAD moved or entries changed
+    #[serde(skip)]
+    changed: bool,
+}
+
+impl MetadataCache {
+    /// Creates an empty cache for the given HEAD and signing keys.
+    pub fn new(head: &str, signing_keys: &[[u8; 20]]) -> Self {
+        Self {
+            version: CACHE_VERSION,
+            head: head.to_owned(),
+            signing_keys: signing_keys.iter().map(hex::encode_upper).collect(),
+            entries: HashMap::new(),
+            changed: true,
+        }
+    }
+
+    /// Loads the cache of the repository and brings it up to date with `head`. If there is no
+    /// cache, or it can't be used, an empty cache is returned.
+    pub fn load(repo: &git2::Repository, head: git2::Oid, signing_keys: &[[u8; 20]]) -> Self {
+        let empty = Self::new(&head.to_string(), signing_keys);
+
+        let cache = match fs::read(cache_path(repo)) {
+            Ok(data) => Self::parse(&data, signing_keys),
+            Err(_) => None,
+        };
+        let Some(mut cache) = cache else {
+            return empty;
+        };
+
+        if cache.head == empty.head {
+            return cache;
+        }
+        match changed_paths(repo, &cache.head, head) {
+            Ok(changed) => {
+                cache.advance(&empty.head, &changed);
+                cache
+            }
+            Err(_) => empty,
+        }
+    }
+
+    /// Parses a serialized cache, returning `None` if it's damaged, from another version, or
+    /// was written with other signing keys.
+    pub fn parse(data: &[u8], signing_keys: &[[u8; 20]]) -> Option<Self> {
+        let cache: Self = serde_json::from_slice(data).ok()?;
+        let keys: Vec<String> = signing_keys.iter().map(hex::encode_upper).collect();
+
+        if cache.version != CACHE_VERSION || cache.signing_keys != keys {
+            return None;
+        }
+        Some(cache)
+    }
+
+    /// Moves the cache to a new HEAD, forgetting the files that changed on the way.
+    pub fn advance(&mut self, head: &str, changed: &[PathBuf]) {
+        for path in changed {
+            self.entries.remove(path);
+        }
+        if self.head != head {
+            head.clone_into(&mut self.head);
+            self.changed = true;
+        }
+    }
+
+    pub fn get(&self, relpath: &Path) -> Option<&CachedMetadata> {
+        self.entries.get(relpath)
+    }
+
+    pub fn insert(&mut self, relpath: PathBuf, metadata: CachedMetadata) {
+        self.entries.insert(relpath, metadata);
+        self.changed = true;
+    }
+
+    /// Forgets the files that no longer exist in the store.
+    pub fn retain(&mut self, existing: &[PathBuf]) {
+        let existing: HashSet<&PathBuf> = existing.iter().collect();
+        let before = self.entries.len();
+        self.entries.retain(|path, _| existing.contains(path));
+        if self.entries.len() != before {
+            self.changed = true;
+        }
+    }
+
+    /// Returns true if the cache needs to be saved to be in sync with the repository.
+    pub fn is_changed(&self) -> bool {
+        self.changed
+    }
+
+    /// Writes the cache to the `.git` directory of the repository.
+    /// # Errors
+    /// Returns an `Err` if the cache can't be written
+    pub fn save(&self, repo: &git2::Repository) -> Result<()> {
+        let path = cache_path(repo);
+        let tmp_path = path.with_extension("json.tmp");
+
+        // write to a temporary file first, so that a concurrent reader never sees half a cache
+        fs::write(&tmp_path, serde_json::to_vec(self)?)?;
+        fs::rename(&tmp_path, &path)?;
+        Ok(())
+    }
+}
+
+fn cache_path(repo: &git2::Repository) -> PathBuf {
+    repo.path().join(CACHE_FILE_NAME)
+}
+
+/// Returns the paths that differ between the `old` and `new` commits, or an `Err` if `new`
+/// isn't a descendant of `old`, in which case the history has been rewritten.
+fn changed_paths(repo: &git2::Repository, old: &str, new: git2::Oid) -> Result<Vec<PathBuf>> {
+    let old = git2::Oid::from_str(old)?;
+    if !repo.graph_descendant_of(new, old)? {
+        return Err(Error::Generic("the history has been rewritten"));
+    }
+
+    let old_tree = repo.find_commit(old)?.tree()?;
+    let new_tree = repo.find_commit(new)?.tree()?;
+    let diff = repo.diff_tree_to_tree(Some(&old_tree), Some(&new_tree), None)?;
+
+    let mut changed = vec![];
+    for delta in diff.deltas() {
+        for file in [delta.old_file(), delta.new_file()] {
+            if let Some(path) = file.path() {
+                changed.push(path.to_path_buf());
+            }
+        }
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+#[path = "tests/cache.rs"]
+mod cache_tests;
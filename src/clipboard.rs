@@ -0,0 +1,234 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use sha1::{Digest, Sha1};
+use zeroize::Zeroize;
+
+use crate::error::{Error, Result};
+
+/// How long a copied secret stays on the clipboard, if the store doesn't configure anything else.
+pub const DEFAULT_CLIPBOARD_TIMEOUT: Duration = Duration::from_secs(40);
+
+/// The operations the `ClipboardManager` needs from a clipboard.
+pub trait ClipboardBackend: Send {
+    /// Puts the text on the clipboard, marked so that clipboard history tools and clipboard
+    /// managers skip it.
+    /// # Errors
+    /// Returns an `Err` if the clipboard can't be written
+    fn set_secret(&mut self, text: &str) -> Result<()>;
+
+    /// Puts the text on the clipboard.
+    /// # Errors
+    /// Returns an `Err` if the clipboard can't be written
+    fn set_text(&mut self, text: &str) -> Result<()>;
+
+    /// Returns the text that is currently on the clipboard.
+    /// # Errors
+    /// Returns an `Err` if the clipboard can't be read, or doesn't contain text
+    fn get_text(&mut self) -> Result<String>;
+
+    /// Removes everything from the clipboard.
+    /// # Errors
+    /// Returns an `Err` if the clipboard can't be written
+    fn clear(&mut self) -> Result<()>;
+}
+
+/// The clipboard of the desktop, on X11 and Wayland secrets are marked with the
+/// `x-kde-passwordManagerHint` mime type, and on Windows they are excluded from the clipboard
+/// history.
+pub struct SystemClipboard {
+    clipboard: arboard::Clipboard,
+}
+
+impl SystemClipboard {
+    /// Connects to the clipboard of the desktop.
+    /// # Errors
+    /// Returns an `Err` if there is no clipboard available
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            clipboard: arboard::Clipboard::new()?,
+        })
+    }
+}
+
+impl ClipboardBackend for SystemClipboard {
+    fn set_secret(&mut self, text: &str) -> Result<()> {
+        let set = self.clipboard.set();
+        #[cfg(all(
+            unix,
+            not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+        ))]
+        let set = {
+            use arboard::SetExtLinux;
+            set.exclude_from_history()
+        };
+        #[cfg(windows)]
+        let set = {
+            use arboard::SetExtWindows;
+            set.exclude_from_history()
+        };
+        Ok(set.text(text)?)
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<()> {
+        Ok(self.clipboard.set_text(text)?)
+    }
+
+    fn get_text(&mut self) -> Result<String> {
+        Ok(self.clipboard.get_text()?)
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        Ok(self.clipboard.clear()?)
+    }
+}
+
+/// The secret that was copied last, only kept as a salted hash.
+struct CopiedSecret {
+    salt: [u8; 16],
+    hash: [u8; 20],
+    /// Increased for every copied secret, so that the timer of an earlier secret doesn't
+    /// clear a later one early
+    generation: u64,
+}
+
+impl CopiedSecret {
+    fn matches(&self, text: &str) -> bool {
+        hash(&self.salt, text) == self.hash
+    }
+}
+
+fn hash(salt: &[u8; 16], text: &str) -> [u8; 20] {
+    let mut hasher = Sha1::new();
+    hasher.update(salt);
+    hasher.update(text.as_bytes());
+    hasher.finalize().into()
+}
+
+/// Puts secrets on the clipboard and removes them again after a timeout, but only if the
+/// clipboard still holds the secret, so that anything the user copied in the meantime is left
+/// alone.
+///
+/// On X11 and Wayland the clipboard content is served by the process that set it, so the
+/// manager should live for as long as the program runs.
+#[derive(Clone)]
+pub struct ClipboardManager {
+    backend: Arc<Mutex<Box<dyn ClipboardBackend>>>,
+    copied: Arc<Mutex<Option<CopiedSecret>>>,
+    generations: Arc<AtomicU64>,
+}
+
+impl ClipboardManager {
+    /// Creates a manager for the clipboard of the desktop.
+    /// # Errors
+    /// Returns an `Err` if there is no clipboard available
+    pub fn new() -> Result<Self> {
+        Ok(Self::with_backend(Box::new(SystemClipboard::new()?)))
+    }
+
+    /// Creates a manager for another clipboard.
+    pub fn with_backend(backend: Box<dyn ClipboardBackend>) -> Self {
+        Self {
+            backend: Arc::new(Mutex::new(backend)),
+            copied: Arc::new(Mutex::new(None)),
+            generations: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Puts the secret on the clipboard and clears it after `timeout`, unless something else
+    /// has been copied by then.
+    /// # Errors
+    /// Returns an `Err` if the clipboard can't be written
+    pub fn copy_secret(&self, secret: &str, timeout: Duration) -> Result<()> {
+        let generation = {
+            let mut copied = self.lock_copied()?;
+            self.lock_backend()?.set_secret(secret)?;
+
+            let salt: [u8; 16] = rand::random();
+            let generation = self.generations.fetch_add(1, Ordering::SeqCst);
+            *copied = Some(CopiedSecret {
+                salt,
+                hash: hash(&salt, secret),
+                generation,
+            });
+            generation
+        };
+
+        let manager = self.clone();
+        thread::spawn(move || {
+            thread::sleep(timeout);
+            let _ = manager.clear_if_unchanged(Some(generation));
+        });
+
+        Ok(())
+    }
+
+    /// Puts text that isn't secret, like the name of a password, on the clipboard. It is not
+    /// cleared.
+    /// # Errors
+    /// Returns an `Err` if the clipboard can't be written
+    pub fn copy_text(&self, text: &str) -> Result<()> {
+        let _copied = self.lock_copied()?;
+        self.lock_backend()?.set_text(text)
+    }
+
+    /// Clears the clipboard right away if it still holds the last copied secret, for example
+    /// when the program exits. Returns true if the clipboard was cleared.
+    /// # Errors
+    /// Returns an `Err` if the clipboard can't be written
+    pub fn clear(&self) -> Result<bool> {
+        self.clear_if_unchanged(None)
+    }
+
+    /// Clears the clipboard if it holds the last copied secret, and that secret is from
+    /// `generation`, if given.
+    fn clear_if_unchanged(&self, generation: Option<u64>) -> Result<bool> {
+        let mut copied = self.lock_copied()?;
+        let Some(secret) = copied.as_ref() else {
+            return Ok(false);
+        };
+        if generation.is_some_and(|g| g != secret.generation) {
+            return Ok(false);
+        }
+
+        let mut backend = self.lock_backend()?;
+        // an error here means that the clipboard holds something that isn't text, and then
+        // it's not our secret
+        let ours = match backend.get_text() {
+            Ok(mut text) => {
+                let ours = secret.matches(&text);
+                text.zeroize();
+                ours
+            }
+            Err(_) => false,
+        };
+        *copied = None;
+
+        if ours {
+            backend.clear()?;
+        }
+        Ok(ours)
+    }
+
+    fn lock_copied(&self) -> Result<std::sync::MutexGuard<'_, Option<CopiedSecret>>> {
+        self.copied
+            .lock()
+            .map_err(|_| Error::Generic("problem locking the mutex"))
+    }
+
+    fn lock_backend(&self) -> Result<std::sync::MutexGuard<'_, Box<dyn ClipboardBackend>>> {
+        self.backend
+            .lock()
+            .map_err(|_| Error::Generic("problem locking the mutex"))
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/clipboard.rs"]
+mod clipboard_tests;
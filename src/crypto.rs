@@ -3,14 +3,18 @@ use std::{
     fmt::{Display, Formatter, Write},
     fs,
     fs::File,
-    io::Write as IoWrite,
-    path::Path,
-    sync::Arc,
+    io::{Read, Write as IoWrite},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
 };
 
+use chrono::{DateTime, Local};
 use hex::FromHex;
 use sequoia_openpgp::{
-    crypto::SessionKey,
+    crypto::{Password, SessionKey},
+    packet::key::{SecretParts, UnspecifiedRole},
     parse::{
         stream::{
             DecryptionHelper, DecryptorBuilder, DetachedVerifierBuilder, MessageLayer,
@@ -24,15 +28,18 @@ use sequoia_openpgp::{
         Serialize,
     },
     types::{RevocationStatus, SymmetricAlgorithm},
-    Cert, KeyHandle,
+    Cert, Fingerprint, KeyHandle,
 };
+use sha1::{Digest, Sha1};
 use zeroize::Zeroize;
 
 pub use crate::error::{Error, Result};
 use crate::{
     crypto::VerificationError::InfrastructureError,
+    discovery::{find_key, KeySource},
     pass::OwnerTrustLevel,
-    signature::{KeyRingStatus, Recipient, SignatureStatus},
+    signature::{EncryptionSubkey, KeyRingStatus, Recipient, SignatureStatus},
+    wot::web_of_trust,
 };
 
 /// The different pgp implementations we support
@@ -43,6 +50,26 @@ pub enum CryptoImpl {
     GpgMe,
     /// Implemented with the help of the sequoia crate
     Sequoia,
+    /// Implemented with the help of the age crate, in the same format as passage
+    Age,
+}
+
+impl CryptoImpl {
+    /// Returns the extension of the password files, without the leading dot
+    pub fn password_file_extension(&self) -> &'static str {
+        match self {
+            Self::GpgMe | Self::Sequoia => "gpg",
+            Self::Age => "age",
+        }
+    }
+
+    /// Returns the name of the file that lists the recipients of the passwords in a directory
+    pub fn recipients_file_name(&self) -> &'static str {
+        match self {
+            Self::GpgMe | Self::Sequoia => ".gpg-id",
+            Self::Age => ".age-recipients",
+        }
+    }
 }
 
 impl std::convert::TryFrom<&str> for CryptoImpl {
@@ -52,8 +79,9 @@ impl std::convert::TryFrom<&str> for CryptoImpl {
         match value {
             "gpg" => Ok(Self::GpgMe),
             "sequoia" => Ok(Self::Sequoia),
+            "age" => Ok(Self::Age),
             _ => Err(Error::Generic(
-                "unknown pgp implementation value, valid values are 'gpg' and 'sequoia'",
+                "unknown pgp implementation value, valid values are 'gpg', 'sequoia' and 'age'",
             )),
         }
     }
@@ -64,6 +92,7 @@ impl Display for CryptoImpl {
         match self {
             Self::GpgMe => write!(f, "gpg"),
             Self::Sequoia => write!(f, "sequoia"),
+            Self::Age => write!(f, "age"),
         }?;
         Ok(())
     }
@@ -124,6 +153,18 @@ pub trait Key {
 
     /// returns if the key isn't usable
     fn is_not_usable(&self) -> bool;
+
+    /// returns when the key was created, if it's known
+    fn creation_time(&self) -> Option<DateTime<Local>>;
+
+    /// returns when the key expires, `None` if it never does
+    fn expiration_time(&self) -> Option<DateTime<Local>>;
+
+    /// returns why the key was revoked, `None` if it isn't revoked
+    fn revocation_reason(&self) -> Option<String>;
+
+    /// returns the subkeys that can currently be used to encrypt to the key
+    fn encryption_subkeys(&self) -> Vec<EncryptionSubkey>;
 }
 
 /// A key gotten from gpgme
@@ -153,6 +194,51 @@ impl Key for GpgMeKey {
             || self.key.is_disabled()
             || self.key.is_invalid()
     }
+
+    fn creation_time(&self) -> Option<DateTime<Local>> {
+        self.key.primary_key()?.creation_time().map(DateTime::from)
+    }
+
+    fn expiration_time(&self) -> Option<DateTime<Local>> {
+        self.key
+            .primary_key()?
+            .expiration_time()
+            .map(DateTime::from)
+    }
+
+    fn revocation_reason(&self) -> Option<String> {
+        // gpgme doesn't list the reason of revocations
+        self.key.is_revoked().then(|| "no reason given".to_owned())
+    }
+
+    fn encryption_subkeys(&self) -> Vec<EncryptionSubkey> {
+        self.key
+            .subkeys()
+            .filter(|subkey| {
+                subkey.can_encrypt()
+                    && !subkey.is_revoked()
+                    && !subkey.is_expired()
+                    && !subkey.is_disabled()
+                    && !subkey.is_invalid()
+            })
+            .map(|subkey| EncryptionSubkey {
+                fingerprint: subkey.fingerprint().unwrap_or("?").to_owned(),
+                created: subkey.creation_time().map(DateTime::from),
+                expires: subkey.expiration_time().map(DateTime::from),
+            })
+            .collect()
+    }
+}
+
+/// How long an unlocked secret key is kept in memory, if the store doesn't configure it.
+pub const DEFAULT_KEY_CACHE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Asks the user for the passphrase of a locked secret key. Each frontend implements this
+/// with its own dialog, returning `None` means that the user didn't answer.
+pub trait PassphrasePrompt {
+    /// Asks for the passphrase of the secret key described by `key`, its user id and
+    /// fingerprint. `attempt` starts at 1 and is increased after every wrong passphrase.
+    fn passphrase(&self, key: &str, attempt: u32) -> Option<String>;
 }
 
 /// All operations that can be done through pgp, either with gpgme or sequoia.
@@ -168,6 +254,12 @@ pub trait Crypto {
     /// isn't capable of encrypting.
     fn encrypt_string(&self, plaintext: &str, recipients: &[Recipient]) -> Result<Vec<u8>>;
 
+    /// Encrypts binary data, like an archive
+    /// # Errors
+    /// Will return `Err` if encryption fails, for example if the current users key
+    /// isn't capable of encrypting.
+    fn encrypt_bytes(&self, plaintext: &[u8], recipients: &[Recipient]) -> Result<Vec<u8>>;
+
     /// Returns a gpg signature for the supplied string. Suitable to add to a gpg commit.
     /// # Errors
     /// Will return `Err` if signing fails, for example if the current users key
@@ -192,10 +284,15 @@ pub trait Crypto {
     /// Returns true if a recipient is in the users keyring.
     fn is_key_in_keyring(&self, recipient: &Recipient) -> Result<bool>;
 
-    /// Pull keys from the keyserver for those recipients.
+    /// Pull keys for those recipients, from the first of the `sources` that has them.
     /// # Errors
     /// Will return `Err` on network errors and similar.
-    fn pull_keys(&mut self, recipients: &[&Recipient], config_path: &Path) -> Result<String>;
+    fn pull_keys(
+        &mut self,
+        recipients: &[&Recipient],
+        config_path: &Path,
+        sources: &[KeySource],
+    ) -> Result<String>;
 
     /// Import a key from text.
     /// # Errors
@@ -217,6 +314,18 @@ pub trait Crypto {
 
     /// Returns the fingerprint of the user using ripasso
     fn own_fingerprint(&self) -> Option<[u8; 20]>;
+
+    /// Sets the prompt that asks for the passphrase of locked secret keys. Implementations
+    /// where another program, like gpg-agent, asks for it ignore the prompt.
+    fn set_passphrase_prompt(&mut self, _prompt: Arc<dyn PassphrasePrompt + Send + Sync>) {}
+
+    /// Sets how long an unlocked secret key is kept in memory, zero to ask for the passphrase
+    /// every time.
+    fn set_key_cache_timeout(&mut self, _timeout: Duration) {}
+
+    /// Sets the fingerprints of the keys that are trusted to introduce other keys, in addition
+    /// to the users own key. Implementations that keep their own trust database ignore them.
+    fn set_trust_roots(&mut self, _roots: Vec<[u8; 20]>) {}
 }
 
 /// Used when the user configures gpgme to be used as a pgp backend.
@@ -234,6 +343,10 @@ impl Crypto for GpgMe {
     }
 
     fn encrypt_string(&self, plaintext: &str, recipients: &[Recipient]) -> Result<Vec<u8>> {
+        self.encrypt_bytes(plaintext.as_bytes(), recipients)
+    }
+
+    fn encrypt_bytes(&self, plaintext: &[u8], recipients: &[Recipient]) -> Result<Vec<u8>> {
         let mut ctx = gpgme::Context::from_protocol(gpgme::Protocol::OpenPgp)?;
         ctx.set_armor(false);
 
@@ -355,12 +468,17 @@ impl Crypto for GpgMe {
         }
     }
 
-    fn pull_keys(&mut self, recipients: &[&Recipient], _config_path: &Path) -> Result<String> {
+    fn pull_keys(
+        &mut self,
+        recipients: &[&Recipient],
+        _config_path: &Path,
+        sources: &[KeySource],
+    ) -> Result<String> {
         let mut ctx = gpgme::Context::from_protocol(gpgme::Protocol::OpenPgp)?;
 
         let mut result_str = String::new();
         for recipient in recipients {
-            let response = download_keys(&recipient.key_id)?;
+            let response = find_key(sources, &recipient.key_id)?;
 
             let result = ctx.import(response)?;
 
@@ -419,25 +537,6 @@ impl Crypto for GpgMe {
     }
 }
 
-/// Tries to download keys from keys.openpgp.org
-fn download_keys(recipient_key_id: &str) -> Result<String> {
-    let url = match recipient_key_id.len() {
-        16 => format!("https://keys.openpgp.org/vks/v1/by-keyid/{recipient_key_id}"),
-        18 if recipient_key_id.starts_with("0x") => format!(
-            "https://keys.openpgp.org/vks/v1/by-keyid/{}",
-            &recipient_key_id[2..]
-        ),
-        40 => format!("https://keys.openpgp.org/vks/v1/by-fingerprint/{recipient_key_id}"),
-        42 if recipient_key_id.starts_with("0x") => format!(
-            "https://keys.openpgp.org/vks/v1/by-fingerprint/{}",
-            &recipient_key_id[2..]
-        ),
-        _ => return Err(Error::Generic("key id is not 16 or 40 hex chars")),
-    };
-
-    Ok(reqwest::blocking::get(url)?.text()?)
-}
-
 /// Internal helper struct for sequoia implementation.
 struct Helper<'a> {
     /// A sequoia policy to use in various operations
@@ -450,6 +549,8 @@ struct Helper<'a> {
     public_keys: Vec<Arc<sequoia_openpgp::Cert>>,
     /// context if talking to gpg_agent for example
     ctx: Option<sequoia_gpg_agent::gnupg::Context>,
+    /// unlocks the secret keys of the users cert that are protected by a passphrase
+    unlocker: Option<&'a KeyUnlocker>,
     /// to do verification or not
     do_signature_verification: bool,
 }
@@ -544,37 +645,173 @@ impl<'a> DecryptionHelper for Helper<'a> {
 
             return Ok(selected_fingerprint);
         }
-        // The encryption key is the first and only subkey.
-        let key = self
+        let secret = self
             .secret
-            .ok_or_else(|| anyhow::anyhow!("no user secret"))?
+            .ok_or_else(|| anyhow::anyhow!("no user secret"))?;
+        let unlocker = self
+            .unlocker
+            .ok_or_else(|| anyhow::anyhow!("no way to unlock the secret keys"))?;
+
+        let keys: Vec<_> = secret
             .keys()
-            .unencrypted_secret()
+            .secret()
             .with_policy(self.policy, None)
             .for_transport_encryption()
-            .next()
-            .ok_or_else(|| anyhow::anyhow!("no keys capable of encryption"))?
-            .key()
-            .clone();
-
-        // The secret key is not encrypted.
-        let mut pair = key.into_keypair()?;
+            .map(|k| k.key().clone())
+            .collect();
+        if keys.is_empty() {
+            return Err(anyhow::anyhow!("no keys capable of encryption"));
+        }
 
-        for pkesk in pkesks {
-            if pkesk
-                .decrypt(&mut pair, sym_algo)
-                .map(|(algo, sk)| decrypt(algo, &sk))
-                .unwrap_or(false)
+        // a key that can't be unlocked, like when the prompt was cancelled, doesn't stop the
+        // other keys that the message is encrypted for from being tried
+        let mut unlock_error = None;
+        for key in keys {
+            let keyid = key.keyid();
+            // only ask for the passphrase of keys that the message is encrypted for
+            if !pkesks
+                .iter()
+                .any(|pkesk| pkesk.recipient().is_wildcard() || *pkesk.recipient() == keyid)
             {
-                return Ok(Some(
-                    self.secret
-                        .ok_or_else(|| anyhow::anyhow!("no user secret"))?
-                        .fingerprint(),
-                ));
+                continue;
+            }
+
+            let unlocked = unlocker
+                .unlock(secret, key)
+                .map_err(|err| anyhow::anyhow!("{err}"))
+                .and_then(|key| key.into_keypair());
+            let mut pair = match unlocked {
+                Ok(pair) => pair,
+                Err(err) => {
+                    unlock_error = Some(err);
+                    continue;
+                }
+            };
+            for pkesk in pkesks {
+                if pkesk
+                    .decrypt(&mut pair, sym_algo)
+                    .map(|(algo, sk)| decrypt(algo, &sk))
+                    .unwrap_or(false)
+                {
+                    return Ok(Some(secret.fingerprint()));
+                }
             }
         }
 
-        Err(anyhow::anyhow!("no pkesks managed to descrypt ciphertext"))
+        Err(unlock_error
+            .unwrap_or_else(|| anyhow::anyhow!("no pkesks managed to descrypt ciphertext")))
+    }
+}
+
+/// A secret key, primary key or subkey, from a cert.
+type SecretKey = sequoia_openpgp::packet::Key<SecretParts, UnspecifiedRole>;
+
+/// Unlocked secret keys, and when they expire.
+type UnlockedKeys = Mutex<HashMap<Fingerprint, (SecretKey, Instant)>>;
+
+/// Unlocks the secret keys that are protected by a passphrase, by asking the user for it, and
+/// keeps the unlocked keys in memory for a while so that the user isn't asked every time.
+struct KeyUnlocker {
+    /// asks for the passphrases, locked keys can't be used without one
+    prompt: Option<Arc<dyn PassphrasePrompt + Send + Sync>>,
+    /// how long an unlocked key is kept
+    timeout: Duration,
+    /// the unlocked keys, and when they expire, they are removed by a timer when they do
+    cache: Arc<UnlockedKeys>,
+}
+
+impl KeyUnlocker {
+    /// How many times the user is asked, when the passphrase is wrong.
+    const ATTEMPTS: u32 = 3;
+
+    fn new() -> Self {
+        Self {
+            prompt: None,
+            timeout: DEFAULT_KEY_CACHE_TIMEOUT,
+            cache: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Sets how long an unlocked key is kept, and forgets the keys that are unlocked.
+    fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+        self.clear();
+    }
+
+    /// Forgets all the unlocked keys.
+    fn clear(&self) {
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.clear();
+        }
+    }
+
+    /// Forgets the unlocked keys in `cache` that have expired.
+    fn evict_expired(cache: &UnlockedKeys) {
+        if let Ok(mut cache) = cache.lock() {
+            let now = Instant::now();
+            cache.retain(|_, (_, until)| *until > now);
+        }
+    }
+
+    /// Returns `key`, a secret key of `cert`, with its secret unencrypted.
+    /// # Errors
+    /// Errors if the key is locked and there is no prompt, the user didn't answer or gave the
+    /// wrong passphrase too many times.
+    fn unlock(&self, cert: &Cert, key: SecretKey) -> Result<SecretKey> {
+        if !key.secret().is_encrypted() {
+            return Ok(key);
+        }
+
+        let fingerprint = key.fingerprint();
+        let mut cache = self
+            .cache
+            .lock()
+            .map_err(|_| Error::Generic("Error obtaining lock"))?;
+        let now = Instant::now();
+        cache.retain(|_, (_, until)| *until > now);
+        if let Some((unlocked, _)) = cache.get(&fingerprint) {
+            return Ok(unlocked.clone());
+        }
+
+        let prompt = self.prompt.as_ref().ok_or(Error::Generic(
+            "the secret key is protected by a passphrase, and there is no way to ask for it",
+        ))?;
+        let description = match cert.userids().next() {
+            Some(user_id) => format!("{} ({})", user_id.userid(), fingerprint.to_hex()),
+            None => fingerprint.to_hex(),
+        };
+
+        for attempt in 1..=Self::ATTEMPTS {
+            let mut passphrase = prompt
+                .passphrase(&description, attempt)
+                .ok_or(Error::Generic("no passphrase was given for the secret key"))?;
+            let password = Password::from(passphrase.as_str());
+            passphrase.zeroize();
+
+            if let Ok(unlocked) = key.clone().decrypt_secret(&password) {
+                if !self.timeout.is_zero() {
+                    cache.insert(fingerprint, (unlocked.clone(), now + self.timeout));
+
+                    let cache = Arc::downgrade(&self.cache);
+                    let timeout = self.timeout;
+                    thread::spawn(move || {
+                        thread::sleep(timeout);
+                        if let Some(cache) = cache.upgrade() {
+                            Self::evict_expired(&cache);
+                        }
+                    });
+                }
+                return Ok(unlocked);
+            }
+        }
+
+        Err(Error::Generic("wrong passphrase for the secret key"))
+    }
+}
+
+impl Drop for KeyUnlocker {
+    fn drop(&mut self) {
+        self.clear();
     }
 }
 
@@ -616,6 +853,59 @@ impl Key for SequoiaKey {
         self.cert.revocation_status(&p, None) != RevocationStatus::NotAsFarAsWeKnow
             || policy.alive().is_err()
     }
+
+    fn creation_time(&self) -> Option<DateTime<Local>> {
+        Some(self.cert.primary_key().key().creation_time().into())
+    }
+
+    fn expiration_time(&self) -> Option<DateTime<Local>> {
+        let p = sequoia_openpgp::policy::StandardPolicy::new();
+
+        self.cert
+            .with_policy(&p, None)
+            .ok()?
+            .primary_key()
+            .key_expiration_time()
+            .map(DateTime::from)
+    }
+
+    fn revocation_reason(&self) -> Option<String> {
+        let p = sequoia_openpgp::policy::StandardPolicy::new();
+
+        match self.cert.revocation_status(&p, None) {
+            RevocationStatus::Revoked(sigs) => Some(
+                sigs.iter()
+                    .find_map(|sig| sig.reason_for_revocation())
+                    .map_or("no reason given".to_owned(), |(code, message)| {
+                        let message = String::from_utf8_lossy(message);
+                        if message.is_empty() {
+                            code.to_string()
+                        } else {
+                            format!("{code}, {message}")
+                        }
+                    }),
+            ),
+            _ => None,
+        }
+    }
+
+    fn encryption_subkeys(&self) -> Vec<EncryptionSubkey> {
+        let p = sequoia_openpgp::policy::StandardPolicy::new();
+
+        self.cert
+            .keys()
+            .with_policy(&p, None)
+            .supported()
+            .alive()
+            .revoked(false)
+            .for_transport_encryption()
+            .map(|key| EncryptionSubkey {
+                fingerprint: key.key().fingerprint().to_hex(),
+                created: Some(key.key().creation_time().into()),
+                expires: key.key_expiration_time().map(DateTime::from),
+            })
+            .collect()
+    }
 }
 
 /// If the users configures to use sequoia as their pgp implementation.
@@ -626,6 +916,10 @@ pub struct Sequoia {
     key_ring: HashMap<[u8; 20], Arc<sequoia_openpgp::Cert>>,
     /// The home directory of the user, for gnupg context
     user_home: std::path::PathBuf,
+    /// Unlocks the users secret keys that are protected by a passphrase
+    unlocker: KeyUnlocker,
+    /// Keys that are trusted to introduce other keys, in addition to the users own key
+    trust_roots: Vec<[u8; 20]>,
 }
 
 impl Sequoia {
@@ -654,6 +948,8 @@ impl Sequoia {
             user_key_id: own_fingerprint,
             key_ring,
             user_home: user_home.to_path_buf(),
+            unlocker: KeyUnlocker::new(),
+            trust_roots: vec![],
         })
     }
 
@@ -666,6 +962,8 @@ impl Sequoia {
             user_key_id,
             key_ring,
             user_home: user_home.to_path_buf(),
+            unlocker: KeyUnlocker::new(),
+            trust_roots: vec![],
         }
     }
 
@@ -701,11 +999,16 @@ impl Sequoia {
         Ok(result)
     }
 
-    /// Download keys from the internet and write them to the keys dir.
+    /// Finds a key in the key sources and writes it to the keys dir.
     /// # Errors
     /// Errors on download problems
-    fn pull_and_write(&mut self, key_id: &str, keys_dir: &Path) -> Result<String> {
-        let response = download_keys(key_id)?;
+    fn pull_and_write(
+        &mut self,
+        key_id: &str,
+        sources: &[KeySource],
+        keys_dir: &Path,
+    ) -> Result<String> {
+        let response = find_key(sources, key_id)?;
 
         self.write_cert(&response, keys_dir)
     }
@@ -748,19 +1051,23 @@ impl Crypto for Sequoia {
                 key_ring: &self.key_ring,
                 public_keys: vec![],
                 ctx: None,
+                unlocker: Some(&self.unlocker),
                 do_signature_verification: false,
             };
 
             // Now, create a decryptor with a helper using the given Certs.
-            let mut decryptor = DecryptorBuilder::from_bytes(ciphertext)?
-                .with_policy(&p, None, helper)
-                .unwrap();
+            let mut decryptor =
+                DecryptorBuilder::from_bytes(ciphertext)?.with_policy(&p, None, helper)?;
 
             // Decrypt the data.
-            std::io::copy(&mut decryptor, &mut sink).unwrap();
-            let result = std::str::from_utf8(&sink).unwrap().to_owned();
+            let res = match std::io::copy(&mut decryptor, &mut sink) {
+                Ok(_) => std::str::from_utf8(&sink)
+                    .map(str::to_owned)
+                    .map_err(Error::from),
+                Err(err) => Err(Error::from(err)),
+            };
             sink.zeroize();
-            Ok(result)
+            res
         } else {
             // Make a helper that that feeds the recipient's secret key to the
             // decryptor.
@@ -773,6 +1080,7 @@ impl Crypto for Sequoia {
                     sequoia_gpg_agent::gnupg::Context::with_homedir(&self.user_home)
                         .map_err(anyhow::Error::from)?,
                 ),
+                unlocker: None,
                 do_signature_verification: false,
             };
 
@@ -789,6 +1097,10 @@ impl Crypto for Sequoia {
     }
 
     fn encrypt_string(&self, plaintext: &str, recipients: &[Recipient]) -> Result<Vec<u8>> {
+        self.encrypt_bytes(plaintext.as_bytes(), recipients)
+    }
+
+    fn encrypt_bytes(&self, plaintext: &[u8], recipients: &[Recipient]) -> Result<Vec<u8>> {
         let p = sequoia_openpgp::policy::StandardPolicy::new();
 
         let mut recipient_keys = vec![];
@@ -818,7 +1130,7 @@ impl Crypto for Sequoia {
         let mut message = LiteralWriter::new(message).build()?;
 
         // Encrypt the data.
-        message.write_all(plaintext.as_bytes())?;
+        message.write_all(plaintext)?;
 
         // Finalize the OpenPGP message to make sure that all data is
         // written.
@@ -841,9 +1153,9 @@ impl Crypto for Sequoia {
             .ok_or(Error::Generic("no key for user found"))?;
 
         // Get the keypair to do the signing from the Cert.
-        let keypair = tsk
+        let key = tsk
             .keys()
-            .unencrypted_secret()
+            .secret()
             .with_policy(&p, None)
             .alive()
             .revoked(false)
@@ -851,8 +1163,8 @@ impl Crypto for Sequoia {
             .next()
             .ok_or_else(|| anyhow::anyhow!("no cert valid for signing"))?
             .key()
-            .clone()
-            .into_keypair()?;
+            .clone();
+        let keypair = self.unlocker.unlock(tsk, key)?.into_keypair()?;
 
         let mut sink: Vec<u8> = vec![];
 
@@ -905,6 +1217,7 @@ impl Crypto for Sequoia {
             key_ring: &self.key_ring,
             public_keys: senders,
             ctx: None,
+            unlocker: None,
             do_signature_verification: true,
         };
 
@@ -926,13 +1239,18 @@ impl Crypto for Sequoia {
         }
     }
 
-    fn pull_keys(&mut self, recipients: &[&Recipient], config_path: &Path) -> Result<String> {
+    fn pull_keys(
+        &mut self,
+        recipients: &[&Recipient],
+        config_path: &Path,
+        sources: &[KeySource],
+    ) -> Result<String> {
         let p = config_path.join("share").join("ripasso").join("keys");
         std::fs::create_dir_all(&p)?;
 
         let mut ret = String::new();
         for recipient in recipients {
-            let res = self.pull_and_write(&recipient.key_id, &p);
+            let res = self.pull_and_write(&recipient.key_id, sources, &p);
 
             write!(ret, "{}: ", &recipient.key_id)?;
             match res {
@@ -966,13 +1284,12 @@ impl Crypto for Sequoia {
     }
 
     fn get_all_trust_items(&self) -> Result<HashMap<[u8; 20], OwnerTrustLevel>> {
-        let mut res: HashMap<[u8; 20], OwnerTrustLevel> = HashMap::new();
+        let p = sequoia_openpgp::policy::StandardPolicy::new();
 
-        for k in self.key_ring.keys() {
-            res.insert(*k, OwnerTrustLevel::Ultimate);
-        }
+        let mut roots = vec![self.user_key_id];
+        roots.extend_from_slice(&self.trust_roots);
 
-        Ok(res)
+        Ok(web_of_trust(&self.key_ring, &roots, &p))
     }
 
     fn implementation(&self) -> CryptoImpl {
@@ -982,6 +1299,223 @@ impl Crypto for Sequoia {
     fn own_fingerprint(&self) -> Option<[u8; 20]> {
         Some(self.user_key_id)
     }
+
+    fn set_passphrase_prompt(&mut self, prompt: Arc<dyn PassphrasePrompt + Send + Sync>) {
+        self.unlocker.prompt = Some(prompt);
+    }
+
+    fn set_key_cache_timeout(&mut self, timeout: Duration) {
+        self.unlocker.set_timeout(timeout);
+    }
+
+    fn set_trust_roots(&mut self, roots: Vec<[u8; 20]>) {
+        self.trust_roots = roots;
+    }
+}
+
+/// An age recipient, an X25519 public key or a ssh public key.
+pub struct AgeKey {
+    /// The recipient as it's written in the `.age-recipients` file
+    recipient: String,
+}
+
+impl Key for AgeKey {
+    fn user_id_names(&self) -> Vec<String> {
+        // ssh keys have the name of their owner after the key
+        match self.recipient.split_whitespace().nth(2) {
+            Some(comment) => vec![comment.to_owned()],
+            None if self.recipient.starts_with("ssh-") => vec!["ssh key".to_owned()],
+            None => vec!["age key".to_owned()],
+        }
+    }
+
+    fn fingerprint(&self) -> Result<[u8; 20]> {
+        // age keys don't have fingerprints, so the hash of the key is used to tell them apart
+        let key: Vec<&str> = self.recipient.split_whitespace().take(2).collect();
+        Ok(Sha1::digest(key.join(" ").as_bytes()).into())
+    }
+
+    fn is_not_usable(&self) -> bool {
+        false
+    }
+
+    fn creation_time(&self) -> Option<DateTime<Local>> {
+        None
+    }
+
+    fn expiration_time(&self) -> Option<DateTime<Local>> {
+        None
+    }
+
+    fn revocation_reason(&self) -> Option<String> {
+        None
+    }
+
+    fn encryption_subkeys(&self) -> Vec<EncryptionSubkey> {
+        // age keys are encrypted to directly and never expire
+        vec![EncryptionSubkey {
+            fingerprint: self.fingerprint().map_or(String::new(), hex::encode_upper),
+            created: None,
+            expires: None,
+        }]
+    }
+}
+
+/// Parses an age recipient, either an X25519 public key or a ssh public key.
+fn parse_age_recipient(recipient: &str) -> Result<Box<dyn age::Recipient + Send>> {
+    let recipient = recipient.trim();
+    if let Ok(r) = recipient.parse::<age::x25519::Recipient>() {
+        return Ok(Box::new(r));
+    }
+    match recipient.parse::<age::ssh::Recipient>() {
+        Ok(r) => Ok(Box::new(r)),
+        Err(_) => Err(Error::GenericDyn(format!(
+            "{recipient} isn't an age or ssh public key"
+        ))),
+    }
+}
+
+/// If the user configures to use age, for stores in the format of passage. Age has no
+/// key ring, signatures or key servers, the recipients are the public keys themselves.
+pub struct Age {
+    /// The file with the identities, the private keys, of the user
+    identities_file: PathBuf,
+}
+
+impl Age {
+    /// creates the age object, that decrypts with the identities in `identities_file`
+    pub fn new(identities_file: &Path) -> Self {
+        Self {
+            identities_file: identities_file.to_path_buf(),
+        }
+    }
+
+    /// Reads the identities of the user, either a file of age identities or a ssh private key.
+    /// # Errors
+    /// Errors if the file can't be read or doesn't contain identities, or if it's protected
+    /// with a passphrase, which isn't supported
+    fn identities(&self) -> Result<Vec<Box<dyn age::Identity>>> {
+        let filename = self.identities_file.to_string_lossy().to_string();
+        let data = fs::read(&self.identities_file)?;
+
+        if data.starts_with(b"age-encryption.org/")
+            || data.starts_with(b"-----BEGIN AGE ENCRYPTED FILE-----")
+        {
+            return Err(Error::GenericDyn(format!(
+                "the identities file {filename} is protected with a passphrase, which isn't supported"
+            )));
+        }
+        if data.starts_with(b"-----BEGIN") {
+            match age::ssh::Identity::from_buffer(data.as_slice(), Some(filename.clone()))? {
+                age::ssh::Identity::Encrypted(_) => Err(Error::GenericDyn(format!(
+                    "the ssh key {filename} is protected with a passphrase, which isn't supported"
+                ))),
+                age::ssh::Identity::Unsupported(_) => Err(Error::GenericDyn(format!(
+                    "the ssh key {filename} is of a type that age doesn't support"
+                ))),
+                identity => Ok(vec![Box::new(identity)]),
+            }
+        } else {
+            Ok(age::IdentityFile::from_buffer(data.as_slice())?.into_identities()?)
+        }
+    }
+}
+
+impl Crypto for Age {
+    fn decrypt_string(&self, ciphertext: &[u8]) -> Result<String> {
+        let identities = self.identities()?;
+        let decryptor = age::Decryptor::new_buffered(ciphertext)?;
+
+        let mut sink: Vec<u8> = vec![];
+        let mut reader =
+            decryptor.decrypt(identities.iter().map(|i| i.as_ref() as &dyn age::Identity))?;
+        reader.read_to_end(&mut sink)?;
+
+        let result = std::str::from_utf8(&sink)?.to_owned();
+        sink.zeroize();
+        Ok(result)
+    }
+
+    fn encrypt_string(&self, plaintext: &str, recipients: &[Recipient]) -> Result<Vec<u8>> {
+        self.encrypt_bytes(plaintext.as_bytes(), recipients)
+    }
+
+    fn encrypt_bytes(&self, plaintext: &[u8], recipients: &[Recipient]) -> Result<Vec<u8>> {
+        let recipients = recipients
+            .iter()
+            .map(|r| parse_age_recipient(&r.key_id))
+            .collect::<Result<Vec<_>>>()?;
+
+        let encryptor = age::Encryptor::with_recipients(
+            recipients.iter().map(|r| r.as_ref() as &dyn age::Recipient),
+        )?;
+
+        let mut sink: Vec<u8> = vec![];
+        let mut writer = encryptor.wrap_output(&mut sink)?;
+        writer.write_all(plaintext)?;
+        writer.finish()?;
+
+        Ok(sink)
+    }
+
+    fn sign_string(
+        &self,
+        _to_sign: &str,
+        _valid_gpg_signing_keys: &[[u8; 20]],
+        _strategy: &FindSigningFingerprintStrategy,
+    ) -> Result<String> {
+        Err(Error::Generic("age can't sign"))
+    }
+
+    fn verify_sign(
+        &self,
+        _data: &[u8],
+        _sig: &[u8],
+        _valid_signing_keys: &[[u8; 20]],
+    ) -> std::result::Result<SignatureStatus, VerificationError> {
+        Err(InfrastructureError(
+            "age can't verify signatures".to_owned(),
+        ))
+    }
+
+    fn is_key_in_keyring(&self, recipient: &Recipient) -> Result<bool> {
+        Ok(parse_age_recipient(&recipient.key_id).is_ok())
+    }
+
+    fn pull_keys(
+        &mut self,
+        _recipients: &[&Recipient],
+        _config_path: &Path,
+        _sources: &[KeySource],
+    ) -> Result<String> {
+        Err(Error::Generic("age recipients can't be downloaded"))
+    }
+
+    fn import_key(&mut self, _key: &str, _config_path: &Path) -> Result<String> {
+        Err(Error::Generic(
+            "age recipients are added by their public key, not imported",
+        ))
+    }
+
+    fn get_key(&self, key_id: &str) -> Result<Box<dyn Key>> {
+        parse_age_recipient(key_id)?;
+
+        Ok(Box::new(AgeKey {
+            recipient: key_id.trim().to_owned(),
+        }))
+    }
+
+    fn get_all_trust_items(&self) -> Result<HashMap<[u8; 20], OwnerTrustLevel>> {
+        Ok(HashMap::new())
+    }
+
+    fn implementation(&self) -> CryptoImpl {
+        CryptoImpl::Age
+    }
+
+    fn own_fingerprint(&self) -> Option<[u8; 20]> {
+        None
+    }
 }
 
 #[cfg(test)]
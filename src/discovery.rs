@@ -0,0 +1,328 @@
+use std::{
+    fmt::{Display, Formatter},
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use sequoia_openpgp::{cert::CertParser, parse::Parse, serialize::SerializeInto, Cert};
+use sha1::{Digest, Sha1};
+
+use crate::error::{Error, Result};
+
+/// The keyserver that is used when a store doesn't configure any key sources.
+pub const DEFAULT_KEYSERVER: &str = "https://keys.openpgp.org";
+
+/// The port of HKP keyservers that are given without one.
+const HKP_PORT: u16 = 11371;
+
+/// A place to look for the key of a recipient that isn't in the keyring.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeySource {
+    /// The Web Key Directory of the domain of the email address, at `https://openpgpkey.<domain>`
+    /// and then `https://<domain>`, or at the url if one is set with `wkd:<url>`. WKD only finds
+    /// recipients that are given by email address in the `.gpg-id` file, not the ones that are
+    /// given by key id or fingerprint.
+    Wkd(Option<String>),
+    /// A HKP keyserver, `hkp://` or `hkps://` followed by the host
+    Hkp(String),
+    /// A VKS keyserver like keys.openpgp.org, `http://` or `https://` followed by the host
+    Vks(String),
+    /// A directory of keys in `.asc` files
+    Directory(PathBuf),
+}
+
+impl KeySource {
+    /// Parses a comma separated list of key sources, `wkd` or `wkd:` followed by the url of the
+    /// directory, the url of a HKP or VKS keyserver, or the path of a directory, in the order
+    /// they should be tried.
+    /// # Errors
+    /// Returns an `Err` if a source can't be parsed
+    pub fn parse_list(list: &str) -> Result<Vec<Self>> {
+        list.split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::parse)
+            .collect()
+    }
+
+    /// Looks for the key that `query` identifies.
+    fn lookup(&self, query: &Query) -> Result<Option<Cert>> {
+        let certs = match self {
+            Self::Wkd(base) => match query {
+                Query::Email(email) => {
+                    let [advanced, direct] = wkd_urls(email, base.as_deref())?;
+                    match fetch(&advanced) {
+                        Ok(certs) if !certs.is_empty() => certs,
+                        _ => fetch(&direct)?,
+                    }
+                }
+                _ => return Ok(None),
+            },
+            Self::Hkp(server) => fetch(&hkp_url(server, query))?,
+            Self::Vks(server) => fetch(&vks_url(server, query))?,
+            Self::Directory(dir) => directory_certs(dir)?,
+        };
+
+        Ok(certs.into_iter().find(|cert| query.matches(cert)))
+    }
+}
+
+impl FromStr for KeySource {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim_end_matches('/');
+        if s.is_empty() {
+            return Err(Error::Generic("a key source can't be empty"));
+        }
+
+        if s == "wkd" {
+            Ok(Self::Wkd(None))
+        } else if let Some(base) = s.strip_prefix("wkd:") {
+            Ok(Self::Wkd(Some(base.to_owned())))
+        } else if s.starts_with("hkp://") || s.starts_with("hkps://") {
+            Ok(Self::Hkp(s.to_owned()))
+        } else if s.starts_with("http://") || s.starts_with("https://") {
+            Ok(Self::Vks(s.to_owned()))
+        } else {
+            Ok(Self::Directory(PathBuf::from(s)))
+        }
+    }
+}
+
+impl Display for KeySource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Wkd(None) => f.write_str("wkd"),
+            Self::Wkd(Some(base)) => write!(f, "wkd:{base}"),
+            Self::Hkp(server) | Self::Vks(server) => f.write_str(server),
+            Self::Directory(dir) => write!(f, "{}", dir.display()),
+        }
+    }
+}
+
+/// The key sources of stores that don't configure any, the same keyserver that ripasso always
+/// used.
+pub fn default_key_sources() -> Vec<KeySource> {
+    vec![KeySource::Vks(DEFAULT_KEYSERVER.to_owned())]
+}
+
+/// Goes through `sources` in order and returns the first key that matches `key_id`, armored.
+/// `key_id` is a key id or fingerprint, optionally starting with `0x`, or an email address.
+/// # Errors
+/// Returns an `Err` if `key_id` isn't any of those, or if none of the sources had the key, with
+/// the errors of the sources that couldn't be reached.
+pub fn find_key(sources: &[KeySource], key_id: &str) -> Result<String> {
+    let query = Query::parse(key_id)?;
+
+    let mut errors = vec![];
+    for source in sources {
+        match source.lookup(&query) {
+            Ok(Some(cert)) => {
+                let armored = cert.strip_secret_key_material().armored().to_vec()?;
+                return Ok(String::from_utf8(armored)?);
+            }
+            Ok(None) => {}
+            Err(err) => errors.push(format!("{source}: {err}")),
+        }
+    }
+
+    if errors.is_empty() {
+        Err(Error::GenericDyn(format!("no key found for {key_id}")))
+    } else {
+        Err(Error::GenericDyn(format!(
+            "no key found for {key_id}, {}",
+            errors.join(", ")
+        )))
+    }
+}
+
+/// What a recipient is identified by.
+#[derive(Debug, PartialEq, Eq)]
+enum Query {
+    /// 16 upper case hex chars
+    KeyId(String),
+    /// 40 upper case hex chars
+    Fingerprint(String),
+    /// in lower case
+    Email(String),
+}
+
+impl Query {
+    fn parse(key_id: &str) -> Result<Self> {
+        let key_id = key_id.trim();
+        if key_id.contains('@') {
+            let email = key_id.trim_start_matches('<').trim_end_matches('>');
+            return Ok(Self::Email(email.to_lowercase()));
+        }
+
+        let hex = key_id.strip_prefix("0x").unwrap_or(key_id);
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(Error::Generic(
+                "key id is not 16 or 40 hex chars or an email address",
+            ));
+        }
+        match hex.len() {
+            16 => Ok(Self::KeyId(hex.to_uppercase())),
+            40 => Ok(Self::Fingerprint(hex.to_uppercase())),
+            _ => Err(Error::Generic(
+                "key id is not 16 or 40 hex chars or an email address",
+            )),
+        }
+    }
+
+    /// If `cert` is the key that was asked for, sources can return other keys than the one
+    /// asked for, and directories contain many.
+    fn matches(&self, cert: &Cert) -> bool {
+        match self {
+            Self::KeyId(id) => cert.keys().any(|key| key.key().keyid().to_hex() == *id),
+            Self::Fingerprint(fp) => cert
+                .keys()
+                .any(|key| key.key().fingerprint().to_hex() == *fp),
+            Self::Email(email) => cert.userids().any(|user_id| {
+                let user_id = String::from_utf8_lossy(user_id.userid().value()).to_lowercase();
+                user_id == *email || user_id.contains(&format!("<{email}>"))
+            }),
+        }
+    }
+
+    /// The search term in the urls of keyservers.
+    fn search(&self) -> String {
+        match self {
+            Self::KeyId(hex) | Self::Fingerprint(hex) => format!("0x{hex}"),
+            Self::Email(email) => percent_encode(email),
+        }
+    }
+}
+
+/// The urls of the key of `email` in the Web Key Directory of its domain, first for the
+/// advanced method and then for the direct method. The directory is looked for at `base`
+/// instead of on the domain if it's set.
+/// # Errors
+/// Returns an `Err` if `email` isn't an email address
+pub fn wkd_urls(email: &str, base: Option<&str>) -> Result<[String; 2]> {
+    let (local, domain) = email
+        .rsplit_once('@')
+        .filter(|(local, domain)| !local.is_empty() && !domain.is_empty())
+        .ok_or(Error::Generic("not an email address"))?;
+    let domain = domain.to_lowercase();
+    let hash = zbase32(&Sha1::digest(local.to_lowercase().as_bytes()));
+    let local = percent_encode(local);
+
+    let (advanced, direct) = match base {
+        Some(base) => (base.to_owned(), base.to_owned()),
+        None => (
+            format!("https://openpgpkey.{domain}"),
+            format!("https://{domain}"),
+        ),
+    };
+
+    Ok([
+        format!("{advanced}/.well-known/openpgpkey/{domain}/hu/{hash}?l={local}"),
+        format!("{direct}/.well-known/openpgpkey/hu/{hash}?l={local}"),
+    ])
+}
+
+/// The lookup url of a HKP keyserver, hkp is plain http on its own port, and hkps is https.
+fn hkp_url(server: &str, query: &Query) -> String {
+    let base = if let Some(host) = server.strip_prefix("hkps://") {
+        format!("https://{host}")
+    } else {
+        let host = server.strip_prefix("hkp://").unwrap_or(server);
+        if host.contains(':') {
+            format!("http://{host}")
+        } else {
+            format!("http://{host}:{HKP_PORT}")
+        }
+    };
+
+    format!(
+        "{base}/pks/lookup?op=get&options=mr&search={}",
+        query.search()
+    )
+}
+
+/// The lookup url of a VKS keyserver.
+fn vks_url(server: &str, query: &Query) -> String {
+    match query {
+        Query::KeyId(hex) => format!("{server}/vks/v1/by-keyid/{hex}"),
+        Query::Fingerprint(hex) => format!("{server}/vks/v1/by-fingerprint/{hex}"),
+        Query::Email(email) => format!("{server}/vks/v1/by-email/{}", percent_encode(email)),
+    }
+}
+
+/// Downloads the keys at `url`, a missing key isn't an error.
+fn fetch(url: &str) -> Result<Vec<Cert>> {
+    let response = reqwest::blocking::get(url)?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(vec![]);
+    }
+    let body = response.error_for_status()?.bytes()?;
+
+    parse_certs(&body)
+}
+
+/// All the keys in the `.asc` files in `dir`.
+fn directory_certs(dir: &Path) -> Result<Vec<Cert>> {
+    let mut certs = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|e| e == "asc") {
+            certs.extend(parse_certs(&fs::read(path)?)?);
+        }
+    }
+
+    Ok(certs)
+}
+
+/// Parses all the keys in `data`, skipping the ones that are broken.
+fn parse_certs(data: &[u8]) -> Result<Vec<Cert>> {
+    if data.is_empty() {
+        return Ok(vec![]);
+    }
+
+    Ok(CertParser::from_bytes(data)?
+        .filter_map(std::result::Result::ok)
+        .collect())
+}
+
+/// Encodes the bytes as z-base-32, the encoding of the hashes in WKD urls.
+fn zbase32(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"ybndrfg8ejkmcpqxot1uwisza345h769";
+
+    let mut res = String::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for byte in data {
+        buffer = (buffer << 8) | u32::from(*byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            res.push(char::from(ALPHABET[((buffer >> bits) & 31) as usize]));
+        }
+    }
+    if bits > 0 {
+        res.push(char::from(ALPHABET[((buffer << (5 - bits)) & 31) as usize]));
+    }
+
+    res
+}
+
+/// Encodes everything except letters, digits and `-._~` for use in a url.
+fn percent_encode(s: &str) -> String {
+    let mut res = String::new();
+    for byte in s.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            res.push(char::from(byte));
+        } else {
+            res.push_str(&format!("%{byte:02X}"));
+        }
+    }
+
+    res
+}
+
+#[cfg(test)]
+#[path = "tests/discovery.rs"]
+mod discovery_tests;
@@ -32,6 +32,10 @@ pub enum Error {
     FmtError(std::fmt::Error),
     TotpUrlError(totp_rs::TotpUrlError),
     SystemTimeError(std::time::SystemTimeError),
+    JsonError(serde_json::Error),
+    Notify(notify::Error),
+    AgeEncrypt(age::EncryptError),
+    AgeDecrypt(age::DecryptError),
 }
 
 impl From<arboard::Error> for Error {
@@ -181,6 +185,30 @@ impl From<std::time::SystemTimeError> for Error {
     }
 }
 
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Self::JsonError(err)
+    }
+}
+
+impl From<notify::Error> for Error {
+    fn from(err: notify::Error) -> Self {
+        Self::Notify(err)
+    }
+}
+
+impl From<age::EncryptError> for Error {
+    fn from(err: age::EncryptError) -> Self {
+        Self::AgeEncrypt(err)
+    }
+}
+
+impl From<age::DecryptError> for Error {
+    fn from(err: age::DecryptError) -> Self {
+        Self::AgeDecrypt(err)
+    }
+}
+
 impl From<PoisonError<MutexGuard<'_, Vec<Arc<Mutex<PasswordStore>>>>>> for Error {
     fn from(_err: PoisonError<MutexGuard<'_, Vec<Arc<Mutex<PasswordStore>>>>>) -> Self {
         Self::Generic("Error obtaining lock")
@@ -217,6 +245,10 @@ impl std::fmt::Display for Error {
             Self::FmtError(err) => write!(f, "{err}"),
             Self::TotpUrlError(_err) => write!(f, "TOTP url error"),
             Self::SystemTimeError(err) => write!(f, "{err}"),
+            Self::JsonError(err) => write!(f, "{err}"),
+            Self::Notify(err) => write!(f, "{err}"),
+            Self::AgeEncrypt(err) => write!(f, "{err}"),
+            Self::AgeDecrypt(err) => write!(f, "{err}"),
         }
     }
 }
@@ -0,0 +1,294 @@
+use std::fmt::{Display, Formatter};
+
+use serde::{Serialize, Serializer};
+use zeroize::Zeroize;
+
+use crate::{
+    error::Result,
+    parsed::{EntryLine, ParsedEntry},
+};
+
+/// The formats of plaintext exports.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One row per password, with a header row naming the columns
+    Csv,
+    /// An array with one object per password
+    Json,
+}
+
+/// Which parts of the passwords to export.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldSelection {
+    /// The first line of the entries
+    pub password: bool,
+    /// The `key: value` fields to export, compared without regard to case, or `None` for all
+    /// fields
+    pub fields: Option<Vec<String>>,
+    /// The otpauth:// urls
+    pub otp: bool,
+    /// The free form lines
+    pub notes: bool,
+}
+
+impl Default for FieldSelection {
+    fn default() -> Self {
+        Self {
+            password: true,
+            fields: None,
+            otp: true,
+            notes: true,
+        }
+    }
+}
+
+impl FieldSelection {
+    fn includes_field(&self, key: &str) -> bool {
+        match &self.fields {
+            None => true,
+            Some(fields) => fields.iter().any(|f| f.eq_ignore_ascii_case(key)),
+        }
+    }
+}
+
+/// Settings for an export.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExportOptions {
+    /// Only export the passwords in this directory, empty for the whole store
+    pub subtree: String,
+    pub selection: FieldSelection,
+}
+
+impl ExportOptions {
+    /// Returns true if the password with the name is inside the subtree.
+    pub(crate) fn includes(&self, name: &str) -> bool {
+        let subtree = self.subtree.trim_matches('/');
+        subtree.is_empty()
+            || name == subtree
+            || name
+                .strip_prefix(subtree)
+                .is_some_and(|rest| rest.starts_with('/'))
+    }
+}
+
+/// Reasons to think twice before an export, meant to be shown to the user before the export
+/// is started.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportWarning {
+    /// The passwords will be written unencrypted
+    Plaintext,
+    /// The file is inside the password store, and could be committed and pushed with it
+    InsidePasswordStore,
+    /// The file is inside a git repository, and could be committed and pushed with it
+    InsideGitRepository,
+    /// There is already a file with that name, it will be replaced
+    OverwritesFile,
+}
+
+impl Display for ExportWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Plaintext => write!(
+                f,
+                "The passwords will be written to disk unencrypted, anyone who can read the file can read them"
+            ),
+            Self::InsidePasswordStore => write!(
+                f,
+                "The file is inside the password store, and could be committed and pushed with it"
+            ),
+            Self::InsideGitRepository => write!(
+                f,
+                "The file is inside a git repository, and could be committed and pushed with it"
+            ),
+            Self::OverwritesFile => write!(f, "The file already exists and will be replaced"),
+        }
+    }
+}
+
+/// The outcome of an export.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExportReport {
+    /// The names of the exported passwords
+    pub exported: Vec<String>,
+    /// The warnings that applied to the export
+    pub warnings: Vec<ExportWarning>,
+}
+
+/// The selected parts of a decrypted password.
+#[derive(Serialize)]
+pub(crate) struct ExportedEntry {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    password: Option<String>,
+    #[serde(serialize_with = "serialize_fields")]
+    fields: Vec<(String, String)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    otp: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    notes: Option<String>,
+    /// The entry in the format of pass, with only the selected parts
+    #[serde(skip)]
+    content: String,
+}
+
+impl ExportedEntry {
+    pub(crate) fn new(name: &str, parsed: &ParsedEntry, selection: &FieldSelection) -> Self {
+        let mut content = if selection.password {
+            parsed.password().to_owned()
+        } else {
+            String::new()
+        };
+        for line in parsed.lines() {
+            let include = match line {
+                EntryLine::Field(f) => selection.includes_field(&f.key),
+                EntryLine::Otp(_) => selection.otp,
+                EntryLine::Note(_) => selection.notes,
+            };
+            if include {
+                content.push('\n');
+                content.push_str(&line.to_string());
+            }
+        }
+
+        Self {
+            name: name.to_owned(),
+            password: selection.password.then(|| parsed.password().to_owned()),
+            fields: parsed
+                .fields()
+                .into_iter()
+                .filter(|f| selection.includes_field(&f.key))
+                .map(|f| (f.key.clone(), f.value.clone()))
+                .collect(),
+            otp: selection
+                .otp
+                .then(|| parsed.otp_urls().into_iter().map(str::to_owned).collect()),
+            notes: selection.notes.then(|| parsed.notes()),
+            content,
+        }
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Fields are kept in a list to preserve their order, but are exported as a JSON object.
+fn serialize_fields<S: Serializer>(
+    fields: &[(String, String)],
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_map(fields.iter().map(|(k, v)| (k, v)))
+}
+
+impl Drop for ExportedEntry {
+    fn drop(&mut self) {
+        self.password.zeroize();
+        for (key, value) in &mut self.fields {
+            key.zeroize();
+            value.zeroize();
+        }
+        self.otp.zeroize();
+        self.notes.zeroize();
+        self.content.zeroize();
+    }
+}
+
+/// Returns the entries as CSV. The columns are `name`, `password`, one column for each field
+/// name in the order they first appear, `otp` and `notes`, the columns that aren't selected
+/// are left out.
+pub(crate) fn to_csv(entries: &[ExportedEntry], selection: &FieldSelection) -> String {
+    let mut field_names: Vec<String> = match &selection.fields {
+        Some(fields) => fields.clone(),
+        None => vec![],
+    };
+    for entry in entries {
+        for (key, _) in &entry.fields {
+            if !field_names.iter().any(|f| f.eq_ignore_ascii_case(key)) {
+                field_names.push(key.clone());
+            }
+        }
+    }
+
+    let mut header = vec!["name".to_owned()];
+    if selection.password {
+        header.push("password".to_owned());
+    }
+    header.extend(field_names.iter().cloned());
+    if selection.otp {
+        header.push("otp".to_owned());
+    }
+    if selection.notes {
+        header.push("notes".to_owned());
+    }
+
+    let mut csv = csv_row(&header);
+    for entry in entries {
+        let mut row = vec![entry.name.clone()];
+        if let Some(password) = &entry.password {
+            row.push(password.clone());
+        }
+        for name in &field_names {
+            row.push(
+                entry
+                    .fields
+                    .iter()
+                    .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                    .map(|(_, v)| v.clone())
+                    .unwrap_or_default(),
+            );
+        }
+        if let Some(otp) = &entry.otp {
+            row.push(otp.join("\n"));
+        }
+        if let Some(notes) = &entry.notes {
+            row.push(notes.clone());
+        }
+        csv.push_str(&csv_row(&row));
+        row.zeroize();
+    }
+
+    csv
+}
+
+fn csv_row(cells: &[String]) -> String {
+    let mut row = cells
+        .iter()
+        .map(|c| {
+            if c.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", c.replace('"', "\"\""))
+            } else {
+                c.clone()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(",");
+    row.push_str("\r\n");
+    row
+}
+
+/// Returns the entries as a JSON array of objects.
+pub(crate) fn to_json(entries: &[ExportedEntry]) -> Result<String> {
+    Ok(serde_json::to_string_pretty(entries)?)
+}
+
+/// Returns an uncompressed tar archive with one file per entry, named like the entry with
+/// `.txt` appended, in the format of pass.
+pub(crate) fn to_tar(entries: &[ExportedEntry], mtime: u64) -> Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(vec![]);
+    for entry in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(entry.content.len() as u64);
+        header.set_mode(0o600);
+        header.set_mtime(mtime);
+        builder.append_data(
+            &mut header,
+            format!("{}.txt", entry.name),
+            entry.content.as_bytes(),
+        )?;
+    }
+    Ok(builder.into_inner()?)
+}
+
+#[cfg(test)]
+#[path = "tests/export.rs"]
+mod export_tests;
@@ -0,0 +1,358 @@
+use std::fmt::{Display, Formatter};
+
+use rand::seq::SliceRandom;
+use zeroize::Zeroize;
+
+use crate::{
+    error::{Error, Result},
+    words::WORDS,
+};
+
+/// Characters that are easy to mistake for each other when read by a human.
+pub const AMBIGUOUS_CHARACTERS: &str = "0Oo1lI|`'\"";
+
+const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &str = "0123456789";
+const SYMBOLS: &str = "!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+
+/// The character classes a generated password is built from, every enabled class is
+/// guaranteed to be present at least once in the password.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CharacterClasses {
+    pub lowercase: bool,
+    pub uppercase: bool,
+    pub digits: bool,
+    pub symbols: bool,
+    /// Leave out the characters in `AMBIGUOUS_CHARACTERS`
+    pub exclude_ambiguous: bool,
+}
+
+impl Default for CharacterClasses {
+    fn default() -> Self {
+        Self {
+            lowercase: true,
+            uppercase: true,
+            digits: true,
+            symbols: true,
+            exclude_ambiguous: false,
+        }
+    }
+}
+
+impl CharacterClasses {
+    /// Returns the characters of each enabled class.
+    fn alphabets(&self) -> Vec<Vec<char>> {
+        [
+            (self.lowercase, LOWERCASE),
+            (self.uppercase, UPPERCASE),
+            (self.digits, DIGITS),
+            (self.symbols, SYMBOLS),
+        ]
+        .iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, chars)| {
+            chars
+                .chars()
+                .filter(|c| !self.exclude_ambiguous || !AMBIGUOUS_CHARACTERS.contains(*c))
+                .collect()
+        })
+        .collect()
+    }
+}
+
+/// How the words in a passphrase should be capitalized.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Capitalization {
+    /// all words in lowercase, like the EFF word list
+    #[default]
+    Lowercase,
+    /// The First Letter Of Every Word Capitalized
+    Capitalized,
+    /// ALL WORDS IN UPPERCASE
+    Uppercase,
+    /// each word is randomly either lowercase or capitalized, this adds one bit of entropy per word
+    Random,
+}
+
+/// Describes what kind of password to generate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PasswordGenerator {
+    /// A password of random characters
+    Characters {
+        length: usize,
+        classes: CharacterClasses,
+    },
+    /// A passphrase of random words from the long word list from EFF
+    Words {
+        number_of_words: usize,
+        separator: String,
+        capitalization: Capitalization,
+    },
+}
+
+impl Default for PasswordGenerator {
+    fn default() -> Self {
+        Self::Characters {
+            length: 20,
+            classes: CharacterClasses::default(),
+        }
+    }
+}
+
+impl PasswordGenerator {
+    /// Returns the number of bits of entropy that passwords from this generator have.
+    /// # Errors
+    /// Returns an `Err` if the settings can't produce a password.
+    pub fn entropy(&self) -> Result<f64> {
+        self.validate()?;
+
+        match self {
+            Self::Characters { length, classes } => {
+                let alphabets = classes.alphabets();
+                let sizes: Vec<usize> = alphabets.iter().map(Vec::len).collect();
+                Ok(characters_entropy(*length, &sizes))
+            }
+            Self::Words {
+                number_of_words,
+                capitalization,
+                ..
+            } => {
+                let per_word = (WORDS.len() as f64).log2()
+                    + if *capitalization == Capitalization::Random {
+                        1.0
+                    } else {
+                        0.0
+                    };
+                Ok(per_word * *number_of_words as f64)
+            }
+        }
+    }
+
+    /// Generates a new password.
+    /// # Errors
+    /// Returns an `Err` if the settings can't produce a password, for example if no character
+    /// classes are enabled.
+    pub fn generate(&self) -> Result<GeneratedPassword> {
+        let entropy = self.entropy()?;
+        let mut rng = rand::thread_rng();
+
+        let password = match self {
+            Self::Characters { length, classes } => {
+                let alphabets = classes.alphabets();
+                let all: Vec<char> = alphabets.iter().flatten().copied().collect();
+
+                // Draw until every class is represented, this keeps the distribution uniform
+                // over all valid passwords, which is what the entropy calculation assumes.
+                loop {
+                    let mut candidate: String = (0..*length)
+                        .map(|_| *all.choose(&mut rng).unwrap())
+                        .collect();
+                    if alphabets
+                        .iter()
+                        .all(|a| candidate.chars().any(|c| a.contains(&c)))
+                    {
+                        break candidate;
+                    }
+                    candidate.zeroize();
+                }
+            }
+            Self::Words {
+                number_of_words,
+                separator,
+                capitalization,
+            } => {
+                let mut words: Vec<String> = (0..*number_of_words)
+                    .map(|_| {
+                        let word = WORDS.choose(&mut rng).unwrap();
+                        match capitalization {
+                            Capitalization::Lowercase => (*word).to_owned(),
+                            Capitalization::Capitalized => capitalize(word),
+                            Capitalization::Uppercase => word.to_uppercase(),
+                            Capitalization::Random => {
+                                if rand::random() {
+                                    capitalize(word)
+                                } else {
+                                    (*word).to_owned()
+                                }
+                            }
+                        }
+                    })
+                    .collect();
+                let password = words.join(separator);
+                words.zeroize();
+                password
+            }
+        };
+
+        Ok(GeneratedPassword { password, entropy })
+    }
+
+    fn validate(&self) -> Result<()> {
+        match self {
+            Self::Characters { length, classes } => {
+                let alphabets = classes.alphabets();
+                if alphabets.is_empty() {
+                    return Err(Error::Generic(
+                        "at least one character class must be selected",
+                    ));
+                }
+                if *length < alphabets.len() {
+                    return Err(Error::Generic(
+                        "the password must be at least as long as the number of character classes",
+                    ));
+                }
+            }
+            Self::Words {
+                number_of_words, ..
+            } => {
+                if *number_of_words == 0 {
+                    return Err(Error::Generic(
+                        "the passphrase must contain at least one word",
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// The entropy of a password of `length` characters drawn uniformly from the union of the
+/// alphabets, given that every alphabet must be represented at least once.
+///
+/// The number of such passwords is calculated with the inclusion-exclusion principle, as
+/// `N^length * sum((-1)^|S| * ((N - |S|) / N)^length)` where `S` ranges over all subsets of
+/// the alphabets and `|S|` is the number of characters in them.
+fn characters_entropy(length: usize, alphabet_sizes: &[usize]) -> f64 {
+    let total: usize = alphabet_sizes.iter().sum();
+    let mut fraction = 0.0;
+    for subset in 0_u32..(1 << alphabet_sizes.len()) {
+        let excluded: usize = alphabet_sizes
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| subset & (1 << i) != 0)
+            .map(|(_, size)| size)
+            .sum();
+        let sign = if subset.count_ones() % 2 == 0 {
+            1.0
+        } else {
+            -1.0
+        };
+        fraction += sign * ((total - excluded) as f64 / total as f64).powi(length as i32);
+    }
+
+    length as f64 * (total as f64).log2() + fraction.log2()
+}
+
+/// A rough estimate of the entropy of any password, for example one that was chosen by a
+/// human. Every character is assumed to be drawn from the character classes that occur in the
+/// password, except characters that repeat or continue a sequence from the previous character,
+/// like `aa` or `123`, which only count as one bit.
+pub fn estimate_entropy(password: &str) -> f64 {
+    let classes = [LOWERCASE, UPPERCASE, DIGITS, SYMBOLS];
+    let mut pool: usize = classes
+        .iter()
+        .filter(|class| password.chars().any(|c| class.contains(c)))
+        .map(|class| class.len())
+        .sum();
+    if password
+        .chars()
+        .any(|c| !classes.iter().any(|class| class.contains(c)))
+    {
+        // other unicode characters, a guess at how many an attacker would try
+        pool += 100;
+    }
+    if pool == 0 {
+        return 0.0;
+    }
+
+    let bits_per_character = (pool as f64).log2();
+    let mut entropy = 0.0;
+    let mut previous: Option<char> = None;
+    for c in password.chars() {
+        let predictable = previous.is_some_and(|p| (p as i64 - c as i64).abs() <= 1);
+        entropy += if predictable { 1.0 } else { bits_per_character };
+        previous = Some(c);
+    }
+    entropy
+}
+
+/// A rough classification of how hard a password is to guess, based on its entropy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Strength {
+    VeryWeak,
+    Weak,
+    Reasonable,
+    Strong,
+    VeryStrong,
+}
+
+impl Strength {
+    /// Classifies a number of bits of entropy.
+    pub fn from_entropy(bits: f64) -> Self {
+        if bits < 40.0 {
+            Self::VeryWeak
+        } else if bits < 60.0 {
+            Self::Weak
+        } else if bits < 80.0 {
+            Self::Reasonable
+        } else if bits < 100.0 {
+            Self::Strong
+        } else {
+            Self::VeryStrong
+        }
+    }
+}
+
+impl Display for Strength {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::VeryWeak => write!(f, "Very weak"),
+            Self::Weak => write!(f, "Weak"),
+            Self::Reasonable => write!(f, "Reasonable"),
+            Self::Strong => write!(f, "Strong"),
+            Self::VeryStrong => write!(f, "Very strong"),
+        }
+    }
+}
+
+/// A password produced by a `PasswordGenerator`, together with the entropy of the generator.
+pub struct GeneratedPassword {
+    password: String,
+    entropy: f64,
+}
+
+impl GeneratedPassword {
+    /// Returns the generated password.
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+
+    /// Returns the number of bits of entropy of the generator that produced the password.
+    pub fn entropy(&self) -> f64 {
+        self.entropy
+    }
+
+    /// Returns the strength of the password.
+    pub fn strength(&self) -> Strength {
+        Strength::from_entropy(self.entropy)
+    }
+}
+
+impl Drop for GeneratedPassword {
+    fn drop(&mut self) {
+        self.password.zeroize();
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/generate.rs"]
+mod generate_tests;
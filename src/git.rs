@@ -1,17 +1,22 @@
 use std::{
     fmt::Display,
+    fs,
     path::{Path, PathBuf},
     str,
+    str::FromStr,
 };
 
 use chrono::{DateTime, Local, TimeZone};
 use git2::{Oid, Repository};
+use zeroize::Zeroize;
 
 use crate::{
+    credentials::{CredentialPrompt, Credentials},
     crypto::{Crypto, FindSigningFingerprintStrategy, VerificationError},
     error::{Error, Result},
+    merge,
     pass::{to_result, PasswordEntry, PasswordStore, RepositoryStatus},
-    signature::SignatureStatus,
+    signature::{Recipient, SignatureStatus},
 };
 
 fn git_branch_name(repo: &git2::Repository) -> Result<String> {
@@ -40,19 +45,7 @@ pub fn commit(
     crypto: &(dyn Crypto + Send),
 ) -> Result<git2::Oid> {
     if should_sign(repo) {
-        let commit_buf = repo.commit_create_buffer(
-            signature, // author
-            signature, // committer
-            message,   // commit message
-            tree,      // tree
-            parents,   // parents
-        )?;
-
-        let commit_as_str = str::from_utf8(&commit_buf)?;
-
-        let sig = crypto.sign_string(commit_as_str, &[], &FindSigningFingerprintStrategy::GIT)?;
-
-        let commit = repo.commit_signed(commit_as_str, &sig, Some("gpgsig"))?;
+        let commit = create_commit(repo, signature, signature, message, tree, parents, crypto)?;
 
         if let Ok(mut head) = repo.head() {
             head.set_target(commit, "added a signed commit using ripasso")?;
@@ -75,6 +68,30 @@ pub fn commit(
     }
 }
 
+/// Creates a commit without moving any branch to it, signed if the repository is configured
+/// with `commit.gpgsign`.
+fn create_commit(
+    repo: &git2::Repository,
+    author: &git2::Signature,
+    committer: &git2::Signature,
+    message: &str,
+    tree: &git2::Tree,
+    parents: &[&git2::Commit],
+    crypto: &(dyn Crypto + Send),
+) -> Result<git2::Oid> {
+    if should_sign(repo) {
+        let commit_buf = repo.commit_create_buffer(author, committer, message, tree, parents)?;
+
+        let commit_as_str = str::from_utf8(&commit_buf)?;
+
+        let sig = crypto.sign_string(commit_as_str, &[], &FindSigningFingerprintStrategy::GIT)?;
+
+        Ok(repo.commit_signed(commit_as_str, &sig, Some("gpgsig"))?)
+    } else {
+        Ok(repo.commit(None, author, committer, message, tree, parents)?)
+    }
+}
+
 pub fn find_last_commit(repo: &git2::Repository) -> Result<git2::Commit> {
     let obj = repo.head()?.resolve()?.peel(git2::ObjectType::Commit)?;
     obj.into_commit()
@@ -130,7 +147,8 @@ pub fn add_and_commit_internal(
     Ok(oid)
 }
 
-/// Remove a file from the store, and commit the deletion to the supplied git repository.
+/// Remove a file from the store, and commit the deletion to the supplied git repository. If
+/// the store has a sync service, it then pushes the commit.
 pub fn remove_and_commit(
     store: &PasswordStore,
     paths: &[PathBuf],
@@ -165,11 +183,13 @@ pub fn remove_and_commit(
         &parents,
         store.get_crypto(),
     )?;
+    store.request_push();
 
     Ok(oid)
 }
 
 /// Move a file to a new place in the store, and commit the move to the supplied git repository.
+/// If the store has a sync service, it then pushes the commit.
 pub fn move_and_commit(
     store: &PasswordStore,
     old_name: &Path,
@@ -203,6 +223,7 @@ pub fn move_and_commit(
         &parents,
         store.get_crypto(),
     )?;
+    store.request_push();
 
     Ok(oid)
 }
@@ -229,51 +250,157 @@ fn find_origin(repo: &git2::Repository) -> Result<(git2::Remote, String)> {
     Err(Error::Generic("no remotes configured"))
 }
 
-/// function that can be used for callback handling of the ssh interaction in git2
-fn cred(
-    tried_sshkey: &mut bool,
-    _url: &str,
-    username: Option<&str>,
-    allowed: git2::CredentialType,
-) -> std::result::Result<git2::Cred, git2::Error> {
-    let sys_username = whoami::username();
-    let user: &str = username.map_or(&sys_username, |name| name);
+/// A remote that a store is synced with, and the branch on it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyncRemote {
+    /// Name of the remote in the git config of the repository
+    pub name: String,
+    /// The branch on the remote, the current local branch is used if it's `None`
+    pub branch: Option<String>,
+}
+
+impl SyncRemote {
+    /// Parses a comma separated list of remotes, on the format `name` or `name:branch`.
+    /// # Errors
+    /// Returns an `Err` if a remote has an empty name or branch
+    pub fn parse_list(list: &str) -> Result<Vec<Self>> {
+        list.split(',')
+            .map(str::trim)
+            .filter(|r| !r.is_empty())
+            .map(str::parse)
+            .collect()
+    }
+}
+
+impl FromStr for SyncRemote {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, branch) = match s.split_once(':') {
+            Some((name, branch)) => (name, Some(branch)),
+            None => (s, None),
+        };
+        if name.is_empty() || branch.is_some_and(str::is_empty) {
+            return Err(Error::Generic(
+                "a remote must be on the format name or name:branch",
+            ));
+        }
+
+        Ok(Self {
+            name: name.to_owned(),
+            branch: branch.map(str::to_owned),
+        })
+    }
+}
 
-    if allowed.contains(git2::CredentialType::USERNAME) {
-        return git2::Cred::username(user);
+impl Display for SyncRemote {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.branch {
+            Some(branch) => write!(f, "{}:{branch}", self.name),
+            None => f.write_str(&self.name),
+        }
     }
+}
 
-    if *tried_sshkey {
-        return Err(git2::Error::from_str("no authentication available"));
+/// The outcome of syncing with one of the remotes.
+#[derive(Debug)]
+pub struct RemoteStatus<T> {
+    /// The remote, with the branch that was used
+    pub remote: SyncRemote,
+    /// The result of talking to that remote
+    pub result: Result<T>,
+}
+
+/// The remotes that the store syncs with, each with the branch on the remote that's used. If
+/// the store doesn't configure any remotes, the upstream of the current branch is used.
+fn sync_remotes(store: &PasswordStore, repo: &git2::Repository) -> Result<Vec<SyncRemote>> {
+    if store.get_remotes().is_empty() {
+        let (origin, branch_name) = find_origin(repo)?;
+        return Ok(vec![SyncRemote {
+            name: origin.name().ok_or("remote without a name")?.to_owned(),
+            branch: Some(branch_name),
+        }]);
     }
-    *tried_sshkey = true;
 
-    git2::Cred::ssh_key_from_agent(user)
+    let local_branch = git_branch_name(repo)?;
+    Ok(store
+        .get_remotes()
+        .iter()
+        .map(|remote| SyncRemote {
+            name: remote.name.clone(),
+            branch: Some(
+                remote
+                    .branch
+                    .clone()
+                    .unwrap_or_else(|| local_branch.clone()),
+            ),
+        })
+        .collect())
 }
 
-/// Push your changes to the remote git repository.
-/// # Errors
-/// Returns an `Err` if the repository doesn't exist or if an git operation fails
-pub fn push(store: &PasswordStore) -> Result<()> {
-    let repo = store
-        .repo()
-        .map_err(|_| Error::Generic("must have a repository"))?;
+/// The remote that `push` and `pull` use, the first one that the store syncs with.
+pub(crate) fn primary_remote(store: &PasswordStore, repo: &git2::Repository) -> Result<SyncRemote> {
+    sync_remotes(store, repo)?
+        .into_iter()
+        .next()
+        .ok_or(Error::Generic("no remotes configured"))
+}
+
+fn remote_branch(remote: &SyncRemote) -> Result<&str> {
+    remote
+        .branch
+        .as_deref()
+        .ok_or(Error::Generic("no branch for the remote"))
+}
+
+/// Fetches the branch of the remote, and returns the commit it points to.
+pub(crate) fn fetch_from(
+    repo: &git2::Repository,
+    remote: &SyncRemote,
+    mut credentials: Credentials,
+) -> Result<git2::Oid> {
+    let branch = remote_branch(remote)?;
+    let tracking_ref = format!("refs/remotes/{}/{branch}", remote.name);
+
+    let mut cb = git2::RemoteCallbacks::new();
+    cb.credentials(|url, username, allowed| credentials.credentials(url, username, allowed));
+
+    let mut opts = git2::FetchOptions::new();
+    opts.remote_callbacks(cb);
+    repo.find_remote(&remote.name)?.fetch(
+        &[format!("+refs/heads/{branch}:{tracking_ref}")],
+        Some(&mut opts),
+        None,
+    )?;
+
+    Ok(repo.refname_to_id(&tracking_ref)?)
+}
+
+/// Pushes the current local branch to the branch of the remote.
+pub(crate) fn push_to(
+    repo: &git2::Repository,
+    remote: &SyncRemote,
+    mut credentials: Credentials,
+) -> Result<()> {
+    let local_branch = git_branch_name(repo)?;
+    let branch = remote_branch(remote)?;
+    let mut origin = repo.find_remote(&remote.name)?;
 
     let mut ref_status = None;
-    let (mut origin, branch_name) = find_origin(&repo)?;
     let res = {
         let mut callbacks = git2::RemoteCallbacks::new();
-        let mut tried_ssh_key = false;
-        callbacks.credentials(|_url, username, allowed| {
-            cred(&mut tried_ssh_key, _url, username, allowed)
-        });
+        callbacks
+            .credentials(|url, username, allowed| credentials.credentials(url, username, allowed));
         callbacks.push_update_reference(|_refname, status| {
             ref_status = status.map(std::borrow::ToOwned::to_owned);
             Ok(())
         });
         let mut opts = git2::PushOptions::new();
         opts.remote_callbacks(callbacks);
-        origin.push(&[format!("refs/heads/{branch_name}")], Some(&mut opts))
+        origin.push(
+            &[format!("refs/heads/{local_branch}:refs/heads/{branch}")],
+            Some(&mut opts),
+        )
     };
     match res {
         Ok(()) if ref_status.is_none() => Ok(()),
@@ -284,52 +411,554 @@ pub fn push(store: &PasswordStore) -> Result<()> {
     }
 }
 
-/// Pull new changes from the remote git repository.
+/// Pushes the current branch to all the remotes that the store syncs with, asking `prompt` for
+/// the credentials that are needed.
+/// # Errors
+/// Returns an `Err` if the repository doesn't exist or the remotes can't be found, failures to
+/// push to a remote are reported in the status of that remote
+pub fn push_all(
+    store: &PasswordStore,
+    prompt: &dyn CredentialPrompt,
+) -> Result<Vec<RemoteStatus<()>>> {
+    let repo = store
+        .repo()
+        .map_err(|_| Error::Generic("must have a repository"))?;
+
+    Ok(sync_remotes(store, &repo)?
+        .into_iter()
+        .map(|remote| RemoteStatus {
+            result: push_to(&repo, &remote, credentials(store, &repo, prompt)),
+            remote,
+        })
+        .collect())
+}
+
+/// Fetches from all the remotes that the store syncs with, without changing the local branch,
+/// asking `prompt` for the credentials that are needed. The status of each remote is how many commits it has that the local branch doesn't have.
+/// # Errors
+/// Returns an `Err` if the repository doesn't exist or the remotes can't be found, failures to
+/// fetch from a remote are reported in the status of that remote
+pub fn fetch_all(
+    store: &PasswordStore,
+    prompt: &dyn CredentialPrompt,
+) -> Result<Vec<RemoteStatus<usize>>> {
+    let repo = store
+        .repo()
+        .map_err(|_| Error::Generic("must have a repository"))?;
+    let head_oid = repo.refname_to_id("HEAD")?;
+
+    Ok(sync_remotes(store, &repo)?
+        .into_iter()
+        .map(|remote| RemoteStatus {
+            result: fetch_from(&repo, &remote, credentials(store, &repo, prompt)).and_then(
+                |remote_oid| {
+                    let (_, behind) = repo.graph_ahead_behind(head_oid, remote_oid)?;
+                    Ok(behind)
+                },
+            ),
+            remote,
+        })
+        .collect())
+}
+
+/// Push your changes to the remote git repository, the first of the remotes that the store
+/// syncs with. `prompt` is asked for the credentials that are needed.
 /// # Errors
 /// Returns an `Err` if the repository doesn't exist or if an git operation fails
-pub fn pull(store: &PasswordStore) -> Result<()> {
+pub fn push(store: &PasswordStore, prompt: &dyn CredentialPrompt) -> Result<()> {
     let repo = store
         .repo()
         .map_err(|_| Error::Generic("must have a repository"))?;
 
-    let (mut origin, branch_name) = find_origin(&repo)?;
+    push_to(
+        &repo,
+        &primary_remote(store, &repo)?,
+        credentials(store, &repo, prompt),
+    )
+}
 
-    let mut cb = git2::RemoteCallbacks::new();
-    let mut tried_ssh_key = false;
-    cb.credentials(|_url, username, allowed| cred(&mut tried_ssh_key, _url, username, allowed));
+/// The credentials for one network operation on the repository of the store.
+fn credentials<'a>(
+    store: &PasswordStore,
+    repo: &git2::Repository,
+    prompt: &'a dyn CredentialPrompt,
+) -> Credentials<'a> {
+    Credentials::new(repo, store.get_user_home(), prompt)
+}
 
-    let mut opts = git2::FetchOptions::new();
-    opts.remote_callbacks(cb);
-    origin.fetch(&[branch_name], Some(&mut opts), None)?;
+/// How `pull` combines the local commits with the ones fetched from the remote, when both
+/// sides have new commits. If only the remote has new commits, the branch is always
+/// fast-forwarded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PullStrategy {
+    /// Refuse to pull if the local branch has commits that the remote doesn't have
+    FastForwardOnly,
+    /// Create a merge commit
+    #[default]
+    Merge,
+    /// Replay the local commits on top of the fetched ones
+    Rebase,
+}
+
+impl PullStrategy {
+    /// The name of the strategy in the config file.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::FastForwardOnly => "fast-forward-only",
+            Self::Merge => "merge",
+            Self::Rebase => "rebase",
+        }
+    }
+}
+
+impl FromStr for PullStrategy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "fast-forward-only" => Ok(Self::FastForwardOnly),
+            "merge" => Ok(Self::Merge),
+            "rebase" => Ok(Self::Rebase),
+            _ => Err(Error::Generic(
+                "unknown pull strategy, must be fast-forward-only, merge or rebase",
+            )),
+        }
+    }
+}
+
+impl Display for PullStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An entry that both sides of a pull changed, in ways that couldn't be merged automatically.
+pub struct MergeConflict {
+    /// Path of the file, relative to the root of the store
+    pub path: PathBuf,
+    /// The merged content, with the lines that both sides changed between git style `<<<<<<<`,
+    /// `=======` and `>>>>>>>` markers. Set this to the wanted content before passing the
+    /// conflict to `resolve_conflicts`, an empty content removes the entry.
+    pub merged: String,
+}
+
+impl Drop for MergeConflict {
+    fn drop(&mut self) {
+        self.merged.zeroize();
+    }
+}
+
+/// Pull new changes from the remote git repository, the first of the remotes that the store
+/// syncs with, with the pull strategy of the store. `prompt` is asked for the credentials that
+/// are needed.
+///
+/// Entries that were changed both locally and on the remote are decrypted and merged line by
+/// line. With the merge strategy, the entries that couldn't be merged are returned, and then
+/// the pull is left unfinished until they are passed to `resolve_conflicts`, or the pull is
+/// undone with `abort_merge`.
+/// # Errors
+/// Returns an `Err` if the repository doesn't exist, if an git operation fails, if a
+/// conflicting file can't be decrypted, in which case the merge is aborted, or if the branches
+/// have diverged and the strategy can't combine them
+pub fn pull(store: &PasswordStore, prompt: &dyn CredentialPrompt) -> Result<Vec<MergeConflict>> {
+    let repo = store
+        .repo()
+        .map_err(|_| Error::Generic("must have a repository"))?;
+
+    let remote_oid = fetch_from(
+        &repo,
+        &primary_remote(store, &repo)?,
+        credentials(store, &repo, prompt),
+    )?;
+    merge_fetched(store, &repo, remote_oid)
+}
 
-    let remote_oid = repo.refname_to_id("FETCH_HEAD")?;
+/// The second half of `pull`, combines the fetched commit with the local branch.
+pub(crate) fn merge_fetched(
+    store: &PasswordStore,
+    repo: &git2::Repository,
+    remote_oid: git2::Oid,
+) -> Result<Vec<MergeConflict>> {
     let head_oid = repo.refname_to_id("HEAD")?;
 
-    let (_, behind) = repo.graph_ahead_behind(head_oid, remote_oid)?;
+    let (ahead, behind) = repo.graph_ahead_behind(head_oid, remote_oid)?;
 
     if behind == 0 {
-        return Ok(());
+        return Ok(vec![]);
+    }
+    if ahead == 0 {
+        fast_forward(repo, remote_oid)?;
+        return Ok(vec![]);
     }
 
-    let remote_annotated_commit = repo.find_annotated_commit(remote_oid)?;
+    match store.get_pull_strategy() {
+        PullStrategy::FastForwardOnly => Err(Error::Generic(
+            "the local and remote branches have diverged, and the store only allows fast-forward pulls",
+        )),
+        PullStrategy::Merge => merge_remote(store, repo, remote_oid),
+        PullStrategy::Rebase => {
+            rebase(store, repo, head_oid, remote_oid)?;
+            Ok(vec![])
+        }
+    }
+}
+
+/// Moves the branch and the working tree to the fetched commit.
+fn fast_forward(repo: &git2::Repository, remote_oid: git2::Oid) -> Result<()> {
     let remote_commit = repo.find_commit(remote_oid)?;
+    repo.checkout_tree(
+        remote_commit.as_object(),
+        Some(git2::build::CheckoutBuilder::new().safe()),
+    )?;
+    repo.head()?
+        .set_target(remote_oid, "pull: fast-forward by ripasso")?;
+    Ok(())
+}
+
+/// Merges the fetched commit into the branch, returning the entries that couldn't be merged.
+fn merge_remote(
+    store: &PasswordStore,
+    repo: &git2::Repository,
+    remote_oid: git2::Oid,
+) -> Result<Vec<MergeConflict>> {
+    let remote_annotated_commit = repo.find_annotated_commit(remote_oid)?;
     repo.merge(&[&remote_annotated_commit], None, None)?;
 
+    let conflicts = match merge_conflicting_entries(store, repo) {
+        Ok(conflicts) => conflicts,
+        Err(err) => {
+            abort_merge(store)?;
+            return Err(err);
+        }
+    };
+
+    if conflicts.is_empty() {
+        finish_merge(store, repo)?;
+    }
+    Ok(conflicts)
+}
+
+/// Replays the local commits on top of the fetched commit, and moves the branch there. Merge
+/// commits are left out, like `git rebase` does.
+fn rebase(
+    store: &PasswordStore,
+    repo: &git2::Repository,
+    head_oid: git2::Oid,
+    remote_oid: git2::Oid,
+) -> Result<()> {
+    let mut walk = repo.revwalk()?;
+    walk.push(head_oid)?;
+    walk.hide(repo.merge_base(head_oid, remote_oid)?)?;
+    walk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+
+    let committer = repo.signature()?;
+    let mut onto = repo.find_commit(remote_oid)?;
+    for oid in walk {
+        let commit = repo.find_commit(oid?)?;
+        if commit.parent_count() > 1 {
+            continue;
+        }
+
+        let mut index = repo.cherrypick_commit(&commit, &onto, 0, None)?;
+        if index.has_conflicts() {
+            merge_conflicts_in_index(store, repo, &mut index)?;
+        }
+        let tree = repo.find_tree(index.write_tree_to(repo)?)?;
+
+        let rebased = create_commit(
+            repo,
+            &commit.author(),
+            &committer,
+            commit.message().unwrap_or_default(),
+            &tree,
+            &[&onto],
+            store.get_crypto(),
+        )?;
+        onto = repo.find_commit(rebased)?;
+    }
+
+    repo.checkout_tree(
+        onto.as_object(),
+        Some(git2::build::CheckoutBuilder::new().safe()),
+    )?;
+    repo.head()?
+        .set_target(onto.id(), "pull: rebase by ripasso")?;
+    Ok(())
+}
+
+/// Merges the conflicting entries of an in-memory index, fails if any of them can't be merged.
+fn merge_conflicts_in_index(
+    store: &PasswordStore,
+    repo: &git2::Repository,
+    index: &mut git2::Index,
+) -> Result<()> {
+    let conflicts = index
+        .conflicts()?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    for conflict in conflicts {
+        let (path, merged) = merge_conflict(store, repo, &conflict)?;
+        if merged.conflicts > 0 {
+            return Err(Error::GenericDyn(format!(
+                "both sides changed the same lines of {}, use the merge pull strategy to resolve it",
+                path.display()
+            )));
+        }
+
+        index.conflict_remove(&path)?;
+        if merged.text.is_empty() {
+            continue;
+        }
+        let content = encrypt_resolution(store, &path, &merged.text, || {
+            recipients_in_index(store, repo, &*index, &path)
+        })?;
+        let mode = conflict
+            .our
+            .as_ref()
+            .or(conflict.their.as_ref())
+            .map_or(0o100_644, |e| e.mode);
+        // the index of a cherry-pick isn't backed by the repository, so the content has to
+        // be written as a blob before it can be added
+        let entry = git2::IndexEntry {
+            ctime: git2::IndexTime::new(0, 0),
+            mtime: git2::IndexTime::new(0, 0),
+            dev: 0,
+            ino: 0,
+            mode,
+            uid: 0,
+            gid: 0,
+            file_size: u32::try_from(content.len()).unwrap_or(u32::MAX),
+            id: repo.blob(&content)?,
+            flags: 0,
+            flags_extended: 0,
+            path: path.to_string_lossy().as_bytes().to_vec(),
+        };
+        index.add(&entry)?;
+    }
+    Ok(())
+}
+
+/// Returns the recipients of the closest `.gpg-id` file above `path` in an index, for the
+/// entries of a commit that is being rebased, whose directories might not be in the working
+/// tree.
+fn recipients_in_index(
+    store: &PasswordStore,
+    repo: &git2::Repository,
+    index: &git2::Index,
+    path: &Path,
+) -> Result<Vec<Recipient>> {
+    let file_name = store.recipients_file_name();
+    let mut dir = path.parent();
+    while let Some(d) = dir {
+        if let Some(gpg_id) = index.get_path(&d.join(file_name), 0) {
+            let gpg_id = repo.find_blob(gpg_id.id)?;
+            let gpg_id_sig = match index.get_path(&d.join(format!("{file_name}.sig")), 0) {
+                Some(sig) => Some(repo.find_blob(sig.id)?),
+                None => None,
+            };
+            return store.recipients_from_contents(
+                gpg_id.content(),
+                gpg_id_sig.as_ref().map(git2::Blob::content),
+            );
+        }
+        dir = d.parent();
+    }
+
+    Err(Error::GenericDyn(format!("No {file_name} file found")))
+}
+
+/// Finishes a pull that stopped on conflicting entries, with the content chosen for each of
+/// them.
+/// # Errors
+/// Returns an `Err` if there are conflicts left, or if the entries can't be encrypted or the
+/// merge can't be committed
+pub fn resolve_conflicts(store: &PasswordStore, resolved: &[MergeConflict]) -> Result<()> {
+    let repo = store
+        .repo()
+        .map_err(|_| Error::Generic("must have a repository"))?;
+
+    let mut index = repo.index()?;
+    for conflict in resolved {
+        write_resolution(store, &mut index, &conflict.path, &conflict.merged)?;
+    }
+    index.write()?;
+
+    if index.has_conflicts() {
+        return Err(Error::Generic(
+            "not all conflicting entries have been resolved",
+        ));
+    }
+    finish_merge(store, &repo)
+}
+
+/// Undoes a pull that stopped on conflicting entries.
+/// # Errors
+/// Returns an `Err` if the repository can't be reset
+pub fn abort_merge(store: &PasswordStore) -> Result<()> {
+    let repo = store
+        .repo()
+        .map_err(|_| Error::Generic("must have a repository"))?;
+
+    let head = find_last_commit(&repo)?;
+    repo.reset(head.as_object(), git2::ResetType::Hard, None)?;
+    repo.cleanup_state()?;
+    Ok(())
+}
+
+/// Merges the entries that git couldn't merge, since they are encrypted. Returns the ones
+/// that were changed on the same lines on both sides.
+fn merge_conflicting_entries(
+    store: &PasswordStore,
+    repo: &git2::Repository,
+) -> Result<Vec<MergeConflict>> {
+    let mut index = repo.index()?;
+    if !index.has_conflicts() {
+        return Ok(vec![]);
+    }
+
+    let conflicts = index
+        .conflicts()?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let mut unresolved = vec![];
+    for conflict in conflicts {
+        let (path, mut merged) = merge_conflict(store, repo, &conflict)?;
+
+        if merged.conflicts == 0 {
+            write_resolution(store, &mut index, &path, &merged.text)?;
+        } else {
+            unresolved.push(MergeConflict {
+                path,
+                merged: std::mem::take(&mut merged.text),
+            });
+        }
+    }
+    index.write()?;
+
+    Ok(unresolved)
+}
+
+/// Decrypts the three versions of a conflicting file and merges them line by line.
+fn merge_conflict(
+    store: &PasswordStore,
+    repo: &git2::Repository,
+    conflict: &git2::IndexConflict,
+) -> Result<(PathBuf, merge::MergedText)> {
+    let path = conflict
+        .our
+        .as_ref()
+        .or(conflict.their.as_ref())
+        .or(conflict.ancestor.as_ref())
+        .ok_or(Error::Generic("conflict without any file"))?
+        .path
+        .clone();
+    let path = PathBuf::from(String::from_utf8(path)?);
+
+    let mut base = read_conflict_side(store, repo, &path, conflict.ancestor.as_ref())?;
+    let mut ours = read_conflict_side(store, repo, &path, conflict.our.as_ref())?;
+    let mut theirs = read_conflict_side(store, repo, &path, conflict.their.as_ref())?;
+    let merged = merge::merge(&base, &ours, &theirs);
+    base.zeroize();
+    ours.zeroize();
+    theirs.zeroize();
+
+    Ok((path, merged))
+}
+
+/// Returns the plaintext of one side of a conflict, or an empty string if the file doesn't
+/// exist on that side.
+fn read_conflict_side(
+    store: &PasswordStore,
+    repo: &git2::Repository,
+    path: &Path,
+    entry: Option<&git2::IndexEntry>,
+) -> Result<String> {
+    let Some(entry) = entry else {
+        return Ok(String::new());
+    };
+    let blob = repo.find_blob(entry.id)?;
+
+    let plaintext = if store.is_password_file(path) {
+        store.get_crypto().decrypt_string(blob.content())
+    } else {
+        String::from_utf8(blob.content().to_vec()).map_err(Error::from)
+    };
+
+    plaintext.map_err(|err| {
+        Error::GenericDyn(format!(
+            "both sides changed {} and it can't be merged: {err}",
+            path.display()
+        ))
+    })
+}
+
+/// Writes the merged content of a conflicting file to the store, encrypted if it's a password.
+fn write_resolution(
+    store: &PasswordStore,
+    index: &mut git2::Index,
+    path: &Path,
+    content: &str,
+) -> Result<()> {
+    let full_path = store.get_store_path().join(path);
+
+    if content.is_empty() {
+        if full_path.exists() {
+            fs::remove_file(&full_path)?;
+        }
+        index.remove_path(path)?;
+        return Ok(());
+    }
+
+    let encrypted = encrypt_resolution(store, path, content, || {
+        let dir = full_path
+            .parent()
+            .ok_or(Error::Generic("entry without a directory"))?;
+        store.recipients_for_path(dir)
+    })?;
+    fs::write(&full_path, encrypted)?;
+    index.add_path(path)?;
+    Ok(())
+}
+
+/// Returns the file content for the merged plaintext, encrypted for the `recipients` if it's a
+/// password.
+fn encrypt_resolution<F>(
+    store: &PasswordStore,
+    path: &Path,
+    content: &str,
+    recipients: F,
+) -> Result<Vec<u8>>
+where
+    F: FnOnce() -> Result<Vec<Recipient>>,
+{
+    if !store.is_password_file(path) {
+        return Ok(content.as_bytes().to_vec());
+    }
+
+    store.get_crypto().encrypt_string(content, &recipients()?)
+}
+
+/// Commits the merged index, with the fetched commit as the second parent.
+fn finish_merge(store: &PasswordStore, repo: &git2::Repository) -> Result<()> {
+    let remote_oid = repo.refname_to_id("MERGE_HEAD")?;
+    let remote_commit = repo.find_commit(remote_oid)?;
+
     //commit it
     let mut index = repo.index()?;
     let oid = index.write_tree()?;
     let signature = repo.signature()?;
-    let parent_commit = find_last_commit(&repo)?;
+    let parent_commit = find_last_commit(repo)?;
     let tree = repo.find_tree(oid)?;
     let message = "pull and merge by ripasso";
-    let _commit = repo.commit(
-        Some("HEAD"), //  point HEAD to our new commit
-        &signature,   // author
-        &signature,   // committer
-        message,      // commit message
-        &tree,        // tree
+    commit(
+        repo,
+        &signature,
+        message,
+        &tree,
         &[&parent_commit, &remote_commit],
-    )?; // parents
+        store.get_crypto(),
+    )?;
 
     //cleanup
     repo.cleanup_state()?;
@@ -447,6 +1076,7 @@ pub fn push_password_if_match(
         passwords.push(PasswordEntry::new(
             &store.get_store_path(),
             target,
+            store.password_file_extension(),
             time_return,
             name_return,
             signature_return,
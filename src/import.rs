@@ -0,0 +1,538 @@
+use std::{fs, path::Path};
+
+use serde::Deserialize;
+use zeroize::Zeroize;
+
+use crate::error::{Error, Result};
+
+/// The export formats that can be imported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// A KeePass 2 database, `.kdbx`, opened with a password
+    Kdbx,
+    /// The unencrypted JSON export from Bitwarden
+    BitwardenJson,
+    /// The CSV export from 1Password
+    OnePasswordCsv,
+    /// The CSV export of saved passwords from Chrome, Chromium, Edge or Firefox
+    BrowserCsv,
+}
+
+impl ImportFormat {
+    /// Parses the name of a format, as used on the command line.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "kdbx" | "keepass" => Some(Self::Kdbx),
+            "bitwarden" | "bitwarden-json" => Some(Self::BitwardenJson),
+            "1password" | "1password-csv" | "onepassword" => Some(Self::OnePasswordCsv),
+            "browser" | "browser-csv" | "chrome" | "firefox" => Some(Self::BrowserCsv),
+            _ => None,
+        }
+    }
+}
+
+/// What to do when an imported entry has the same name as an existing password, or as another
+/// imported entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ConflictStrategy {
+    /// Leave the existing password as it is and don't import the entry
+    #[default]
+    Skip,
+    /// Import the entry with a number appended to the name, like `github-2`
+    Rename,
+}
+
+/// Settings for an import.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct ImportOptions {
+    /// The directory in the store that the entries are imported into, empty for the root
+    pub prefix: String,
+    pub on_conflict: ConflictStrategy,
+}
+
+/// A password read from an export file, before it's written to the store.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImportedEntry {
+    /// The folders the entry was in, outermost first
+    pub folders: Vec<String>,
+    pub title: String,
+    pub password: String,
+    pub username: Option<String>,
+    pub urls: Vec<String>,
+    /// A otpauth:// url
+    pub otp: Option<String>,
+    /// Custom fields, as name and value
+    pub fields: Vec<(String, String)>,
+    pub notes: String,
+}
+
+impl ImportedEntry {
+    /// The name of the entry in the store, relative to the import prefix. Folders and the title
+    /// are cleaned up so that they are valid path segments.
+    pub fn name(&self) -> String {
+        let mut title = self.title.trim().to_owned();
+        if title.is_empty() {
+            title = self
+                .urls
+                .first()
+                .map(|u| host_of(u).to_owned())
+                .filter(|h| !h.is_empty())
+                .unwrap_or_else(|| "unnamed".to_owned());
+        }
+
+        self.folders
+            .iter()
+            .map(|f| f.trim())
+            .filter(|f| !f.is_empty())
+            .chain(std::iter::once(title.as_str()))
+            .map(path_segment)
+            .collect::<Vec<String>>()
+            .join("/")
+    }
+
+    /// The content of the entry in the format of pass: the password on the first line,
+    /// followed by `key: value` fields, the otpauth url and the notes.
+    pub fn to_secret(&self) -> String {
+        let mut secret = self.password.clone();
+
+        if let Some(username) = &self.username {
+            if !username.is_empty() {
+                secret.push_str(&format!("\nlogin: {}", one_line(username)));
+            }
+        }
+        for url in &self.urls {
+            if !url.is_empty() {
+                secret.push_str(&format!("\nurl: {}", one_line(url)));
+            }
+        }
+        let mut multi_line_fields = vec![];
+        for (key, value) in &self.fields {
+            let key = one_line(key).replace(':', "-");
+            if key.is_empty() || value.is_empty() {
+                continue;
+            }
+            if value.contains('\n') {
+                multi_line_fields.push(format!("{key}:\n{value}"));
+            } else {
+                secret.push_str(&format!("\n{key}: {value}"));
+            }
+        }
+        if let Some(otp) = &self.otp {
+            secret.push('\n');
+            secret.push_str(otp);
+        }
+        let notes = self.notes.trim_end();
+        if !notes.is_empty() {
+            secret.push('\n');
+            secret.push_str(notes);
+        }
+        for field in &mut multi_line_fields {
+            secret.push('\n');
+            secret.push_str(field);
+            field.zeroize();
+        }
+        secret.push('\n');
+
+        secret
+    }
+}
+
+impl Drop for ImportedEntry {
+    fn drop(&mut self) {
+        self.password.zeroize();
+        self.otp.zeroize();
+        for (key, value) in &mut self.fields {
+            key.zeroize();
+            value.zeroize();
+        }
+        self.notes.zeroize();
+    }
+}
+
+/// The outcome of an import.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    /// The names, in the store, of the imported passwords
+    pub imported: Vec<String>,
+    /// Entries that were imported under another name because of a conflict, as the name
+    /// they would have had and the name they got
+    pub renamed: Vec<(String, String)>,
+    /// Entries that weren't imported, as the name they would have had and the reason
+    pub skipped: Vec<(String, &'static str)>,
+}
+
+/// Reads all entries from an export file. `password` is needed to open KeePass databases.
+/// # Errors
+/// Returns an `Err` if the file can't be read or isn't in the given format.
+pub fn read_entries(
+    format: ImportFormat,
+    path: &Path,
+    password: Option<&str>,
+) -> Result<Vec<ImportedEntry>> {
+    match format {
+        ImportFormat::Kdbx => read_kdbx(
+            path,
+            password.ok_or(Error::Generic(
+                "a password is needed to open a KeePass database",
+            ))?,
+        ),
+        ImportFormat::BitwardenJson => {
+            let mut content = fs::read_to_string(path)?;
+            let entries = parse_bitwarden_json(&content);
+            content.zeroize();
+            entries
+        }
+        ImportFormat::OnePasswordCsv | ImportFormat::BrowserCsv => {
+            let mut content = fs::read_to_string(path)?;
+            let entries = parse_csv(&content);
+            content.zeroize();
+            entries
+        }
+    }
+}
+
+fn read_kdbx(path: &Path, password: &str) -> Result<Vec<ImportedEntry>> {
+    let mut file = fs::File::open(path)?;
+    let key = keepass::DatabaseKey::new().with_password(password);
+    let db = keepass::Database::open(&mut file, key)
+        .map_err(|e| Error::GenericDyn(format!("failed to open KeePass database: {e}")))?;
+
+    let mut entries = vec![];
+    // The name of the root group is the name of the database, so it's not used as a folder
+    collect_kdbx_group(&db.root, &[], &mut entries);
+    Ok(entries)
+}
+
+fn collect_kdbx_group(
+    group: &keepass::db::Group,
+    folders: &[String],
+    entries: &mut Vec<ImportedEntry>,
+) {
+    for node in &group.children {
+        match node {
+            keepass::db::Node::Group(child) => {
+                // KeePass keeps deleted entries in a group of its own
+                if child.name == "Recycle Bin" {
+                    continue;
+                }
+                let mut child_folders = folders.to_vec();
+                child_folders.push(child.name.clone());
+                collect_kdbx_group(child, &child_folders, entries);
+            }
+            keepass::db::Node::Entry(e) => {
+                let title = e.get_title().unwrap_or("").to_owned();
+                let otp = match (e.get("otp"), e.get("TimeOtp-Secret-Base32")) {
+                    (Some(url), _) if url.starts_with("otpauth://") => Some(url.to_owned()),
+                    (_, Some(secret)) => Some(otp_url_from_secret(&title, secret)),
+                    _ => None,
+                };
+                let standard = [
+                    "Title",
+                    "UserName",
+                    "Password",
+                    "URL",
+                    "Notes",
+                    "otp",
+                    "TimeOtp-Secret-Base32",
+                ];
+                let mut keys: Vec<&String> = e
+                    .fields
+                    .keys()
+                    .filter(|k| !standard.contains(&k.as_str()))
+                    .collect();
+                keys.sort();
+                let fields = keys
+                    .into_iter()
+                    .filter_map(|k| e.get(k).map(|v| (k.clone(), v.to_owned())))
+                    .collect();
+
+                entries.push(ImportedEntry {
+                    folders: folders.to_vec(),
+                    title,
+                    password: e.get_password().unwrap_or("").to_owned(),
+                    username: e.get_username().map(str::to_owned),
+                    urls: e.get_url().map(str::to_owned).into_iter().collect(),
+                    otp,
+                    fields,
+                    notes: e.get("Notes").unwrap_or("").to_owned(),
+                });
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct BitwardenExport {
+    #[serde(default)]
+    encrypted: bool,
+    #[serde(default)]
+    folders: Vec<BitwardenFolder>,
+    items: Vec<BitwardenItem>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenFolder {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BitwardenItem {
+    folder_id: Option<String>,
+    name: String,
+    notes: Option<String>,
+    login: Option<BitwardenLogin>,
+    #[serde(default)]
+    fields: Vec<BitwardenField>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenLogin {
+    username: Option<String>,
+    password: Option<String>,
+    totp: Option<String>,
+    #[serde(default)]
+    uris: Vec<BitwardenUri>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenUri {
+    uri: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenField {
+    name: Option<String>,
+    value: Option<String>,
+}
+
+/// Parses the unencrypted JSON export from Bitwarden. Folders in Bitwarden are flat, but their
+/// names may contain `/` to form a hierarchy, those become directories.
+/// # Errors
+/// Returns an `Err` if the content isn't a Bitwarden export, or if the export is encrypted.
+pub fn parse_bitwarden_json(content: &str) -> Result<Vec<ImportedEntry>> {
+    let export: BitwardenExport = serde_json::from_str(content)
+        .map_err(|e| Error::GenericDyn(format!("invalid Bitwarden export: {e}")))?;
+    if export.encrypted {
+        return Err(Error::Generic(
+            "encrypted Bitwarden exports can't be imported, export unencrypted JSON",
+        ));
+    }
+
+    let entries = export
+        .items
+        .into_iter()
+        .map(|item| {
+            let folders = item
+                .folder_id
+                .and_then(|id| export.folders.iter().find(|f| f.id == id))
+                .map(|f| f.name.split('/').map(str::to_owned).collect())
+                .unwrap_or_default();
+            let login = item.login.unwrap_or(BitwardenLogin {
+                username: None,
+                password: None,
+                totp: None,
+                uris: vec![],
+            });
+            let otp = login
+                .totp
+                .filter(|t| !t.is_empty())
+                .map(|t| otp_url_from_secret(&item.name, &t));
+
+            ImportedEntry {
+                folders,
+                password: login.password.unwrap_or_default(),
+                username: login.username,
+                urls: login.uris.into_iter().filter_map(|u| u.uri).collect(),
+                otp,
+                fields: item
+                    .fields
+                    .into_iter()
+                    .filter_map(|f| Some((f.name?, f.value?)))
+                    .collect(),
+                notes: item.notes.unwrap_or_default(),
+                title: item.name,
+            }
+        })
+        .collect();
+
+    Ok(entries)
+}
+
+/// Parses a CSV export from 1Password or a browser. The columns are found by their names in the
+/// header row, so that the different variants of the formats can be read:
+/// 1Password: `Title,Url,Username,Password,OTPAuth,Favorite,Archived,Tags,Notes`,
+/// Chrome: `name,url,username,password,note` and
+/// Firefox: `url,username,password,httpRealm,formActionOrigin,guid,...`.
+/// # Errors
+/// Returns an `Err` if the content isn't valid CSV or if there is no password column.
+pub fn parse_csv(content: &str) -> Result<Vec<ImportedEntry>> {
+    let mut rows = parse_csv_rows(content)?;
+    if rows.is_empty() {
+        return Ok(vec![]);
+    }
+    let header: Vec<String> = rows
+        .remove(0)
+        .iter()
+        .map(|h| h.trim().trim_start_matches('\u{feff}').to_lowercase())
+        .collect();
+
+    let column = |names: &[&str]| header.iter().position(|h| names.contains(&h.as_str()));
+    let title = column(&["title", "name"]);
+    let url = column(&["url", "website", "urls", "login_uri"]);
+    let username = column(&["username", "login", "login_username", "user"]);
+    let password = column(&["password", "login_password"])
+        .ok_or(Error::Generic("the CSV file has no password column"))?;
+    let otp = column(&["otpauth", "totp", "otp", "one-time password"]);
+    let notes = column(&["notes", "note", "extra", "notesplain"]);
+    let folder = column(&["folder", "group", "vault"]);
+
+    let entries = rows
+        .iter_mut()
+        .filter(|row| row.iter().any(|c| !c.is_empty()))
+        .map(|row| {
+            let get =
+                |index: Option<usize>| index.and_then(|i| row.get(i)).cloned().unwrap_or_default();
+            let title = get(title);
+            let urls: Vec<String> = get(url)
+                .split(['\n', ','])
+                .map(|u| u.trim().to_owned())
+                .filter(|u| !u.is_empty())
+                .collect();
+            let otp = Some(get(otp))
+                .filter(|o| !o.is_empty())
+                .map(|o| otp_url_from_secret(&title, &o));
+
+            let entry = ImportedEntry {
+                folders: get(folder)
+                    .split('/')
+                    .filter(|f| !f.is_empty())
+                    .map(str::to_owned)
+                    .collect(),
+                password: get(Some(password)),
+                username: Some(get(username)).filter(|u| !u.is_empty()),
+                urls,
+                otp,
+                fields: vec![],
+                notes: get(notes),
+                title,
+            };
+            row.zeroize();
+            entry
+        })
+        .collect();
+
+    Ok(entries)
+}
+
+/// Splits CSV content into rows of cells, following RFC 4180: cells can be quoted with `"`,
+/// and quoted cells can contain commas, newlines and `""` for a quote.
+fn parse_csv_rows(content: &str) -> Result<Vec<Vec<String>>> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut cell = String::new();
+    let mut in_quotes = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    cell.push('"');
+                }
+                '"' => in_quotes = false,
+                _ => cell.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_quotes = true,
+            ',' => row.push(std::mem::take(&mut cell)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                row.push(std::mem::take(&mut cell));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => cell.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(Error::Generic("unterminated quoted value in CSV file"));
+    }
+    if !cell.is_empty() || !row.is_empty() {
+        row.push(cell);
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+/// Returns an otpauth:// url for a TOTP seed. Exports store either a complete url, a bare base32
+/// secret or Bitwarden's `steam://` form.
+fn otp_url_from_secret(title: &str, secret: &str) -> String {
+    let secret = secret.trim();
+    if secret.starts_with("otpauth://") {
+        return secret.to_owned();
+    }
+
+    let label = percent_encode(title.trim());
+    match secret.strip_prefix("steam://") {
+        Some(steam_secret) => {
+            format!("otpauth://totp/Steam:{label}?secret={steam_secret}&issuer=Steam")
+        }
+        None => {
+            let secret: String = secret
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect::<String>()
+                .to_uppercase();
+            format!("otpauth://totp/{label}?secret={secret}")
+        }
+    }
+}
+
+fn percent_encode(s: &str) -> String {
+    let mut encoded = String::new();
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+            encoded.push(b as char);
+        } else {
+            encoded.push_str(&format!("%{b:02X}"));
+        }
+    }
+    encoded
+}
+
+fn host_of(url: &str) -> &str {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    without_scheme
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or("")
+        .rsplit('@')
+        .next()
+        .unwrap_or("")
+}
+
+fn one_line(s: &str) -> String {
+    s.replace(['\r', '\n'], " ")
+}
+
+/// Turns a folder or title into a single path segment that can't escape the store.
+fn path_segment(s: &str) -> String {
+    let segment = one_line(s).replace(['/', '\\'], "-");
+    let segment = segment.trim_start_matches('.');
+    if segment.is_empty() {
+        "_".to_owned()
+    } else {
+        segment.to_owned()
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/import.rs"]
+mod import_tests;
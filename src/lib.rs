@@ -1,20 +1,56 @@
 //! This implements a handling of a pass directory compatible with <https://www.passwordstore.org/> .
 //! The encryption is handled by `GPGme` or `sequoia` and the git integration is with libgit2.
 
+/// Password health checks: reused, weak, old and breached passwords
+pub mod audit;
+/// A cache of the git metadata of the passwords, so that big stores open quickly
+pub(crate) mod cache;
+/// Copying secrets to the clipboard, and clearing them again only if they are still there
+pub mod clipboard;
+/// Credentials for git remotes: the ssh agent, ssh keys, credential helpers and tokens, with a
+/// trait that the frontends implement to ask the user for passphrases and tokens
+pub mod credentials;
 /// This is the library part that handles all encryption and decryption
 pub mod crypto;
+/// Finding the keys of recipients that aren't in the keyring, through WKD, HKP and VKS
+/// keyservers and local directories of `.asc` files
+pub mod discovery;
 /// All functions and structs related to error handling
 pub(crate) mod error;
+/// Export of decrypted passwords to encrypted archives, CSV and JSON, for offboarding and audits
+pub mod export;
+/// Configurable password generation, from character classes or from the EFF word list, with
+/// an estimate of how strong the result is
+pub mod generate;
 /// All git related operations.
 pub mod git;
+/// Reading of the export formats of other password managers, for importing into a store
+pub mod import;
+/// Line by line three-way merge of decrypted entries that were changed on both sides of a pull
+pub(crate) mod merge;
+/// One time passwords from otpauth:// urls, TOTP, HOTP and Steam Guard
+pub(crate) mod otp;
+/// Parsing of the decrypted content of a password entry into password, fields and notes
+pub(crate) mod parsed;
 /// This is the library part of ripasso, it implements the functions needed to manipulate a pass
 /// directory.
 pub mod pass;
+/// Ranked fuzzy search of password entries, with filters on their git metadata
+pub(crate) mod search;
 /// All functions and structs related to handling the identity and signing of things
 pub(crate) mod signature;
+/// Background syncing of a store with its remote, reported to the UI through a channel
+pub mod sync;
+/// Watching of the store directory, so that the password list follows changes made outside
+/// of ripasso
+pub mod watch;
 /// This is the library that handles password generation, based on the long word list from EFF
 /// <https://www.eff.org/sv/deeplinks/2016/07/new-wordlists-random-passphrases>
 pub mod words;
+/// The web of trust of the Sequoia keyring: how valid each cert is from the certifications and
+/// trust signatures of the trust roots and the introducers that they delegate to. It's kept
+/// apart from the rest of the crypto so that it can be audited and tested on its own.
+pub(crate) mod wot;
 
 #[cfg(test)]
 #[path = "tests/test_helpers.rs"]
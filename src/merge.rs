@@ -0,0 +1,170 @@
+use zeroize::Zeroize;
+
+/// The result of merging two edited versions of an entry.
+pub struct MergedText {
+    /// The merged content, the lines that both sides changed in different ways are put between
+    /// git style `<<<<<<<`, `=======` and `>>>>>>>` markers
+    pub text: String,
+    /// How many places that both sides changed in different ways
+    pub conflicts: usize,
+}
+
+impl Drop for MergedText {
+    fn drop(&mut self) {
+        self.text.zeroize();
+    }
+}
+
+/// A line of a line by line diff between two versions of an entry, without its line break.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiffLine {
+    /// A line that is in both versions
+    Unchanged(String),
+    /// A line that is only in the old version
+    Removed(String),
+    /// A line that is only in the new version
+    Added(String),
+}
+
+impl DiffLine {
+    /// The text of the line.
+    pub fn text(&self) -> &str {
+        match self {
+            Self::Unchanged(line) | Self::Removed(line) | Self::Added(line) => line,
+        }
+    }
+}
+
+impl Drop for DiffLine {
+    fn drop(&mut self) {
+        match self {
+            Self::Unchanged(line) | Self::Removed(line) | Self::Added(line) => line.zeroize(),
+        }
+    }
+}
+
+/// Diffs two versions of an entry line by line. The lines that are removed from a changed
+/// range come before the ones that are added to it.
+pub fn diff(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    let mut lines = vec![];
+    let mut n = 0;
+    for (line, matching) in old.iter().zip(matching_lines(&old, &new)) {
+        match matching {
+            Some(m) => {
+                lines.extend(new[n..m].iter().map(|l| DiffLine::Added((*l).to_owned())));
+                lines.push(DiffLine::Unchanged((*line).to_owned()));
+                n = m + 1;
+            }
+            None => lines.push(DiffLine::Removed((*line).to_owned())),
+        }
+    }
+    lines.extend(new[n..].iter().map(|l| DiffLine::Added((*l).to_owned())));
+    lines
+}
+
+/// Merges the changes that `ours` and `theirs` made to `base`, line by line. Changes that only
+/// one side made are taken as they are, and changes to the same lines are only merged if both
+/// sides made the same change.
+pub fn merge(base: &str, ours: &str, theirs: &str) -> MergedText {
+    let base: Vec<&str> = base.split_inclusive('\n').collect();
+    let ours: Vec<&str> = ours.split_inclusive('\n').collect();
+    let theirs: Vec<&str> = theirs.split_inclusive('\n').collect();
+
+    let in_ours = matching_lines(&base, &ours);
+    let in_theirs = matching_lines(&base, &theirs);
+
+    let mut merged = MergedText {
+        text: String::new(),
+        conflicts: 0,
+    };
+    let (mut b, mut o, mut t) = (0, 0, 0);
+    loop {
+        // the next base line that neither side changed
+        let unchanged = (b..base.len()).find_map(|i| Some((i, in_ours[i]?, in_theirs[i]?)));
+        let (next_b, next_o, next_t) = unchanged.unwrap_or((base.len(), ours.len(), theirs.len()));
+
+        merge_chunk(
+            &mut merged,
+            &base[b..next_b],
+            &ours[o..next_o],
+            &theirs[t..next_t],
+        );
+
+        if unchanged.is_none() {
+            break;
+        }
+        merged.text.push_str(base[next_b]);
+        (b, o, t) = (next_b + 1, next_o + 1, next_t + 1);
+    }
+
+    merged
+}
+
+/// Merges a range of lines that was changed by at least one of the sides.
+fn merge_chunk(merged: &mut MergedText, base: &[&str], ours: &[&str], theirs: &[&str]) {
+    if ours == theirs || theirs == base {
+        push_lines(&mut merged.text, ours);
+    } else if ours == base {
+        push_lines(&mut merged.text, theirs);
+    } else {
+        merged.conflicts += 1;
+        push_marker(&mut merged.text, "<<<<<<< ours");
+        push_lines(&mut merged.text, ours);
+        push_marker(&mut merged.text, "=======");
+        push_lines(&mut merged.text, theirs);
+        push_marker(&mut merged.text, ">>>>>>> theirs");
+    }
+}
+
+fn push_lines(text: &mut String, lines: &[&str]) {
+    for line in lines {
+        text.push_str(line);
+    }
+}
+
+/// Appends a conflict marker on a line of its own.
+fn push_marker(text: &mut String, marker: &str) {
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text.push_str(marker);
+    text.push('\n');
+}
+
+/// For each line in `a`, the index of the same line in `b`, if it's part of the longest common
+/// subsequence of the two.
+fn matching_lines(a: &[&str], b: &[&str]) -> Vec<Option<usize>> {
+    // lcs[i][j] is the length of the longest common subsequence of a[i..] and b[j..]
+    let mut lcs = vec![vec![0_usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut matches = vec![None; a.len()];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            matches[i] = Some(j);
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+#[path = "tests/merge.rs"]
+mod merge_tests;
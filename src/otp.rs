@@ -0,0 +1,220 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use totp_rs::{Algorithm, TOTP};
+use zeroize::Zeroize;
+
+use crate::error::{Error, Result};
+
+/// The kind of one time password an `otpauth://` url describes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OtpKind {
+    /// Time based codes, RFC 6238
+    Totp,
+    /// Counter based codes, RFC 4226, the counter is the one stored in the url
+    Hotp { counter: u64 },
+    /// Steam Guard, time based codes of 5 characters from Steam's own alphabet
+    Steam,
+}
+
+/// Describes one of the `otpauth://` urls in a password entry, without the secret.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OtpAccount {
+    pub kind: OtpKind,
+    pub issuer: Option<String>,
+    pub account_name: String,
+}
+
+impl OtpAccount {
+    /// A name for the account that can be shown to the user, `issuer:account_name`
+    /// if the url has an issuer.
+    pub fn label(&self) -> String {
+        match &self.issuer {
+            Some(issuer) if !issuer.is_empty() => format!("{issuer}:{}", self.account_name),
+            _ => self.account_name.clone(),
+        }
+    }
+}
+
+/// A generated one time password code. Time based codes also carry how long they are valid
+/// and the code that follows them, so that a user interface can avoid handing out a code that
+/// is about to expire.
+pub struct OtpCode {
+    code: String,
+    next_code: Option<String>,
+    period: Option<u64>,
+    seconds_remaining: Option<u64>,
+}
+
+impl OtpCode {
+    /// Returns the code.
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// Returns the code that is valid after this one, `None` for hotp codes.
+    pub fn next_code(&self) -> Option<&str> {
+        self.next_code.as_deref()
+    }
+
+    /// Returns how many seconds each code is valid, `None` for hotp codes.
+    pub fn period(&self) -> Option<u64> {
+        self.period
+    }
+
+    /// Returns how many seconds the code was valid for at the time it was generated,
+    /// `None` for hotp codes.
+    pub fn seconds_remaining(&self) -> Option<u64> {
+        self.seconds_remaining
+    }
+
+    /// Returns true if the code is time based and was valid for less than `seconds` seconds
+    /// when it was generated.
+    pub fn expires_within(&self, seconds: u64) -> bool {
+        self.seconds_remaining.is_some_and(|r| r < seconds)
+    }
+}
+
+impl Drop for OtpCode {
+    fn drop(&mut self) {
+        self.code.zeroize();
+        self.next_code.zeroize();
+    }
+}
+
+/// A parsed `otpauth://` url.
+pub(crate) struct OtpUrl {
+    kind: OtpKind,
+    totp: TOTP,
+}
+
+impl OtpUrl {
+    /// Parses an `otpauth://totp/`, `otpauth://hotp/` or `otpauth://steam/` url. Urls
+    /// with the issuer `Steam` are also treated as Steam Guard.
+    pub(crate) fn parse(url: &str) -> Result<Self> {
+        let url = url.trim();
+        let (host, rest) = url
+            .strip_prefix("otpauth://")
+            .and_then(|u| u.split_once('/'))
+            .ok_or(Error::Generic("not a otpauth:// url"))?;
+
+        // totp-rs only understands totp urls, the other kinds share the same parameters
+        let mut totp_url = format!("otpauth://totp/{rest}");
+        let totp = TOTP::from_url(&totp_url);
+        totp_url.zeroize();
+        let mut totp = totp?;
+
+        let kind = match host.to_lowercase().as_str() {
+            "totp"
+                if totp
+                    .issuer
+                    .as_ref()
+                    .is_some_and(|i| i.eq_ignore_ascii_case("steam")) =>
+            {
+                OtpKind::Steam
+            }
+            "totp" => OtpKind::Totp,
+            "steam" => OtpKind::Steam,
+            "hotp" => {
+                let counter = query_value(url, "counter")
+                    .ok_or(Error::Generic("hotp url without a counter"))?
+                    .parse()
+                    .map_err(|_| Error::Generic("the counter of the hotp url isn't a number"))?;
+                // With a step of one second, the time based code for time t is the counter
+                // based code for counter t.
+                totp.step = 1;
+                totp.skew = 0;
+                OtpKind::Hotp { counter }
+            }
+            _ => return Err(Error::Generic("unsupported otpauth:// url type")),
+        };
+
+        if kind == OtpKind::Steam {
+            totp.algorithm = Algorithm::Steam;
+            totp.digits = 5;
+        }
+
+        Ok(Self { kind, totp })
+    }
+
+    pub(crate) fn kind(&self) -> OtpKind {
+        self.kind
+    }
+
+    pub(crate) fn account(&self) -> OtpAccount {
+        OtpAccount {
+            kind: self.kind,
+            issuer: self.totp.issuer.clone(),
+            account_name: self.totp.account_name.clone(),
+        }
+    }
+
+    /// Returns the current code for time based urls, and the code for the stored counter
+    /// for hotp urls.
+    pub(crate) fn generate(&self) -> Result<OtpCode> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        Ok(self.generate_at(now))
+    }
+
+    /// Like `generate`, but with the time based codes generated for `time`, in seconds since
+    /// the unix epoch.
+    pub(crate) fn generate_at(&self, time: u64) -> OtpCode {
+        match self.kind {
+            OtpKind::Hotp { counter } => OtpCode {
+                code: self.totp.generate(counter),
+                next_code: None,
+                period: None,
+                seconds_remaining: None,
+            },
+            OtpKind::Totp | OtpKind::Steam => {
+                let period = self.totp.step;
+                let seconds_remaining = period - time % period;
+                OtpCode {
+                    code: self.totp.generate(time),
+                    next_code: Some(self.totp.generate(time + seconds_remaining)),
+                    period: Some(period),
+                    seconds_remaining: Some(seconds_remaining),
+                }
+            }
+        }
+    }
+}
+
+/// Returns the value of a parameter in the query part of the url.
+fn query_value<'a>(url: &'a str, key: &str) -> Option<&'a str> {
+    let (_, query) = url.split_once('?')?;
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, v)| v)
+}
+
+/// Returns the url with its counter parameter set to `counter`.
+pub(crate) fn with_counter(url: &str, counter: u64) -> String {
+    let Some((base, query)) = url.split_once('?') else {
+        return format!("{url}?counter={counter}");
+    };
+
+    let mut found = false;
+    let mut params: Vec<String> = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((k, _)) if k.eq_ignore_ascii_case("counter") => {
+                found = true;
+                format!("{k}={counter}")
+            }
+            _ => pair.to_owned(),
+        })
+        .collect();
+    if !found {
+        params.push(format!("counter={counter}"));
+    }
+
+    let url = format!("{base}?{}", params.join("&"));
+    params.zeroize();
+    url
+}
+
+#[cfg(test)]
+#[path = "tests/otp.rs"]
+mod otp_tests;
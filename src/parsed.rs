@@ -0,0 +1,256 @@
+use std::fmt::{Display, Formatter};
+
+use zeroize::Zeroize;
+
+/// One key/value line in the body of a password entry, for example `login: alice`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EntryField {
+    /// The text before the first colon on the line.
+    pub key: String,
+    /// The text after the colon and the whitespace following it.
+    pub value: String,
+    /// The colon and the whitespace that separated the key from the value, kept so that
+    /// the entry can be written back exactly as it was read.
+    separator: String,
+}
+
+impl EntryField {
+    /// Creates a field that will be written as `key: value`
+    pub fn new(key: &str, value: &str) -> Self {
+        Self {
+            key: key.to_owned(),
+            value: value.to_owned(),
+            separator: ": ".to_owned(),
+        }
+    }
+}
+
+impl Zeroize for EntryField {
+    fn zeroize(&mut self) {
+        self.key.zeroize();
+        self.value.zeroize();
+        self.separator.zeroize();
+    }
+}
+
+/// One line of the body of a password entry, that is everything after the first line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EntryLine {
+    /// A `key: value` line
+    Field(EntryField),
+    /// A line that contains a `otpauth://` url, the original line is kept including
+    /// any leading whitespace
+    Otp(String),
+    /// Anything else, free form text
+    Note(String),
+}
+
+impl Zeroize for EntryLine {
+    fn zeroize(&mut self) {
+        match self {
+            Self::Field(f) => f.zeroize(),
+            Self::Otp(s) | Self::Note(s) => s.zeroize(),
+        }
+    }
+}
+
+impl Display for EntryLine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Field(field) => write!(f, "{}{}{}", field.key, field.separator, field.value),
+            Self::Otp(s) | Self::Note(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+/// The content of a password entry split up in the parts that pass, gopass and browserpass
+/// uses: the password on the first line, followed by an ordered list of `key: value` fields,
+/// otpauth urls and free form notes.
+///
+/// Converting a `ParsedEntry` back to a string with `to_string()` returns exactly the text
+/// it was parsed from, as long as it hasn't been modified.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParsedEntry {
+    /// The first line of the entry
+    password: String,
+    /// All lines after the first, in order
+    lines: Vec<EntryLine>,
+}
+
+impl ParsedEntry {
+    /// Parses the decrypted content of a password entry.
+    pub fn parse(secret: &str) -> Self {
+        let mut split = secret.split('\n');
+        let password = split.next().unwrap_or("").to_owned();
+
+        let lines = split.map(parse_line).collect();
+
+        Self { password, lines }
+    }
+
+    /// Returns the password, the first line of the entry.
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+
+    /// Replaces the password, the rest of the entry is left as is.
+    pub fn set_password(&mut self, password: &str) {
+        self.password.zeroize();
+        password.clone_into(&mut self.password);
+    }
+
+    /// Returns all lines after the password, in the order they appear in the file.
+    pub fn lines(&self) -> &[EntryLine] {
+        &self.lines
+    }
+
+    /// Returns all `key: value` fields, in the order they appear in the file.
+    pub fn fields(&self) -> Vec<&EntryField> {
+        self.lines
+            .iter()
+            .filter_map(|l| match l {
+                EntryLine::Field(f) => Some(f),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the value of the first field with the name `key`, the comparison ignores case.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields()
+            .into_iter()
+            .find(|f| f.key.eq_ignore_ascii_case(key))
+            .map(|f| f.value.as_str())
+    }
+
+    /// Sets the value of the first field named `key`, or appends a new field last in the
+    /// entry if there is no such field.
+    pub fn set(&mut self, key: &str, value: &str) {
+        for line in &mut self.lines {
+            if let EntryLine::Field(f) = line {
+                if f.key.eq_ignore_ascii_case(key) {
+                    f.value.zeroize();
+                    value.clone_into(&mut f.value);
+                    return;
+                }
+            }
+        }
+
+        // keep a trailing newline last in the file
+        let insert_at = match self.lines.last() {
+            Some(EntryLine::Note(s)) if s.is_empty() => self.lines.len() - 1,
+            _ => self.lines.len(),
+        };
+        self.lines
+            .insert(insert_at, EntryLine::Field(EntryField::new(key, value)));
+    }
+
+    /// Removes all fields named `key`, returns true if any field was removed.
+    pub fn remove(&mut self, key: &str) -> bool {
+        let before = self.lines.len();
+        self.lines.retain_mut(|l| match l {
+            EntryLine::Field(f) if f.key.eq_ignore_ascii_case(key) => {
+                f.zeroize();
+                false
+            }
+            _ => true,
+        });
+        before != self.lines.len()
+    }
+
+    /// Returns all the otpauth:// urls in the entry, both those on a line of their own
+    /// and those that are the value of a field, like `totp: otpauth://...`. An url on the
+    /// first line, the layout of pass-otp, comes first.
+    pub fn otp_urls(&self) -> Vec<&str> {
+        let first_line = self.password.trim();
+        first_line
+            .starts_with("otpauth://")
+            .then_some(first_line)
+            .into_iter()
+            .chain(self.lines.iter().filter_map(|l| match l {
+                EntryLine::Otp(s) => Some(s.trim()),
+                EntryLine::Field(f) if f.value.trim_start().starts_with("otpauth://") => {
+                    Some(f.value.trim())
+                }
+                _ => None,
+            }))
+            .collect()
+    }
+
+    /// Returns the free form lines of the entry joined with newlines, without trailing
+    /// whitespace.
+    pub fn notes(&self) -> String {
+        let notes = self
+            .lines
+            .iter()
+            .filter_map(|l| match l {
+                EntryLine::Note(s) => Some(s.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<&str>>()
+            .join("\n");
+
+        notes.trim_end().to_owned()
+    }
+}
+
+impl Display for ParsedEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.password)?;
+        for line in &self.lines {
+            write!(f, "\n{line}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Zeroize for ParsedEntry {
+    fn zeroize(&mut self) {
+        self.password.zeroize();
+        for line in &mut self.lines {
+            line.zeroize();
+        }
+        self.lines.clear();
+    }
+}
+
+impl Drop for ParsedEntry {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+fn parse_line(line: &str) -> EntryLine {
+    if line.trim_start().starts_with("otpauth://") {
+        return EntryLine::Otp(line.to_owned());
+    }
+
+    if let Some(pos) = line.find(':') {
+        let key = &line[..pos];
+        let rest = &line[pos + 1..];
+
+        // A key must start at the beginning of the line, and the colon must be followed by
+        // whitespace or the end of the line, so that urls like https://example.com stay notes.
+        let valid_key = !key.is_empty()
+            && !key.starts_with(char::is_whitespace)
+            && !key.ends_with(char::is_whitespace);
+        let valid_rest = rest.is_empty() || rest.starts_with([' ', '\t']);
+
+        if valid_key && valid_rest {
+            let value = rest.trim_start_matches([' ', '\t']);
+            let separator = format!(":{}", &rest[..rest.len() - value.len()]);
+            return EntryLine::Field(EntryField {
+                key: key.to_owned(),
+                value: value.to_owned(),
+                separator,
+            });
+        }
+    }
+
+    EntryLine::Note(line.to_owned())
+}
+
+#[cfg(test)]
+#[path = "tests/parsed.rs"]
+mod parsed_tests;
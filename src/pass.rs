@@ -19,30 +19,52 @@ use std::{
     fs,
     fs::{create_dir_all, File},
     io::prelude::*,
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
     str,
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
 use chrono::prelude::*;
-use totp_rs::TOTP;
 use zeroize::Zeroize;
 
 use crate::{
-    crypto::{Crypto, CryptoImpl, GpgMe, Sequoia, VerificationError},
+    audit::{self, AuditOptions, AuditReport, AuditedPassword},
+    cache::{CachedMetadata, MetadataCache},
+    clipboard::DEFAULT_CLIPBOARD_TIMEOUT,
+    crypto::{
+        Age, Crypto, CryptoImpl, GpgMe, PassphrasePrompt, Sequoia, VerificationError,
+        DEFAULT_KEY_CACHE_TIMEOUT,
+    },
+    discovery::{default_key_sources, KeySource},
+    export::{self, ExportFormat, ExportOptions, ExportReport, ExportWarning, ExportedEntry},
     git::{
         add_and_commit_internal, commit, find_last_commit, init_git_repo, match_with_parent,
         move_and_commit, push_password_if_match, read_git_meta_data, remove_and_commit,
-        verify_git_signature,
+        verify_git_signature, PullStrategy, SyncRemote,
     },
+    import::{ConflictStrategy, ImportOptions, ImportReport, ImportedEntry},
+    merge,
+    otp::{self, OtpUrl},
+    signature::trust_levels,
+    sync::SyncHandle,
 };
 pub use crate::{
     error::{to_result, Error, Result},
+    merge::DiffLine,
+    otp::{OtpAccount, OtpCode, OtpKind},
+    parsed::{EntryField, EntryLine, ParsedEntry},
+    search::SearchQuery,
     signature::{
-        parse_signing_keys, Comment, KeyRingStatus, OwnerTrustLevel, Recipient, SignatureStatus,
+        parse_fingerprints, parse_signing_keys, Comment, EncryptionSubkey, KeyRingStatus,
+        KeyWarning, OwnerTrustLevel, Recipient, SignatureStatus,
     },
 };
 
+/// How many days before a recipient can no longer be encrypted to that it's warned about, if
+/// the store doesn't configure it.
+pub const DEFAULT_KEY_EXPIRY_WARNING_DAYS: u32 = 30;
+
 /// Represents a complete password store directory
 pub struct PasswordStore {
     /// Name given to the store in a config file
@@ -60,6 +82,26 @@ pub struct PasswordStore {
     crypto: Box<dyn Crypto + Send>,
     /// The home dir of the user, if it exists
     user_home: Option<PathBuf>,
+    /// How many seconds copied secrets stay on the clipboard, `None` for the default
+    clipboard_timeout: Option<u64>,
+    /// How many seconds unlocked secret keys stay in memory, `None` for the default
+    key_cache_timeout: Option<u64>,
+    /// How many days before the keys of recipients expire that they are warned about, `None`
+    /// for the default
+    key_expiry_warning_days: Option<u32>,
+    /// Fingerprints of the keys that are trusted to introduce other keys, for stores that
+    /// use Sequoia
+    trust_roots: Vec<[u8; 20]>,
+    /// Where to look for keys that aren't in the keyring, in order, empty for the default
+    key_sources: Vec<KeySource>,
+    /// How pulls combine local and remote commits
+    pull_strategy: PullStrategy,
+    /// The remotes to sync with, the upstream of the current branch if it's empty
+    remotes: Vec<SyncRemote>,
+    /// How many seconds between the fetches of the sync service, `None` if it shouldn't run
+    sync_interval: Option<u64>,
+    /// The sync service of the store, that is told to push after every commit
+    sync: Option<SyncHandle>,
 }
 
 impl Default for PasswordStore {
@@ -72,6 +114,15 @@ impl Default for PasswordStore {
             style_file: None,
             crypto: Box::new(GpgMe {}),
             user_home: None,
+            clipboard_timeout: None,
+            key_cache_timeout: None,
+            key_expiry_warning_days: None,
+            trust_roots: vec![],
+            key_sources: vec![],
+            pull_strategy: PullStrategy::Merge,
+            remotes: vec![],
+            sync_interval: None,
+            sync: None,
         }
     }
 }
@@ -107,6 +158,19 @@ impl PasswordStore {
                     &home,
                 )?)
             }
+            CryptoImpl::Age => {
+                let identities_file = match std::env::var("PASSAGE_IDENTITIES_FILE") {
+                    Ok(file) => PathBuf::from(file),
+                    Err(_) => home
+                        .clone()
+                        .ok_or(Error::Generic(
+                            "no home, required for finding the age identities",
+                        ))?
+                        .join(".passage")
+                        .join("identities"),
+                };
+                Box::new(Age::new(&identities_file))
+            }
         };
 
         let signing_keys = parse_signing_keys(password_store_signing_key, crypto.as_ref())?;
@@ -119,6 +183,15 @@ impl PasswordStore {
             style_file: style_file.to_owned(),
             crypto,
             user_home: home.clone(),
+            clipboard_timeout: None,
+            key_cache_timeout: None,
+            key_expiry_warning_days: None,
+            trust_roots: vec![],
+            key_sources: vec![],
+            pull_strategy: PullStrategy::Merge,
+            remotes: vec![],
+            sync_interval: None,
+            sync: None,
         };
 
         if !store.valid_gpg_signing_keys.is_empty() {
@@ -212,6 +285,15 @@ impl PasswordStore {
             style_file: style_file.to_owned(),
             crypto,
             user_home: home.clone(),
+            clipboard_timeout: None,
+            key_cache_timeout: None,
+            key_expiry_warning_days: None,
+            trust_roots: vec![],
+            key_sources: vec![],
+            pull_strategy: PullStrategy::Merge,
+            remotes: vec![],
+            sync_interval: None,
+            sync: None,
         };
 
         Ok(store)
@@ -236,6 +318,142 @@ impl PasswordStore {
         self.user_home.clone()
     }
 
+    /// Returns how long copied secrets from this store should stay on the clipboard.
+    pub fn get_clipboard_timeout(&self) -> Duration {
+        self.clipboard_timeout
+            .map_or(DEFAULT_CLIPBOARD_TIMEOUT, Duration::from_secs)
+    }
+
+    /// Sets how many seconds copied secrets from this store should stay on the clipboard,
+    /// `None` for the default.
+    pub fn set_clipboard_timeout(&mut self, seconds: Option<u64>) {
+        self.clipboard_timeout = seconds;
+    }
+
+    /// Returns how long secret keys that were unlocked with a passphrase stay in memory.
+    pub fn get_key_cache_timeout(&self) -> Duration {
+        self.key_cache_timeout
+            .map_or(DEFAULT_KEY_CACHE_TIMEOUT, Duration::from_secs)
+    }
+
+    /// Returns the configured number of seconds that secret keys that were unlocked with a
+    /// passphrase stay in memory, `None` if the default is used.
+    pub fn get_key_cache_timeout_setting(&self) -> Option<u64> {
+        self.key_cache_timeout
+    }
+
+    /// Sets how many seconds secret keys that were unlocked with a passphrase stay in memory,
+    /// `None` for the default and zero to ask for the passphrase every time.
+    pub fn set_key_cache_timeout(&mut self, seconds: Option<u64>) {
+        self.key_cache_timeout = seconds;
+        let timeout = self.get_key_cache_timeout();
+        self.crypto.set_key_cache_timeout(timeout);
+    }
+
+    /// Returns how many days before the keys of recipients expire that they are warned about.
+    pub fn get_key_expiry_warning_days(&self) -> u32 {
+        self.key_expiry_warning_days
+            .unwrap_or(DEFAULT_KEY_EXPIRY_WARNING_DAYS)
+    }
+
+    /// Returns the configured number of days before the keys of recipients expire that they
+    /// are warned about, `None` if the default is used.
+    pub fn get_key_expiry_warning_days_setting(&self) -> Option<u32> {
+        self.key_expiry_warning_days
+    }
+
+    /// Sets how many days before the keys of recipients expire that they are warned about,
+    /// `None` for the default.
+    pub fn set_key_expiry_warning_days(&mut self, days: Option<u32>) {
+        self.key_expiry_warning_days = days;
+    }
+
+    /// Returns the fingerprints of the keys that, together with the users own key, are trusted
+    /// to introduce other keys.
+    pub fn get_trust_roots(&self) -> &[[u8; 20]] {
+        &self.trust_roots
+    }
+
+    /// Sets the fingerprints of the keys that, together with the users own key, are trusted to
+    /// introduce other keys. Only stores that use Sequoia use them, gpg has its own trust
+    /// database. Roots that aren't in the keyring are ignored.
+    pub fn set_trust_roots(&mut self, roots: Vec<[u8; 20]>) {
+        self.trust_roots.clone_from(&roots);
+        self.crypto.set_trust_roots(roots);
+    }
+
+    /// Returns where keys that aren't in the keyring are looked for, in order, an empty list
+    /// means the default keyserver.
+    pub fn get_key_sources(&self) -> &[KeySource] {
+        &self.key_sources
+    }
+
+    /// Sets where keys that aren't in the keyring are looked for, an empty list means the
+    /// default keyserver.
+    pub fn set_key_sources(&mut self, sources: Vec<KeySource>) {
+        self.key_sources = sources;
+    }
+
+    /// The key sources to use, the default keyserver if none are configured.
+    fn key_sources_or_default(&self) -> Vec<KeySource> {
+        if self.key_sources.is_empty() {
+            default_key_sources()
+        } else {
+            self.key_sources.clone()
+        }
+    }
+
+    /// Sets the prompt that asks for the passphrases of locked secret keys. Only stores that
+    /// use Sequoia ask, gpg asks with its own pinentry.
+    pub fn set_passphrase_prompt(&mut self, prompt: Arc<dyn PassphrasePrompt + Send + Sync>) {
+        self.crypto.set_passphrase_prompt(prompt);
+    }
+
+    /// Returns how pulls combine local and remote commits.
+    pub fn get_pull_strategy(&self) -> PullStrategy {
+        self.pull_strategy
+    }
+
+    /// Sets how pulls combine local and remote commits.
+    pub fn set_pull_strategy(&mut self, pull_strategy: PullStrategy) {
+        self.pull_strategy = pull_strategy;
+    }
+
+    /// Returns the remotes that the store syncs with, `push` and `pull` use the first one.
+    pub fn get_remotes(&self) -> &[SyncRemote] {
+        &self.remotes
+    }
+
+    /// Sets the remotes that the store syncs with, an empty list means the upstream of the
+    /// current branch.
+    pub fn set_remotes(&mut self, remotes: Vec<SyncRemote>) {
+        self.remotes = remotes;
+    }
+
+    /// Returns how often the sync service should fetch, `None` if the store isn't synced in
+    /// the background.
+    pub fn get_sync_interval(&self) -> Option<Duration> {
+        self.sync_interval.map(Duration::from_secs)
+    }
+
+    /// Sets how many seconds there should be between the fetches of the sync service, `None`
+    /// if the store shouldn't be synced in the background.
+    pub fn set_sync_interval(&mut self, seconds: Option<u64>) {
+        self.sync_interval = seconds;
+    }
+
+    /// Connects the store to a running sync service, that then pushes after every commit.
+    pub fn set_sync(&mut self, sync: Option<SyncHandle>) {
+        self.sync = sync;
+    }
+
+    /// Tells the sync service, if there is one, that there is a new commit to push.
+    pub(crate) fn request_push(&self) {
+        if let Some(sync) = &self.sync {
+            sync.push();
+        }
+    }
+
     /// returns the style file for the store
     pub fn get_style_file(&self) -> Option<PathBuf> {
         self.style_file.clone()
@@ -246,6 +464,36 @@ impl PasswordStore {
         &*self.crypto
     }
 
+    /// Returns the extension of the password files in the store, `gpg`, or `age` for stores
+    /// that are encrypted with age
+    pub fn password_file_extension(&self) -> &'static str {
+        self.crypto.implementation().password_file_extension()
+    }
+
+    /// Returns the name of the files that list the recipients of a directory, `.gpg-id`, or
+    /// `.age-recipients` for stores that are encrypted with age
+    pub fn recipients_file_name(&self) -> &'static str {
+        self.crypto.implementation().recipients_file_name()
+    }
+
+    /// Returns if `path` has the extension of the password files in the store.
+    pub fn is_password_file(&self, path: &Path) -> bool {
+        path.extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case(self.password_file_extension()))
+    }
+
+    /// Returns the path of the password file of the entry `name`.
+    fn password_file(&self, name: impl Into<PathBuf>) -> PathBuf {
+        append_extension(name.into(), &format!(".{}", self.password_file_extension()))
+    }
+
+    /// Returns a glob pattern that matches all password files below `dir`.
+    fn password_glob(&self, dir: &Path) -> String {
+        dir.join(format!("**/*.{}", self.password_file_extension()))
+            .to_string_lossy()
+            .to_string()
+    }
+
     pub fn repo(&self) -> Result<git2::Repository> {
         Ok(git2::Repository::open(&self.root)?)
     }
@@ -292,16 +540,18 @@ impl PasswordStore {
         };
 
         let gpg_id = fs::read(gpg_id_file)?;
-        let gpg_id_sig = match fs::read(gpg_id_sig_file) {
-            Ok(c) => c,
-            Err(_) => {
-                return Err(Error::Generic(
-                    "problem reading .gpg-id.sig, and strict signature checking was asked for",
-                ))
-            }
-        };
+        let gpg_id_sig = fs::read(gpg_id_sig_file).ok();
+
+        self.verify_gpg_id(&gpg_id, gpg_id_sig.as_deref())
+    }
 
-        match self.crypto.verify_sign(&gpg_id, &gpg_id_sig, &self.valid_gpg_signing_keys) {
+    /// Checks that the content of a `.gpg-id` file is signed by one of the valid signing keys.
+    fn verify_gpg_id(&self, gpg_id: &[u8], gpg_id_sig: Option<&[u8]>) -> Result<SignatureStatus> {
+        let gpg_id_sig = gpg_id_sig.ok_or(Error::Generic(
+            "problem reading .gpg-id.sig, and strict signature checking was asked for",
+        ))?;
+
+        match self.crypto.verify_sign(gpg_id, gpg_id_sig, &self.valid_gpg_signing_keys) {
             Ok(r) => Ok(r),
             Err(VerificationError::InfrastructureError(message)) => Err(Error::GenericDyn(message)),
             Err(VerificationError::SignatureFromWrongRecipient) => Err(Error::Generic("the .gpg-id file wasn't signed by one of the keys specified in the environmental variable PASSWORD_STORE_SIGNING_KEY")),
@@ -315,6 +565,27 @@ impl PasswordStore {
     /// # Errors
     /// Returns an `Err` if the path points to an file outside of the password store or the file already exists.
     pub fn new_password_file(&mut self, path_end: &str, content: &str) -> Result<PasswordEntry> {
+        let path = self.new_password_file_path(path_end)?;
+
+        if path.exists() {
+            return Err(Error::Generic("file already exist"));
+        }
+
+        match self.new_password_file_internal(&path, path_end, content) {
+            Ok(pe) => Ok(pe),
+            Err(err) => {
+                // try to remove the file we created, as cleanup
+                let _ = std::fs::remove_file(path);
+
+                // but always return the original error
+                Err(err)
+            }
+        }
+    }
+
+    /// Returns the path of the file for the password `path_end`, and creates the directories
+    /// leading up to it.
+    fn new_password_file_path(&self, path_end: &str) -> Result<PathBuf> {
         let mut path = self.root.clone();
 
         let c_path = std::fs::canonicalize(path.as_path())?;
@@ -336,24 +607,254 @@ impl PasswordStore {
                     std::fs::create_dir(&path)?;
                 }
             } else {
-                path.push(format!("{p}.gpg"));
+                path.push(self.password_file(p));
             }
         }
 
-        if path.exists() {
-            return Err(Error::Generic("file already exist"));
+        Ok(path)
+    }
+
+    /// Imports entries read from an export file, see `import::read_entries`, into the
+    /// directory `options.prefix`. All imported entries are committed to git in a single
+    /// commit if a repository is supplied.
+    /// # Errors
+    /// Returns an `Err` if an entry can't be encrypted or written, in that case no entries are
+    /// imported.
+    pub fn import(
+        &mut self,
+        entries: &[ImportedEntry],
+        options: &ImportOptions,
+    ) -> Result<ImportReport> {
+        if !self.valid_gpg_signing_keys.is_empty() {
+            self.verify_gpg_id_files()?;
         }
 
-        match self.new_password_file_internal(&path, path_end, content) {
-            Ok(pe) => Ok(pe),
-            Err(err) => {
-                // try to remove the file we created, as cleanup
+        let prefix = options.prefix.trim_matches('/');
+        let mut report = ImportReport::default();
+        let mut written: Vec<PathBuf> = vec![];
+
+        let res = self.import_internal(entries, prefix, options, &mut report, &mut written);
+        if let Err(err) = res {
+            for path in &written {
                 let _ = std::fs::remove_file(path);
+            }
+            return Err(err);
+        }
 
-                // but always return the original error
-                Err(err)
+        if !written.is_empty() && self.repo().is_ok() {
+            let paths: Vec<PathBuf> = report
+                .imported
+                .iter()
+                .map(|name| self.password_file(name))
+                .collect();
+            let message = if prefix.is_empty() {
+                format!("Import {} passwords using ripasso", paths.len())
+            } else {
+                format!(
+                    "Import {} passwords into {prefix} using ripasso",
+                    paths.len()
+                )
+            };
+            self.add_and_commit(&paths, &message)?;
+        }
+
+        self.reload_password_list()?;
+
+        Ok(report)
+    }
+
+    fn import_internal(
+        &self,
+        entries: &[ImportedEntry],
+        prefix: &str,
+        options: &ImportOptions,
+        report: &mut ImportReport,
+        written: &mut Vec<PathBuf>,
+    ) -> Result<()> {
+        for entry in entries {
+            let name = if prefix.is_empty() {
+                entry.name()
+            } else {
+                format!("{prefix}/{}", entry.name())
+            };
+
+            if entry.password.contains('\n') {
+                report
+                    .skipped
+                    .push((name, "the password contains a line break"));
+                continue;
+            }
+
+            let taken = |n: &str| {
+                self.root.join(self.password_file(n)).exists()
+                    || report.imported.iter().any(|i| i == n)
+            };
+            let mut target = name.clone();
+            if taken(&target) {
+                match options.on_conflict {
+                    ConflictStrategy::Skip => {
+                        report
+                            .skipped
+                            .push((name, "a password with that name already exists"));
+                        continue;
+                    }
+                    ConflictStrategy::Rename => {
+                        let mut number = 2;
+                        target = format!("{name}-{number}");
+                        while taken(&target) {
+                            number += 1;
+                            target = format!("{name}-{number}");
+                        }
+                    }
+                }
+            }
+
+            let path = self.new_password_file_path(&target)?;
+            let recipients = self.recipients_for_path(&path)?;
+            let mut secret = entry.to_secret();
+            let ciphertext = self.crypto.encrypt_string(&secret, &recipients);
+            secret.zeroize();
+
+            let mut file = File::create(&path)?;
+            written.push(path);
+            file.write_all(&ciphertext?)?;
+
+            if target != name {
+                report.renamed.push((name, target.clone()));
+            }
+            report.imported.push(target);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the reasons to think twice before exporting passwords to `path`, these should be
+    /// shown to the user before the export is started.
+    pub fn export_warnings(&self, path: &Path, encrypted: bool) -> Vec<ExportWarning> {
+        let mut warnings = vec![];
+        if !encrypted {
+            warnings.push(ExportWarning::Plaintext);
+        }
+
+        let parent = match path.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let parent = fs::canonicalize(&parent).unwrap_or(parent);
+        let root = fs::canonicalize(&self.root).unwrap_or_else(|_| self.root.clone());
+        if parent.starts_with(&root) {
+            warnings.push(ExportWarning::InsidePasswordStore);
+        } else if git2::Repository::discover(&parent).is_ok() {
+            warnings.push(ExportWarning::InsideGitRepository);
+        }
+
+        if path.exists() {
+            warnings.push(ExportWarning::OverwritesFile);
+        }
+
+        warnings
+    }
+
+    /// Exports the passwords selected by `options` to a tar archive, with one file per password
+    /// in the format of pass, that is encrypted to `recipients`. The decrypted passwords never
+    /// touch the disk.
+    /// # Errors
+    /// Returns an `Err` if a password can't be decrypted, if the encryption fails or if the
+    /// file can't be written
+    pub fn export_archive(
+        &self,
+        path: &Path,
+        recipients: &[Recipient],
+        options: &ExportOptions,
+    ) -> Result<ExportReport> {
+        if recipients.is_empty() {
+            return Err(Error::Generic("no recipients to encrypt the export to"));
+        }
+
+        let warnings = self.export_warnings(path, true);
+        let entries = self.export_entries(options)?;
+        let mtime = Local::now().timestamp().max(0) as u64;
+
+        let mut archive = export::to_tar(&entries, mtime)?;
+        let ciphertext = self.crypto.encrypt_bytes(&archive, recipients);
+        archive.zeroize();
+
+        write_private_file(path, &ciphertext?)?;
+
+        Ok(ExportReport {
+            exported: entries.iter().map(|e| e.name().to_owned()).collect(),
+            warnings,
+        })
+    }
+
+    /// Exports the passwords selected by `options` unencrypted, as CSV or JSON. The returned
+    /// report always contains the `Plaintext` warning.
+    /// # Errors
+    /// Returns an `Err` if a password can't be decrypted or if the file can't be written
+    pub fn export_plaintext(
+        &self,
+        path: &Path,
+        format: ExportFormat,
+        options: &ExportOptions,
+    ) -> Result<ExportReport> {
+        let warnings = self.export_warnings(path, false);
+        let entries = self.export_entries(options)?;
+
+        let mut content = match format {
+            ExportFormat::Csv => export::to_csv(&entries, &options.selection),
+            ExportFormat::Json => export::to_json(&entries)?,
+        };
+        let res = write_private_file(path, content.as_bytes());
+        content.zeroize();
+        res?;
+
+        Ok(ExportReport {
+            exported: entries.iter().map(|e| e.name().to_owned()).collect(),
+            warnings,
+        })
+    }
+
+    fn export_entries(&self, options: &ExportOptions) -> Result<Vec<ExportedEntry>> {
+        let mut passwords: Vec<&PasswordEntry> = self
+            .passwords
+            .iter()
+            .filter(|p| options.includes(&p.name))
+            .collect();
+        passwords.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut entries = vec![];
+        for password in passwords {
+            let parsed = password.parsed(self)?;
+            entries.push(ExportedEntry::new(
+                &password.name,
+                &parsed,
+                &options.selection,
+            ));
+        }
+        Ok(entries)
+    }
+
+    /// Decrypts every password in the store once and checks them for reuse, low entropy, age
+    /// and presence in a breach list. Entries that can't be decrypted are reported as
+    /// unreadable instead of failing the whole audit.
+    /// # Errors
+    /// Returns an `Err` if the breach list can't be read
+    pub fn audit(&self, options: &AuditOptions) -> Result<AuditReport> {
+        let mut passwords = vec![];
+        let mut unreadable = vec![];
+        for entry in &self.passwords {
+            match entry.parsed(self) {
+                Ok(parsed) if parsed.password().is_empty() => {}
+                Ok(parsed) => passwords.push(AuditedPassword::new(
+                    &entry.name,
+                    parsed.password(),
+                    entry.updated,
+                )),
+                Err(err) => unreadable.push((entry.name.clone(), err.to_string())),
             }
         }
+
+        audit::audit(&passwords, unreadable, Local::now(), options)
     }
 
     fn new_password_file_internal(
@@ -378,11 +879,13 @@ impl PasswordStore {
             Err(_) => {
                 self.passwords.push(PasswordEntry::load_from_filesystem(
                     &self.root,
-                    &append_extension(PathBuf::from(path_end), ".gpg"),
+                    &self.password_file(path_end),
+                    self.password_file_extension(),
                 ));
                 Ok(PasswordEntry::load_from_filesystem(
                     &self.root,
-                    &append_extension(PathBuf::from(path_end), ".gpg"),
+                    &self.password_file(path_end),
+                    self.password_file_extension(),
                 ))
             }
             Ok(repo) => {
@@ -390,7 +893,7 @@ impl PasswordStore {
 
                 add_and_commit_internal(
                     &repo,
-                    &[append_extension(PathBuf::from(path_end), ".gpg")],
+                    &[self.password_file(path_end)],
                     &message,
                     self.crypto.as_ref(),
                 )?;
@@ -444,40 +947,88 @@ impl PasswordStore {
 
         // Not a git repository
         if repo.is_err() {
-            let password_path_glob = self.root.join("**/*.gpg");
-            let existing_iter = glob::glob(&password_path_glob.to_string_lossy())?;
+            let existing_iter = glob::glob(&self.password_glob(&self.root))?;
 
             for existing_file in existing_iter {
                 let relpath = existing_file?.strip_prefix(&self.root)?.to_path_buf();
-                passwords.push(PasswordEntry::load_from_filesystem(&self.root, &relpath));
+                passwords.push(PasswordEntry::load_from_filesystem(
+                    &self.root,
+                    &relpath,
+                    self.password_file_extension(),
+                ));
             }
 
             return Ok(passwords);
         }
 
         let repo = repo?;
+        let head = repo.head()?.target().ok_or("missing Oid on head")?;
 
         // First, collect all files we need to find the first commit for
-        let password_path_glob = self.root.join("**/*.gpg");
-        let existing_iter = glob::glob(&password_path_glob.to_string_lossy())?;
-        let mut files_to_find: Vec<PathBuf> = vec![];
+        let existing_iter = glob::glob(&self.password_glob(&self.root))?;
+        let mut files: Vec<PathBuf> = vec![];
         for existing_file in existing_iter {
-            files_to_find.push(existing_file?.strip_prefix(&self.root)?.to_path_buf());
+            files.push(existing_file?.strip_prefix(&self.root)?.to_path_buf());
         }
 
-        if files_to_find.is_empty() {
+        if files.is_empty() {
             return Ok(vec![]);
         }
 
+        // Files that haven't changed since the cache was written don't need to be looked up in
+        // the history again
+        let mut cache = MetadataCache::load(&repo, head, &self.valid_gpg_signing_keys);
+        let mut files_to_find: Vec<PathBuf> = vec![];
+        for file in &files {
+            match cache.get(file) {
+                Some(metadata) => passwords.push(metadata.to_entry(
+                    &self.root,
+                    file,
+                    self.password_file_extension(),
+                )),
+                None => files_to_find.push(file.clone()),
+            }
+        }
+
+        if !files_to_find.is_empty() {
+            let found_from = passwords.len();
+            self.find_in_history(&repo, head, files_to_find, &mut passwords)?;
+
+            for entry in &passwords[found_from..] {
+                let relpath = entry.path.strip_prefix(&self.root)?.to_path_buf();
+                cache.insert(relpath, CachedMetadata::from_entry(entry));
+            }
+        }
+        cache.retain(&files);
+
+        // also save when HEAD moved without any lookups, otherwise the diff from the old HEAD
+        // would be redone on every listing
+        if cache.is_changed() {
+            // the cache only makes things faster, so failing to write it isn't an error
+            let _ = cache.save(&repo);
+        }
+
+        Ok(passwords)
+    }
+
+    /// Walks the history from `head` to find the commit that last touched each of the files.
+    fn find_in_history(
+        &self,
+        repo: &git2::Repository,
+        head: git2::Oid,
+        mut files_to_find: Vec<PathBuf>,
+        passwords: &mut Vec<PasswordEntry>,
+    ) -> Result<()> {
         // Walk through all commits in reverse order, if the commit contains
         // the file, mark it
         let mut walk = repo.revwalk()?;
-        walk.push(repo.head()?.target().ok_or("missing Oid on head")?)?;
-        let mut last_tree = repo
-            .find_commit(repo.head()?.target().ok_or("missing Oid on head")?)?
-            .tree()?;
-        let mut last_commit = repo.head()?.peel_to_commit()?;
+        walk.push(head)?;
+        let mut last_tree = repo.find_commit(head)?.tree()?;
+        let mut last_commit = repo.find_commit(head)?;
         for rev in walk {
+            if files_to_find.is_empty() {
+                break;
+            }
             if rev.is_err() {
                 continue;
             }
@@ -493,13 +1044,7 @@ impl PasswordStore {
                     if let Some(found) = delta.new_file().path() {
                         files_to_find.retain(|target| {
                             push_password_if_match(
-                                target,
-                                found,
-                                &commit,
-                                &repo,
-                                &mut passwords,
-                                &oid,
-                                self,
+                                target, found, &commit, repo, passwords, &oid, self,
                             )
                         });
                     }
@@ -524,8 +1069,8 @@ impl PasswordStore {
                         target,
                         &found,
                         &last_commit,
-                        &repo,
-                        &mut passwords,
+                        repo,
+                        passwords,
                         &last_commit.id(),
                         self,
                     )
@@ -539,6 +1084,7 @@ impl PasswordStore {
             passwords.push(PasswordEntry::new(
                 &self.root,
                 &not_found.clone(),
+                self.password_file_extension(),
                 Err(Error::Generic("")),
                 Err(Error::Generic("")),
                 Err(Error::Generic("")),
@@ -546,7 +1092,7 @@ impl PasswordStore {
             ));
         }
 
-        Ok(passwords)
+        Ok(())
     }
 
     /// Return a list of all the Recipients in the `$PASSWORD_STORE_DIR/.gpg-id` file.
@@ -557,9 +1103,10 @@ impl PasswordStore {
             self.verify_gpg_id_files()?;
         }
 
+        let trusts = trust_levels(self.crypto.as_ref());
         let mut recipients = vec![];
         for file in self.recipients_files()? {
-            for r in Recipient::all_recipients(&file, self.crypto.as_ref())? {
+            for r in Recipient::all_recipients_with_trust(&file, self.crypto.as_ref(), &trusts)? {
                 if !recipients.contains(&r) {
                     recipients.push(r);
                 }
@@ -568,6 +1115,25 @@ impl PasswordStore {
         Ok(recipients)
     }
 
+    /// Checks the recipients of all the `.gpg-id` files in the store, and returns the ones
+    /// whose keys are revoked, can't be encrypted to, or can't be encrypted to within the
+    /// configured number of days, together with the reason.
+    /// # Errors
+    /// Returns an `Err` if the recipients can't be read
+    pub fn recipient_warnings(&self) -> Result<Vec<(Recipient, KeyWarning)>> {
+        let now = Local::now();
+        let days = self.get_key_expiry_warning_days();
+
+        Ok(self
+            .all_recipients()?
+            .into_iter()
+            .filter_map(|recipient| {
+                let warning = recipient.key_warning(now, days)?;
+                Some((recipient, warning))
+            })
+            .collect())
+    }
+
     /// Return a list of all the Recipients in the `.gpg-id` file that is the
     /// closest parent to `path`.
     /// # Errors
@@ -580,6 +1146,27 @@ impl PasswordStore {
         Recipient::all_recipients(&self.recipients_file_for_dir(path)?, self.crypto.as_ref())
     }
 
+    /// Return a list of all the Recipients in the content of a `.gpg-id` file that isn't in
+    /// the working tree, like one in a commit that is being rebased. `gpg_id_sig` is the content
+    /// of its signature, if there is one.
+    /// # Errors
+    /// Returns an `Err` if the gpg_id file should be verified and it can't be
+    pub(crate) fn recipients_from_contents(
+        &self,
+        gpg_id: &[u8],
+        gpg_id_sig: Option<&[u8]>,
+    ) -> Result<Vec<Recipient>> {
+        if !self.valid_gpg_signing_keys.is_empty() {
+            self.verify_gpg_id(gpg_id, gpg_id_sig)?;
+        }
+
+        Ok(Recipient::recipients_from_contents(
+            std::str::from_utf8(gpg_id)?,
+            self.crypto.as_ref(),
+            &trust_levels(self.crypto.as_ref()),
+        ))
+    }
+
     fn recipients_file_for_dir(&self, path: &Path) -> Result<PathBuf> {
         let mut new_dir = std::fs::canonicalize(self.root.join(path))?;
 
@@ -590,7 +1177,7 @@ impl PasswordStore {
         }
 
         while new_dir.starts_with(&root) {
-            let f = new_dir.join(".gpg-id");
+            let f = new_dir.join(self.recipients_file_name());
 
             if f.exists() {
                 return Ok(f);
@@ -598,17 +1185,20 @@ impl PasswordStore {
             new_dir.pop();
         }
 
-        Err(Error::Generic("No .gpg-id file found"))
+        Err(Error::GenericDyn(format!(
+            "No {} file found",
+            self.recipients_file_name()
+        )))
     }
 
-    fn visit_dirs(dir: &Path, result: &mut Vec<PathBuf>) -> Result<()> {
+    fn visit_dirs(dir: &Path, file_name: &str, result: &mut Vec<PathBuf>) -> Result<()> {
         if dir.is_dir() {
             for entry in fs::read_dir(dir)? {
                 let entry = entry?;
                 let path = entry.path();
                 if path.is_dir() {
-                    Self::visit_dirs(&path, result)?;
-                } else if entry.file_name() == ".gpg-id" {
+                    Self::visit_dirs(&path, file_name, result)?;
+                } else if entry.file_name() == file_name {
                     result.push(entry.path());
                 }
             }
@@ -618,22 +1208,93 @@ impl PasswordStore {
 
     fn recipients_files(&self) -> Result<Vec<PathBuf>> {
         let mut results = vec![];
-        Self::visit_dirs(&self.root, &mut results)?;
+        Self::visit_dirs(&self.root, self.recipients_file_name(), &mut results)?;
         Ok(results)
     }
 
+    /// Returns the entries below `dir` that are encrypted to the recipients in `gpg_id_file`,
+    /// that is the ones that doesn't have a `.gpg-id` file closer to them.
+    fn entries_encrypted_with(
+        &self,
+        dir: &Path,
+        gpg_id_file: &Option<PathBuf>,
+    ) -> Result<Vec<PasswordEntry>> {
+        let root = fs::canonicalize(&self.root)?;
+        let password_path_glob = self.password_glob(&fs::canonicalize(self.root.join(dir))?);
+
+        let mut entries = vec![];
+        for file in glob::glob(&password_path_glob)? {
+            let file = file?;
+            if self.recipients_file_for_dir(&file).ok() == *gpg_id_file {
+                let relpath = file.strip_prefix(&root)?;
+                entries.push(PasswordEntry::load_from_filesystem(
+                    &self.root,
+                    relpath,
+                    self.password_file_extension(),
+                ));
+            }
+        }
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(entries)
+    }
+
+    /// Returns the directory that `add_recipient` will add the recipient to, that is `path`
+    /// if it's a directory inside the store.
+    fn recipient_dir(&self, path: &Path) -> Result<PathBuf> {
+        let dir = self.root.join(path);
+        if !dir.exists() {
+            return Err(Error::Generic("path doesn't exist"));
+        }
+        let dir = std::fs::canonicalize(dir)?;
+        let root = std::fs::canonicalize(&self.root)?;
+
+        if !dir.starts_with(root) {
+            return Err(Error::Generic("path traversal not allowed"));
+        }
+        Ok(dir)
+    }
+
+    /// Returns the entries that would be re-encrypted if a recipient was removed from the
+    /// .gpg-id file that governs `path`, without changing anything.
+    /// # Errors
+    /// Returns an `Err` if there is no .gpg-id file for the path.
+    pub fn remove_recipient_dry_run(&self, path: &Path) -> Result<Vec<PasswordEntry>> {
+        let gpg_id_file = self.recipients_file_for_dir(path)?;
+        let dir = gpg_id_file
+            .parent()
+            .ok_or(Error::Generic("the .gpg-id file has no parent directory"))?;
+
+        self.entries_encrypted_with(dir, &Some(gpg_id_file.clone()))
+    }
+
+    /// Returns the entries that would be re-encrypted if a recipient was added to the
+    /// directory `path`, without changing anything. If the directory doesn't have a .gpg-id
+    /// file of its own, only the entries below it are affected, as a new .gpg-id file will
+    /// be created in it.
+    /// # Errors
+    /// Returns an `Err` if the path doesn't exist or is outside of the store.
+    pub fn add_recipient_dry_run(&self, path: &Path) -> Result<Vec<PasswordEntry>> {
+        let dir = self.recipient_dir(path)?;
+
+        self.entries_encrypted_with(&dir, &self.recipients_file_for_dir(&dir).ok())
+    }
+
     fn remove_recipient_inner(&self, r: &Recipient, path: &Path) -> Result<()> {
+        let gpg_id_file = self.recipients_file_for_dir(path)?;
+        let entries = self.remove_recipient_dry_run(path)?;
+
         Recipient::remove_recipient_from_file(
             r,
-            &self.recipients_file_for_dir(path)?,
+            &gpg_id_file,
             &self.root,
             &self.valid_gpg_signing_keys,
             self.crypto.as_ref(),
         )?;
-        self.reencrypt_all_password_entries()
+        self.reencrypt_password_entries(&entries, &gpg_id_file)
     }
 
-    /// Removes a key from the .gpg-id file and re-encrypts all the passwords
+    /// Removes a key from the .gpg-id file and re-encrypts the passwords that are encrypted
+    /// with that file, see `remove_recipient_dry_run`
     /// # Errors
     /// Returns an `Err` if the gpg_id file should be verified and it can't be or if the recipient is the last one.
     pub fn remove_recipient(&self, r: &Recipient, path: &Path) -> Result<()> {
@@ -648,13 +1309,15 @@ impl PasswordStore {
         res
     }
 
-    /// Adds a key to the .gpg-id file in the path directory and re-encrypts all the passwords
+    /// Adds a key to the .gpg-id file in the path directory and re-encrypts the passwords in
+    /// that directory that are affected, see `add_recipient_dry_run`
     /// # Errors
     /// Returns an `Err` if the gpg_id file should be verified and it can't be or there is some problem with
     /// the encryption.
     pub fn add_recipient(&mut self, r: &Recipient, path: &Path, config_path: &Path) -> Result<()> {
         if !self.crypto.is_key_in_keyring(r)? {
-            self.crypto.pull_keys(&[r], config_path)?;
+            let sources = self.key_sources_or_default();
+            self.crypto.pull_keys(&[r], config_path, &sources)?;
         }
         if !self.crypto.is_key_in_keyring(r)? {
             return Err(Error::Generic(
@@ -662,65 +1325,82 @@ impl PasswordStore {
             ));
         }
 
-        let dir = self.root.join(path);
-        if !dir.exists() {
-            return Err(Error::Generic("path doesn't exist"));
-        }
-        let dir = std::fs::canonicalize(self.root.join(path))?;
-        let root = std::fs::canonicalize(&self.root)?;
+        let dir = self.recipient_dir(path)?;
+        let entries = self.add_recipient_dry_run(path)?;
 
-        if !dir.starts_with(root) {
-            return Err(Error::Generic("path traversal not allowed"));
-        }
-        if !dir.join(".gpg-id").exists() {
-            std::fs::File::create(dir.join(".gpg-id"))?;
+        let gpg_id_file = dir.join(self.recipients_file_name());
+        if !gpg_id_file.exists() {
+            std::fs::File::create(&gpg_id_file)?;
         }
 
         Recipient::add_recipient_to_file(
             r,
-            &self.recipients_file_for_dir(path)?,
+            &gpg_id_file,
             &self.valid_gpg_signing_keys,
             self.crypto.as_ref(),
         )?;
-        self.reencrypt_all_password_entries()
+        self.reencrypt_password_entries(&entries, &gpg_id_file)
     }
 
-    /// Reencrypt all the entries in the store, for example when a new collaborator is added
-    /// to the team.
+    /// Reencrypt the entries after the `.gpg-id` file `gpg_id_file` have changed, for example
+    /// when a new collaborator is added to the team.
     /// # Errors
     /// Returns an `Err` if the gpg_id file should be verified and it can't be or there is some problem with
     /// the encryption.
-    fn reencrypt_all_password_entries(&self) -> Result<()> {
+    fn reencrypt_password_entries(
+        &self,
+        entries: &[PasswordEntry],
+        gpg_id_file: &Path,
+    ) -> Result<()> {
         let mut names: Vec<PathBuf> = Vec::new();
-        for entry in self.all_passwords()? {
+        for entry in entries {
             let mut secret = entry.secret(self)?;
             entry.update_internal(&secret, self)?;
             secret.zeroize();
-            names.push(append_extension(PathBuf::from(&entry.name), ".gpg"));
+            names.push(self.password_file(&entry.name));
+        }
+
+        let root = fs::canonicalize(&self.root)?;
+        let gpg_id_relpath = gpg_id_file.strip_prefix(&root)?.to_path_buf();
+        let dir = gpg_id_relpath
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        names.push(gpg_id_relpath.clone());
+        if self
+            .root
+            .join(append_extension(gpg_id_relpath.clone(), ".sig"))
+            .exists()
+        {
+            names.push(append_extension(gpg_id_relpath, ".sig"));
         }
-        names.push(PathBuf::from(".gpg-id"));
 
         if self.repo().is_err() {
             return Ok(());
         }
 
-        let keys = self
-            .all_recipients()?
-            .into_iter()
-            .fold(String::new(), |mut acc, r| {
-                use std::fmt::Write;
-                let _ = write!(acc, ", 0x{}", r.key_id);
-                acc
-            });
+        let keys =
+            Recipient::all_recipients(&self.recipients_file_for_dir(&dir)?, self.crypto.as_ref())?
+                .into_iter()
+                .fold(String::new(), |mut acc, r| {
+                    use std::fmt::Write;
+                    let _ = write!(acc, ", 0x{}", r.key_id);
+                    acc
+                });
 
-        let message = format!("Reencrypt password store with new GPG ids {keys}");
+        let message = if dir.as_os_str().is_empty() {
+            format!("Reencrypt password store with new GPG ids {keys}")
+        } else {
+            format!("Reencrypt {} with new GPG ids {keys}", dir.display())
+        };
 
         self.add_and_commit(&names, &message)?;
 
         Ok(())
     }
 
-    /// Add a file to the store, and commit it to the supplied git repository.
+    /// Add a file to the store, and commit it to the supplied git repository. If the store has
+    /// a sync service, it then pushes the commit.
     /// # Errors
     /// Returns an `Err` if there is any problems with git.
     pub fn add_and_commit(&self, paths: &[PathBuf], message: &str) -> Result<git2::Oid> {
@@ -732,7 +1412,12 @@ impl PasswordStore {
 
         let mut index = repo.index()?;
         for path in paths {
-            index.add_path(path)?;
+            // a path that no longer exists, like a removed .gpg-id file, is staged as deleted
+            if self.root.join(path).exists() {
+                index.add_path(path)?;
+            } else {
+                index.remove_path(path)?;
+            }
         }
         let oid = index.write_tree()?;
         let signature = repo.signature()?;
@@ -755,6 +1440,7 @@ impl PasswordStore {
         )?;
         let obj = repo.find_object(oid, None)?;
         repo.reset(&obj, git2::ResetType::Hard, None)?;
+        self.request_push();
 
         Ok(oid)
     }
@@ -768,12 +1454,8 @@ impl PasswordStore {
             return Err(Error::Generic("directory traversal not allowed"));
         }
 
-        let mut old_path = self.root.clone();
-        old_path.push(PathBuf::from(old_name));
-        let old_path = append_extension(old_path, ".gpg");
-        let mut new_path = self.root.clone();
-        new_path.push(PathBuf::from(new_name));
-        let new_path = append_extension(new_path, ".gpg");
+        let old_path = self.root.join(self.password_file(old_name));
+        let new_path = self.root.join(self.password_file(new_name));
 
         if !old_path.exists() {
             return Err(Error::Generic("source file is missing"));
@@ -798,11 +1480,12 @@ impl PasswordStore {
         std::fs::remove_file(&old_path)?;
 
         if self.repo().is_ok() {
-            let old_file_name = append_extension(PathBuf::from(old_name), ".gpg");
-            let new_file_name = append_extension(PathBuf::from(new_name), ".gpg");
+            let old_file_name = self.password_file(old_name);
+            let new_file_name = self.password_file(new_name);
             move_and_commit(self, &old_file_name, &new_file_name, "moved file")?;
         }
 
+        let extension = self.password_file_extension();
         let passwords = &mut self.passwords;
         let mut index = usize::MAX;
         for (i, entry) in passwords.iter().enumerate() {
@@ -810,16 +1493,249 @@ impl PasswordStore {
                 index = i;
             }
         }
+        let relpath = new_path.strip_prefix(&self.root)?.to_path_buf();
         if index != usize::MAX {
             let old_entry = passwords.swap_remove(index);
-            let relpath = new_path.strip_prefix(&self.root)?.to_path_buf();
-            let new_entry = PasswordEntry::with_new_name(old_entry, &self.root, &relpath);
+            let new_entry =
+                PasswordEntry::with_new_name(old_entry, &self.root, &relpath, extension);
             passwords.push(new_entry);
+        } else {
+            // the password list haven't been loaded, for example when used from the command line
+            passwords.push(PasswordEntry::load_from_filesystem(
+                &self.root, &relpath, extension,
+            ));
         }
 
         Ok(passwords.len() - 1)
     }
 
+    /// Copies a password file to a new name, re-encrypted for the recipients of the new place.
+    /// returns the index in the password vec of the new `PasswordEntry`
+    /// # Errors
+    /// Returns an `Err` if the file is missing, or the target already exists.
+    pub fn copy_file(&mut self, old_name: &str, new_name: &str) -> Result<usize> {
+        let files = [(
+            self.password_file(checked_relpath(old_name)?),
+            self.password_file(checked_relpath(new_name)?),
+        )];
+
+        let indices = self.transfer_files(
+            &[],
+            &files,
+            false,
+            &format!("copied {old_name} to {new_name}"),
+        )?;
+        Ok(indices[0])
+    }
+
+    /// Moves a directory, with its `.gpg-id` files, to a new directory and commits it at once.
+    /// Each password file is re-encrypted for the recipients it has at its new place, like
+    /// `pass mv` does.
+    /// returns the indices in the password vec of the moved `PasswordEntry`s
+    /// # Errors
+    /// Returns an `Err` if the directory is empty, or any of the targets already exists.
+    pub fn rename_dir(&mut self, old_dir: &str, new_dir: &str) -> Result<Vec<usize>> {
+        let (recipients_files, files) = self.dir_transfer_files(old_dir, new_dir)?;
+
+        let message = format!("moved {old_dir} to {new_dir}");
+        self.transfer_files(&recipients_files, &files, true, &message)
+    }
+
+    /// Copies a directory, with its `.gpg-id` files, to a new directory and commits it at once.
+    /// Each password file is re-encrypted for the recipients it has at its new place, like
+    /// `pass cp` does.
+    /// returns the indices in the password vec of the new `PasswordEntry`s
+    /// # Errors
+    /// Returns an `Err` if the directory is empty, or any of the targets already exists.
+    pub fn copy_dir(&mut self, old_dir: &str, new_dir: &str) -> Result<Vec<usize>> {
+        let (recipients_files, files) = self.dir_transfer_files(old_dir, new_dir)?;
+
+        let message = format!("copied {old_dir} to {new_dir}");
+        self.transfer_files(&recipients_files, &files, false, &message)
+    }
+
+    /// Pairs up the `.gpg-id` files, with their signatures, and the password files below
+    /// `old_dir` with their place below `new_dir`, all relative to the root of the store.
+    #[allow(clippy::type_complexity)]
+    fn dir_transfer_files(
+        &self,
+        old_dir: &str,
+        new_dir: &str,
+    ) -> Result<(Vec<(PathBuf, PathBuf)>, Vec<(PathBuf, PathBuf)>)> {
+        let old_dir = checked_relpath(old_dir.trim_end_matches('/'))?;
+        let new_dir = checked_relpath(new_dir.trim_end_matches('/'))?;
+        if new_dir.starts_with(&old_dir) {
+            return Err(Error::Generic("can't move or copy a directory into itself"));
+        }
+        if !self.root.join(&old_dir).is_dir() {
+            return Err(Error::Generic("source directory is missing"));
+        }
+
+        let transfer = |relpath: PathBuf| -> Result<(PathBuf, PathBuf)> {
+            let target = new_dir.join(relpath.strip_prefix(&old_dir)?);
+            Ok((relpath, target))
+        };
+
+        let mut gpg_id_files = vec![];
+        Self::visit_dirs(
+            &self.root.join(&old_dir),
+            self.recipients_file_name(),
+            &mut gpg_id_files,
+        )?;
+        let mut recipients_files = vec![];
+        for gpg_id_file in gpg_id_files {
+            let relpath = gpg_id_file.strip_prefix(&self.root)?.to_path_buf();
+            let sig = append_extension(relpath.clone(), ".sig");
+            recipients_files.push(transfer(relpath)?);
+            if self.root.join(&sig).is_file() {
+                recipients_files.push(transfer(sig)?);
+            }
+        }
+
+        let mut files = vec![];
+        for existing_file in glob::glob(&self.password_glob(&self.root.join(&old_dir)))? {
+            files.push(transfer(
+                existing_file?.strip_prefix(&self.root)?.to_path_buf(),
+            )?);
+        }
+        if files.is_empty() && recipients_files.is_empty() {
+            return Err(Error::Generic("there are no passwords in the directory"));
+        }
+        Ok((recipients_files, files))
+    }
+
+    /// Copies each `(from, to)` recipients file and then re-encrypts each `(from, to)` password
+    /// file, all relative to the root of the store, for the recipients of its new place. The
+    /// sources are removed if `is_move`. Everything is committed at once with `message`.
+    /// Nothing is changed if a source is missing, a target already exists, or copying or
+    /// re-encrypting any of the files fails.
+    fn transfer_files(
+        &mut self,
+        recipients_files: &[(PathBuf, PathBuf)],
+        files: &[(PathBuf, PathBuf)],
+        is_move: bool,
+        message: &str,
+    ) -> Result<Vec<usize>> {
+        for (from, to) in recipients_files.iter().chain(files) {
+            if !self.root.join(from).is_file() {
+                return Err(Error::Generic("source file is missing"));
+            }
+            if self.root.join(to).exists() {
+                return Err(Error::Generic("can't target file already exists"));
+            }
+        }
+
+        let mut written: Vec<PathBuf> = vec![];
+        let mut copy_all = || -> Result<()> {
+            for (from, to) in recipients_files {
+                let new_path = self.root.join(to);
+                if let Some(new_dir) = new_path.parent() {
+                    create_dir_all(new_dir)?;
+                }
+                written.push(to.clone());
+                fs::copy(self.root.join(from), new_path)?;
+            }
+            for (from, to) in files {
+                written.push(to.clone());
+                self.reencrypt_file(from, to)?;
+            }
+            Ok(())
+        };
+        if let Err(err) = copy_all() {
+            for path in &written {
+                let _ = fs::remove_file(self.root.join(path));
+            }
+            for path in &written {
+                self.remove_empty_parents(path);
+            }
+            return Err(err);
+        }
+
+        let mut changed = written;
+        if is_move {
+            for (from, _) in recipients_files.iter().chain(files) {
+                fs::remove_file(self.root.join(from))?;
+                changed.push(from.clone());
+            }
+            for (from, _) in recipients_files.iter().chain(files) {
+                self.remove_empty_parents(from);
+            }
+        }
+
+        if self.repo().is_ok() {
+            self.add_and_commit(&changed, message)?;
+        }
+
+        let mut indices = vec![];
+        for (from, to) in files {
+            let moved = self
+                .passwords
+                .iter()
+                .position(|entry| is_move && entry.path == self.root.join(from));
+            match moved {
+                Some(index) => {
+                    let old_entry = self.passwords[index].clone();
+                    self.passwords[index] = PasswordEntry::with_new_name(
+                        old_entry,
+                        &self.root,
+                        to,
+                        self.password_file_extension(),
+                    );
+                    indices.push(index);
+                }
+                None => {
+                    let entry = match self.repo() {
+                        Ok(repo) => PasswordEntry::load_from_git(
+                            &self.root,
+                            &self.root.join(to),
+                            &repo,
+                            self,
+                        ),
+                        Err(_) => PasswordEntry::load_from_filesystem(
+                            &self.root,
+                            to,
+                            self.password_file_extension(),
+                        ),
+                    };
+                    self.passwords.push(entry);
+                    indices.push(self.passwords.len() - 1);
+                }
+            }
+        }
+        Ok(indices)
+    }
+
+    /// Writes the password file `from` to `to`, encrypted for the recipients of `to`.
+    fn reencrypt_file(&self, from: &Path, to: &Path) -> Result<()> {
+        let new_path = self.root.join(to);
+        let new_dir = new_path
+            .parent()
+            .ok_or(Error::Generic("target has no directory"))?;
+        create_dir_all(new_dir)?;
+
+        let recipients = self.recipients_for_path(new_dir)?;
+        let mut secret = self
+            .crypto
+            .decrypt_string(&fs::read(self.root.join(from))?)?;
+        let encrypted = self.crypto.encrypt_string(&secret, &recipients);
+        secret.zeroize();
+
+        let mut file = File::create(&new_path)?;
+        file.write_all(&encrypted?)?;
+        Ok(())
+    }
+
+    /// Removes the directories between the root and `relpath` that are empty.
+    fn remove_empty_parents(&self, relpath: &Path) {
+        let mut dir = relpath.parent();
+        while let Some(d) = dir {
+            if d.as_os_str().is_empty() || fs::remove_dir(self.root.join(d)).is_err() {
+                break;
+            }
+            dir = d.parent();
+        }
+    }
+
     /// Creates a `Recipient` their key_id.
     /// # Errors
     /// Returns an `Err` if there is anything wrong with the `Recipient`
@@ -873,6 +1789,8 @@ pub struct GitLogLine {
     pub commit_time: DateTime<Local>,
     /// the commit signature status
     pub signature_status: Option<SignatureStatus>,
+    /// the id of the commit, to get the content of the file at that commit
+    pub commit_id: git2::Oid,
 }
 
 impl GitLogLine {
@@ -881,11 +1799,13 @@ impl GitLogLine {
         message: String,
         commit_time: DateTime<Local>,
         signature_status: Option<SignatureStatus>,
+        commit_id: git2::Oid,
     ) -> Self {
         Self {
             message,
             commit_time,
             signature_status,
+            commit_id,
         }
     }
 }
@@ -920,13 +1840,13 @@ pub struct PasswordEntry {
     pub is_in_git: RepositoryStatus,
 }
 
-fn to_name(relpath: &Path) -> String {
+fn to_name(relpath: &Path, extension: &str) -> String {
     let mut s = relpath.to_string_lossy().to_string();
     if relpath
         .extension()
-        .map_or(false, |ext| ext.eq_ignore_ascii_case("gpg"))
+        .map_or(false, |ext| ext.eq_ignore_ascii_case(extension))
     {
-        s.truncate(s.len() - 4);
+        s.truncate(s.len() - extension.len() - 1);
         s
     } else {
         s
@@ -936,15 +1856,16 @@ fn to_name(relpath: &Path) -> String {
 impl PasswordEntry {
     /// constructs a a `PasswordEntry` from the supplied parts
     pub fn new(
-        base: &Path,    // Root of the password directory
-        relpath: &Path, // Relative path to the password.
+        base: &Path,     // Root of the password directory
+        relpath: &Path,  // Relative path to the password.
+        extension: &str, // File extension of the password files in the store.
         update_time: Result<DateTime<Local>>,
         committed_by: Result<String>,
         signature_status: Result<SignatureStatus>,
         is_in_git: RepositoryStatus,
     ) -> Self {
         Self {
-            name: to_name(relpath),
+            name: to_name(relpath, extension),
             path: base.join(relpath),
             updated: update_time.ok(),
             committed_by: committed_by.ok(),
@@ -954,9 +1875,9 @@ impl PasswordEntry {
     }
 
     /// Consumes an `PasswordEntry`, and returns a new one with a new name
-    pub fn with_new_name(old: Self, base: &Path, relpath: &Path) -> Self {
+    pub fn with_new_name(old: Self, base: &Path, relpath: &Path, extension: &str) -> Self {
         Self {
-            name: to_name(relpath),
+            name: to_name(relpath, extension),
             path: base.join(relpath),
             updated: old.updated,
             committed_by: old.committed_by,
@@ -982,6 +1903,7 @@ impl PasswordEntry {
         Self::new(
             base,
             &relpath,
+            store.password_file_extension(),
             update_time,
             committed_by,
             signature_status,
@@ -990,9 +1912,9 @@ impl PasswordEntry {
     }
 
     /// creates a `PasswordEntry` based on data in the filesystem
-    pub fn load_from_filesystem(base: &Path, relpath: &Path) -> Self {
+    pub fn load_from_filesystem(base: &Path, relpath: &Path, extension: &str) -> Self {
         Self {
-            name: to_name(relpath),
+            name: to_name(relpath, extension),
             path: base.join(relpath),
             updated: None,
             committed_by: None,
@@ -1024,30 +1946,98 @@ impl PasswordEntry {
         Ok(password)
     }
 
-    /// decrypts and returns a TOTP code if the entry contains a otpauth:// url
+    /// Decrypts the `PasswordEntry` and splits it into password, fields, otp urls and notes
+    /// # Errors
+    /// Returns an `Err` if the decryption fails
+    pub fn parsed(&self, store: &PasswordStore) -> Result<ParsedEntry> {
+        let mut secret = self.secret(store)?;
+        let parsed = ParsedEntry::parse(&secret);
+        secret.zeroize();
+        Ok(parsed)
+    }
+
+    /// Decrypts and returns a one time password code from the first otpauth:// url in the entry,
+    /// see `mfa_for`.
     /// # Errors
     /// Returns an `Err` if the code generation fails
     pub fn mfa(&self, store: &PasswordStore) -> Result<String> {
+        self.mfa_for(store, 0)
+    }
+
+    /// Decrypts and returns a one time password code from the otpauth:// url at `index` among
+    /// the urls in the entry, see `mfa_code_for`.
+    /// # Errors
+    /// Returns an `Err` if there is no such url or if the code generation fails
+    pub fn mfa_for(&self, store: &PasswordStore, index: usize) -> Result<String> {
+        Ok(self.mfa_code_for(store, index)?.code().to_owned())
+    }
+
+    /// Decrypts and returns a one time password code from the first otpauth:// url in the entry,
+    /// together with how long it's valid and the next code, see `mfa_code_for`.
+    /// # Errors
+    /// Returns an `Err` if the code generation fails
+    pub fn mfa_code(&self, store: &PasswordStore) -> Result<OtpCode> {
+        self.mfa_code_for(store, 0)
+    }
+
+    /// Decrypts and returns a one time password code from the otpauth:// url at `index` among
+    /// the urls in the entry, as listed by `otp_accounts`. For time based codes, the result
+    /// also contains the number of seconds the code is valid for and the code after it. For
+    /// hotp urls, the entry is re-encrypted with the counter incremented, and committed to git
+    /// if a repository is supplied, before the code is returned.
+    /// # Errors
+    /// Returns an `Err` if there is no such url or if the code generation fails
+    pub fn mfa_code_for(&self, store: &PasswordStore, index: usize) -> Result<OtpCode> {
         let mut secret = self.secret(store)?;
+        let res = self.mfa_internal(&secret, store, index);
+        secret.zeroize();
+        res
+    }
 
-        if let Some(start_pos) = secret.find("otpauth://") {
-            let end_pos = {
-                let mut end_pos = secret.len();
-                for (pos, c) in secret.chars().skip(start_pos).enumerate() {
-                    if c.is_whitespace() {
-                        end_pos = pos + start_pos;
-                        break;
-                    }
-                }
-                end_pos
-            };
-            let totp = TOTP::from_url(&secret[start_pos..end_pos])?;
-            secret.zeroize();
-            Ok(totp.generate_current()?)
-        } else {
-            secret.zeroize();
-            Err(Error::Generic("No otpauth:// url in secret"))
+    fn mfa_internal(&self, secret: &str, store: &PasswordStore, index: usize) -> Result<OtpCode> {
+        let parsed = ParsedEntry::parse(secret);
+        let urls = parsed.otp_urls();
+        if urls.is_empty() {
+            return Err(Error::Generic("No otpauth:// url in secret"));
+        }
+        let url = urls.get(index).ok_or(Error::Generic(
+            "No otpauth:// url with that index in secret",
+        ))?;
+
+        let otp = OtpUrl::parse(url)?;
+        let code = otp.generate()?;
+
+        if let OtpKind::Hotp { counter } = otp.kind() {
+            let next = counter
+                .checked_add(1)
+                .ok_or(Error::Generic("the hotp counter can't be incremented"))?;
+            let mut next_url = otp::with_counter(url, next);
+            let mut new_secret = secret.replacen(url, &next_url, 1);
+            next_url.zeroize();
+            let res = self.update_internal(&new_secret, store);
+            new_secret.zeroize();
+            res?;
+
+            if store.repo().is_ok() {
+                let message = format!("Increment HOTP counter for {} using ripasso", &self.name);
+                store.add_and_commit(&[store.password_file(&self.name)], &message)?;
+            }
         }
+
+        Ok(code)
+    }
+
+    /// Decrypts the `PasswordEntry` and describes the otpauth:// urls in it, in the order
+    /// they appear. The position in the list is the index to pass to `mfa_for`.
+    /// # Errors
+    /// Returns an `Err` if the decryption fails or if one of the urls is invalid
+    pub fn otp_accounts(&self, store: &PasswordStore) -> Result<Vec<OtpAccount>> {
+        let parsed = self.parsed(store)?;
+        parsed
+            .otp_urls()
+            .iter()
+            .map(|url| Ok(OtpUrl::parse(url)?.account()))
+            .collect()
     }
 
     /// All calls to this function must be followed by secret.zeroize()
@@ -1078,10 +2068,7 @@ impl PasswordEntry {
 
         let message = format!("Edit password for {} using ripasso", &self.name);
 
-        store.add_and_commit(
-            &[append_extension(PathBuf::from(&self.name), ".gpg")],
-            &message,
-        )?;
+        store.add_and_commit(&[store.password_file(&self.name)], &message)?;
 
         Ok(())
     }
@@ -1097,11 +2084,7 @@ impl PasswordEntry {
         }
         let message = format!("Removed password file for {} using ripasso", &self.name);
 
-        remove_and_commit(
-            store,
-            &[append_extension(PathBuf::from(&self.name), ".gpg")],
-            &message,
-        )?;
+        remove_and_commit(store, &[store.password_file(&self.name)], &message)?;
         Ok(())
     }
 
@@ -1160,6 +2143,7 @@ impl PasswordEntry {
                             commit.message().unwrap_or("<no message>").to_owned(),
                             dt,
                             signature_status.ok(),
+                            oid,
                         ))
                     } else {
                         None
@@ -1172,6 +2156,68 @@ impl PasswordEntry {
 
         Ok(walk_res)
     }
+
+    /// Decrypts and returns the full content that the `PasswordEntry` had at the commit
+    /// `commit_id`, one of the commits from `get_history`.
+    /// # Errors
+    /// Returns an `Err` if the store has no repository, the entry didn't exist at that commit or
+    /// the decryption fails
+    pub fn secret_at(&self, store: &PasswordStore, commit_id: git2::Oid) -> Result<String> {
+        let repo = store
+            .repo()
+            .map_err(|_| Error::Generic("must have a repository"))?;
+        let relpath = self.path.strip_prefix(&store.root)?;
+
+        let tree = repo.find_commit(commit_id)?.tree()?;
+        let entry = tree
+            .get_path(relpath)
+            .map_err(|_| Error::Generic("the password file didn't exist in that commit"))?;
+        let blob = entry.to_object(&repo)?.peel_to_blob()?;
+        if blob.content().is_empty() {
+            return Err(Error::Generic("empty password file"));
+        }
+
+        store.crypto.decrypt_string(blob.content())
+    }
+
+    /// Diffs the decrypted content of the `PasswordEntry` at the commit `old` with the content at
+    /// the commit `new`, line by line.
+    /// # Errors
+    /// Returns an `Err` if the content of either commit can't be decrypted
+    pub fn diff(
+        &self,
+        store: &PasswordStore,
+        old: git2::Oid,
+        new: git2::Oid,
+    ) -> Result<Vec<DiffLine>> {
+        let mut old = self.secret_at(store, old)?;
+        let mut new = self.secret_at(store, new)?;
+        let lines = merge::diff(&old, &new);
+        old.zeroize();
+        new.zeroize();
+        Ok(lines)
+    }
+
+    /// Sets the content of the `PasswordEntry` back to what it was at the commit `commit_id`,
+    /// and commits that. The old content is encrypted for the current recipients, so restoring
+    /// doesn't give access back to someone that has been removed since.
+    /// # Errors
+    /// Returns an `Err` if the old content can't be decrypted, or the update fails
+    pub fn restore(&self, store: &PasswordStore, commit_id: git2::Oid) -> Result<()> {
+        let mut secret = self.secret_at(store, commit_id)?;
+        let res = self.update_internal(&secret, store);
+        secret.zeroize();
+        res?;
+
+        let message = format!(
+            "Restored password for {} to the version from commit {} using ripasso",
+            &self.name,
+            &commit_id.to_string()[..7]
+        );
+        store.add_and_commit(&[store.password_file(&self.name)], &message)?;
+
+        Ok(())
+    }
 }
 
 /// Import the key_ids from the signature file from a keyserver.
@@ -1181,7 +2227,10 @@ pub fn pgp_pull(store: &mut PasswordStore, config_path: &Path) -> Result<String>
     let recipients = store.all_recipients()?;
     let recipients_refs: Vec<&Recipient> = recipients.iter().collect();
 
-    let result = store.crypto.pull_keys(&recipients_refs, config_path)?;
+    let sources = store.key_sources_or_default();
+    let result = store
+        .crypto
+        .pull_keys(&recipients_refs, config_path, &sources)?;
 
     Ok(result)
 }
@@ -1193,17 +2242,18 @@ pub fn pgp_import(store: &mut PasswordStore, text: &str, config_path: &Path) ->
     store.crypto.import_key(text, config_path)
 }
 
-/// Return a list of all passwords whose name contains `query`.
+/// Return a list of all passwords that match `query`, the best match first.
+///
+/// See `SearchQuery` for the syntax of the query. When the query only contains filters,
+/// the passwords are sorted by name.
 pub fn search(store: &PasswordStore, query: &str) -> Vec<PasswordEntry> {
-    let passwords = &store.passwords;
-    fn normalized(s: &str) -> String {
-        s.to_lowercase()
+    let query = SearchQuery::parse(query);
+    let mut matching: Vec<PasswordEntry> =
+        query.rank(&store.passwords).into_iter().cloned().collect();
+    if !query.has_terms() {
+        matching.sort_by(|a, b| a.name.cmp(&b.name));
     }
-    fn matches(s: &str, q: &str) -> bool {
-        normalized(s).as_str().contains(normalized(q).as_str())
-    }
-    let matching = passwords.iter().filter(|p| matches(&p.name, query));
-    matching.cloned().collect()
+    matching
 }
 
 /// Determine password directory
@@ -1359,12 +2409,43 @@ fn file_settings(xdg_config_file: &Path) -> config::File<config::FileSourceFile>
     config::File::from(xdg_config_file.to_path_buf())
 }
 
+/// Returns `name` as a path relative to the root of a store, if it stays inside of the store.
+fn checked_relpath(name: &str) -> Result<PathBuf> {
+    let path = PathBuf::from(name);
+    let inside = path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if name.is_empty() || !inside {
+        return Err(Error::Generic("directory traversal not allowed"));
+    }
+    Ok(path)
+}
+
 fn append_extension(path: PathBuf, extension: &str) -> PathBuf {
     let mut str = path.into_os_string();
     str.push(extension);
     PathBuf::from(str)
 }
 
+/// Writes `content` to `path`, replacing any existing file, readable only by the user.
+fn write_private_file(path: &Path, content: &[u8]) -> Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(content)?;
+    Ok(())
+}
+
 /// reads ripassos config file, in `$XDG_CONFIG_HOME/ripasso/settings.toml`
 pub fn read_config(
     store_dir: &Option<String>,
@@ -1435,6 +2516,54 @@ pub fn save_config(
         if let Some(fp) = store.crypto.own_fingerprint() {
             store_map.insert("own_fingerprint", hex::encode_upper(fp));
         }
+        if let Some(timeout) = store.clipboard_timeout {
+            store_map.insert("clipboard_timeout", timeout.to_string());
+        }
+        if let Some(timeout) = store.key_cache_timeout {
+            store_map.insert("key_cache_timeout", timeout.to_string());
+        }
+        if let Some(days) = store.key_expiry_warning_days {
+            store_map.insert("key_expiry_warning_days", days.to_string());
+        }
+        if !store.key_sources.is_empty() {
+            store_map.insert(
+                "key_sources",
+                store
+                    .key_sources
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<String>>()
+                    .join(","),
+            );
+        }
+        if !store.trust_roots.is_empty() {
+            store_map.insert(
+                "trust_roots",
+                store
+                    .trust_roots
+                    .iter()
+                    .map(hex::encode_upper)
+                    .collect::<Vec<String>>()
+                    .join(","),
+            );
+        }
+        if store.pull_strategy != PullStrategy::default() {
+            store_map.insert("pull_strategy", store.pull_strategy.to_string());
+        }
+        if let Some(interval) = store.sync_interval {
+            store_map.insert("sync_interval", interval.to_string());
+        }
+        if !store.remotes.is_empty() {
+            store_map.insert(
+                "remotes",
+                store
+                    .remotes
+                    .iter()
+                    .map(SyncRemote::to_string)
+                    .collect::<Vec<String>>()
+                    .join(","),
+            );
+        }
         stores_map.insert(store.get_name().clone(), store_map);
     }
 
@@ -0,0 +1,244 @@
+use chrono::{DateTime, Duration, Local};
+
+use crate::{pass::PasswordEntry, signature::SignatureStatus};
+
+/// Score for each character of the query that matches
+const SCORE_MATCH: i64 = 16;
+/// Bonus for matches at the start of the name, or at the start of a path segment or word
+const BONUS_BOUNDARY: i64 = 8;
+/// Bonus for each match that directly follows the previous match
+const BONUS_CONSECUTIVE: i64 = 8;
+/// Penalty for starting a gap between two matched characters
+const PENALTY_GAP_START: i64 = 3;
+/// Penalty for each additional character in a gap
+const PENALTY_GAP_EXTENSION: i64 = 1;
+
+/// A restriction on the git metadata of the entries, written as `key:value` in the query.
+#[derive(Clone, Debug, PartialEq)]
+enum Filter {
+    /// `dir:work/`, the entry is in the directory or one of its subdirectories
+    Dir(String),
+    /// `modified:<30d`, the entry was last changed after the point in time
+    ModifiedAfter(DateTime<Local>),
+    /// `modified:>30d`, the entry was last changed before the point in time
+    ModifiedBefore(DateTime<Local>),
+    /// `signer:alice`, the name of the last committer contains the text
+    Signer(String),
+    /// `signature:good`, the status of the signature on the last commit
+    Signature(Option<SignatureStatus>),
+}
+
+impl Filter {
+    /// Parses a `key:value` token, returns `None` if the key isn't a known filter.
+    /// Filters with a known key but an invalid value, like `modified:<soon`, are parsed as
+    /// `Some(None)` so that they can be ignored while the user is still typing them.
+    fn parse(token: &str, now: DateTime<Local>) -> Option<Option<Self>> {
+        let (key, value) = token.split_once(':')?;
+
+        match key.to_lowercase().as_str() {
+            "dir" => {
+                let dir = value.trim_matches('/').to_lowercase();
+                Some((!dir.is_empty()).then_some(Self::Dir(dir)))
+            }
+            "modified" => {
+                let (before, age) = match value.strip_prefix('>') {
+                    Some(age) => (true, age),
+                    None => (false, value.strip_prefix('<').unwrap_or(value)),
+                };
+                // ages that reach before the earliest representable time are invalid
+                let time = parse_age(age).and_then(|age| now.checked_sub_signed(age));
+                Some(time.map(|time| {
+                    if before {
+                        Self::ModifiedBefore(time)
+                    } else {
+                        Self::ModifiedAfter(time)
+                    }
+                }))
+            }
+            "signer" => Some((!value.is_empty()).then(|| Self::Signer(value.to_lowercase()))),
+            "signature" => Some(match value.to_lowercase().as_str() {
+                "good" => Some(Self::Signature(Some(SignatureStatus::Good))),
+                "almostgood" => Some(Self::Signature(Some(SignatureStatus::AlmostGood))),
+                "bad" => Some(Self::Signature(Some(SignatureStatus::Bad))),
+                "none" => Some(Self::Signature(None)),
+                _ => None,
+            }),
+            _ => None,
+        }
+    }
+
+    fn matches(&self, entry: &PasswordEntry) -> bool {
+        match self {
+            Self::Dir(dir) => entry.name.to_lowercase().starts_with(&format!("{dir}/")),
+            Self::ModifiedAfter(time) => entry.updated.is_some_and(|u| u >= *time),
+            Self::ModifiedBefore(time) => entry.updated.is_some_and(|u| u < *time),
+            Self::Signer(name) => entry
+                .committed_by
+                .as_ref()
+                .is_some_and(|c| c.to_lowercase().contains(name)),
+            Self::Signature(status) => entry.signature_status == *status,
+        }
+    }
+}
+
+/// Parses an age like `30d`, the units are hours, days, weeks, months of 30 days and years
+/// of 365 days.
+fn parse_age(age: &str) -> Option<Duration> {
+    let unit = age.chars().last()?;
+    let number: i64 = age[..age.len() - unit.len_utf8()].parse().ok()?;
+
+    match unit {
+        'h' => Duration::try_hours(number),
+        'd' => Duration::try_days(number),
+        'w' => Duration::try_weeks(number),
+        'm' => Duration::try_days(number.checked_mul(30)?),
+        'y' => Duration::try_days(number.checked_mul(365)?),
+        _ => None,
+    }
+}
+
+/// A search query for password entries.
+///
+/// The query is split on whitespace into tokens, every token must match for an entry to be
+/// included. A token is either a filter on the git metadata, like `dir:work/`,
+/// `modified:<30d`, `modified:>1y`, `signer:alice` or `signature:bad`, or a search term that
+/// is matched as a fuzzy subsequence against the name of the entry. Matches of search terms
+/// are ranked so that consecutive characters and matches at the start of path segments and
+/// words are preferred.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchQuery {
+    terms: Vec<Vec<char>>,
+    filters: Vec<Filter>,
+}
+
+impl SearchQuery {
+    /// Parses a query, relative to the current time.
+    pub fn parse(query: &str) -> Self {
+        Self::parse_at(query, Local::now())
+    }
+
+    /// Parses a query, with ages in filters relative to `now`.
+    pub fn parse_at(query: &str, now: DateTime<Local>) -> Self {
+        let mut terms = vec![];
+        let mut filters = vec![];
+        for token in query.split_whitespace() {
+            match Filter::parse(token, now) {
+                Some(Some(filter)) => filters.push(filter),
+                Some(None) => {}
+                None => terms.push(lowercase_chars(token)),
+            }
+        }
+
+        Self { terms, filters }
+    }
+
+    /// Returns true if the query contains search terms, without them all matching entries
+    /// have the same score.
+    pub fn has_terms(&self) -> bool {
+        !self.terms.is_empty()
+    }
+
+    /// Returns how well the entry matches the query, higher is better, or `None` if
+    /// the entry doesn't match.
+    pub fn score(&self, entry: &PasswordEntry) -> Option<i64> {
+        if !self.filters.iter().all(|f| f.matches(entry)) {
+            return None;
+        }
+
+        let name = lowercase_chars(&entry.name);
+        let mut score = 0;
+        for term in &self.terms {
+            score += fuzzy_score(term, &name)?;
+        }
+        Some(score)
+    }
+
+    /// Returns the entries that match the query, the best match first. Entries with the same
+    /// score are kept in the order they were given.
+    pub fn rank<'a, I>(&self, entries: I) -> Vec<&'a PasswordEntry>
+    where
+        I: IntoIterator<Item = &'a PasswordEntry>,
+    {
+        let mut scored: Vec<(i64, &PasswordEntry)> = entries
+            .into_iter()
+            .filter_map(|e| self.score(e).map(|s| (s, e)))
+            .collect();
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        scored.into_iter().map(|(_, e)| e).collect()
+    }
+}
+
+fn lowercase_chars(s: &str) -> Vec<char> {
+    s.chars()
+        .map(|c| c.to_lowercase().next().unwrap_or(c))
+        .collect()
+}
+
+fn is_boundary(name: &[char], index: usize) -> bool {
+    index == 0 || matches!(name[index - 1], '/' | ' ' | '-' | '_' | '.' | '@')
+}
+
+/// Finds the best scoring way to match `term` as a subsequence of `name`, returns `None` if
+/// `term` isn't a subsequence of `name`.
+fn fuzzy_score(term: &[char], name: &[char]) -> Option<i64> {
+    if term.is_empty() {
+        return Some(0);
+    }
+    if term.len() > name.len() {
+        return None;
+    }
+
+    // matched[j] is the best score with the current term character matched at name[j],
+    // best_until[j] is the best score with the current term character matched at or before
+    // name[j], including the penalty for the gap up to j.
+    let mut matched: Vec<Option<i64>> = vec![None; name.len()];
+    let mut best_until: Vec<Option<i64>> = vec![None; name.len()];
+
+    for (i, t) in term.iter().enumerate() {
+        let mut new_matched = vec![None; name.len()];
+        let mut new_best_until: Vec<Option<i64>> = vec![None; name.len()];
+
+        for j in 0..name.len() {
+            if name[j] == *t {
+                let bonus = SCORE_MATCH
+                    + if is_boundary(name, j) {
+                        BONUS_BOUNDARY
+                    } else {
+                        0
+                    };
+
+                new_matched[j] = if i == 0 {
+                    Some(bonus)
+                } else {
+                    let consecutive = if j > 0 {
+                        matched[j - 1].map(|s| s + BONUS_CONSECUTIVE)
+                    } else {
+                        None
+                    };
+                    let after_gap = if j > 1 {
+                        best_until[j - 2].map(|s| s - PENALTY_GAP_START)
+                    } else {
+                        None
+                    };
+                    consecutive.max(after_gap).map(|s| s + bonus)
+                };
+            }
+
+            let carried = if j > 0 {
+                new_best_until[j - 1].map(|s: i64| s - PENALTY_GAP_EXTENSION)
+            } else {
+                None
+            };
+            new_best_until[j] = new_matched[j].max(carried);
+        }
+
+        matched = new_matched;
+        best_until = new_best_until;
+    }
+
+    matched.into_iter().flatten().max()
+}
+
+#[cfg(test)]
+#[path = "tests/search.rs"]
+mod search_tests;
@@ -6,14 +6,25 @@ use std::{
     path::{Path, PathBuf},
 };
 
+use chrono::{DateTime, Duration, Local};
 use hex::FromHex;
+use serde::{Deserialize, Serialize};
 
-use crate::crypto::FindSigningFingerprintStrategy;
+use crate::crypto::{CryptoImpl, FindSigningFingerprintStrategy};
 pub use crate::error::{Error, Result};
 
+/// The trust levels of all the keys in the key ring, or why they couldn't be computed.
+pub(crate) type TrustLevels = std::result::Result<HashMap<[u8; 20], OwnerTrustLevel>, String>;
+
+/// Computes the trust levels of all the keys in the key ring of `crypto`, once for all the
+/// recipients that are created with them.
+pub(crate) fn trust_levels(crypto: &(dyn crate::crypto::Crypto + Send)) -> TrustLevels {
+    crypto.get_all_trust_items().map_err(|err| err.to_string())
+}
+
 /// A git commit for a password might be signed by a gpg key, and this signature's verification
 /// state is one of these values.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[non_exhaustive]
 pub enum SignatureStatus {
     /// Everything is fine with the signature, corresponds to the gpg status of VALID
@@ -64,15 +75,32 @@ pub fn parse_signing_keys(
             )));
         }
 
-        if trimmed.len() == 40 {
-            signing_keys.push(<[u8; 20]>::from_hex(trimmed)?);
-        } else {
-            signing_keys.push(<[u8; 20]>::from_hex(&trimmed[2..])?);
-        }
+        signing_keys.push(parse_fingerprint(&trimmed)?);
     }
     Ok(signing_keys)
 }
 
+/// Parses a comma separated list of full 40 character fingerprints, optionally prefixed with
+/// `0x`, without looking them up in the keyring.
+/// # Errors
+/// Returns an `Err` if any of them isn't a full fingerprint
+pub fn parse_fingerprints(fingerprints: &str) -> Result<Vec<[u8; 20]>> {
+    fingerprints
+        .split(',')
+        .map(str::trim)
+        .filter(|fingerprint| !fingerprint.is_empty())
+        .map(parse_fingerprint)
+        .collect()
+}
+
+fn parse_fingerprint(fingerprint: &str) -> Result<[u8; 20]> {
+    let hex = fingerprint.strip_prefix("0x").unwrap_or(fingerprint);
+    if hex.len() != 40 {
+        return Err(Error::Generic("key isn't in full 40 character id format"));
+    }
+    Ok(<[u8; 20]>::from_hex(hex)?)
+}
+
 /// the GPG trust level for a key
 #[derive(Clone, PartialEq, Eq, Debug)]
 #[non_exhaustive]
@@ -183,6 +211,51 @@ pub struct Recipient {
     /// If the key isn't usable for any reason, i.e. if any of the gpg function
     /// `is_bad`, `is_revoked`, `is_expired`, `is_disabled` or `is_invalid` returns true
     pub not_usable: bool,
+    /// When the key was created, if it's known
+    pub created: Option<DateTime<Local>>,
+    /// When the key expires, `None` if it never does or if it's not known
+    pub expires: Option<DateTime<Local>>,
+    /// Why the key was revoked, `None` if it isn't revoked
+    pub revocation_reason: Option<String>,
+    /// The subkeys that entries can currently be encrypted with
+    pub encryption_subkeys: Vec<EncryptionSubkey>,
+}
+
+/// A subkey of a recipient that can be used for encryption.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncryptionSubkey {
+    /// The fingerprint of the subkey, as upper case hex
+    pub fingerprint: String,
+    /// When the subkey was created, if it's known
+    pub created: Option<DateTime<Local>>,
+    /// When the subkey expires, `None` if it never does
+    pub expires: Option<DateTime<Local>>,
+}
+
+/// Why entries can't be, or soon can't be, encrypted for a recipient.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum KeyWarning {
+    /// The key is revoked, with the reason
+    Revoked(String),
+    /// The key itself has expired
+    Expired,
+    /// The key has no subkey that can be used for encryption, they have all expired or
+    /// been revoked
+    NoEncryptionSubkey,
+    /// The key can't be encrypted to after this time
+    ExpiresSoon(DateTime<Local>),
+}
+
+impl std::fmt::Display for KeyWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Revoked(reason) => write!(f, "revoked: {reason}"),
+            Self::Expired => f.write_str("expired"),
+            Self::NoEncryptionSubkey => f.write_str("no usable encryption subkey"),
+            Self::ExpiresSoon(time) => write!(f, "expires {}", time.format("%Y-%m-%d")),
+        }
+    }
 }
 
 impl Recipient {
@@ -204,6 +277,55 @@ impl Recipient {
             key_ring_status,
             trust_level,
             not_usable,
+            created: None,
+            expires: None,
+            revocation_reason: None,
+            encryption_subkeys: vec![],
+        }
+    }
+
+    /// Returns when entries can no longer be encrypted for the recipient, that is when the
+    /// last of its encryption subkeys expires, or the key itself if that is earlier. `None` if
+    /// that never happens.
+    pub fn encryption_expires(&self) -> Option<DateTime<Local>> {
+        let subkeys = if self.encryption_subkeys.iter().any(|k| k.expires.is_none()) {
+            None
+        } else {
+            self.encryption_subkeys
+                .iter()
+                .filter_map(|k| k.expires)
+                .max()
+        };
+
+        match (self.expires, subkeys) {
+            (Some(key), Some(subkey)) => Some(key.min(subkey)),
+            (key, subkey) => key.or(subkey),
+        }
+    }
+
+    /// Checks if the key of the recipient is revoked, can't be encrypted to, or can't be
+    /// encrypted to within `days` days from `now`. Recipients that aren't in the key ring
+    /// aren't checked.
+    pub fn key_warning(&self, now: DateTime<Local>, days: u32) -> Option<KeyWarning> {
+        if self.key_ring_status != KeyRingStatus::InKeyRing {
+            return None;
+        }
+
+        if let Some(reason) = &self.revocation_reason {
+            return Some(KeyWarning::Revoked(reason.clone()));
+        }
+        if self.expires.is_some_and(|expires| expires <= now) {
+            return Some(KeyWarning::Expired);
+        }
+        if self.encryption_subkeys.is_empty() {
+            return Some(KeyWarning::NoEncryptionSubkey);
+        }
+
+        let expires = self.encryption_expires()?;
+        let horizon = Duration::try_days(i64::from(days)).and_then(|d| now.checked_add_signed(d));
+        match horizon {
+            Some(horizon) if expires > horizon => None,
+            _ => Some(KeyWarning::ExpiresSoon(expires)),
         }
     }
 
@@ -215,6 +337,26 @@ impl Recipient {
         pre_comment: &[String],
         post_comment: Option<String>,
         crypto: &(dyn crate::crypto::Crypto + Send),
+    ) -> Result<Self> {
+        Self::from_with_trust(
+            key_id,
+            pre_comment,
+            post_comment,
+            crypto,
+            &trust_levels(crypto),
+        )
+    }
+
+    /// Creates a `Recipient` from a gpg key id string, with the already computed `trusts`.
+    /// # Errors
+    /// Returns an `Err` if the trust levels couldn't be computed or there is something wrong
+    /// with the fingerprint.
+    pub(crate) fn from_with_trust(
+        key_id: &str,
+        pre_comment: &[String],
+        post_comment: Option<String>,
+        crypto: &(dyn crate::crypto::Crypto + Send),
+        trusts: &TrustLevels,
     ) -> Result<Self> {
         let comment_opt = match pre_comment.len() {
             0 => None,
@@ -247,11 +389,13 @@ impl Recipient {
             _ => names.pop().unwrap(),
         };
 
-        let trusts: HashMap<[u8; 20], OwnerTrustLevel> = crypto.get_all_trust_items()?;
+        let trusts = trusts
+            .as_ref()
+            .map_err(|err| Error::GenericDyn(err.clone()))?;
 
         let fingerprint = real_key.fingerprint()?;
 
-        Ok(Self::new(
+        let mut recipient = Self::new(
             name,
             comment,
             key_id.to_owned(),
@@ -262,7 +406,13 @@ impl Recipient {
                 .unwrap_or(&OwnerTrustLevel::Unknown))
             .clone(),
             real_key.is_not_usable(),
-        ))
+        );
+        recipient.created = real_key.creation_time();
+        recipient.expires = real_key.expiration_time();
+        recipient.revocation_reason = real_key.revocation_reason();
+        recipient.encryption_subkeys = real_key.encryption_subkeys();
+
+        Ok(recipient)
     }
 
     /// Return a list of all the Recipients in the supplied file.
@@ -271,9 +421,30 @@ impl Recipient {
     pub fn all_recipients(
         recipients_file: &Path,
         crypto: &(dyn crate::crypto::Crypto + Send),
+    ) -> Result<Vec<Self>> {
+        Self::all_recipients_with_trust(recipients_file, crypto, &trust_levels(crypto))
+    }
+
+    /// Return a list of all the Recipients in the supplied file, with the already computed
+    /// `trusts`.
+    /// # Errors
+    /// Returns an `Err` if there is a problem reading the .gpg_id file
+    pub(crate) fn all_recipients_with_trust(
+        recipients_file: &Path,
+        crypto: &(dyn crate::crypto::Crypto + Send),
+        trusts: &TrustLevels,
     ) -> Result<Vec<Self>> {
         let contents = fs::read_to_string(recipients_file)?;
+        Ok(Self::recipients_from_contents(&contents, crypto, trusts))
+    }
 
+    /// Return a list of all the Recipients in the contents of a .gpg_id file, with the already
+    /// computed `trusts`.
+    pub(crate) fn recipients_from_contents(
+        contents: &str,
+        crypto: &(dyn crate::crypto::Crypto + Send),
+        trusts: &TrustLevels,
+    ) -> Vec<Self> {
         let mut recipients: Vec<Recipient> = Vec::new();
         let mut unique_recipients_keys: HashSet<IdComment> = HashSet::new();
         let mut comment_buf = vec![];
@@ -304,33 +475,38 @@ impl Recipient {
         }
 
         for key in unique_recipients_keys {
-            let recipient =
-                match Self::from(&key.id, &key.pre_comment, key.post_comment.clone(), crypto) {
-                    Ok(r) => r,
-                    Err(err) => {
-                        let comment_opt = match key.pre_comment.len() {
-                            0 => None,
-                            _ => Some(key.pre_comment.join("\n")),
-                        };
-
-                        Self::new(
-                            err.to_string(),
-                            Comment {
-                                pre_comment: comment_opt,
-                                post_comment: key.post_comment,
-                            },
-                            key.id.clone(),
-                            None,
-                            KeyRingStatus::NotInKeyRing,
-                            OwnerTrustLevel::Unknown,
-                            true,
-                        )
-                    }
-                };
+            let recipient = match Self::from_with_trust(
+                &key.id,
+                &key.pre_comment,
+                key.post_comment.clone(),
+                crypto,
+                trusts,
+            ) {
+                Ok(r) => r,
+                Err(err) => {
+                    let comment_opt = match key.pre_comment.len() {
+                        0 => None,
+                        _ => Some(key.pre_comment.join("\n")),
+                    };
+
+                    Self::new(
+                        err.to_string(),
+                        Comment {
+                            pre_comment: comment_opt,
+                            post_comment: key.post_comment,
+                        },
+                        key.id.clone(),
+                        None,
+                        KeyRingStatus::NotInKeyRing,
+                        OwnerTrustLevel::Unknown,
+                        true,
+                    )
+                }
+            };
             recipients.push(recipient)
         }
 
-        Ok(recipients)
+        recipients
     }
 
     /// write the .gpg-id.sig file
@@ -348,13 +524,16 @@ impl Recipient {
             .truncate(true)
             .open(recipients_file)?;
 
+        // age recipients are the public keys themselves, and don't have fingerprints
+        let is_age = crypto.implementation() == CryptoImpl::Age;
+
         let mut file_content = String::new();
         let mut sorted_recipients = recipients.to_owned();
         sorted_recipients.sort_by(|a, b| a.fingerprint.cmp(&b.fingerprint));
         for recipient in sorted_recipients {
             let to_add = match recipient.fingerprint {
-                Some(f) => hex::encode_upper(f),
-                None => recipient.key_id,
+                Some(f) if !is_age => hex::encode_upper(f),
+                _ => recipient.key_id,
             };
 
             if recipient.comment.pre_comment.is_some() {
@@ -365,7 +544,7 @@ impl Recipient {
                 }
             }
 
-            if !to_add.starts_with("0x") {
+            if !is_age && !to_add.starts_with("0x") {
                 file_content.push_str("0x");
             }
             file_content.push_str(&to_add);
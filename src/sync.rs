@@ -0,0 +1,212 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::{
+    credentials::{Credentials, NoPrompt},
+    error::{Error, Result},
+    git::{self, SyncRemote},
+    pass::PasswordStore,
+};
+
+#[derive(Debug)]
+enum Request {
+    Fetch,
+    Push,
+    Stop,
+}
+
+/// Tells a running sync service what to do. The store keeps one, to ask for a push after
+/// every commit.
+#[derive(Clone, Debug)]
+pub struct SyncHandle {
+    requests: Sender<Request>,
+}
+
+impl SyncHandle {
+    /// Asks the service to fetch now, instead of at the next interval.
+    pub fn fetch(&self) {
+        // if the service has stopped there is nothing to do
+        let _ = self.requests.send(Request::Fetch);
+    }
+
+    /// Asks the service to push the local commits.
+    pub fn push(&self) {
+        let _ = self.requests.send(Request::Push);
+    }
+}
+
+/// What the sync service is doing, sent to the UI.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum SyncEvent {
+    /// A fetch or a push has started
+    Syncing,
+    /// The remote didn't have any new commits
+    UpToDate,
+    /// New commits were pulled, and the password list of the store has been reloaded
+    Updated,
+    /// The local commits were pushed
+    Pushed,
+    /// Both sides changed the same entries, so the pull has to be done by hand to resolve the
+    /// conflicts. Whatever the service had started is undone.
+    Conflicted,
+    /// Syncing failed, fetching is tried again at the next interval
+    Failed(Error),
+}
+
+/// Keeps a store in sync with its remote in a background thread: it fetches when it starts
+/// and then at an interval, pulls in the new commits with the pull strategy of the store, and
+/// pushes after every commit. The service can't ask for credentials, so it only works with
+/// remotes that the ssh agent, unencrypted ssh keys or a credential helper can log in to.
+///
+/// The service stops when it's dropped.
+pub struct SyncService {
+    handle: SyncHandle,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl SyncService {
+    /// Starts syncing `store`, fetching every `interval`. The UI is told what happens through
+    /// the returned receiver, so that it never has to wait for the network.
+    /// # Errors
+    /// Returns an `Err` if the store is poisoned or the thread can't be started
+    pub fn start(
+        store: Arc<Mutex<PasswordStore>>,
+        interval: Duration,
+    ) -> Result<(Self, Receiver<SyncEvent>)> {
+        let (request_sender, requests) = mpsc::channel();
+        let (event_sender, events) = mpsc::channel();
+        let handle = SyncHandle {
+            requests: request_sender,
+        };
+
+        store.lock()?.set_sync(Some(handle.clone()));
+        let thread = thread::Builder::new()
+            .name("ripasso-sync".to_owned())
+            .spawn(move || run(&store, interval, &requests, &event_sender))?;
+
+        Ok((
+            Self {
+                handle,
+                thread: Some(thread),
+            },
+            events,
+        ))
+    }
+
+    /// Returns a handle that can ask the service to fetch or push.
+    pub fn handle(&self) -> SyncHandle {
+        self.handle.clone()
+    }
+}
+
+impl Drop for SyncService {
+    fn drop(&mut self) {
+        let _ = self.handle.requests.send(Request::Stop);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn run(
+    store: &Mutex<PasswordStore>,
+    interval: Duration,
+    requests: &Receiver<Request>,
+    events: &Sender<SyncEvent>,
+) {
+    let mut request = Request::Fetch;
+    loop {
+        let res = match request {
+            Request::Fetch => fetch(store, events),
+            Request::Push => push(store, events),
+            Request::Stop => break,
+        };
+        if let Err(err) = res {
+            // nobody listens anymore, so there is no point in going on
+            if events.send(SyncEvent::Failed(err)).is_err() {
+                break;
+            }
+        }
+
+        request = match requests.recv_timeout(interval) {
+            Ok(request) => request,
+            Err(RecvTimeoutError::Timeout) => Request::Fetch,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+    }
+
+    if let Ok(mut store) = store.lock() {
+        store.set_sync(None);
+    }
+}
+
+/// Opens the repository of the store and finds the remote, the store is only locked while
+/// doing so, so that the UI can use it during the slow network operations.
+fn open(store: &Mutex<PasswordStore>) -> Result<(git2::Repository, SyncRemote, Option<PathBuf>)> {
+    let store = store.lock()?;
+    let repo = store
+        .repo()
+        .map_err(|_| Error::Generic("must have a repository"))?;
+    let remote = git::primary_remote(&store, &repo)?;
+    Ok((repo, remote, store.get_user_home()))
+}
+
+/// Fetches from the remote and merges the new commits, and pushes if that left the local
+/// branch ahead of the remote.
+fn fetch(store: &Mutex<PasswordStore>, events: &Sender<SyncEvent>) -> Result<()> {
+    let _ = events.send(SyncEvent::Syncing);
+    let (repo, remote, home) = open(store)?;
+    let remote_oid = git::fetch_from(&repo, &remote, Credentials::new(&repo, home, &NoPrompt))?;
+
+    let event = {
+        let mut store = store.lock()?;
+        if repo.state() != git2::RepositoryState::Clean {
+            // the user is in the middle of resolving a pull
+            let _ = events.send(SyncEvent::Conflicted);
+            return Ok(());
+        }
+
+        let head_oid = repo.refname_to_id("HEAD")?;
+        let conflicts = git::merge_fetched(&store, &repo, remote_oid)?;
+        if !conflicts.is_empty() {
+            git::abort_merge(&store)?;
+            let _ = events.send(SyncEvent::Conflicted);
+            return Ok(());
+        }
+
+        if repo.refname_to_id("HEAD")? == head_oid {
+            SyncEvent::UpToDate
+        } else {
+            store.reload_password_list()?;
+            SyncEvent::Updated
+        }
+    };
+    let _ = events.send(event);
+
+    let (ahead, _) = repo.graph_ahead_behind(repo.refname_to_id("HEAD")?, remote_oid)?;
+    if ahead > 0 {
+        push(store, events)?;
+    }
+    Ok(())
+}
+
+fn push(store: &Mutex<PasswordStore>, events: &Sender<SyncEvent>) -> Result<()> {
+    let _ = events.send(SyncEvent::Syncing);
+    let (repo, remote, home) = open(store)?;
+    git::push_to(&repo, &remote, Credentials::new(&repo, home, &NoPrompt))?;
+
+    let _ = events.send(SyncEvent::Pushed);
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "tests/sync.rs"]
+mod sync_tests;
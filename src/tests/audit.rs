@@ -0,0 +1,174 @@
+use std::fs;
+
+use chrono::{Duration, TimeZone};
+use tempfile::tempdir;
+
+use super::*;
+
+// SHA-1 of "password" is 5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8
+const SORTED_LIST: &str = "000000005AD76BD555C1D6D771DE417A4B87E4B4:10\r\n\
+                           5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8:9545824\r\n\
+                           7C4A8D09CA3762AF61E59520943DC26494F8941B:37359195\r\n\
+                           FFFFFFFEE791CBAC0F6305CAF0CEE06BBE131160:3\r\n";
+
+fn now() -> DateTime<Local> {
+    Local.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap()
+}
+
+fn options() -> AuditOptions {
+    AuditOptions {
+        min_entropy: 60.0,
+        max_age_days: Some(365),
+        breach_list: None,
+    }
+}
+
+#[test]
+fn reused_and_weak() {
+    let strong = "x7#Kp!2qZ@9mWv$4Lt";
+    let passwords = vec![
+        AuditedPassword::new("a", strong, None),
+        AuditedPassword::new("b", strong, None),
+        AuditedPassword::new("c", "password", None),
+        AuditedPassword::new("d", "Tq9$mZ!4vL@2xK#7pW", None),
+    ];
+
+    let report = audit(&passwords, vec![], now(), &options()).unwrap();
+
+    assert_eq!(4, report.audited);
+    assert_eq!(
+        &[AuditIssue::Reused {
+            by: vec!["b".to_owned()]
+        }],
+        report.issues_for("a")
+    );
+    assert!(matches!(
+        report.issues_for("c"),
+        [AuditIssue::Weak {
+            strength: Strength::VeryWeak,
+            ..
+        }]
+    ));
+    assert!(report.issues_for("d").is_empty());
+    assert_eq!(2, report.count(|i| matches!(i, AuditIssue::Reused { .. })));
+}
+
+#[test]
+fn not_rotated() {
+    let passwords = vec![
+        AuditedPassword::new(
+            "old",
+            "x7#Kp!2qZ@9mWv$4Lt",
+            Some(now() - Duration::days(400)),
+        ),
+        AuditedPassword::new(
+            "new",
+            "Tq9$mZ!4vL@2xK#7pW",
+            Some(now() - Duration::days(10)),
+        ),
+        AuditedPassword::new("untracked", "pW7#xK2@Lt$4vZ!9mq", None),
+    ];
+
+    let report = audit(&passwords, vec![], now(), &options()).unwrap();
+
+    assert_eq!(
+        &[AuditIssue::NotRotated { days: 400 }],
+        report.issues_for("old")
+    );
+    assert!(report.issues_for("new").is_empty());
+    assert!(report.issues_for("untracked").is_empty());
+}
+
+#[test]
+fn breached_in_sorted_list() {
+    let td = tempdir().unwrap();
+    let list = td.path().join("pwned-passwords-sha1-ordered-by-hash.txt");
+    fs::write(&list, SORTED_LIST).unwrap();
+
+    let passwords = vec![
+        AuditedPassword::new("first", "password", None),
+        AuditedPassword::new("second", "123456", None),
+        AuditedPassword::new("safe", "x7#Kp!2qZ@9mWv$4Lt", None),
+    ];
+    let options = AuditOptions {
+        breach_list: Some(list),
+        ..options()
+    };
+
+    let report = audit(&passwords, vec![], now(), &options).unwrap();
+
+    assert!(report
+        .issues_for("first")
+        .contains(&AuditIssue::Breached { count: 9545824 }));
+    assert!(report
+        .issues_for("second")
+        .contains(&AuditIssue::Breached { count: 37359195 }));
+    assert!(report.issues_for("safe").is_empty());
+}
+
+#[test]
+fn search_sorted_finds_every_line() {
+    let td = tempdir().unwrap();
+    let list = td.path().join("list.txt");
+    fs::write(&list, SORTED_LIST).unwrap();
+    let mut reader = BufReader::new(File::open(&list).unwrap());
+    let len = SORTED_LIST.len() as u64;
+
+    for line in SORTED_LIST.lines() {
+        let (hash, count) = line.split_once(':').unwrap();
+        assert_eq!(
+            Some(count.parse().unwrap()),
+            search_sorted(&mut reader, len, hash).unwrap()
+        );
+    }
+    for missing in [
+        "0000000000000000000000000000000000000000",
+        "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD9",
+        "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF",
+    ] {
+        assert_eq!(None, search_sorted(&mut reader, len, missing).unwrap());
+    }
+}
+
+#[test]
+fn breached_in_range_directory() {
+    let td = tempdir().unwrap();
+    fs::write(
+        td.path().join("5BAA6.txt"),
+        "1E4C9B93F3F0682250B6CF8331B7EE68FD7:2\r\n1E4C9B93F3F0682250B6CF8331B7EE68FD8:9545824\r\n",
+    )
+    .unwrap();
+
+    let passwords = vec![
+        AuditedPassword::new("breached", "password", None),
+        AuditedPassword::new("safe", "x7#Kp!2qZ@9mWv$4Lt", None),
+    ];
+    let options = AuditOptions {
+        breach_list: Some(td.path().to_path_buf()),
+        ..options()
+    };
+
+    let report = audit(&passwords, vec![], now(), &options).unwrap();
+
+    assert!(report
+        .issues_for("breached")
+        .contains(&AuditIssue::Breached { count: 9545824 }));
+    assert!(report.issues_for("safe").is_empty());
+}
+
+#[test]
+fn unreadable_entries_are_reported() {
+    let report = audit(
+        &[],
+        vec![("broken".to_owned(), "no secret key".to_owned())],
+        now(),
+        &options(),
+    )
+    .unwrap();
+
+    assert_eq!(0, report.audited);
+    assert_eq!(
+        &[AuditIssue::Unreadable("no secret key".to_owned())],
+        report.issues_for("broken")
+    );
+}
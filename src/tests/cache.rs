@@ -0,0 +1,88 @@
+use super::*;
+
+const KEY: [u8; 20] = [
+    0x7E, 0x06, 0x8C, 0x36, 0x3F, 0xB0, 0x1C, 0x40, 0x1D, 0x8A, 0x16, 0x22, 0xAC, 0x94, 0x0A, 0x49,
+    0x3E, 0x4B, 0x1B, 0x30,
+];
+
+fn metadata(committed_by: &str) -> CachedMetadata {
+    CachedMetadata {
+        updated: Some(1_700_000_000),
+        committed_by: Some(committed_by.to_owned()),
+        signature_status: Some(SignatureStatus::Good),
+        in_repo: true,
+    }
+}
+
+fn cache() -> MetadataCache {
+    let mut cache = MetadataCache::new("c0ffee", &[KEY]);
+    cache.insert(PathBuf::from("a.gpg"), metadata("Alice"));
+    cache.insert(PathBuf::from("dir/b.gpg"), metadata("Bob"));
+    cache
+}
+
+#[test]
+fn parse_round_trip() {
+    let data = serde_json::to_vec(&cache()).unwrap();
+
+    let parsed = MetadataCache::parse(&data, &[KEY]).unwrap();
+
+    assert_eq!(Some(&metadata("Alice")), parsed.get(Path::new("a.gpg")));
+    assert_eq!(Some(&metadata("Bob")), parsed.get(Path::new("dir/b.gpg")));
+}
+
+#[test]
+fn parse_rejects_other_signing_keys_and_damaged_caches() {
+    let data = serde_json::to_vec(&cache()).unwrap();
+
+    assert!(MetadataCache::parse(&data, &[]).is_none());
+    assert!(MetadataCache::parse(&data[..data.len() / 2], &[KEY]).is_none());
+}
+
+#[test]
+fn advance_forgets_changed_files() {
+    let mut cache = cache();
+
+    cache.advance("decade", &[PathBuf::from("dir/b.gpg")]);
+
+    assert!(cache.get(Path::new("a.gpg")).is_some());
+    assert!(cache.get(Path::new("dir/b.gpg")).is_none());
+    assert_eq!("decade", cache.head);
+}
+
+#[test]
+fn changed_when_head_moves() {
+    let data = serde_json::to_vec(&cache()).unwrap();
+    let mut cache = MetadataCache::parse(&data, &[KEY]).unwrap();
+    assert!(!cache.is_changed());
+
+    cache.advance("c0ffee", &[]);
+    cache.retain(&[PathBuf::from("a.gpg"), PathBuf::from("dir/b.gpg")]);
+    assert!(!cache.is_changed());
+
+    cache.advance("decade", &[]);
+    assert!(cache.is_changed());
+}
+
+#[test]
+fn retain_forgets_removed_files() {
+    let mut cache = cache();
+
+    cache.retain(&[PathBuf::from("a.gpg")]);
+
+    assert!(cache.get(Path::new("a.gpg")).is_some());
+    assert!(cache.get(Path::new("dir/b.gpg")).is_none());
+}
+
+#[test]
+fn entry_round_trip() {
+    let base = Path::new("/tmp/store");
+
+    let entry = metadata("Alice").to_entry(base, Path::new("dir/a.gpg"), "gpg");
+
+    assert_eq!("dir/a", entry.name);
+    assert_eq!(base.join("dir/a.gpg"), entry.path);
+    assert_eq!(Some(1_700_000_000), entry.updated.map(|t| t.timestamp()));
+    assert_eq!(RepositoryStatus::InRepo, entry.is_in_git);
+    assert_eq!(metadata("Alice"), CachedMetadata::from_entry(&entry));
+}
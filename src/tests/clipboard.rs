@@ -0,0 +1,102 @@
+use super::*;
+
+/// What a `MockClipboard` holds, and if it was marked as a secret.
+type Content = Arc<Mutex<Option<(String, bool)>>>;
+
+struct MockClipboard {
+    content: Content,
+}
+
+impl ClipboardBackend for MockClipboard {
+    fn set_secret(&mut self, text: &str) -> Result<()> {
+        *self.content.lock().unwrap() = Some((text.to_owned(), true));
+        Ok(())
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<()> {
+        *self.content.lock().unwrap() = Some((text.to_owned(), false));
+        Ok(())
+    }
+
+    fn get_text(&mut self) -> Result<String> {
+        match &*self.content.lock().unwrap() {
+            Some((text, _)) => Ok(text.clone()),
+            None => Err(Error::Generic("the clipboard is empty")),
+        }
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        *self.content.lock().unwrap() = None;
+        Ok(())
+    }
+}
+
+fn manager() -> (ClipboardManager, Content) {
+    let content: Content = Arc::new(Mutex::new(None));
+    let manager = ClipboardManager::with_backend(Box::new(MockClipboard {
+        content: content.clone(),
+    }));
+    (manager, content)
+}
+
+const LONG: Duration = Duration::from_secs(3600);
+
+#[test]
+fn secret_is_marked_and_cleared() {
+    let (manager, content) = manager();
+
+    manager.copy_secret("hunter2", LONG).unwrap();
+    assert_eq!(Some(("hunter2".to_owned(), true)), *content.lock().unwrap());
+
+    assert!(manager.clear().unwrap());
+    assert_eq!(None, *content.lock().unwrap());
+}
+
+#[test]
+fn text_copied_by_the_user_is_not_cleared() {
+    let (manager, content) = manager();
+
+    manager.copy_secret("hunter2", LONG).unwrap();
+    *content.lock().unwrap() = Some(("something else".to_owned(), false));
+
+    assert!(!manager.clear().unwrap());
+    assert_eq!(
+        Some(("something else".to_owned(), false)),
+        *content.lock().unwrap()
+    );
+}
+
+#[test]
+fn names_are_not_secret() {
+    let (manager, content) = manager();
+
+    manager.copy_text("github").unwrap();
+
+    assert_eq!(Some(("github".to_owned(), false)), *content.lock().unwrap());
+    assert!(!manager.clear().unwrap());
+}
+
+#[test]
+fn cleared_after_timeout() {
+    let (manager, content) = manager();
+
+    manager
+        .copy_secret("hunter2", Duration::from_millis(10))
+        .unwrap();
+    thread::sleep(Duration::from_millis(500));
+
+    assert_eq!(None, *content.lock().unwrap());
+}
+
+#[test]
+fn earlier_timeout_does_not_clear_later_secret() {
+    let (manager, content) = manager();
+
+    manager
+        .copy_secret("first", Duration::from_millis(10))
+        .unwrap();
+    manager.copy_secret("second", LONG).unwrap();
+    thread::sleep(Duration::from_millis(500));
+
+    assert_eq!(Some(("second".to_owned(), true)), *content.lock().unwrap());
+}
@@ -0,0 +1,254 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    path::PathBuf,
+    sync::mpsc,
+    thread,
+};
+
+use sequoia_openpgp::{parse::Parse, serialize::SerializeInto, Cert};
+use tempfile::tempdir;
+
+use crate::{
+    discovery::{default_key_sources, find_key, wkd_urls, KeySource},
+    test_helpers::generate_sequoia_cert,
+};
+
+fn armored(cert: &Cert) -> String {
+    String::from_utf8(cert.armored().to_vec().unwrap()).unwrap()
+}
+
+fn fingerprint(cert: &Cert) -> String {
+    cert.fingerprint().to_hex()
+}
+
+/// A stand-in for a keyserver on localhost, that answers `requests` requests with `status`
+/// and `body`. Returns its address and the paths that were requested.
+fn serve(status: &'static str, body: String, requests: usize) -> (String, mpsc::Receiver<String>) {
+    serve_responses(vec![(status, body); requests])
+}
+
+/// Like `serve`, but answers each request with the next of `responses`.
+fn serve_responses(responses: Vec<(&'static str, String)>) -> (String, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for ((status, body), stream) in responses.into_iter().zip(listener.incoming()) {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            loop {
+                let mut header = String::new();
+                if reader.read_line(&mut header).unwrap() == 0 || header == "\r\n" {
+                    break;
+                }
+            }
+            let path = request_line.split(' ').nth(1).unwrap_or_default();
+            tx.send(path.to_owned()).unwrap();
+
+            write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+        }
+    });
+
+    (address, rx)
+}
+
+#[test]
+fn key_source_parse_list() {
+    let sources =
+        KeySource::parse_list(
+            "wkd, wkd:https://wkd.example.com/, hkps://keys.example.com,https://keys.openpgp.org/,/srv/keys",
+        )
+        .unwrap();
+
+    assert_eq!(
+        vec![
+            KeySource::Wkd(None),
+            KeySource::Wkd(Some("https://wkd.example.com".to_owned())),
+            KeySource::Hkp("hkps://keys.example.com".to_owned()),
+            KeySource::Vks("https://keys.openpgp.org".to_owned()),
+            KeySource::Directory(PathBuf::from("/srv/keys")),
+        ],
+        sources
+    );
+    assert_eq!(
+        "wkd,wkd:https://wkd.example.com,hkps://keys.example.com,https://keys.openpgp.org,/srv/keys",
+        sources
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<String>>()
+            .join(",")
+    );
+    assert_eq!(Vec::<KeySource>::new(), KeySource::parse_list("").unwrap());
+}
+
+#[test]
+fn wkd_urls_from_email() {
+    let [advanced, direct] = wkd_urls("Joe.Doe@Example.ORG", None).unwrap();
+
+    assert_eq!("https://openpgpkey.example.org/.well-known/openpgpkey/example.org/hu/iy9q119eutrkn8s1mk4r39qejnbu3n5q?l=Joe.Doe", advanced);
+    assert_eq!(
+        "https://example.org/.well-known/openpgpkey/hu/iy9q119eutrkn8s1mk4r39qejnbu3n5q?l=Joe.Doe",
+        direct
+    );
+    assert!(wkd_urls("example.org", None).is_err());
+
+    let [advanced, direct] = wkd_urls("Joe.Doe@Example.ORG", Some("http://localhost")).unwrap();
+    assert_eq!("http://localhost/.well-known/openpgpkey/example.org/hu/iy9q119eutrkn8s1mk4r39qejnbu3n5q?l=Joe.Doe", advanced);
+    assert_eq!(
+        "http://localhost/.well-known/openpgpkey/hu/iy9q119eutrkn8s1mk4r39qejnbu3n5q?l=Joe.Doe",
+        direct
+    );
+}
+
+#[test]
+fn find_key_invalid_key_id() {
+    assert!(find_key(&default_key_sources(), "not a key").is_err());
+    assert!(find_key(&default_key_sources(), "0x1234").is_err());
+}
+
+#[test]
+fn find_key_vks_by_fingerprint() {
+    let alice = generate_sequoia_cert("alice@example.org");
+    let (address, requests) = serve("200 OK", armored(&alice), 1);
+
+    let key = find_key(
+        &[KeySource::Vks(format!("http://{address}"))],
+        &format!("0x{}", fingerprint(&alice)),
+    )
+    .unwrap();
+
+    assert_eq!(
+        alice.fingerprint(),
+        Cert::from_bytes(&key).unwrap().fingerprint()
+    );
+    assert_eq!(
+        format!("/vks/v1/by-fingerprint/{}", fingerprint(&alice)),
+        requests.recv().unwrap()
+    );
+}
+
+#[test]
+fn find_key_hkp_by_email() {
+    let alice = generate_sequoia_cert("alice@example.org");
+    let (address, requests) = serve("200 OK", armored(&alice), 1);
+
+    let key = find_key(
+        &[KeySource::Hkp(format!("hkp://{address}"))],
+        "alice@example.org",
+    )
+    .unwrap();
+
+    assert_eq!(
+        alice.fingerprint(),
+        Cert::from_bytes(&key).unwrap().fingerprint()
+    );
+    assert_eq!(
+        "/pks/lookup?op=get&options=mr&search=alice%40example.org",
+        requests.recv().unwrap()
+    );
+}
+
+#[test]
+fn find_key_wkd_falls_back_to_the_direct_method() {
+    let alice = generate_sequoia_cert("alice@example.org");
+    let (address, requests) = serve_responses(vec![
+        ("404 Not Found", String::new()),
+        ("200 OK", armored(&alice)),
+    ]);
+    let base = format!("http://{address}");
+
+    let key = find_key(&[KeySource::Wkd(Some(base.clone()))], "alice@example.org").unwrap();
+
+    assert_eq!(
+        alice.fingerprint(),
+        Cert::from_bytes(&key).unwrap().fingerprint()
+    );
+    let [advanced, direct] = wkd_urls("alice@example.org", Some(&base)).unwrap();
+    assert_eq!(advanced[base.len()..], requests.recv().unwrap());
+    assert_eq!(direct[base.len()..], requests.recv().unwrap());
+}
+
+#[test]
+fn find_key_wkd_only_looks_up_email_addresses() {
+    let alice = generate_sequoia_cert("alice@example.org");
+    let (address, requests) = serve("200 OK", armored(&alice), 1);
+
+    let res = find_key(
+        &[KeySource::Wkd(Some(format!("http://{address}")))],
+        &fingerprint(&alice),
+    );
+
+    assert!(res.is_err());
+    assert!(requests.try_recv().is_err());
+}
+
+#[test]
+fn find_key_rejects_other_keys() {
+    let alice = generate_sequoia_cert("alice@example.org");
+    let bob = generate_sequoia_cert("bob@example.org");
+    let (address, _requests) = serve("200 OK", armored(&bob), 1);
+
+    let res = find_key(
+        &[KeySource::Vks(format!("http://{address}"))],
+        &fingerprint(&alice),
+    );
+
+    assert!(res.is_err());
+}
+
+#[test]
+fn find_key_tries_sources_in_order() {
+    let alice = generate_sequoia_cert("alice@example.org");
+    let bob = generate_sequoia_cert("bob@example.org");
+    let dir = tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("team.asc"),
+        armored(&bob) + &armored(&alice),
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("ignored.txt"), "not a key").unwrap();
+    let (address, requests) = serve("404 Not Found", String::new(), 1);
+
+    let key = find_key(
+        &[
+            KeySource::Vks(format!("http://{address}")),
+            KeySource::Directory(dir.path().to_path_buf()),
+        ],
+        &alice.keyid().to_hex(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        alice.fingerprint(),
+        Cert::from_bytes(&key).unwrap().fingerprint()
+    );
+    assert_eq!(
+        format!("/vks/v1/by-keyid/{}", alice.keyid().to_hex()),
+        requests.recv().unwrap()
+    );
+}
+
+#[test]
+fn find_key_reports_unreachable_sources() {
+    let alice = generate_sequoia_cert("alice@example.org");
+    let address = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let err = find_key(
+        &[KeySource::Vks(format!("http://{address}"))],
+        &fingerprint(&alice),
+    )
+    .unwrap_err();
+
+    assert!(format!("{err}").contains(&format!("http://{address}")));
+}
@@ -0,0 +1,92 @@
+use std::io::Read;
+
+use super::*;
+
+const SECRET: &str = "hunter2\n\
+                      login: alice\n\
+                      url: https://example.com\n\
+                      otpauth://totp/Example:alice?secret=JBSWY3DPEHPK3PXP\n\
+                      remember, \"always\"";
+
+fn entry(name: &str, selection: &FieldSelection) -> ExportedEntry {
+    ExportedEntry::new(name, &ParsedEntry::parse(SECRET), selection)
+}
+
+#[test]
+fn subtree_includes() {
+    let options = ExportOptions {
+        subtree: "work/".to_owned(),
+        ..ExportOptions::default()
+    };
+
+    assert!(options.includes("work/github"));
+    assert!(options.includes("work/cloud/aws"));
+    assert!(!options.includes("workshop/github"));
+    assert!(!options.includes("home/work/github"));
+    assert!(ExportOptions::default().includes("anything"));
+}
+
+#[test]
+fn csv_with_all_fields() {
+    let selection = FieldSelection::default();
+
+    let csv = to_csv(&[entry("work/github", &selection)], &selection);
+
+    assert_eq!(
+        "name,password,login,url,otp,notes\r\n\
+         work/github,hunter2,alice,https://example.com,otpauth://totp/Example:alice?secret=JBSWY3DPEHPK3PXP,\"remember, \"\"always\"\"\"\r\n",
+        csv
+    );
+}
+
+#[test]
+fn csv_with_selected_fields() {
+    let selection = FieldSelection {
+        password: false,
+        fields: Some(vec!["Login".to_owned(), "pin".to_owned()]),
+        otp: false,
+        notes: false,
+    };
+
+    let csv = to_csv(&[entry("github", &selection)], &selection);
+
+    assert_eq!("name,Login,pin\r\ngithub,alice,\r\n", csv);
+}
+
+#[test]
+fn json_export() {
+    let selection = FieldSelection {
+        otp: false,
+        ..FieldSelection::default()
+    };
+
+    let json = to_json(&[entry("github", &selection)]).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+    assert_eq!("github", value[0]["name"]);
+    assert_eq!("hunter2", value[0]["password"]);
+    assert_eq!("alice", value[0]["fields"]["login"]);
+    assert_eq!("remember, \"always\"", value[0]["notes"]);
+    assert!(value[0].get("otp").is_none());
+}
+
+#[test]
+fn tar_contains_selected_parts() {
+    let selection = FieldSelection {
+        fields: Some(vec!["login".to_owned()]),
+        otp: false,
+        ..FieldSelection::default()
+    };
+
+    let archive = to_tar(&[entry("work/github", &selection)], 0).unwrap();
+
+    let mut archive = tar::Archive::new(archive.as_slice());
+    let mut files = archive.entries().unwrap();
+    let mut file = files.next().unwrap().unwrap();
+    assert_eq!("work/github.txt", file.path().unwrap().to_str().unwrap());
+    assert_eq!(0o600, file.header().mode().unwrap());
+    let mut content = String::new();
+    file.read_to_string(&mut content).unwrap();
+    assert_eq!("hunter2\nlogin: alice\nremember, \"always\"", content);
+    assert!(files.next().is_none());
+}
@@ -0,0 +1,171 @@
+use super::*;
+
+fn characters(length: usize, classes: CharacterClasses) -> PasswordGenerator {
+    PasswordGenerator::Characters { length, classes }
+}
+
+#[test]
+fn generate_characters_of_requested_length() {
+    let generator = characters(32, CharacterClasses::default());
+
+    let p = generator.generate().unwrap();
+
+    assert_eq!(32, p.password().chars().count());
+}
+
+#[test]
+fn generate_characters_contains_every_class() {
+    let generator = characters(4, CharacterClasses::default());
+
+    for _ in 0..50 {
+        let p = generator.generate().unwrap();
+        let password = p.password();
+
+        assert!(password.chars().any(|c| c.is_ascii_lowercase()));
+        assert!(password.chars().any(|c| c.is_ascii_uppercase()));
+        assert!(password.chars().any(|c| c.is_ascii_digit()));
+        assert!(password.chars().any(|c| c.is_ascii_punctuation()));
+    }
+}
+
+#[test]
+fn generate_characters_only_digits() {
+    let generator = characters(
+        12,
+        CharacterClasses {
+            lowercase: false,
+            uppercase: false,
+            digits: true,
+            symbols: false,
+            exclude_ambiguous: true,
+        },
+    );
+
+    let p = generator.generate().unwrap();
+
+    assert!(p.password().chars().all(|c| c.is_ascii_digit()));
+    assert!(!p.password().contains(['0', '1']));
+}
+
+#[test]
+fn generate_characters_without_classes_is_error() {
+    let generator = characters(
+        12,
+        CharacterClasses {
+            lowercase: false,
+            uppercase: false,
+            digits: false,
+            symbols: false,
+            exclude_ambiguous: false,
+        },
+    );
+
+    assert!(generator.generate().is_err());
+    assert!(generator.entropy().is_err());
+}
+
+#[test]
+fn generate_characters_too_short_for_classes_is_error() {
+    assert!(characters(3, CharacterClasses::default())
+        .generate()
+        .is_err());
+}
+
+#[test]
+fn characters_entropy_single_class() {
+    let generator = characters(
+        10,
+        CharacterClasses {
+            lowercase: false,
+            uppercase: false,
+            digits: true,
+            symbols: false,
+            exclude_ambiguous: false,
+        },
+    );
+
+    assert!((generator.entropy().unwrap() - 10.0 * 10_f64.log2()).abs() < 1e-9);
+}
+
+#[test]
+fn characters_entropy_two_classes() {
+    // 2 characters from two classes with 10 characters each: every password has one
+    // character from each class, 2 * 10 * 10 = 200 possibilities
+    let entropy = characters_entropy(2, &[10, 10]);
+
+    assert!((entropy - 200_f64.log2()).abs() < 1e-9);
+}
+
+#[test]
+fn characters_entropy_is_below_unconstrained() {
+    let entropy = characters(20, CharacterClasses::default())
+        .entropy()
+        .unwrap();
+
+    assert!(entropy < 20.0 * 94_f64.log2());
+    assert!(entropy > 20.0 * 94_f64.log2() - 1.0);
+}
+
+#[test]
+fn generate_words() {
+    let generator = PasswordGenerator::Words {
+        number_of_words: 4,
+        separator: "-".to_owned(),
+        capitalization: Capitalization::Capitalized,
+    };
+
+    let p = generator.generate().unwrap();
+
+    let words: Vec<&str> = p.password().split('-').collect();
+    assert_eq!(4, words.len());
+    assert!(words
+        .iter()
+        .all(|w| w.chars().next().unwrap().is_uppercase()));
+    assert!((p.entropy() - 4.0 * 7776_f64.log2()).abs() < 1e-9);
+}
+
+#[test]
+fn generate_words_random_capitalization_adds_entropy() {
+    let generator = PasswordGenerator::Words {
+        number_of_words: 6,
+        separator: " ".to_owned(),
+        capitalization: Capitalization::Random,
+    };
+
+    assert!((generator.entropy().unwrap() - 6.0 * (7776_f64.log2() + 1.0)).abs() < 1e-9);
+}
+
+#[test]
+fn generate_zero_words_is_error() {
+    let generator = PasswordGenerator::Words {
+        number_of_words: 0,
+        separator: " ".to_owned(),
+        capitalization: Capitalization::Lowercase,
+    };
+
+    assert!(generator.generate().is_err());
+}
+
+#[test]
+fn strength_from_entropy() {
+    assert_eq!(Strength::VeryWeak, Strength::from_entropy(20.0));
+    assert_eq!(Strength::Reasonable, Strength::from_entropy(77.5));
+    assert_eq!(Strength::VeryStrong, Strength::from_entropy(128.0));
+}
+
+#[test]
+fn estimate_entropy_of_chosen_passwords() {
+    assert_eq!(0.0, estimate_entropy(""));
+    // 12 lowercase characters, of which 11 repeat the previous one
+    assert!((estimate_entropy("aaaaaaaaaaaa") - (26_f64.log2() + 11.0)).abs() < 1e-9);
+    // the sequence 1234 is cheaper than four random digits
+    assert!(estimate_entropy("Password1234") < estimate_entropy("Password1739"));
+    assert_eq!(
+        Strength::Weak,
+        Strength::from_entropy(estimate_entropy("Password1234"))
+    );
+    assert_eq!(
+        Strength::VeryStrong,
+        Strength::from_entropy(estimate_entropy("x7#Kp!2qZ@9mWv$4Lt"))
+    );
+}
@@ -1,4 +1,131 @@
-use crate::{error::Result, git::should_sign, test_helpers::UnpackedDir};
+use std::{fs, path::Path};
+
+use age::secrecy::ExposeSecret;
+use tempfile::tempdir;
+
+use crate::{
+    credentials::NoPrompt,
+    crypto::{Age, Crypto, CryptoImpl},
+    error::Result,
+    git::{fetch_all, pull, push_all, resolve_conflicts, should_sign, PullStrategy, SyncRemote},
+    pass::PasswordStore,
+    signature::Recipient,
+    test_helpers::UnpackedDir,
+};
+
+fn commit_file(repo: &git2::Repository, name: &str, content: impl AsRef<[u8]>) -> Result<()> {
+    let path = repo.workdir().unwrap().join(name);
+    fs::create_dir_all(path.parent().unwrap())?;
+    fs::write(path, content)?;
+    let mut index = repo.index()?;
+    index.add_path(Path::new(name))?;
+    index.write()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let signature = git2::Signature::now("Tester", "tester@example.com")?;
+
+    let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        "test commit",
+        &tree,
+        &parents,
+    )?;
+    Ok(())
+}
+
+/// Creates a remote repository and a clone of it, both with the file `notes` containing `base`.
+fn remote_and_clone(dir: &Path, base: &str) -> Result<(git2::Repository, git2::Repository)> {
+    let remote = git2::Repository::init(dir.join("remote"))?;
+    commit_file(&remote, "notes", base)?;
+
+    let local = git2::Repository::clone(dir.join("remote").to_str().unwrap(), dir.join("local"))?;
+    let mut config = local.config()?;
+    config.set_str("user.name", "Tester")?;
+    config.set_str("user.email", "tester@example.com")?;
+
+    Ok((remote, local))
+}
+
+fn store(dir: &Path) -> Result<PasswordStore> {
+    PasswordStore::new(
+        "default",
+        &Some(dir.join("local")),
+        &None,
+        &None,
+        &None,
+        &CryptoImpl::GpgMe,
+        &None,
+    )
+}
+
+/// Writes a new age identity to the identities file below `home`, and returns its public key
+/// and the crypto that uses it.
+fn age_identity(home: &Path) -> Result<(String, Age)> {
+    let identity = age::x25519::Identity::generate();
+    let identities_file = home.join(".passage").join("identities");
+    fs::create_dir_all(home.join(".passage"))?;
+    fs::write(&identities_file, identity.to_string().expose_secret())?;
+    Ok((identity.to_public().to_string(), Age::new(&identities_file)))
+}
+
+fn age_encrypt(age: &Age, content: &str, public_keys: &[&str]) -> Result<Vec<u8>> {
+    let recipients = public_keys
+        .iter()
+        .map(|key| Recipient::from(key, &[], None, age))
+        .collect::<Result<Vec<_>>>()?;
+    age.encrypt_string(content, &recipients)
+}
+
+/// Decrypts the entry at `path` in the tree of the last commit.
+fn committed_secret(repo: &git2::Repository, age: &Age, path: &str) -> Result<String> {
+    let tree = repo.head()?.peel_to_tree()?;
+    let blob = repo.find_blob(tree.get_path(Path::new(path))?.id())?;
+    age.decrypt_string(blob.content())
+}
+
+/// Creates a remote repository and a clone of it, both with an age store with the entry
+/// `work/site.age` containing `base`. Returns the repositories, the crypto and public key of
+/// the identity of the store, and the store of the clone.
+fn age_remote_and_clone(
+    dir: &Path,
+    base: &str,
+) -> Result<(
+    git2::Repository,
+    git2::Repository,
+    Age,
+    String,
+    PasswordStore,
+)> {
+    let home = dir.join("home");
+    let (public_key, age) = age_identity(&home)?;
+
+    let remote = git2::Repository::init(dir.join("remote"))?;
+    commit_file(&remote, ".age-recipients", format!("{public_key}\n"))?;
+    commit_file(
+        &remote,
+        "work/site.age",
+        age_encrypt(&age, base, &[&public_key])?,
+    )?;
+
+    let local = git2::Repository::clone(dir.join("remote").to_str().unwrap(), dir.join("local"))?;
+    let mut config = local.config()?;
+    config.set_str("user.name", "Tester")?;
+    config.set_str("user.email", "tester@example.com")?;
+
+    let store = PasswordStore::new(
+        "default",
+        &Some(dir.join("local")),
+        &None,
+        &Some(home),
+        &None,
+        &CryptoImpl::Age,
+        &None,
+    )?;
+    Ok((remote, local, age, public_key, store))
+}
 
 #[test]
 fn test_should_sign_true() -> Result<()> {
@@ -25,3 +152,319 @@ fn test_should_sign_false() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn pull_merges_changes_to_different_lines() -> Result<()> {
+    let td = tempdir()?;
+    let (remote, local) = remote_and_clone(td.path(), "a\nb\nc\n")?;
+    commit_file(&remote, "notes", "a\nb\nC\n")?;
+    commit_file(&local, "notes", "A\nb\nc\n")?;
+
+    let conflicts = pull(&store(td.path())?, &NoPrompt)?;
+
+    assert!(conflicts.is_empty());
+    assert_eq!(
+        "A\nb\nC\n",
+        fs::read_to_string(td.path().join("local/notes"))?
+    );
+    let head = local.head()?.peel_to_commit()?;
+    assert_eq!(2, head.parent_count());
+    assert_eq!(git2::RepositoryState::Clean, local.state());
+    Ok(())
+}
+
+#[test]
+fn pull_returns_conflicts_and_resolve_finishes_the_merge() -> Result<()> {
+    let td = tempdir()?;
+    let (remote, local) = remote_and_clone(td.path(), "a\nb\n")?;
+    commit_file(&remote, "notes", "theirs\nb\n")?;
+    commit_file(&local, "notes", "ours\nb\n")?;
+    let store = store(td.path())?;
+
+    let mut conflicts = pull(&store, &NoPrompt)?;
+
+    assert_eq!(1, conflicts.len());
+    assert_eq!(Path::new("notes"), conflicts[0].path);
+    assert_eq!(
+        "<<<<<<< ours\nours\n=======\ntheirs\n>>>>>>> theirs\nb\n",
+        conflicts[0].merged
+    );
+    assert_eq!(git2::RepositoryState::Merge, local.state());
+
+    conflicts[0].merged = "both\nb\n".to_owned();
+    resolve_conflicts(&store, &conflicts)?;
+
+    assert_eq!(
+        "both\nb\n",
+        fs::read_to_string(td.path().join("local/notes"))?
+    );
+    let head = local.head()?.peel_to_commit()?;
+    assert_eq!(2, head.parent_count());
+    assert_eq!(git2::RepositoryState::Clean, local.state());
+    Ok(())
+}
+
+#[test]
+fn pull_merges_encrypted_entries_changed_on_different_lines() -> Result<()> {
+    let td = tempdir()?;
+    let (remote, local, age, public_key, store) =
+        age_remote_and_clone(td.path(), "password\nuser: alice\nurl: example.com\n")?;
+    commit_file(
+        &remote,
+        "work/site.age",
+        age_encrypt(
+            &age,
+            "password\nuser: alice\nurl: example.org\n",
+            &[&public_key],
+        )?,
+    )?;
+    commit_file(
+        &local,
+        "work/site.age",
+        age_encrypt(
+            &age,
+            "new password\nuser: alice\nurl: example.com\n",
+            &[&public_key],
+        )?,
+    )?;
+
+    let conflicts = pull(&store, &NoPrompt)?;
+
+    assert!(conflicts.is_empty());
+    let head = local.head()?.peel_to_commit()?;
+    assert_eq!(2, head.parent_count());
+    assert_eq!(git2::RepositoryState::Clean, local.state());
+    assert_eq!(
+        "new password\nuser: alice\nurl: example.org\n",
+        committed_secret(&local, &age, "work/site.age")?
+    );
+    assert_eq!(
+        "new password\nuser: alice\nurl: example.org\n",
+        age.decrypt_string(&fs::read(td.path().join("local/work/site.age"))?)?
+    );
+    Ok(())
+}
+
+#[test]
+fn pull_returns_conflicting_encrypted_entries_and_resolve_encrypts_them() -> Result<()> {
+    let td = tempdir()?;
+    let (remote, local, age, public_key, store) =
+        age_remote_and_clone(td.path(), "password\nuser: alice\n")?;
+    commit_file(
+        &remote,
+        "work/site.age",
+        age_encrypt(&age, "theirs\nuser: alice\n", &[&public_key])?,
+    )?;
+    commit_file(
+        &local,
+        "work/site.age",
+        age_encrypt(&age, "ours\nuser: alice\n", &[&public_key])?,
+    )?;
+
+    let mut conflicts = pull(&store, &NoPrompt)?;
+
+    assert_eq!(1, conflicts.len());
+    assert_eq!(Path::new("work/site.age"), conflicts[0].path);
+    assert_eq!(
+        "<<<<<<< ours\nours\n=======\ntheirs\n>>>>>>> theirs\nuser: alice\n",
+        conflicts[0].merged
+    );
+    assert_eq!(git2::RepositoryState::Merge, local.state());
+
+    conflicts[0].merged = "both\nuser: alice\n".to_owned();
+    resolve_conflicts(&store, &conflicts)?;
+
+    let head = local.head()?.peel_to_commit()?;
+    assert_eq!(2, head.parent_count());
+    assert_eq!(git2::RepositoryState::Clean, local.state());
+    assert_eq!(
+        "both\nuser: alice\n",
+        committed_secret(&local, &age, "work/site.age")?
+    );
+    Ok(())
+}
+
+#[test]
+fn pull_fast_forwards_without_local_commits() -> Result<()> {
+    let td = tempdir()?;
+    let (remote, local) = remote_and_clone(td.path(), "a\n")?;
+    commit_file(&remote, "notes", "b\n")?;
+
+    let conflicts = pull(&store(td.path())?, &NoPrompt)?;
+
+    assert!(conflicts.is_empty());
+    assert_eq!("b\n", fs::read_to_string(td.path().join("local/notes"))?);
+    assert_eq!(
+        remote.head()?.target(),
+        local.head()?.target(),
+        "no merge commit should be created"
+    );
+    Ok(())
+}
+
+#[test]
+fn pull_fast_forward_only_refuses_diverged_branches() -> Result<()> {
+    let td = tempdir()?;
+    let (remote, local) = remote_and_clone(td.path(), "a\nb\n")?;
+    commit_file(&remote, "notes", "a\nB\n")?;
+    commit_file(&local, "notes", "A\nb\n")?;
+    let head = local.head()?.target();
+    let mut store = store(td.path())?;
+    store.set_pull_strategy(PullStrategy::FastForwardOnly);
+
+    assert!(pull(&store, &NoPrompt).is_err());
+    assert_eq!(head, local.head()?.target());
+    Ok(())
+}
+
+#[test]
+fn pull_rebases_local_commits() -> Result<()> {
+    let td = tempdir()?;
+    let (remote, local) = remote_and_clone(td.path(), "a\nb\nc\n")?;
+    commit_file(&remote, "notes", "a\nb\nC\n")?;
+    commit_file(&local, "notes", "A\nb\nc\n")?;
+    let mut store = store(td.path())?;
+    store.set_pull_strategy(PullStrategy::Rebase);
+
+    let conflicts = pull(&store, &NoPrompt)?;
+
+    assert!(conflicts.is_empty());
+    assert_eq!(
+        "A\nb\nC\n",
+        fs::read_to_string(td.path().join("local/notes"))?
+    );
+    let head = local.head()?.peel_to_commit()?;
+    assert_eq!(1, head.parent_count());
+    assert_eq!(remote.head()?.target(), Some(head.parent_id(0)?));
+    assert_eq!("Tester", head.author().name().unwrap());
+    Ok(())
+}
+
+#[test]
+fn pull_rebases_encrypted_entries_for_the_recipients_of_the_rebased_tree() -> Result<()> {
+    let td = tempdir()?;
+    let (remote, local, age, public_key, mut store) =
+        age_remote_and_clone(td.path(), "password\nuser: alice\nurl: example.com\n")?;
+    store.set_pull_strategy(PullStrategy::Rebase);
+
+    // the remote adds a recipient to the directory, and changes another line of the entry
+    let (other_key, other_age) = age_identity(&td.path().join("other"))?;
+    commit_file(
+        &remote,
+        "work/.age-recipients",
+        format!("{public_key}\n{other_key}\n"),
+    )?;
+    commit_file(
+        &remote,
+        "work/site.age",
+        age_encrypt(
+            &age,
+            "password\nuser: alice\nurl: example.org\n",
+            &[&public_key, &other_key],
+        )?,
+    )?;
+    commit_file(
+        &local,
+        "work/site.age",
+        age_encrypt(
+            &age,
+            "new password\nuser: alice\nurl: example.com\n",
+            &[&public_key],
+        )?,
+    )?;
+
+    let conflicts = pull(&store, &NoPrompt)?;
+
+    assert!(conflicts.is_empty());
+    let head = local.head()?.peel_to_commit()?;
+    assert_eq!(1, head.parent_count());
+    assert_eq!(remote.head()?.target(), Some(head.parent_id(0)?));
+    assert_eq!(
+        "new password\nuser: alice\nurl: example.org\n",
+        committed_secret(&local, &age, "work/site.age")?
+    );
+    assert_eq!(
+        "new password\nuser: alice\nurl: example.org\n",
+        committed_secret(&local, &other_age, "work/site.age")?
+    );
+    Ok(())
+}
+
+#[test]
+fn pull_rebase_refuses_entries_changed_on_the_same_line() -> Result<()> {
+    let td = tempdir()?;
+    let (remote, local, age, public_key, mut store) =
+        age_remote_and_clone(td.path(), "password\nuser: alice\n")?;
+    store.set_pull_strategy(PullStrategy::Rebase);
+    commit_file(
+        &remote,
+        "work/site.age",
+        age_encrypt(&age, "theirs\nuser: alice\n", &[&public_key])?,
+    )?;
+    commit_file(
+        &local,
+        "work/site.age",
+        age_encrypt(&age, "ours\nuser: alice\n", &[&public_key])?,
+    )?;
+    let head = local.head()?.target();
+
+    assert!(pull(&store, &NoPrompt).is_err());
+    assert_eq!(head, local.head()?.target());
+    assert_eq!(
+        "ours\nuser: alice\n",
+        committed_secret(&local, &age, "work/site.age")?
+    );
+    Ok(())
+}
+
+#[test]
+fn sync_remote_parse_list() -> Result<()> {
+    let remotes = SyncRemote::parse_list("origin, backup:main,")?;
+
+    assert_eq!(2, remotes.len());
+    assert_eq!("origin", remotes[0].to_string());
+    assert_eq!(None, remotes[0].branch);
+    assert_eq!("backup:main", remotes[1].to_string());
+    assert_eq!(Some("main".to_owned()), remotes[1].branch);
+    assert!(SyncRemote::parse_list("backup:").is_err());
+    assert!(SyncRemote::parse_list(":main").is_err());
+    Ok(())
+}
+
+#[test]
+fn push_all_and_fetch_all_report_status_per_remote() -> Result<()> {
+    let td = tempdir()?;
+    let (remote, local) = remote_and_clone(td.path(), "a\n")?;
+    let backup = git2::Repository::init_bare(td.path().join("backup"))?;
+    local.remote(
+        "backup",
+        &format!("file://{}", td.path().join("backup").display()),
+    )?;
+    commit_file(&local, "notes", "b\n")?;
+    let branch = local.head()?.shorthand().unwrap().to_owned();
+    let mut store = store(td.path())?;
+    store.set_remotes(SyncRemote::parse_list("backup,missing")?);
+
+    let pushed = push_all(&store, &NoPrompt)?;
+
+    assert_eq!(2, pushed.len());
+    assert!(pushed[0].result.is_ok());
+    assert!(pushed[1].result.is_err());
+    assert_eq!(
+        local.head()?.target(),
+        Some(backup.refname_to_id(&format!("refs/heads/{branch}"))?)
+    );
+
+    commit_file(&remote, "notes", "c\n")?;
+    store.set_remotes(SyncRemote::parse_list("origin,backup")?);
+
+    let fetched = fetch_all(&store, &NoPrompt)?;
+
+    assert_eq!(
+        "origin:".to_owned() + &branch,
+        fetched[0].remote.to_string()
+    );
+    assert_eq!(1, *fetched[0].result.as_ref().unwrap());
+    assert_eq!(0, *fetched[1].result.as_ref().unwrap());
+    Ok(())
+}
@@ -0,0 +1,162 @@
+use super::*;
+
+#[test]
+fn parse_chrome_csv() {
+    let csv = "name,url,username,password,note\r\n\
+               github.com,https://github.com/login,alice,hunter2,\r\n\
+               \"Bank, main\",https://bank.example.com,alice,\"pa\"\"ss,word\",\"line one\nline two\"\r\n";
+
+    let entries = parse_csv(csv).unwrap();
+
+    assert_eq!(2, entries.len());
+    assert_eq!("github.com", entries[0].name());
+    assert_eq!("hunter2", entries[0].password);
+    assert_eq!(Some("alice".to_owned()), entries[0].username);
+    assert_eq!(vec!["https://github.com/login"], entries[0].urls);
+    assert_eq!("Bank, main", entries[1].title);
+    assert_eq!("pa\"ss,word", entries[1].password);
+    assert_eq!("line one\nline two", entries[1].notes);
+}
+
+#[test]
+fn parse_firefox_csv_uses_host_as_name() {
+    let csv = "\"url\",\"username\",\"password\",\"httpRealm\",\"formActionOrigin\",\"guid\"\n\
+               \"https://alice@mail.example.com:443/inbox\",\"alice\",\"secret\",,\"\",\"{1}\"\n";
+
+    let entries = parse_csv(csv).unwrap();
+
+    assert_eq!(1, entries.len());
+    assert_eq!("mail.example.com:443", entries[0].name());
+}
+
+#[test]
+fn parse_1password_csv() {
+    let csv = "Title,Url,Username,Password,OTPAuth,Favorite,Archived,Tags,Notes\n\
+               Work mail,https://mail.example.com,alice,hunter2,JBSW Y3DP EHPK 3PXP,false,false,,remember me\n";
+
+    let entries = parse_csv(csv).unwrap();
+
+    assert_eq!(1, entries.len());
+    assert_eq!(
+        Some("otpauth://totp/Work%20mail?secret=JBSWY3DPEHPK3PXP".to_owned()),
+        entries[0].otp
+    );
+    assert_eq!("remember me", entries[0].notes);
+}
+
+#[test]
+fn parse_csv_without_password_column_is_error() {
+    assert!(parse_csv("name,url\nfoo,bar\n").is_err());
+}
+
+#[test]
+fn parse_csv_unterminated_quote_is_error() {
+    assert!(parse_csv("name,password\nfoo,\"bar\n").is_err());
+}
+
+#[test]
+fn parse_bitwarden() {
+    let json = r#"{
+        "encrypted": false,
+        "folders": [{"id": "f1", "name": "Work/Cloud"}],
+        "items": [
+            {
+                "id": "i1",
+                "folderId": "f1",
+                "type": 1,
+                "name": "AWS",
+                "notes": "root account",
+                "fields": [{"name": "account id", "value": "1234", "type": 0}],
+                "login": {
+                    "username": "alice",
+                    "password": "hunter2",
+                    "totp": "steam://ABCDEFGH",
+                    "uris": [{"match": null, "uri": "https://aws.amazon.com"}]
+                }
+            },
+            {
+                "id": "i2",
+                "folderId": null,
+                "type": 2,
+                "name": "Wifi",
+                "notes": "the password is on the router"
+            }
+        ]
+    }"#;
+
+    let entries = parse_bitwarden_json(json).unwrap();
+
+    assert_eq!(2, entries.len());
+    assert_eq!("Work/Cloud/AWS", entries[0].name());
+    assert_eq!(
+        Some("otpauth://totp/Steam:AWS?secret=ABCDEFGH&issuer=Steam".to_owned()),
+        entries[0].otp
+    );
+    assert_eq!(
+        vec![("account id".to_owned(), "1234".to_owned())],
+        entries[0].fields
+    );
+    assert_eq!("Wifi", entries[1].name());
+    assert_eq!("", entries[1].password);
+}
+
+#[test]
+fn parse_encrypted_bitwarden_is_error() {
+    assert!(parse_bitwarden_json(r#"{"encrypted": true, "items": []}"#).is_err());
+}
+
+#[test]
+fn name_cannot_escape_store() {
+    let entry = ImportedEntry {
+        folders: vec!["..".to_owned(), "a/b".to_owned()],
+        title: "../../etc/passwd".to_owned(),
+        password: String::new(),
+        username: None,
+        urls: vec![],
+        otp: None,
+        fields: vec![],
+        notes: String::new(),
+    };
+
+    assert_eq!("_/a-b/-..-etc-passwd", entry.name());
+}
+
+#[test]
+fn to_secret_in_pass_format() {
+    let entry = ImportedEntry {
+        folders: vec![],
+        title: "github".to_owned(),
+        password: "hunter2".to_owned(),
+        username: Some("alice".to_owned()),
+        urls: vec!["https://github.com".to_owned()],
+        otp: Some("otpauth://totp/github?secret=JBSWY3DPEHPK3PXP".to_owned()),
+        fields: vec![
+            ("pin".to_owned(), "1234".to_owned()),
+            ("recovery codes".to_owned(), "one\ntwo".to_owned()),
+        ],
+        notes: "some notes\n".to_owned(),
+    };
+
+    assert_eq!(
+        "hunter2\n\
+         login: alice\n\
+         url: https://github.com\n\
+         pin: 1234\n\
+         otpauth://totp/github?secret=JBSWY3DPEHPK3PXP\n\
+         some notes\n\
+         recovery codes:\n\
+         one\n\
+         two\n",
+        entry.to_secret()
+    );
+}
+
+#[test]
+fn format_from_name() {
+    assert_eq!(Some(ImportFormat::Kdbx), ImportFormat::from_name("KeePass"));
+    assert_eq!(
+        Some(ImportFormat::BrowserCsv),
+        ImportFormat::from_name("firefox")
+    );
+    assert_eq!(None, ImportFormat::from_name("lastpass"));
+}
@@ -0,0 +1,100 @@
+use super::*;
+
+const BASE: &str = "hunter2\nuser: alice\nurl: https://example.com\n";
+
+#[test]
+fn changes_to_different_lines_are_merged() {
+    let ours = "hunter3\nuser: alice\nurl: https://example.com\n";
+    let theirs = "hunter2\nuser: alice\nurl: https://example.org\nnotes\n";
+
+    let merged = merge(BASE, ours, theirs);
+
+    assert_eq!(0, merged.conflicts);
+    assert_eq!(
+        "hunter3\nuser: alice\nurl: https://example.org\nnotes\n",
+        merged.text
+    );
+}
+
+#[test]
+fn same_change_on_both_sides() {
+    let changed = "correct horse\nuser: alice\nurl: https://example.com\n";
+
+    let merged = merge(BASE, changed, changed);
+
+    assert_eq!(0, merged.conflicts);
+    assert_eq!(changed, merged.text);
+}
+
+#[test]
+fn different_changes_to_the_same_line_conflict() {
+    let ours = "correct horse\nuser: alice\nurl: https://example.com\n";
+    let theirs = "battery staple\nuser: alice\nurl: https://example.com\n";
+
+    let merged = merge(BASE, ours, theirs);
+
+    assert_eq!(1, merged.conflicts);
+    assert_eq!(
+        "<<<<<<< ours\ncorrect horse\n=======\nbattery staple\n>>>>>>> theirs\n\
+         user: alice\nurl: https://example.com\n",
+        merged.text
+    );
+}
+
+#[test]
+fn conflict_markers_start_on_new_lines() {
+    let merged = merge("a\nb", "a\nc", "a\nd");
+
+    assert_eq!(1, merged.conflicts);
+    assert_eq!(
+        "a\n<<<<<<< ours\nc\n=======\nd\n>>>>>>> theirs\n",
+        merged.text
+    );
+}
+
+#[test]
+fn added_on_both_sides_without_base() {
+    let merged = merge("", "hunter2\n", "hunter2\n");
+
+    assert_eq!(0, merged.conflicts);
+    assert_eq!("hunter2\n", merged.text);
+}
+
+#[test]
+fn removed_on_one_side() {
+    let merged = merge(BASE, "", BASE);
+
+    assert_eq!(0, merged.conflicts);
+    assert_eq!("", merged.text);
+}
+
+#[test]
+fn diff_of_changed_lines() {
+    let new = "hunter3\nuser: alice\nurl: https://example.com\nnotes\n";
+
+    assert_eq!(
+        vec![
+            DiffLine::Removed("hunter2".to_owned()),
+            DiffLine::Added("hunter3".to_owned()),
+            DiffLine::Unchanged("user: alice".to_owned()),
+            DiffLine::Unchanged("url: https://example.com".to_owned()),
+            DiffLine::Added("notes".to_owned()),
+        ],
+        diff(BASE, new)
+    );
+}
+
+#[test]
+fn diff_from_nothing() {
+    assert_eq!(
+        vec![
+            DiffLine::Added("hunter2".to_owned()),
+            DiffLine::Added("user: alice".to_owned()),
+            DiffLine::Added("url: https://example.com".to_owned()),
+        ],
+        diff("", BASE)
+    );
+    assert!(diff(BASE, BASE)
+        .iter()
+        .all(|line| matches!(line, DiffLine::Unchanged(_))));
+}
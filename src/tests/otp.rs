@@ -0,0 +1,116 @@
+use super::*;
+
+// The secret from the test vectors in RFC 4226, "12345678901234567890" in base32
+const RFC4226_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+#[test]
+fn parse_totp() {
+    let otp = OtpUrl::parse(
+        "otpauth://totp/Example:alice@google.com?secret=JBSWY3DPEHPK3PXPAAAAAAAAAAAA&issuer=Example",
+    )
+    .unwrap();
+
+    assert_eq!(OtpKind::Totp, otp.kind());
+    assert_eq!("Example:alice@google.com", otp.account().label());
+    let code = otp.generate().unwrap();
+    assert_eq!(
+        6,
+        code.code().chars().filter(|c| c.is_ascii_digit()).count()
+    );
+}
+
+#[test]
+fn hotp_rfc4226_test_vectors() {
+    let expected = ["755224", "287082", "359152", "969429", "338314"];
+
+    for (counter, code) in expected.iter().enumerate() {
+        let otp = OtpUrl::parse(&format!(
+            "otpauth://hotp/Token:alice?secret={RFC4226_SECRET}&counter={counter}"
+        ))
+        .unwrap();
+
+        assert_eq!(
+            OtpKind::Hotp {
+                counter: counter as u64
+            },
+            otp.kind()
+        );
+        let generated = otp.generate().unwrap();
+        assert_eq!(*code, generated.code());
+        assert_eq!(None, generated.seconds_remaining());
+        assert_eq!(None, generated.next_code());
+    }
+}
+
+#[test]
+fn totp_rfc6238_test_vectors() {
+    // The SHA1 test vectors from RFC 6238, with 8 digits
+    let otp = OtpUrl::parse(&format!(
+        "otpauth://totp/Example:alice?secret={RFC4226_SECRET}&digits=8"
+    ))
+    .unwrap();
+
+    let code = otp.generate_at(59);
+    assert_eq!("94287082", code.code());
+    assert_eq!(Some(30), code.period());
+    assert_eq!(Some(1), code.seconds_remaining());
+    assert!(code.expires_within(5));
+
+    let code = otp.generate_at(1111111109);
+    assert_eq!("07081804", code.code());
+    assert_eq!(Some("14050471"), code.next_code());
+    assert_eq!(Some(1), code.seconds_remaining());
+
+    let code = otp.generate_at(1111111110);
+    assert_eq!("14050471", code.code());
+    assert_eq!(Some(30), code.seconds_remaining());
+    assert!(!code.expires_within(5));
+}
+
+#[test]
+fn hotp_without_counter_is_error() {
+    let res = OtpUrl::parse(&format!(
+        "otpauth://hotp/Token:alice?secret={RFC4226_SECRET}"
+    ));
+
+    assert!(res.is_err());
+}
+
+#[test]
+fn steam_from_issuer_and_host() {
+    let steam_chars = "23456789BCDFGHJKMNPQRTVWXY";
+
+    for url in [
+        "otpauth://totp/Steam:alice?secret=JBSWY3DPEHPK3PXPAAAAAAAAAAAA&issuer=Steam",
+        "otpauth://steam/Steam:alice?secret=JBSWY3DPEHPK3PXPAAAAAAAAAAAA",
+    ] {
+        let otp = OtpUrl::parse(url).unwrap();
+
+        assert_eq!(OtpKind::Steam, otp.kind());
+        let code = otp.generate().unwrap();
+        assert_eq!(5, code.code().len());
+        assert!(code.code().chars().all(|c| steam_chars.contains(c)));
+    }
+}
+
+#[test]
+fn unsupported_url_type_is_error() {
+    assert!(OtpUrl::parse("otpauth://yubico/alice?secret=JBSWY3DPEHPK3PXPAAAAAAAAAAAA").is_err());
+    assert!(OtpUrl::parse("https://example.com").is_err());
+}
+
+#[test]
+fn with_counter_replaces_counter() {
+    assert_eq!(
+        "otpauth://hotp/a?secret=ABC&counter=8&digits=6",
+        with_counter("otpauth://hotp/a?secret=ABC&counter=7&digits=6", 8)
+    );
+}
+
+#[test]
+fn with_counter_adds_missing_counter() {
+    assert_eq!(
+        "otpauth://hotp/a?secret=ABC&counter=1",
+        with_counter("otpauth://hotp/a?secret=ABC", 1)
+    );
+}
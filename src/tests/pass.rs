@@ -13,7 +13,9 @@ use tempfile::tempdir;
 
 use super::*;
 use crate::{
+    audit::AuditIssue,
     crypto::slice_to_20_bytes,
+    generate::{estimate_entropy, Strength},
     test_helpers::{
         count_recipients, generate_sequoia_cert, generate_sequoia_cert_without_private_key,
         MockCrypto, UnpackedDir,
@@ -55,6 +57,15 @@ pub fn setup_store(
             user_home,
         )),
         user_home: None,
+        clipboard_timeout: None,
+        key_cache_timeout: None,
+        key_expiry_warning_days: None,
+        trust_roots: vec![],
+        key_sources: vec![],
+        pull_strategy: PullStrategy::Merge,
+        remotes: vec![],
+        sync_interval: None,
+        sync: None,
     };
 
     Ok((store, users))
@@ -123,6 +134,75 @@ fn populate_password_list_small_repo() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn populate_password_list_small_repo_uses_cache() -> Result<()> {
+    let dir = UnpackedDir::new("populate_password_list_small_repo")?;
+
+    let store = PasswordStore::new(
+        "default",
+        &Some(dir.dir().to_path_buf()),
+        &None,
+        &Some(dir.dir().to_path_buf()),
+        &None,
+        &CryptoImpl::GpgMe,
+        &None,
+    )?;
+    store.all_passwords()?;
+
+    // change the cached committer, to see that the second listing comes from the cache
+    let cache_path = dir.dir().join(".git").join(crate::cache::CACHE_FILE_NAME);
+    let cache = std::fs::read_to_string(&cache_path)?;
+    assert!(cache.contains("Alexander Kjäll"));
+    std::fs::write(&cache_path, cache.replace("Alexander Kjäll", "From Cache"))?;
+
+    let results = store.all_passwords()?;
+
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].name, "test");
+    assert_eq!(results[0].committed_by, Some("From Cache".to_owned()));
+    Ok(())
+}
+
+#[test]
+fn populate_password_list_small_repo_saves_cache_when_head_moves() -> Result<()> {
+    let dir = UnpackedDir::new("populate_password_list_small_repo")?;
+
+    let store = PasswordStore::new(
+        "default",
+        &Some(dir.dir().to_path_buf()),
+        &None,
+        &Some(dir.dir().to_path_buf()),
+        &None,
+        &CryptoImpl::GpgMe,
+        &None,
+    )?;
+    store.all_passwords()?;
+
+    // a commit that doesn't touch any password, so every entry is served from the cache
+    let repo = Repository::open(dir.dir())?;
+    std::fs::write(dir.dir().join("README"), "not a password")?;
+    let mut index = repo.index()?;
+    index.add_path(Path::new("README"))?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let parent = repo.head()?.peel_to_commit()?;
+    let signature = git2::Signature::now("Test", "test@example.com")?;
+    let head = repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        "readme",
+        &tree,
+        &[&parent],
+    )?;
+
+    store.all_passwords()?;
+
+    let cache_path = dir.dir().join(".git").join(crate::cache::CACHE_FILE_NAME);
+    let cache = std::fs::read_to_string(&cache_path)?;
+    assert!(cache.contains(&head.to_string()));
+    Ok(())
+}
+
 #[test]
 fn populate_password_list_repo_with_deleted_files() -> Result<()> {
     let dir = UnpackedDir::new("populate_password_list_repo_with_deleted_files")?;
@@ -868,6 +948,184 @@ fn save_config_one_store_with_pgp_impl() {
     assert!(data.contains(&format!("path = \"{}\"\n", &dir.path().display())));
 }
 
+#[test]
+fn save_config_one_store_with_clipboard_timeout() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let mut store = PasswordStore::new(
+        "default",
+        &Some(dir.path().to_path_buf()),
+        &None,
+        &Some(dir.path().to_path_buf()),
+        &None,
+        &CryptoImpl::GpgMe,
+        &None,
+    )
+    .unwrap();
+    assert_eq!(DEFAULT_CLIPBOARD_TIMEOUT, store.get_clipboard_timeout());
+    store.set_clipboard_timeout(Some(15));
+    assert_eq!(Duration::from_secs(15), store.get_clipboard_timeout());
+
+    save_config(
+        Arc::new(Mutex::new(vec![Arc::new(Mutex::new(store))])),
+        &dir.path().join("file.toml"),
+    )
+    .unwrap();
+
+    let data = fs::read_to_string(dir.path().join("file.toml")).unwrap();
+
+    assert!(data.contains("clipboard_timeout = \"15\""));
+}
+
+#[test]
+fn save_config_one_store_without_key_cache_timeout() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let mut store = PasswordStore::new(
+        "default",
+        &Some(dir.path().to_path_buf()),
+        &None,
+        &Some(dir.path().to_path_buf()),
+        &None,
+        &CryptoImpl::GpgMe,
+        &None,
+    )
+    .unwrap();
+    assert_eq!(None, store.get_key_cache_timeout_setting());
+    store.set_key_cache_timeout(Some(60));
+    assert_eq!(Some(60), store.get_key_cache_timeout_setting());
+    store.set_key_cache_timeout(None);
+    assert_eq!(None, store.get_key_cache_timeout_setting());
+
+    save_config(
+        Arc::new(Mutex::new(vec![Arc::new(Mutex::new(store))])),
+        &dir.path().join("file.toml"),
+    )
+    .unwrap();
+
+    let data = fs::read_to_string(dir.path().join("file.toml")).unwrap();
+
+    // the default isn't written, so that changes to it apply to the store
+    assert!(!data.contains("key_cache_timeout"));
+}
+
+#[test]
+fn save_config_one_store_with_key_expiry_warning_days() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let mut store = PasswordStore::new(
+        "default",
+        &Some(dir.path().to_path_buf()),
+        &None,
+        &Some(dir.path().to_path_buf()),
+        &None,
+        &CryptoImpl::GpgMe,
+        &None,
+    )
+    .unwrap();
+    assert_eq!(
+        DEFAULT_KEY_EXPIRY_WARNING_DAYS,
+        store.get_key_expiry_warning_days()
+    );
+    assert_eq!(None, store.get_key_expiry_warning_days_setting());
+    store.set_key_expiry_warning_days(Some(14));
+    assert_eq!(14, store.get_key_expiry_warning_days());
+    assert_eq!(Some(14), store.get_key_expiry_warning_days_setting());
+
+    save_config(
+        Arc::new(Mutex::new(vec![Arc::new(Mutex::new(store))])),
+        &dir.path().join("file.toml"),
+    )
+    .unwrap();
+
+    let data = fs::read_to_string(dir.path().join("file.toml")).unwrap();
+
+    assert!(data.contains("key_expiry_warning_days = \"14\""));
+}
+
+#[test]
+fn save_config_one_store_with_pull_strategy() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let mut store = PasswordStore::new(
+        "default",
+        &Some(dir.path().to_path_buf()),
+        &None,
+        &Some(dir.path().to_path_buf()),
+        &None,
+        &CryptoImpl::GpgMe,
+        &None,
+    )
+    .unwrap();
+    assert_eq!(PullStrategy::Merge, store.get_pull_strategy());
+    store.set_pull_strategy(PullStrategy::Rebase);
+
+    save_config(
+        Arc::new(Mutex::new(vec![Arc::new(Mutex::new(store))])),
+        &dir.path().join("file.toml"),
+    )
+    .unwrap();
+
+    let data = fs::read_to_string(dir.path().join("file.toml")).unwrap();
+
+    assert!(data.contains("pull_strategy = \"rebase\""));
+}
+
+#[test]
+fn save_config_one_store_with_remotes() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let mut store = PasswordStore::new(
+        "default",
+        &Some(dir.path().to_path_buf()),
+        &None,
+        &Some(dir.path().to_path_buf()),
+        &None,
+        &CryptoImpl::GpgMe,
+        &None,
+    )
+    .unwrap();
+    store.set_remotes(SyncRemote::parse_list("origin,backup:main").unwrap());
+
+    save_config(
+        Arc::new(Mutex::new(vec![Arc::new(Mutex::new(store))])),
+        &dir.path().join("file.toml"),
+    )
+    .unwrap();
+
+    let data = fs::read_to_string(dir.path().join("file.toml")).unwrap();
+
+    assert!(data.contains("remotes = \"origin,backup:main\""));
+}
+
+#[test]
+fn save_config_one_store_with_sync_interval() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let mut store = PasswordStore::new(
+        "default",
+        &Some(dir.path().to_path_buf()),
+        &None,
+        &Some(dir.path().to_path_buf()),
+        &None,
+        &CryptoImpl::GpgMe,
+        &None,
+    )
+    .unwrap();
+    assert_eq!(None, store.get_sync_interval());
+    store.set_sync_interval(Some(300));
+
+    save_config(
+        Arc::new(Mutex::new(vec![Arc::new(Mutex::new(store))])),
+        &dir.path().join("file.toml"),
+    )
+    .unwrap();
+
+    let data = fs::read_to_string(dir.path().join("file.toml")).unwrap();
+
+    assert!(data.contains("sync_interval = \"300\""));
+}
+
 #[test]
 fn save_config_one_store_with_fingerprint() {
     let dir = tempfile::tempdir().unwrap();
@@ -923,6 +1181,15 @@ fn rename_file() -> Result<()> {
         style_file: None,
         crypto: Box::new(MockCrypto::new()),
         user_home: None,
+        clipboard_timeout: None,
+        key_cache_timeout: None,
+        key_expiry_warning_days: None,
+        trust_roots: vec![],
+        key_sources: vec![],
+        pull_strategy: PullStrategy::Merge,
+        remotes: vec![],
+        sync_interval: None,
+        sync: None,
     };
 
     store.reload_password_list()?;
@@ -981,6 +1248,15 @@ fn rename_file_git_index_clean() -> Result<()> {
         style_file: None,
         crypto: Box::new(MockCrypto::new()),
         user_home: None,
+        clipboard_timeout: None,
+        key_cache_timeout: None,
+        key_expiry_warning_days: None,
+        trust_roots: vec![],
+        key_sources: vec![],
+        pull_strategy: PullStrategy::Merge,
+        remotes: vec![],
+        sync_interval: None,
+        sync: None,
     };
     store.reload_password_list()?;
     store.rename_file("1/test", "2/test")?;
@@ -1006,6 +1282,7 @@ fn decrypt_secret_empty_file() -> Result<()> {
     let pe = PasswordEntry::new(
         &dir.path().join(".password-store"),
         &PathBuf::from("file.gpg"),
+        "gpg",
         Ok(Local::now()),
         Ok(String::new()),
         Ok(SignatureStatus::Good),
@@ -1020,6 +1297,15 @@ fn decrypt_secret_empty_file() -> Result<()> {
         style_file: None,
         crypto: Box::new(GpgMe {}),
         user_home: None,
+        clipboard_timeout: None,
+        key_cache_timeout: None,
+        key_expiry_warning_days: None,
+        trust_roots: vec![],
+        key_sources: vec![],
+        pull_strategy: PullStrategy::Merge,
+        remotes: vec![],
+        sync_interval: None,
+        sync: None,
     };
 
     let res = pe.secret(&store);
@@ -1041,6 +1327,7 @@ fn decrypt_secret_missing_file() -> Result<()> {
     let pe = PasswordEntry::new(
         &dir.path().join(".password-store"),
         &PathBuf::from("file.gpg"),
+        "gpg",
         Ok(Local::now()),
         Ok(String::new()),
         Ok(SignatureStatus::Good),
@@ -1055,6 +1342,15 @@ fn decrypt_secret_missing_file() -> Result<()> {
         style_file: None,
         crypto: Box::new(GpgMe {}),
         user_home: None,
+        clipboard_timeout: None,
+        key_cache_timeout: None,
+        key_expiry_warning_days: None,
+        trust_roots: vec![],
+        key_sources: vec![],
+        pull_strategy: PullStrategy::Merge,
+        remotes: vec![],
+        sync_interval: None,
+        sync: None,
     };
 
     let res = pe.secret(&store);
@@ -1083,6 +1379,7 @@ fn decrypt_secret() -> Result<()> {
     let pe = PasswordEntry::new(
         &dir.path().join(".password-store"),
         &PathBuf::from("file.gpg"),
+        "gpg",
         Ok(Local::now()),
         Ok(String::new()),
         Ok(SignatureStatus::Good),
@@ -1100,6 +1397,15 @@ fn decrypt_secret() -> Result<()> {
         style_file: None,
         crypto: Box::new(crypto),
         user_home: None,
+        clipboard_timeout: None,
+        key_cache_timeout: None,
+        key_expiry_warning_days: None,
+        trust_roots: vec![],
+        key_sources: vec![],
+        pull_strategy: PullStrategy::Merge,
+        remotes: vec![],
+        sync_interval: None,
+        sync: None,
     };
 
     let res = pe.secret(&store).unwrap();
@@ -1123,6 +1429,7 @@ fn decrypt_password_empty_file() -> Result<()> {
     let pe = PasswordEntry::new(
         &dir.path().join(".password-store"),
         &PathBuf::from("file.gpg"),
+        "gpg",
         Ok(Local::now()),
         Ok(String::new()),
         Ok(SignatureStatus::Good),
@@ -1137,6 +1444,15 @@ fn decrypt_password_empty_file() -> Result<()> {
         style_file: None,
         crypto: Box::new(GpgMe {}),
         user_home: None,
+        clipboard_timeout: None,
+        key_cache_timeout: None,
+        key_expiry_warning_days: None,
+        trust_roots: vec![],
+        key_sources: vec![],
+        pull_strategy: PullStrategy::Merge,
+        remotes: vec![],
+        sync_interval: None,
+        sync: None,
     };
 
     let res = pe.password(&store);
@@ -1162,6 +1478,7 @@ fn decrypt_password_multiline() -> Result<()> {
     let pe = PasswordEntry::new(
         &dir.path().join(".password-store"),
         &PathBuf::from("file.gpg"),
+        "gpg",
         Ok(Local::now()),
         Ok(String::new()),
         Ok(SignatureStatus::Good),
@@ -1179,6 +1496,15 @@ fn decrypt_password_multiline() -> Result<()> {
         style_file: None,
         crypto: Box::new(crypto),
         user_home: None,
+        clipboard_timeout: None,
+        key_cache_timeout: None,
+        key_expiry_warning_days: None,
+        trust_roots: vec![],
+        key_sources: vec![],
+        pull_strategy: PullStrategy::Merge,
+        remotes: vec![],
+        sync_interval: None,
+        sync: None,
     };
 
     let mut res = pe.password(&store).unwrap();
@@ -1203,6 +1529,7 @@ fn mfa_setup(payload: String) -> Result<(tempfile::TempDir, PasswordEntry, Passw
     let pe = PasswordEntry::new(
         &dir.path().join(".password-store"),
         &PathBuf::from("file.gpg"),
+        "gpg",
         Ok(Local::now()),
         Ok(String::new()),
         Ok(SignatureStatus::Good),
@@ -1219,6 +1546,15 @@ fn mfa_setup(payload: String) -> Result<(tempfile::TempDir, PasswordEntry, Passw
         style_file: None,
         crypto: Box::new(crypto),
         user_home: None,
+        clipboard_timeout: None,
+        key_cache_timeout: None,
+        key_expiry_warning_days: None,
+        trust_roots: vec![],
+        key_sources: vec![],
+        pull_strategy: PullStrategy::Merge,
+        remotes: vec![],
+        sync_interval: None,
+        sync: None,
     };
 
     Ok((dir, pe, store))
@@ -1260,6 +1596,66 @@ fn mfa_example3() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn mfa_code_has_validity() -> Result<()> {
+    let (_dir, pe, store) = mfa_setup("otpauth://totp/Example:alice@google.com?secret=JBSWY3DPEHPK3PXPAAAAAAAAAAAA&issuer=Example".to_owned())?;
+
+    let res = pe.mfa_code(&store)?;
+
+    assert_eq!(6, res.code().len());
+    assert_eq!(Some(30), res.period());
+    assert!((1..=30).contains(&res.seconds_remaining().unwrap()));
+    assert_eq!(6, res.next_code().unwrap().len());
+
+    Ok(())
+}
+
+#[test]
+fn mfa_hotp_on_first_line_increments_counter() -> Result<()> {
+    use age::secrecy::ExposeSecret;
+
+    let td = tempdir()?;
+    let home = td.path().join("home");
+    let store_dir = td.path().join("store");
+    std::fs::create_dir_all(home.join(".passage"))?;
+    std::fs::create_dir_all(&store_dir)?;
+
+    let identity = age::x25519::Identity::generate();
+    std::fs::write(
+        home.join(".passage").join("identities"),
+        identity.to_string().expose_secret(),
+    )?;
+    std::fs::write(
+        store_dir.join(".age-recipients"),
+        format!("{}\n", identity.to_public()),
+    )?;
+
+    let mut store = PasswordStore::new(
+        "default",
+        &Some(store_dir),
+        &None,
+        &Some(home),
+        &None,
+        &CryptoImpl::Age,
+        &None,
+    )?;
+
+    // the test vectors of RFC 4226
+    let pe = store.new_password_file(
+        "hotp",
+        "otpauth://hotp/Example:alice?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&counter=1\nlogin: alice\n",
+    )?;
+
+    assert_eq!("287082", pe.mfa(&store)?);
+    assert_eq!(
+        "otpauth://hotp/Example:alice?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&counter=2\nlogin: alice\n",
+        pe.secret(&store)?
+    );
+    assert_eq!("359152", pe.mfa(&store)?);
+
+    Ok(())
+}
+
 #[test]
 fn mfa_no_otpauth_url() -> Result<()> {
     let (_dir, pe, store) = mfa_setup("password".to_owned())?;
@@ -1271,6 +1667,43 @@ fn mfa_no_otpauth_url() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn mfa_for_picks_url() -> Result<()> {
+    let (_dir, pe, store) = mfa_setup("password\notpauth://totp/Example:alice@google.com?secret=JBSWY3DPEHPK3PXPAAAAAAAAAAAA&issuer=Example\nsteam: otpauth://totp/Steam:alice?secret=JBSWY3DPEHPK3PXPAAAAAAAAAAAA&issuer=Steam\n".to_owned())?;
+
+    let accounts = pe.otp_accounts(&store)?;
+    assert_eq!(2, accounts.len());
+    assert_eq!("Example:alice@google.com", accounts[0].label());
+    assert_eq!(OtpKind::Totp, accounts[0].kind);
+    assert_eq!(OtpKind::Steam, accounts[1].kind);
+
+    assert_eq!(6, pe.mfa_for(&store, 0)?.len());
+    assert_eq!(5, pe.mfa_for(&store, 1)?.len());
+    assert_eq!(
+        Err(Error::Generic(
+            "No otpauth:// url with that index in secret"
+        )),
+        pe.mfa_for(&store, 2)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn parsed_entry() -> Result<()> {
+    let (_dir, pe, store) = mfa_setup("hunter2\nlogin: alice\nurl: https://example.com\notpauth://totp/Example:alice@google.com?secret=JBSWY3DPEHPK3PXPAAAAAAAAAAAA&issuer=Example\nsome notes\n".to_owned())?;
+
+    let res = pe.parsed(&store)?;
+
+    assert_eq!("hunter2", res.password());
+    assert_eq!(Some("alice"), res.get("login"));
+    assert_eq!(Some("https://example.com"), res.get("url"));
+    assert_eq!(1, res.otp_urls().len());
+    assert_eq!("some notes", res.notes());
+
+    Ok(())
+}
+
 #[test]
 fn update() -> Result<()> {
     let dir = tempfile::tempdir().unwrap();
@@ -1286,6 +1719,7 @@ fn update() -> Result<()> {
     let pe = PasswordEntry::new(
         &dir.path().join(".password-store"),
         &PathBuf::from("file.gpg"),
+        "gpg",
         Ok(Local::now()),
         Ok(String::new()),
         Ok(SignatureStatus::Good),
@@ -1302,6 +1736,15 @@ fn update() -> Result<()> {
         style_file: None,
         crypto: Box::new(crypto),
         user_home: None,
+        clipboard_timeout: None,
+        key_cache_timeout: None,
+        key_expiry_warning_days: None,
+        trust_roots: vec![],
+        key_sources: vec![],
+        pull_strategy: PullStrategy::Merge,
+        remotes: vec![],
+        sync_interval: None,
+        sync: None,
     };
 
     let res = pe.update("new content".to_owned(), &store);
@@ -1341,6 +1784,7 @@ fn delete_file() -> Result<()> {
     let pe = PasswordEntry::new(
         &dir.path().join(".password-store"),
         &PathBuf::from("file.gpg"),
+        "gpg",
         Ok(Local::now()),
         Ok(String::new()),
         Ok(SignatureStatus::Good),
@@ -1380,6 +1824,7 @@ fn get_history_no_repo() -> Result<()> {
     let pe = PasswordEntry::new(
         &dir.path().join(".password-store"),
         &PathBuf::from("file.gpg"),
+        "gpg",
         Ok(Local::now()),
         Ok(String::new()),
         Ok(SignatureStatus::Good),
@@ -1428,19 +1873,67 @@ fn get_history_with_repo() -> Result<()> {
 }
 
 #[test]
-fn test_format_error() {
+fn secret_at_diff_and_restore() -> Result<()> {
+    let td = tempdir()?;
+    let user_home = tempdir()?;
+
+    let (mut store, users) = setup_store(&td, user_home.path())?;
+    fs::write(
+        td.path().join(".gpg-id"),
+        hex::encode(users[0].fingerprint().as_bytes()),
+    )?;
+
+    let repo = git2::Repository::init(td.path())?;
+    let mut config = repo.config()?;
+    config.set_str("user.name", "default")?;
+    config.set_str("user.email", "default@example.com")?;
+    config.set_str("commit.gpgsign", "false")?;
+
+    let entry = store.new_password_file("file", "one\nuser: alice\n")?;
+    entry.update("two\nuser: alice\n".to_owned(), &store)?;
+
+    let history = entry.get_history(&store)?;
+    assert_eq!(2, history.len());
     assert_eq!(
-        format!("{}", Error::from(std::io::Error::from_raw_os_error(3))),
-        "No such process (os error 3)"
+        "one\nuser: alice\n",
+        entry.secret_at(&store, history[1].commit_id)?
     );
 
+    let diff = entry.diff(&store, history[1].commit_id, history[0].commit_id)?;
     assert_eq!(
-        format!("{}", Error::from(gpgme::Error::from_errno(3))),
-        "No such process (gpg error 32895)"
+        vec![
+            DiffLine::Removed("one".to_owned()),
+            DiffLine::Added("two".to_owned()),
+            DiffLine::Unchanged("user: alice".to_owned()),
+        ],
+        diff
     );
 
-    assert_eq!(
-        format!("{}", Error::from(git2::Error::from_str("git error"))),
+    entry.restore(&store, history[1].commit_id)?;
+
+    assert_eq!("one\nuser: alice\n", entry.secret(&store)?);
+    let history = entry.get_history(&store)?;
+    assert_eq!(3, history.len());
+    assert!(history[0].message.starts_with("Restored password for file"));
+    assert!(repo.statuses(None)?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_format_error() {
+    assert_eq!(
+        format!("{}", Error::from(std::io::Error::from_raw_os_error(3))),
+        "No such process (os error 3)"
+    );
+
+    assert_eq!(
+        format!("{}", Error::from(gpgme::Error::from_errno(3))),
+        "No such process (gpg error 32895)"
+    );
+
+    assert_eq!(
+        format!("{}", Error::from(git2::Error::from_str("git error"))),
         "git error"
     );
 
@@ -1579,6 +2072,15 @@ fn test_move_and_commit_signed() -> Result<()> {
         style_file: None,
         crypto: Box::new(MockCrypto::new()),
         user_home: None,
+        clipboard_timeout: None,
+        key_cache_timeout: None,
+        key_expiry_warning_days: None,
+        trust_roots: vec![],
+        key_sources: vec![],
+        pull_strategy: PullStrategy::Merge,
+        remotes: vec![],
+        sync_interval: None,
+        sync: None,
     };
     let c_oid = move_and_commit(
         &store,
@@ -1629,6 +2131,15 @@ fn test_search() -> Result<()> {
         style_file: None,
         crypto: Box::new(MockCrypto::new()),
         user_home: None,
+        clipboard_timeout: None,
+        key_cache_timeout: None,
+        key_expiry_warning_days: None,
+        trust_roots: vec![],
+        key_sources: vec![],
+        pull_strategy: PullStrategy::Merge,
+        remotes: vec![],
+        sync_interval: None,
+        sync: None,
     };
     let store = store;
 
@@ -1658,6 +2169,15 @@ fn test_verify_git_signature() -> Result<()> {
         style_file: None,
         crypto: Box::new(MockCrypto::new()),
         user_home: None,
+        clipboard_timeout: None,
+        key_cache_timeout: None,
+        key_expiry_warning_days: None,
+        trust_roots: vec![],
+        key_sources: vec![],
+        pull_strategy: PullStrategy::Merge,
+        remotes: vec![],
+        sync_interval: None,
+        sync: None,
     };
 
     let result = verify_git_signature(&repo, &oid, &store);
@@ -1717,6 +2237,15 @@ fn test_remove_and_commit() -> Result<()> {
         style_file: None,
         crypto: Box::new(MockCrypto::new()),
         user_home: None,
+        clipboard_timeout: None,
+        key_cache_timeout: None,
+        key_expiry_warning_days: None,
+        trust_roots: vec![],
+        key_sources: vec![],
+        pull_strategy: PullStrategy::Merge,
+        remotes: vec![],
+        sync_interval: None,
+        sync: None,
     };
 
     let repo = git2::Repository::open(dir.dir()).unwrap();
@@ -1741,12 +2270,14 @@ fn test_remove_and_commit() -> Result<()> {
 
 #[test]
 fn test_to_name() {
-    assert_eq!("name", to_name(&PathBuf::from("name.gpg")));
-    assert_eq!("dir/name", to_name(&PathBuf::from("dir/name.gpg")));
+    assert_eq!("name", to_name(&PathBuf::from("name.gpg"), "gpg"));
+    assert_eq!("dir/name", to_name(&PathBuf::from("dir/name.gpg"), "gpg"));
     assert_eq!(
         "dir/name without gpg on end",
-        to_name(&PathBuf::from("dir/name without gpg on end"))
+        to_name(&PathBuf::from("dir/name without gpg on end"), "gpg")
     );
+    assert_eq!("dir/name", to_name(&PathBuf::from("dir/name.age"), "age"));
+    assert_eq!("name.age", to_name(&PathBuf::from("name.age.gpg"), "gpg"));
 }
 
 #[test]
@@ -1763,6 +2294,15 @@ fn test_verify_gpg_id_files_missing_sig_file() -> Result<()> {
         style_file: None,
         crypto: Box::new(MockCrypto::new()),
         user_home: None,
+        clipboard_timeout: None,
+        key_cache_timeout: None,
+        key_expiry_warning_days: None,
+        trust_roots: vec![],
+        key_sources: vec![],
+        pull_strategy: PullStrategy::Merge,
+        remotes: vec![],
+        sync_interval: None,
+        sync: None,
     };
 
     fs::write(
@@ -1796,6 +2336,15 @@ fn test_verify_gpg_id_files() -> Result<()> {
         style_file: None,
         crypto: Box::new(MockCrypto::new()),
         user_home: None,
+        clipboard_timeout: None,
+        key_cache_timeout: None,
+        key_expiry_warning_days: None,
+        trust_roots: vec![],
+        key_sources: vec![],
+        pull_strategy: PullStrategy::Merge,
+        remotes: vec![],
+        sync_interval: None,
+        sync: None,
     };
 
     fs::write(
@@ -1904,6 +2453,15 @@ fn test_verify_gpg_id_files_untrusted_key_in_keyring() {
         style_file: None,
         crypto: Box::new(Sequoia::new(&td.path().join("local"), sofp, td.path()).unwrap()),
         user_home: None,
+        clipboard_timeout: None,
+        key_cache_timeout: None,
+        key_expiry_warning_days: None,
+        trust_roots: vec![],
+        key_sources: vec![],
+        pull_strategy: PullStrategy::Merge,
+        remotes: vec![],
+        sync_interval: None,
+        sync: None,
     };
 
     let result = store.verify_gpg_id_files();
@@ -1916,6 +2474,205 @@ fn test_verify_gpg_id_files_untrusted_key_in_keyring() {
     );
 }
 
+fn imported(title: &str, password: &str) -> ImportedEntry {
+    ImportedEntry {
+        folders: vec![],
+        title: title.to_owned(),
+        password: password.to_owned(),
+        username: None,
+        urls: vec![],
+        otp: None,
+        fields: vec![],
+        notes: String::new(),
+    }
+}
+
+#[test]
+fn test_import_conflicts() -> Result<()> {
+    let td = tempdir()?;
+
+    let mut store = PasswordStore {
+        name: "store_name".to_owned(),
+        root: td.path().to_path_buf(),
+        valid_gpg_signing_keys: vec![],
+        passwords: [].to_vec(),
+        style_file: None,
+        crypto: Box::new(MockCrypto::new()),
+        user_home: None,
+        clipboard_timeout: None,
+        key_cache_timeout: None,
+        key_expiry_warning_days: None,
+        trust_roots: vec![],
+        key_sources: vec![],
+        pull_strategy: PullStrategy::Merge,
+        remotes: vec![],
+        sync_interval: None,
+        sync: None,
+    };
+
+    fs::write(
+        td.path().join(".gpg-id"),
+        "7E068070D5EF794B00C8A9D91D108E6C07CBC406",
+    )?;
+    store.new_password_file("imported/github", "password")?;
+
+    let entries = vec![
+        imported("github", "one"),
+        imported("gitlab", "two"),
+        imported("gitlab", "three"),
+        imported("broken", "multi\nline"),
+    ];
+
+    let skip = ImportOptions {
+        prefix: "imported/".to_owned(),
+        on_conflict: ConflictStrategy::Skip,
+    };
+    let report = store.import(&entries, &skip)?;
+
+    assert_eq!(vec!["imported/gitlab".to_owned()], report.imported);
+    assert_eq!(3, report.skipped.len());
+    assert!(report.renamed.is_empty());
+
+    let rename = ImportOptions {
+        prefix: "imported".to_owned(),
+        on_conflict: ConflictStrategy::Rename,
+    };
+    let report = store.import(&entries[..3], &rename)?;
+
+    assert_eq!(
+        vec![
+            "imported/github-2".to_owned(),
+            "imported/gitlab-2".to_owned(),
+            "imported/gitlab-3".to_owned()
+        ],
+        report.imported
+    );
+    assert_eq!(3, report.renamed.len());
+    assert!(td.path().join("imported/gitlab-3.gpg").exists());
+    assert_eq!(5, store.passwords.len());
+
+    Ok(())
+}
+
+#[test]
+fn test_export() -> Result<()> {
+    let td = tempdir()?;
+    let export_dir = tempdir()?;
+
+    let mut store = PasswordStore {
+        name: "store_name".to_owned(),
+        root: td.path().to_path_buf(),
+        valid_gpg_signing_keys: vec![],
+        passwords: [].to_vec(),
+        style_file: None,
+        crypto: Box::new(
+            MockCrypto::new()
+                .with_decrypt_string_return("hunter2\nlogin: alice\n".to_owned())
+                .with_encrypt_string_return(vec![1, 2, 3]),
+        ),
+        user_home: None,
+        clipboard_timeout: None,
+        key_cache_timeout: None,
+        key_expiry_warning_days: None,
+        trust_roots: vec![],
+        key_sources: vec![],
+        pull_strategy: PullStrategy::Merge,
+        remotes: vec![],
+        sync_interval: None,
+        sync: None,
+    };
+
+    fs::write(
+        td.path().join(".gpg-id"),
+        "7E068070D5EF794B00C8A9D91D108E6C07CBC406",
+    )?;
+    store.new_password_file("work/github", "ignored")?;
+    store.new_password_file("work/gitlab", "ignored")?;
+    store.new_password_file("home/bank", "ignored")?;
+
+    let options = ExportOptions {
+        subtree: "work".to_owned(),
+        ..ExportOptions::default()
+    };
+
+    let csv_path = export_dir.path().join("export.csv");
+    let report = store.export_plaintext(&csv_path, ExportFormat::Csv, &options)?;
+    assert_eq!(
+        vec!["work/github".to_owned(), "work/gitlab".to_owned()],
+        report.exported
+    );
+    assert_eq!(vec![ExportWarning::Plaintext], report.warnings);
+    assert_eq!(
+        "name,password,login,otp,notes\r\nwork/github,hunter2,alice,,\r\nwork/gitlab,hunter2,alice,,\r\n",
+        fs::read_to_string(&csv_path)?
+    );
+
+    let archive_path = td.path().join("export.tar.gpg");
+    let recipients = store.all_recipients()?;
+    let report = store.export_archive(&archive_path, &recipients, &ExportOptions::default())?;
+    assert_eq!(3, report.exported.len());
+    assert_eq!(vec![ExportWarning::InsidePasswordStore], report.warnings);
+    assert_eq!(vec![1, 2, 3], fs::read(&archive_path)?);
+
+    let warnings = store.export_warnings(&csv_path, true);
+    assert_eq!(vec![ExportWarning::OverwritesFile], warnings);
+
+    Ok(())
+}
+
+#[test]
+fn test_audit() -> Result<()> {
+    let td = tempdir()?;
+
+    let mut store = PasswordStore {
+        name: "store_name".to_owned(),
+        root: td.path().to_path_buf(),
+        valid_gpg_signing_keys: vec![],
+        passwords: [].to_vec(),
+        style_file: None,
+        crypto: Box::new(
+            MockCrypto::new()
+                .with_decrypt_string_return("hunter2\nlogin: alice\n".to_owned())
+                .with_encrypt_string_return(vec![1, 2, 3]),
+        ),
+        user_home: None,
+        clipboard_timeout: None,
+        key_cache_timeout: None,
+        key_expiry_warning_days: None,
+        trust_roots: vec![],
+        key_sources: vec![],
+        pull_strategy: PullStrategy::Merge,
+        remotes: vec![],
+        sync_interval: None,
+        sync: None,
+    };
+
+    fs::write(
+        td.path().join(".gpg-id"),
+        "7E068070D5EF794B00C8A9D91D108E6C07CBC406",
+    )?;
+    store.new_password_file("work/github", "ignored")?;
+    store.new_password_file("home/bank", "ignored")?;
+
+    let report = store.audit(&AuditOptions::default())?;
+
+    assert_eq!(2, report.audited);
+    assert_eq!(
+        &[
+            AuditIssue::Reused {
+                by: vec!["work/github".to_owned()]
+            },
+            AuditIssue::Weak {
+                entropy: estimate_entropy("hunter2"),
+                strength: Strength::VeryWeak
+            }
+        ],
+        report.issues_for("home/bank")
+    );
+
+    Ok(())
+}
+
 #[test]
 fn test_new_password_file() -> Result<()> {
     let td = tempdir()?;
@@ -1928,6 +2685,15 @@ fn test_new_password_file() -> Result<()> {
         style_file: None,
         crypto: Box::new(MockCrypto::new()),
         user_home: None,
+        clipboard_timeout: None,
+        key_cache_timeout: None,
+        key_expiry_warning_days: None,
+        trust_roots: vec![],
+        key_sources: vec![],
+        pull_strategy: PullStrategy::Merge,
+        remotes: vec![],
+        sync_interval: None,
+        sync: None,
     };
 
     fs::write(
@@ -1964,6 +2730,15 @@ fn test_new_password_file_in_git_repo() -> Result<()> {
         style_file: None,
         crypto: Box::new(MockCrypto::new().with_encrypt_string_return(vec![32, 32, 32, 32])),
         user_home: None,
+        clipboard_timeout: None,
+        key_cache_timeout: None,
+        key_expiry_warning_days: None,
+        trust_roots: vec![],
+        key_sources: vec![],
+        pull_strategy: PullStrategy::Merge,
+        remotes: vec![],
+        sync_interval: None,
+        sync: None,
     };
 
     fs::write(
@@ -2004,6 +2779,15 @@ fn test_new_password_file_encryption_failure() -> Result<()> {
         style_file: None,
         crypto: Box::new(MockCrypto::new().with_encrypt_error("unit test error".to_owned())),
         user_home: None,
+        clipboard_timeout: None,
+        key_cache_timeout: None,
+        key_expiry_warning_days: None,
+        trust_roots: vec![],
+        key_sources: vec![],
+        pull_strategy: PullStrategy::Merge,
+        remotes: vec![],
+        sync_interval: None,
+        sync: None,
     };
 
     fs::write(
@@ -2041,6 +2825,15 @@ fn test_new_password_file_twice() -> Result<()> {
         style_file: None,
         crypto: Box::new(MockCrypto::new().with_encrypt_string_return(vec![32, 32, 32, 32])),
         user_home: None,
+        clipboard_timeout: None,
+        key_cache_timeout: None,
+        key_expiry_warning_days: None,
+        trust_roots: vec![],
+        key_sources: vec![],
+        pull_strategy: PullStrategy::Merge,
+        remotes: vec![],
+        sync_interval: None,
+        sync: None,
     };
 
     fs::write(
@@ -2089,6 +2882,15 @@ fn test_new_password_file_outside_pass_dir() -> Result<()> {
         style_file: None,
         crypto: Box::new(MockCrypto::new()),
         user_home: None,
+        clipboard_timeout: None,
+        key_cache_timeout: None,
+        key_expiry_warning_days: None,
+        trust_roots: vec![],
+        key_sources: vec![],
+        pull_strategy: PullStrategy::Merge,
+        remotes: vec![],
+        sync_interval: None,
+        sync: None,
     };
 
     fs::write(
@@ -2175,6 +2977,200 @@ fn test_rename_file_different_sub_permissions() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn copy_file_different_sub_permissions() -> Result<()> {
+    let td = tempdir()?;
+    let user_home = tempdir()?;
+
+    let (mut store, users) = setup_store(&td, user_home.path())?;
+
+    fs::write(
+        td.path().join(".gpg-id"),
+        hex::encode(users[0].fingerprint().as_bytes())
+            + "\n"
+            + &hex::encode(users[1].fingerprint().as_bytes())
+            + "\n",
+    )?;
+
+    fs::create_dir(td.path().join("dir")).unwrap();
+    fs::write(
+        td.path().join("dir").join(".gpg-id"),
+        hex::encode(users[0].fingerprint().as_bytes()),
+    )?;
+
+    store.new_password_file("dir/file", "password")?;
+
+    let index = store.copy_file("dir/file", "file")?;
+
+    assert_eq!(2, store.passwords.len());
+    assert_eq!("file", store.passwords[index].name);
+    assert_eq!("password", store.passwords[index].secret(&store)?);
+
+    let content = fs::read(td.path().join("dir").join("file.gpg"))?;
+    assert_eq!(1, count_recipients(&content));
+    let content = fs::read(td.path().join("file.gpg"))?;
+    assert_eq!(2, count_recipients(&content));
+
+    Ok(())
+}
+
+#[test]
+fn rename_dir_reencrypts_every_entry() -> Result<()> {
+    let td = tempdir()?;
+    let user_home = tempdir()?;
+
+    let (mut store, users) = setup_store(&td, user_home.path())?;
+
+    fs::write(
+        td.path().join(".gpg-id"),
+        hex::encode(users[0].fingerprint().as_bytes())
+            + "\n"
+            + &hex::encode(users[1].fingerprint().as_bytes())
+            + "\n",
+    )?;
+
+    fs::create_dir(td.path().join("dir")).unwrap();
+    fs::write(
+        td.path().join("dir").join(".gpg-id"),
+        hex::encode(users[0].fingerprint().as_bytes()),
+    )?;
+
+    store.new_password_file("old/a", "a")?;
+    store.new_password_file("old/sub/b", "b")?;
+
+    let indices = store.rename_dir("old", "dir/new")?;
+
+    assert_eq!(2, indices.len());
+    assert_eq!(2, store.passwords.len());
+    let mut names: Vec<&str> = indices
+        .iter()
+        .map(|i| store.passwords[*i].name.as_str())
+        .collect();
+    names.sort_unstable();
+    assert_eq!(vec!["dir/new/a", "dir/new/sub/b"], names);
+
+    assert!(!td.path().join("old").exists());
+    let content = fs::read(td.path().join("dir/new/sub/b.gpg"))?;
+    assert_eq!(1, count_recipients(&content));
+    assert_eq!("b", store.passwords[indices[1]].secret(&store)?);
+
+    Ok(())
+}
+
+#[test]
+fn rename_dir_moves_the_gpg_id_files() -> Result<()> {
+    let td = tempdir()?;
+    let user_home = tempdir()?;
+
+    let (mut store, users) = setup_store(&td, user_home.path())?;
+
+    fs::write(
+        td.path().join(".gpg-id"),
+        hex::encode(users[0].fingerprint().as_bytes())
+            + "\n"
+            + &hex::encode(users[1].fingerprint().as_bytes())
+            + "\n",
+    )?;
+
+    fs::create_dir_all(td.path().join("old").join("sub"))?;
+    fs::write(
+        td.path().join("old").join("sub").join(".gpg-id"),
+        hex::encode(users[0].fingerprint().as_bytes()),
+    )?;
+
+    store.new_password_file("old/a", "a")?;
+    store.new_password_file("old/sub/b", "b")?;
+    assert_eq!(
+        1,
+        count_recipients(&fs::read(td.path().join("old/sub/b.gpg"))?)
+    );
+
+    store.rename_dir("old", "new")?;
+
+    assert!(!td.path().join("old").exists());
+    assert!(td.path().join("new/sub/.gpg-id").is_file());
+    assert_eq!(2, count_recipients(&fs::read(td.path().join("new/a.gpg"))?));
+    assert_eq!(
+        1,
+        count_recipients(&fs::read(td.path().join("new/sub/b.gpg"))?)
+    );
+
+    store.copy_dir("new", "new..copy")?;
+
+    assert!(td.path().join("new/sub/.gpg-id").is_file());
+    assert!(td.path().join("new..copy/sub/.gpg-id").is_file());
+    assert_eq!(
+        1,
+        count_recipients(&fs::read(td.path().join("new..copy/sub/b.gpg"))?)
+    );
+    assert_eq!(4, store.passwords.len());
+
+    Ok(())
+}
+
+#[test]
+fn rename_dir_into_itself() -> Result<()> {
+    let td = tempdir()?;
+    let user_home = tempdir()?;
+
+    let (mut store, users) = setup_store(&td, user_home.path())?;
+    fs::write(
+        td.path().join(".gpg-id"),
+        hex::encode(users[0].fingerprint().as_bytes()),
+    )?;
+    store.new_password_file("old/a", "a")?;
+
+    assert!(store.rename_dir("old", "old/new").is_err());
+    assert!(store.copy_dir("old", "../new").is_err());
+    assert!(td.path().join("old/a.gpg").exists());
+    assert_eq!(1, store.passwords.len());
+
+    Ok(())
+}
+
+#[test]
+fn copy_dir_commits_once() -> Result<()> {
+    let td = tempdir()?;
+    let user_home = tempdir()?;
+
+    let (mut store, users) = setup_store(&td, user_home.path())?;
+    fs::write(
+        td.path().join(".gpg-id"),
+        hex::encode(users[0].fingerprint().as_bytes()),
+    )?;
+
+    let repo = git2::Repository::init(td.path())?;
+    let mut config = repo.config()?;
+    config.set_str("user.name", "default")?;
+    config.set_str("user.email", "default@example.com")?;
+    config.set_str("commit.gpgsign", "false")?;
+
+    store.new_password_file("old/a", "a")?;
+    store.new_password_file("old/b", "b")?;
+    let before = repo.head()?.peel_to_commit()?.id();
+
+    store.copy_dir("old", "new")?;
+
+    let head = repo.head()?.peel_to_commit()?;
+    assert_eq!(before, head.parent_id(0)?);
+    assert_eq!(Some("copied old to new"), head.message());
+    assert_eq!(
+        2,
+        head.tree()?
+            .get_path(Path::new("new"))?
+            .to_object(&repo)?
+            .peel_to_tree()?
+            .len()
+    );
+    assert!(repo.statuses(None)?.is_empty());
+
+    assert_eq!(4, store.passwords.len());
+    assert!(td.path().join("old/a.gpg").exists());
+    assert!(td.path().join("new/b.gpg").exists());
+
+    Ok(())
+}
+
 #[test]
 fn test_add_recipient_different_sub_permissions() -> Result<()> {
     let td = tempdir()?;
@@ -2476,6 +3472,94 @@ fn test_remove_recipient_root() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_add_recipient_to_sub_dir_leaves_other_entries_untouched() -> Result<()> {
+    let td = tempdir()?;
+    let config_path = tempdir()?;
+    let user_home = tempdir()?;
+
+    let (mut store, users) = setup_store(&td, user_home.path())?;
+
+    fs::write(
+        td.path().join(".gpg-id"),
+        hex::encode(users[0].fingerprint().as_bytes()) + "\n",
+    )?;
+    fs::create_dir_all(td.path().join("dir").join("sub"))?;
+    fs::write(
+        td.path().join("dir").join("sub").join(".gpg-id"),
+        hex::encode(users[1].fingerprint().as_bytes()) + "\n",
+    )?;
+
+    store.new_password_file("file", "password")?;
+    store.new_password_file("dir/file", "password")?;
+    store.new_password_file("dir/sub/file", "password")?;
+
+    let root_file_pre = fs::read(td.path().join("file.gpg"))?;
+    let sub_file_pre = fs::read(td.path().join("dir/sub/file.gpg"))?;
+
+    store.add_recipient(
+        &crate::test_helpers::recipient_from_cert(&users[2]),
+        &PathBuf::from("dir"),
+        config_path.path(),
+    )?;
+
+    assert_eq!(root_file_pre, fs::read(td.path().join("file.gpg"))?);
+    assert_eq!(sub_file_pre, fs::read(td.path().join("dir/sub/file.gpg"))?);
+    assert_eq!(
+        2,
+        count_recipients(&fs::read(td.path().join("dir/file.gpg"))?)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_recipient_dry_run() -> Result<()> {
+    let td = tempdir()?;
+    let user_home = tempdir()?;
+
+    let (mut store, users) = setup_store(&td, user_home.path())?;
+
+    fs::write(
+        td.path().join(".gpg-id"),
+        hex::encode(users[0].fingerprint().as_bytes()) + "\n",
+    )?;
+    fs::create_dir_all(td.path().join("dir").join("sub"))?;
+    fs::create_dir_all(td.path().join("other"))?;
+    fs::write(
+        td.path().join("dir").join("sub").join(".gpg-id"),
+        hex::encode(users[0].fingerprint().as_bytes()) + "\n",
+    )?;
+
+    store.new_password_file("file", "password")?;
+    store.new_password_file("dir/file", "password")?;
+    store.new_password_file("dir/sub/file", "password")?;
+    store.new_password_file("other/file", "password")?;
+
+    let names = |entries: Vec<PasswordEntry>| -> Vec<String> {
+        entries.into_iter().map(|e| e.name).collect()
+    };
+
+    assert_eq!(
+        vec!["dir/file"],
+        names(store.add_recipient_dry_run(&PathBuf::from("dir"))?)
+    );
+    assert_eq!(
+        vec!["dir/sub/file"],
+        names(store.add_recipient_dry_run(&PathBuf::from("dir/sub"))?)
+    );
+    assert_eq!(
+        vec!["dir/file", "file", "other/file"],
+        names(store.remove_recipient_dry_run(&PathBuf::from("other"))?)
+    );
+    assert_eq!(
+        vec!["dir/sub/file"],
+        names(store.remove_recipient_dry_run(&PathBuf::from("dir/sub"))?)
+    );
+
+    Ok(())
+}
+
 #[test]
 fn test_recipients_file_for_dir() -> Result<()> {
     let td = tempdir()?;
@@ -2550,6 +3634,15 @@ fn all_recipients_from_stores_plain() -> Result<()> {
         style_file: None,
         crypto: Box::new(MockCrypto::new()),
         user_home: None,
+        clipboard_timeout: None,
+        key_cache_timeout: None,
+        key_expiry_warning_days: None,
+        trust_roots: vec![],
+        key_sources: vec![],
+        pull_strategy: PullStrategy::Merge,
+        remotes: vec![],
+        sync_interval: None,
+        sync: None,
     };
 
     let result = all_recipients_from_stores(Arc::new(Mutex::new(vec![Arc::new(Mutex::new(s1))])))?;
@@ -2559,3 +3652,106 @@ fn all_recipients_from_stores_plain() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn age_store() -> Result<()> {
+    use age::secrecy::ExposeSecret;
+
+    let td = tempdir()?;
+    let home = td.path().join("home");
+    let store_dir = td.path().join("store");
+    std::fs::create_dir_all(home.join(".passage"))?;
+    std::fs::create_dir_all(store_dir.join("work"))?;
+
+    let identity = age::x25519::Identity::generate();
+    std::fs::write(
+        home.join(".passage").join("identities"),
+        identity.to_string().expose_secret(),
+    )?;
+    std::fs::write(
+        store_dir.join(".age-recipients"),
+        format!("# me\n{}\n", identity.to_public()),
+    )?;
+
+    let mut store = PasswordStore::new(
+        "default",
+        &Some(store_dir.clone()),
+        &None,
+        &Some(home),
+        &None,
+        &CryptoImpl::Age,
+        &None,
+    )?;
+
+    store.new_password_file("work/site", "password")?;
+    assert!(store_dir.join("work/site.age").is_file());
+    assert!(!store_dir.join("work/site.gpg").exists());
+
+    std::fs::write(
+        store_dir.join("work/other.gpg"),
+        "not a password of this store",
+    )?;
+    store.reload_password_list()?;
+    assert_eq!(1, store.passwords.len());
+    assert_eq!("work/site", store.passwords[0].name);
+    assert_eq!("password", store.passwords[0].secret(&store)?);
+
+    let recipients = store.all_recipients()?;
+    assert_eq!(1, recipients.len());
+    assert_eq!(identity.to_public().to_string(), recipients[0].key_id);
+
+    store.rename_file("work/site", "site")?;
+    assert!(store_dir.join("site.age").is_file());
+    Ok(())
+}
+
+#[test]
+fn recipient_warnings_expiring_keys() -> Result<()> {
+    let td = tempdir()?;
+    let user_home = tempdir()?;
+
+    let (mut store, users) = setup_store(&td, user_home.path())?;
+    let (erin, _) = CertBuilder::general_purpose(None, Some("erin@example.org"))
+        .set_validity_period(Duration::from_secs(10 * 24 * 60 * 60))
+        .generate()?;
+    store.crypto = Box::new(Sequoia::from_values(
+        slice_to_20_bytes(users[0].fingerprint().as_bytes())?,
+        users
+            .iter()
+            .chain([&Arc::new(erin.clone())])
+            .map(|u| {
+                (
+                    slice_to_20_bytes(u.fingerprint().as_bytes()).unwrap(),
+                    u.clone(),
+                )
+            })
+            .collect(),
+        user_home.path(),
+    ));
+
+    fs::write(
+        td.path().join(".gpg-id"),
+        hex::encode(users[0].fingerprint().as_bytes()) + "\n",
+    )?;
+    fs::create_dir(td.path().join("dir"))?;
+    fs::write(
+        td.path().join("dir").join(".gpg-id"),
+        hex::encode(users[1].fingerprint().as_bytes())
+            + "\n"
+            + &hex::encode(erin.fingerprint().as_bytes())
+            + "\n",
+    )?;
+
+    let warnings = store.recipient_warnings()?;
+    assert_eq!(1, warnings.len());
+    assert_eq!(
+        Some(slice_to_20_bytes(erin.fingerprint().as_bytes())?),
+        warnings[0].0.fingerprint
+    );
+    assert!(matches!(warnings[0].1, KeyWarning::ExpiresSoon(_)));
+
+    store.set_key_expiry_warning_days(Some(5));
+    assert!(store.recipient_warnings()?.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,145 @@
+use chrono::TimeZone;
+
+use super::*;
+use crate::pass::RepositoryStatus;
+
+fn entry(name: &str) -> PasswordEntry {
+    PasswordEntry {
+        name: name.to_owned(),
+        path: Default::default(),
+        updated: None,
+        committed_by: None,
+        signature_status: None,
+        is_in_git: RepositoryStatus::InRepo,
+    }
+}
+
+fn now() -> DateTime<Local> {
+    Local.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap()
+}
+
+fn names(query: &str, entries: &[PasswordEntry]) -> Vec<String> {
+    SearchQuery::parse_at(query, now())
+        .rank(entries)
+        .into_iter()
+        .map(|e| e.name.clone())
+        .collect()
+}
+
+#[test]
+fn fuzzy_subsequence_matches() {
+    let entries = vec![entry("work/github"), entry("personal/gitlab")];
+
+    assert_eq!(vec!["work/github"], names("gthb", &entries));
+    assert!(names("bhtg", &entries).is_empty());
+}
+
+#[test]
+fn search_is_case_insensitive() {
+    let entries = vec![entry("Work/GitHub")];
+
+    assert_eq!(vec!["Work/GitHub"], names("github", &entries));
+    assert_eq!(vec!["Work/GitHub"], names("GITHUB", &entries));
+}
+
+#[test]
+fn every_term_must_match() {
+    let entries = vec![
+        entry("work/github"),
+        entry("personal/github"),
+        entry("work/mail"),
+    ];
+
+    assert_eq!(vec!["work/github"], names("git work", &entries));
+}
+
+#[test]
+fn contiguous_match_ranks_above_scattered() {
+    let entries = vec![entry("web/maxixl"), entry("web/gmail")];
+
+    assert_eq!(vec!["web/gmail", "web/maxixl"], names("mail", &entries));
+}
+
+#[test]
+fn segment_start_ranks_above_middle_of_word() {
+    let entries = vec![entry("mail/piggybank"), entry("bank/mail")];
+
+    assert_eq!(vec!["bank/mail", "mail/piggybank"], names("bank", &entries));
+}
+
+#[test]
+fn equal_scores_keep_order() {
+    let entries = vec![entry("b/test"), entry("a/test")];
+
+    assert_eq!(vec!["b/test", "a/test"], names("test", &entries));
+}
+
+#[test]
+fn dir_filter() {
+    let entries = vec![
+        entry("work/github"),
+        entry("work/ci/github"),
+        entry("workshop/github"),
+        entry("personal/github"),
+    ];
+
+    assert_eq!(
+        vec!["work/github", "work/ci/github"],
+        names("dir:work/ github", &entries)
+    );
+    assert_eq!(vec!["work/ci/github"], names("dir:Work/CI", &entries));
+}
+
+#[test]
+fn modified_filter() {
+    let mut recent = entry("recent");
+    recent.updated = Some(now() - Duration::try_days(2).unwrap());
+    let mut old = entry("old");
+    old.updated = Some(now() - Duration::try_days(400).unwrap());
+    let never = entry("never");
+    let entries = vec![recent, old, never];
+
+    assert_eq!(vec!["recent"], names("modified:<30d", &entries));
+    assert_eq!(vec!["recent"], names("modified:1w", &entries));
+    assert_eq!(vec!["old"], names("modified:>1y", &entries));
+    assert_eq!(vec!["recent", "old"], names("modified:<2y", &entries));
+}
+
+#[test]
+fn signer_and_signature_filters() {
+    let mut signed = entry("signed");
+    signed.committed_by = Some("Alice Andersson".to_owned());
+    signed.signature_status = Some(SignatureStatus::Good);
+    let mut tampered = entry("tampered");
+    tampered.committed_by = Some("Bob".to_owned());
+    tampered.signature_status = Some(SignatureStatus::Bad);
+    let unsigned = entry("unsigned");
+    let entries = vec![signed, tampered, unsigned];
+
+    assert_eq!(vec!["signed"], names("signer:alice", &entries));
+    assert_eq!(vec!["tampered"], names("signature:bad", &entries));
+    assert_eq!(vec!["unsigned"], names("signature:none", &entries));
+}
+
+#[test]
+fn incomplete_filter_is_ignored() {
+    let entries = vec![entry("work/github"), entry("mail")];
+
+    assert_eq!(2, names("modified:<", &entries).len());
+    assert_eq!(2, names("signature:go", &entries).len());
+}
+
+#[test]
+fn huge_age_is_ignored() {
+    let entries = vec![entry("work/github"), entry("mail")];
+
+    assert_eq!(2, names("modified:<99999999d", &entries).len());
+    assert_eq!(2, names("modified:>9999999999y", &entries).len());
+}
+
+#[test]
+fn unknown_key_is_search_term() {
+    let entries = vec![entry("https:example.com"), entry("mail")];
+
+    assert_eq!(vec!["https:example.com"], names("https:ex", &entries));
+}
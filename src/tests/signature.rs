@@ -1,8 +1,9 @@
+use chrono::{Duration, Local, TimeZone};
 use hex::FromHex;
 
 use crate::{
     pass::{KeyRingStatus, OwnerTrustLevel, Recipient},
-    signature::{parse_signing_keys, Comment},
+    signature::{parse_fingerprints, parse_signing_keys, Comment, EncryptionSubkey, KeyWarning},
     test_helpers::{append_file_name, recipient_alex, recipient_alex_old, MockCrypto, MockKey},
 };
 
@@ -91,6 +92,24 @@ fn parse_signing_keys_short() {
     assert!(result.is_err());
 }
 
+#[test]
+fn parse_fingerprints_without_keyring() {
+    let result = parse_fingerprints(
+        "0x7E068070D5EF794B00C8A9D91D108E6C07CBC406, E6A7D758338EC2EF2A8A9F4EE7E3DB4B3217482F",
+    )
+    .unwrap();
+
+    assert_eq!(
+        vec![
+            <[u8; 20]>::from_hex("7E068070D5EF794B00C8A9D91D108E6C07CBC406").unwrap(),
+            <[u8; 20]>::from_hex("E6A7D758338EC2EF2A8A9F4EE7E3DB4B3217482F").unwrap(),
+        ],
+        result
+    );
+    assert!(parse_fingerprints("").unwrap().is_empty());
+    assert!(parse_fingerprints("0x1D108E6C07CBC406").is_err());
+}
+
 #[test]
 fn recipient_from_key_error() {
     let crypto = MockCrypto::new().with_get_key_error("unit test error".to_owned());
@@ -129,6 +148,34 @@ fn all_recipients() {
     assert!(KeyRingStatus::InKeyRing == result[0].key_ring_status);
 }
 
+#[test]
+fn all_recipients_computes_trust_once() {
+    let crypto = MockCrypto::new()
+        .with_get_key_result(
+            "0x1D108E6C07CBC406".to_owned(),
+            MockKey::from_args(
+                <[u8; 20]>::from_hex("7E068070D5EF794B00C8A9D91D108E6C07CBC406").unwrap(),
+                vec!["Alexander Kjäll <alexander.kjall@gmail.com>".to_owned()],
+            ),
+        )
+        .with_get_key_result(
+            "0xE7E3DB4B3217482F".to_owned(),
+            MockKey::from_args(
+                <[u8; 20]>::from_hex("E6A7D758338EC2EF2A8A9F4EE7E3DB4B3217482F").unwrap(),
+                vec!["Alexander Kjäll <alexander.kjall@gmail.com>".to_owned()],
+            ),
+        );
+
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join(".gpg-id");
+    std::fs::write(&file, "0x1D108E6C07CBC406\n0xE7E3DB4B3217482F\n").unwrap();
+
+    let result = Recipient::all_recipients(&file, &crypto).unwrap();
+
+    assert_eq!(2, result.len());
+    assert_eq!(1, *crypto.trust_items_calls.borrow());
+}
+
 #[test]
 fn all_recipients_with_one_comment_line() {
     let crypto = MockCrypto::new().with_get_key_result(
@@ -600,6 +647,10 @@ fn remove_recipient_from_file_same_key_id_different_fingerprint() {
         key_ring_status: KeyRingStatus::InKeyRing,
         trust_level: OwnerTrustLevel::Ultimate,
         not_usable: false,
+        created: None,
+        expires: None,
+        revocation_reason: None,
+        encryption_subkeys: vec![],
     };
     let r2 = Recipient {
         name: "Alexander Kjäll <alexander.kjall@gmail.com>".to_owned(),
@@ -614,6 +665,10 @@ fn remove_recipient_from_file_same_key_id_different_fingerprint() {
         key_ring_status: KeyRingStatus::InKeyRing,
         trust_level: OwnerTrustLevel::Ultimate,
         not_usable: false,
+        created: None,
+        expires: None,
+        revocation_reason: None,
+        encryption_subkeys: vec![],
     };
 
     let recipients = vec![r.clone(), r2.clone()];
@@ -743,6 +798,10 @@ fn recipient_both_none() {
         key_ring_status: KeyRingStatus::InKeyRing,
         trust_level: OwnerTrustLevel::Ultimate,
         not_usable: false,
+        created: None,
+        expires: None,
+        revocation_reason: None,
+        encryption_subkeys: vec![],
     };
     let r2 = Recipient {
         name: "Alexander Kjäll <alexander.kjall@gmail.com>".to_owned(),
@@ -755,6 +814,10 @@ fn recipient_both_none() {
         key_ring_status: KeyRingStatus::InKeyRing,
         trust_level: OwnerTrustLevel::Ultimate,
         not_usable: false,
+        created: None,
+        expires: None,
+        revocation_reason: None,
+        encryption_subkeys: vec![],
     };
 
     assert!(r1 != r2);
@@ -776,6 +839,10 @@ fn recipient_one_none() {
         key_ring_status: KeyRingStatus::InKeyRing,
         trust_level: OwnerTrustLevel::Ultimate,
         not_usable: false,
+        created: None,
+        expires: None,
+        revocation_reason: None,
+        encryption_subkeys: vec![],
     };
     let r2 = Recipient {
         name: "Alexander Kjäll <alexander.kjall@gmail.com>".to_owned(),
@@ -788,6 +855,10 @@ fn recipient_one_none() {
         key_ring_status: KeyRingStatus::InKeyRing,
         trust_level: OwnerTrustLevel::Ultimate,
         not_usable: false,
+        created: None,
+        expires: None,
+        revocation_reason: None,
+        encryption_subkeys: vec![],
     };
 
     assert!(r1 != r2);
@@ -809,6 +880,10 @@ fn recipient_same_fingerprint_different_key_id() {
         key_ring_status: KeyRingStatus::InKeyRing,
         trust_level: OwnerTrustLevel::Ultimate,
         not_usable: false,
+        created: None,
+        expires: None,
+        revocation_reason: None,
+        encryption_subkeys: vec![],
     };
     let r2 = Recipient {
         name: "Alexander Kjäll <alexander.kjall@gmail.com>".to_owned(),
@@ -823,8 +898,72 @@ fn recipient_same_fingerprint_different_key_id() {
         key_ring_status: KeyRingStatus::InKeyRing,
         trust_level: OwnerTrustLevel::Ultimate,
         not_usable: false,
+        created: None,
+        expires: None,
+        revocation_reason: None,
+        encryption_subkeys: vec![],
     };
 
     assert!(r1 == r2);
     assert!(r2 == r1);
 }
+
+fn days(n: i64) -> Duration {
+    Duration::try_days(n).unwrap()
+}
+
+fn subkey(expires: Option<chrono::DateTime<Local>>) -> EncryptionSubkey {
+    EncryptionSubkey {
+        fingerprint: "E6A7D758338EC2EF2A8A9F4EE7E3DB4B3217482F".to_owned(),
+        created: None,
+        expires,
+    }
+}
+
+#[test]
+fn recipient_encryption_expires() {
+    let now = Local.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+    let mut r = recipient_alex();
+    r.encryption_subkeys = vec![subkey(Some(now + days(10)))];
+    assert_eq!(Some(now + days(10)), r.encryption_expires());
+
+    r.encryption_subkeys.push(subkey(Some(now + days(100))));
+    assert_eq!(Some(now + days(100)), r.encryption_expires());
+
+    r.expires = Some(now + days(50));
+    assert_eq!(Some(now + days(50)), r.encryption_expires());
+
+    r.expires = None;
+    r.encryption_subkeys.push(subkey(None));
+    assert_eq!(None, r.encryption_expires());
+}
+
+#[test]
+fn recipient_key_warning() {
+    let now = Local.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+    let mut r = recipient_alex();
+    r.encryption_subkeys = vec![subkey(None)];
+    assert_eq!(None, r.key_warning(now, 30));
+
+    r.encryption_subkeys = vec![subkey(Some(now + days(10)))];
+    assert_eq!(
+        Some(KeyWarning::ExpiresSoon(now + days(10))),
+        r.key_warning(now, 30)
+    );
+    assert_eq!(None, r.key_warning(now, 5));
+
+    r.encryption_subkeys = vec![];
+    assert_eq!(Some(KeyWarning::NoEncryptionSubkey), r.key_warning(now, 30));
+
+    r.expires = Some(now - days(1));
+    assert_eq!(Some(KeyWarning::Expired), r.key_warning(now, 30));
+
+    r.revocation_reason = Some("Key is superseded".to_owned());
+    assert_eq!(
+        Some(KeyWarning::Revoked("Key is superseded".to_owned())),
+        r.key_warning(now, 30)
+    );
+
+    r.key_ring_status = KeyRingStatus::NotInKeyRing;
+    assert_eq!(None, r.key_warning(now, 30));
+}
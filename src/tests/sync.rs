@@ -0,0 +1,123 @@
+use std::{fs, path::Path};
+
+use tempfile::tempdir;
+
+use super::*;
+use crate::crypto::CryptoImpl;
+
+fn commit_file(repo: &git2::Repository, name: &str, content: &str) -> Result<()> {
+    fs::write(repo.workdir().unwrap().join(name), content)?;
+    let mut index = repo.index()?;
+    index.add_path(Path::new(name))?;
+    index.write()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let signature = git2::Signature::now("Tester", "tester@example.com")?;
+    let parent = repo.head().ok().map(|h| h.peel_to_commit()).transpose()?;
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+    repo.commit(Some("HEAD"), &signature, &signature, name, &tree, &parents)?;
+    Ok(())
+}
+
+/// A remote with one commit, and a clone of it in `local`.
+fn remote_and_clone(dir: &Path) -> Result<(git2::Repository, git2::Repository)> {
+    let remote = git2::Repository::init(dir.join("remote"))?;
+    commit_file(&remote, "notes", "a\n")?;
+
+    let local = git2::Repository::clone(dir.join("remote").to_str().unwrap(), dir.join("local"))?;
+    let mut config = local.config()?;
+    config.set_str("user.name", "Tester")?;
+    config.set_str("user.email", "tester@example.com")?;
+
+    Ok((remote, local))
+}
+
+fn store(dir: &Path) -> Result<Arc<Mutex<PasswordStore>>> {
+    Ok(Arc::new(Mutex::new(PasswordStore::new(
+        "default",
+        &Some(dir.join("local")),
+        &None,
+        &None,
+        &None,
+        &CryptoImpl::GpgMe,
+        &None,
+    )?)))
+}
+
+/// Waits for an event that `done` accepts, skipping the others.
+fn wait_for(events: &Receiver<SyncEvent>, done: impl Fn(&SyncEvent) -> bool) {
+    loop {
+        let event = events
+            .recv_timeout(Duration::from_secs(30))
+            .expect("the sync service stopped sending events");
+        if let SyncEvent::Failed(err) = &event {
+            panic!("sync failed: {err}");
+        }
+        if done(&event) {
+            return;
+        }
+    }
+}
+
+#[test]
+fn fetches_and_pulls_on_start() -> Result<()> {
+    let td = tempdir()?;
+    let (remote, local) = remote_and_clone(td.path())?;
+    commit_file(&remote, "notes", "b\n")?;
+
+    let (_service, events) = SyncService::start(store(td.path())?, Duration::from_secs(3600))?;
+
+    wait_for(&events, |e| matches!(e, SyncEvent::Updated));
+    assert_eq!(remote.head()?.target(), local.head()?.target());
+    assert_eq!("b\n", fs::read_to_string(td.path().join("local/notes"))?);
+    Ok(())
+}
+
+#[test]
+fn pushes_after_commit() -> Result<()> {
+    let td = tempdir()?;
+    let (_remote, local) = remote_and_clone(td.path())?;
+    let backup = git2::Repository::init_bare(td.path().join("backup"))?;
+    let branch = local.head()?.shorthand().unwrap().to_owned();
+    local
+        .remote(
+            "backup",
+            &format!("file://{}", td.path().join("backup").display()),
+        )?
+        .push(&[format!("refs/heads/{branch}:refs/heads/{branch}")], None)?;
+    let store = store(td.path())?;
+    store.lock()?.set_remotes(SyncRemote::parse_list("backup")?);
+
+    let (_service, events) = SyncService::start(store.clone(), Duration::from_secs(3600))?;
+    wait_for(&events, |e| matches!(e, SyncEvent::UpToDate));
+
+    fs::write(td.path().join("local/added"), "c\n")?;
+    store
+        .lock()?
+        .add_and_commit(&[PathBuf::from("added")], "add a file")?;
+
+    wait_for(&events, |e| matches!(e, SyncEvent::Pushed));
+    assert_eq!(
+        local.head()?.target(),
+        Some(backup.refname_to_id(&format!("refs/heads/{branch}"))?)
+    );
+    Ok(())
+}
+
+#[test]
+fn stops_when_dropped() -> Result<()> {
+    let td = tempdir()?;
+    remote_and_clone(td.path())?;
+    let (service, events) = SyncService::start(store(td.path())?, Duration::from_secs(3600))?;
+    wait_for(&events, |e| matches!(e, SyncEvent::UpToDate));
+    drop(service);
+
+    // the thread has ended when the channel is closed
+    loop {
+        match events.recv_timeout(Duration::from_secs(30)) {
+            Ok(_) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+            Err(RecvTimeoutError::Timeout) => panic!("the sync service didn't stop"),
+        }
+    }
+    Ok(())
+}
@@ -5,24 +5,28 @@ use std::{
     path::{Path, PathBuf},
 };
 
+use chrono::{DateTime, Local};
 use flate2::read::GzDecoder;
 use hex::FromHex;
 use sequoia_openpgp::{
     cert::CertBuilder,
+    packet::{signature::SignatureBuilder, UserID},
     parse::{
         stream::{DecryptionHelper, DecryptorBuilder, MessageStructure, VerificationHelper},
         Parse,
     },
     policy::StandardPolicy,
+    types::SignatureType,
     Cert, KeyHandle, KeyID,
 };
 use tar::Archive;
 
 use crate::{
     crypto::{Crypto, CryptoImpl, FindSigningFingerprintStrategy, Key, VerificationError},
+    discovery::KeySource,
     error::{Error, Result},
     pass::{KeyRingStatus, OwnerTrustLevel, SignatureStatus},
-    signature::{Comment, Recipient},
+    signature::{Comment, EncryptionSubkey, Recipient},
 };
 
 pub struct UnpackedDir {
@@ -85,6 +89,26 @@ impl Key for MockKey {
     fn is_not_usable(&self) -> bool {
         false
     }
+
+    fn creation_time(&self) -> Option<DateTime<Local>> {
+        None
+    }
+
+    fn expiration_time(&self) -> Option<DateTime<Local>> {
+        None
+    }
+
+    fn revocation_reason(&self) -> Option<String> {
+        None
+    }
+
+    fn encryption_subkeys(&self) -> Vec<EncryptionSubkey> {
+        vec![EncryptionSubkey {
+            fingerprint: hex::encode_upper(self.fingerprint),
+            created: None,
+            expires: None,
+        }]
+    }
 }
 
 impl Default for MockKey {
@@ -115,6 +139,7 @@ pub struct MockCrypto {
     pub encrypt_called: RefCell<bool>,
     pub sign_called: RefCell<bool>,
     pub verify_called: RefCell<bool>,
+    pub trust_items_calls: RefCell<u32>,
     encrypt_string_return: Vec<u8>,
     decrypt_string_return: Option<String>,
     sign_string_return: Option<String>,
@@ -136,6 +161,7 @@ impl MockCrypto {
             encrypt_called: RefCell::new(false),
             sign_called: RefCell::new(false),
             verify_called: RefCell::new(false),
+            trust_items_calls: RefCell::new(0),
             encrypt_string_return: vec![],
             decrypt_string_return: None,
             sign_string_return: None,
@@ -192,7 +218,11 @@ impl Crypto for MockCrypto {
         }
     }
 
-    fn encrypt_string(&self, _: &str, _: &[Recipient]) -> Result<Vec<u8>> {
+    fn encrypt_string(&self, plaintext: &str, recipients: &[Recipient]) -> Result<Vec<u8>> {
+        self.encrypt_bytes(plaintext.as_bytes(), recipients)
+    }
+
+    fn encrypt_bytes(&self, _: &[u8], _: &[Recipient]) -> Result<Vec<u8>> {
         self.encrypt_called.replace(true);
         if self.encrypt_string_error.is_some() {
             Err(Error::GenericDyn(
@@ -230,7 +260,12 @@ impl Crypto for MockCrypto {
         Ok(true)
     }
 
-    fn pull_keys(&mut self, _recipients: &[&Recipient], _config_path: &Path) -> Result<String> {
+    fn pull_keys(
+        &mut self,
+        _recipients: &[&Recipient],
+        _config_path: &Path,
+        _sources: &[KeySource],
+    ) -> Result<String> {
         Ok("dummy implementation".to_owned())
     }
 
@@ -251,6 +286,7 @@ impl Crypto for MockCrypto {
     }
 
     fn get_all_trust_items(&self) -> Result<HashMap<[u8; 20], OwnerTrustLevel>> {
+        *self.trust_items_calls.borrow_mut() += 1;
         Ok(HashMap::new())
     }
 
@@ -277,6 +313,10 @@ pub fn recipient_alex() -> Recipient {
         key_ring_status: KeyRingStatus::InKeyRing,
         trust_level: OwnerTrustLevel::Ultimate,
         not_usable: false,
+        created: None,
+        expires: None,
+        revocation_reason: None,
+        encryption_subkeys: vec![],
     }
 }
 pub fn recipient_alex_old() -> Recipient {
@@ -293,6 +333,10 @@ pub fn recipient_alex_old() -> Recipient {
         key_ring_status: KeyRingStatus::InKeyRing,
         trust_level: OwnerTrustLevel::Ultimate,
         not_usable: false,
+        created: None,
+        expires: None,
+        revocation_reason: None,
+        encryption_subkeys: vec![],
     }
 }
 pub fn recipient_from_cert(cert: &sequoia_openpgp::Cert) -> Recipient {
@@ -307,6 +351,10 @@ pub fn recipient_from_cert(cert: &sequoia_openpgp::Cert) -> Recipient {
         key_ring_status: KeyRingStatus::InKeyRing,
         trust_level: OwnerTrustLevel::Ultimate,
         not_usable: false,
+        created: None,
+        expires: None,
+        revocation_reason: None,
+        encryption_subkeys: vec![],
     }
 }
 
@@ -325,6 +373,39 @@ pub fn generate_sequoia_cert(email: &str) -> sequoia_openpgp::Cert {
     cert
 }
 
+/// Makes `certifier` certify the user id of `cert`, with a trust signature of the given depth
+/// and amount if `trust` is set.
+pub fn certify(certifier: &Cert, cert: Cert, trust: Option<(u8, u8)>) -> Cert {
+    let mut builder = SignatureBuilder::new(SignatureType::GenericCertification);
+    if let Some((depth, amount)) = trust {
+        builder = builder.set_trust_signature(depth, amount).unwrap();
+    }
+    let user_id = cert.userids().next().unwrap().userid().clone();
+    certify_user_id(certifier, cert, &user_id, builder)
+}
+
+/// Makes `certifier` sign `user_id` of `cert` with the certification from `builder`.
+pub fn certify_user_id(
+    certifier: &Cert,
+    cert: Cert,
+    user_id: &UserID,
+    builder: SignatureBuilder,
+) -> Cert {
+    let mut signer = certifier
+        .primary_key()
+        .key()
+        .clone()
+        .parts_into_secret()
+        .unwrap()
+        .into_keypair()
+        .unwrap();
+    let sig = builder
+        .sign_userid_binding(&mut signer, cert.primary_key().key(), user_id)
+        .unwrap();
+
+    cert.insert_packets(sig).unwrap()
+}
+
 pub fn generate_sequoia_cert_without_private_key(email: &str) -> sequoia_openpgp::Cert {
     let (cert, _) = CertBuilder::general_purpose(None, Some(email))
         .generate()
@@ -0,0 +1,141 @@
+use std::fs;
+
+use tempfile::tempdir;
+
+use super::*;
+use crate::crypto::CryptoImpl;
+
+fn store(dir: &Path) -> Result<Arc<Mutex<PasswordStore>>> {
+    let mut store = PasswordStore::new(
+        "default",
+        &Some(dir.to_path_buf()),
+        &None,
+        &None,
+        &None,
+        &CryptoImpl::GpgMe,
+        &None,
+    )?;
+    store.reload_password_list()?;
+    Ok(Arc::new(Mutex::new(store)))
+}
+
+fn next_change(changes: &Receiver<PasswordChange>) -> PasswordChange {
+    changes
+        .recv_timeout(Duration::from_secs(10))
+        .expect("the watcher didn't report a change")
+}
+
+fn names(store: &Mutex<PasswordStore>) -> Vec<String> {
+    let mut names: Vec<String> = store
+        .lock()
+        .unwrap()
+        .passwords
+        .iter()
+        .map(|p| p.name.clone())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn adds_renames_and_removes_files() -> Result<()> {
+    let td = tempdir()?;
+    fs::write(td.path().join("a.gpg"), "a")?;
+    let store = store(td.path())?;
+    let (_watcher, changes) = StoreWatcher::start(store.clone())?;
+
+    fs::create_dir(td.path().join("dir"))?;
+    fs::write(td.path().join("dir/b.gpg"), "b")?;
+    match next_change(&changes) {
+        PasswordChange::Added(entry) => assert_eq!("dir/b", entry.name),
+        change => panic!("unexpected change {change:?}"),
+    }
+
+    fs::rename(td.path().join("a.gpg"), td.path().join("c.gpg"))?;
+    match next_change(&changes) {
+        PasswordChange::Renamed { from, to } => {
+            assert_eq!("a", from.name);
+            assert_eq!("c", to.name);
+        }
+        change => panic!("unexpected change {change:?}"),
+    }
+
+    fs::remove_file(td.path().join("dir/b.gpg"))?;
+    match next_change(&changes) {
+        PasswordChange::Removed(entry) => assert_eq!("dir/b", entry.name),
+        change => panic!("unexpected change {change:?}"),
+    }
+
+    assert_eq!(vec!["c".to_owned()], names(&store));
+    Ok(())
+}
+
+#[test]
+fn renames_directories() -> Result<()> {
+    let td = tempdir()?;
+    fs::create_dir(td.path().join("work"))?;
+    fs::write(td.path().join("work/x.gpg"), "x")?;
+    fs::write(td.path().join("work/y.gpg"), "y")?;
+    let store = store(td.path())?;
+    let (_watcher, changes) = StoreWatcher::start(store.clone())?;
+
+    fs::rename(td.path().join("work"), td.path().join("job"))?;
+    for _ in 0..2 {
+        assert!(matches!(
+            next_change(&changes),
+            PasswordChange::Renamed { .. }
+        ));
+    }
+
+    assert_eq!(vec!["job/x".to_owned(), "job/y".to_owned()], names(&store));
+    Ok(())
+}
+
+#[test]
+fn moving_out_of_the_store_removes() -> Result<()> {
+    let td = tempdir()?;
+    fs::create_dir(td.path().join("store"))?;
+    fs::write(td.path().join("store/a.gpg"), "a")?;
+    let store = store(&td.path().join("store"))?;
+    let (_watcher, changes) = StoreWatcher::start(store.clone())?;
+
+    fs::rename(td.path().join("store/a.gpg"), td.path().join("a.gpg"))?;
+    match next_change(&changes) {
+        PasswordChange::Removed(entry) => assert_eq!("a", entry.name),
+        change => panic!("unexpected change {change:?}"),
+    }
+    assert!(names(&store).is_empty());
+    Ok(())
+}
+
+#[test]
+fn ignores_other_files_and_the_git_directory() -> Result<()> {
+    let td = tempdir()?;
+    fs::create_dir(td.path().join(".git"))?;
+    let store = store(td.path())?;
+    let (_watcher, changes) = StoreWatcher::start(store.clone())?;
+
+    fs::write(td.path().join(".git/a.gpg"), "a")?;
+    fs::write(td.path().join("notes.txt"), "b")?;
+    fs::write(td.path().join("c.gpg"), "c")?;
+
+    match next_change(&changes) {
+        PasswordChange::Added(entry) => assert_eq!("c", entry.name),
+        change => panic!("unexpected change {change:?}"),
+    }
+    assert_eq!(vec!["c".to_owned()], names(&store));
+    Ok(())
+}
+
+#[test]
+fn stops_when_dropped() -> Result<()> {
+    let td = tempdir()?;
+    let (watcher, changes) = StoreWatcher::start(store(td.path())?)?;
+    drop(watcher);
+
+    assert!(matches!(
+        changes.recv_timeout(Duration::from_secs(10)),
+        Err(RecvTimeoutError::Disconnected)
+    ));
+    Ok(())
+}
@@ -0,0 +1,190 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use sequoia_openpgp::{
+    cert::CertBuilder,
+    packet::{signature::SignatureBuilder, UserID},
+    policy::StandardPolicy,
+    types::SignatureType,
+    Cert,
+};
+
+use super::*;
+use crate::{
+    crypto::slice_to_20_bytes,
+    test_helpers::{certify, certify_user_id, generate_sequoia_cert},
+};
+
+fn fp(cert: &Cert) -> [u8; 20] {
+    slice_to_20_bytes(cert.fingerprint().as_bytes()).unwrap()
+}
+
+/// The web of trust of `certs`, with `certs[0]` as the only trust root.
+fn trust(certs: &[Cert]) -> HashMap<[u8; 20], OwnerTrustLevel> {
+    let key_ring = certs
+        .iter()
+        .map(|cert| (fp(cert), Arc::new(cert.clone())))
+        .collect();
+    web_of_trust(&key_ring, &[fp(&certs[0])], &StandardPolicy::new())
+}
+
+#[test]
+fn introducers() {
+    let alice = generate_sequoia_cert("alice@example.org");
+    let bob = certify(
+        &alice,
+        generate_sequoia_cert("bob@example.org"),
+        Some((1, 120)),
+    );
+    let carol = certify(
+        &alice,
+        generate_sequoia_cert("carol@example.org"),
+        Some((1, 60)),
+    );
+    let dave = generate_sequoia_cert("dave@example.org");
+    let eve = certify(&bob, generate_sequoia_cert("eve@example.org"), None);
+    let frank = certify(&carol, generate_sequoia_cert("frank@example.org"), None);
+    // dave isn't an introducer, so his certification doesn't count
+    let grace = certify(&dave, generate_sequoia_cert("grace@example.org"), None);
+    // bob can't delegate further, his trust signature only has depth 1
+    let heidi = certify(
+        &bob,
+        generate_sequoia_cert("heidi@example.org"),
+        Some((1, 120)),
+    );
+    let ivan = certify(&heidi, generate_sequoia_cert("ivan@example.org"), None);
+
+    let trust = trust(&[
+        alice,
+        bob,
+        carol,
+        dave.clone(),
+        eve.clone(),
+        frank.clone(),
+        grace.clone(),
+        heidi.clone(),
+        ivan.clone(),
+    ]);
+
+    assert_eq!(OwnerTrustLevel::Full, trust[&fp(&eve)]);
+    assert_eq!(OwnerTrustLevel::Marginal, trust[&fp(&frank)]);
+    assert_eq!(OwnerTrustLevel::Unknown, trust[&fp(&dave)]);
+    assert_eq!(OwnerTrustLevel::Unknown, trust[&fp(&grace)]);
+    assert_eq!(OwnerTrustLevel::Full, trust[&fp(&heidi)]);
+    assert_eq!(OwnerTrustLevel::Unknown, trust[&fp(&ivan)]);
+}
+
+#[test]
+fn marginal_certifications_add_up() {
+    let alice = generate_sequoia_cert("alice@example.org");
+    let bob = certify(
+        &alice,
+        generate_sequoia_cert("bob@example.org"),
+        Some((1, 60)),
+    );
+    let carol = certify(
+        &alice,
+        generate_sequoia_cert("carol@example.org"),
+        Some((1, 60)),
+    );
+    let dave = certify(
+        &carol,
+        certify(&bob, generate_sequoia_cert("dave@example.org"), None),
+        None,
+    );
+
+    let trust = trust(&[alice, bob, carol, dave.clone()]);
+
+    assert_eq!(OwnerTrustLevel::Full, trust[&fp(&dave)]);
+}
+
+#[test]
+fn regular_expressions_limit_introducers() {
+    let alice = generate_sequoia_cert("alice@example.org");
+    let bob = generate_sequoia_cert("bob@example.org");
+    let user_id = bob.userids().next().unwrap().userid().clone();
+    let builder = SignatureBuilder::new(SignatureType::GenericCertification)
+        .set_trust_signature(1, 120)
+        .unwrap()
+        .set_regular_expression("@example\\.org$")
+        .unwrap();
+    let bob = certify_user_id(&alice, bob, &user_id, builder);
+    let carol = certify(&bob, generate_sequoia_cert("carol@example.org"), None);
+    let dave = certify(&bob, generate_sequoia_cert("dave@example.com"), None);
+
+    let trust = trust(&[alice, bob, carol.clone(), dave.clone()]);
+
+    assert_eq!(OwnerTrustLevel::Full, trust[&fp(&carol)]);
+    assert_eq!(OwnerTrustLevel::Unknown, trust[&fp(&dave)]);
+}
+
+#[test]
+fn expired_or_revoked_introducers() {
+    let alice = generate_sequoia_cert("alice@example.org");
+    let ten_days_ago = SystemTime::now() - Duration::from_secs(10 * 24 * 60 * 60);
+    let (bob, _) = CertBuilder::general_purpose(None, Some("bob@example.org"))
+        .set_creation_time(ten_days_ago)
+        .set_validity_period(Duration::from_secs(24 * 60 * 60))
+        .generate()
+        .unwrap();
+    let bob = certify(&alice, bob, Some((1, 120)));
+    let (carol, revocation) = CertBuilder::general_purpose(None, Some("carol@example.org"))
+        .generate()
+        .unwrap();
+    let carol = certify(&alice, carol, Some((1, 120)))
+        .insert_packets(revocation)
+        .unwrap();
+    let dave = certify(&bob, generate_sequoia_cert("dave@example.org"), None);
+    let eve = certify(&carol, generate_sequoia_cert("eve@example.org"), None);
+
+    let trust = trust(&[alice, bob, carol, dave.clone(), eve.clone()]);
+
+    assert_eq!(OwnerTrustLevel::Unknown, trust[&fp(&dave)]);
+    assert_eq!(OwnerTrustLevel::Unknown, trust[&fp(&eve)]);
+}
+
+#[test]
+fn marginal_certifications_of_different_user_ids() {
+    let alice = generate_sequoia_cert("alice@example.org");
+    let bob = certify(
+        &alice,
+        generate_sequoia_cert("bob@example.org"),
+        Some((1, 60)),
+    );
+    let carol = certify(
+        &alice,
+        generate_sequoia_cert("carol@example.org"),
+        Some((1, 60)),
+    );
+    let (dave, _) = CertBuilder::general_purpose(None, Some("dave@example.org"))
+        .add_userid("dave@example.net")
+        .generate()
+        .unwrap();
+    let user_ids: Vec<UserID> = dave.userids().map(|u| u.userid().clone()).collect();
+    let certification = || SignatureBuilder::new(SignatureType::GenericCertification);
+    let dave = certify_user_id(&bob, dave, &user_ids[0], certification());
+    let dave = certify_user_id(&carol, dave, &user_ids[1], certification());
+
+    let trust = trust(&[alice, bob, carol, dave.clone()]);
+
+    // the two marginal certifications are of different user ids, so they don't add up
+    assert_eq!(OwnerTrustLevel::Marginal, trust[&fp(&dave)]);
+}
+
+#[test]
+fn revoked() {
+    let alice = generate_sequoia_cert("alice@example.org");
+    let (bob, revocation) = CertBuilder::general_purpose(None, Some("bob@example.org"))
+        .generate()
+        .unwrap();
+    let bob = certify(&alice, bob, None)
+        .insert_packets(revocation)
+        .unwrap();
+
+    let trust = trust(&[alice, bob.clone()]);
+
+    assert_eq!(OwnerTrustLevel::Never, trust[&fp(&bob)]);
+}
@@ -0,0 +1,289 @@
+use std::{
+    path::{Component, Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use notify::{
+    event::{ModifyKind, RemoveKind, RenameMode},
+    Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
+};
+
+use crate::{
+    error::Result,
+    pass::{PasswordEntry, PasswordStore},
+};
+
+/// How long the source of a rename waits for its destination, before it's taken to have been
+/// moved out of the store.
+const RENAME_WAIT: Duration = Duration::from_millis(200);
+
+/// A change that was made to the password list of a store, because files in the store
+/// directory were added, removed or renamed.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum PasswordChange {
+    /// A new password file
+    Added(PasswordEntry),
+    /// A password file that is gone
+    Removed(PasswordEntry),
+    /// A password file that was moved to another name in the store, the entry keeps its git
+    /// meta data
+    Renamed {
+        from: PasswordEntry,
+        to: PasswordEntry,
+    },
+}
+
+/// Watches the directory of a store, and keeps the password list of the store in sync with
+/// changes that are made outside of ripasso, like `pass insert` or `git pull` in a terminal.
+/// Changes that ripasso made itself are already in the list, so they aren't reported again.
+///
+/// The watcher stops when it's dropped.
+pub struct StoreWatcher {
+    watcher: Option<RecommendedWatcher>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl StoreWatcher {
+    /// Starts watching the directory of `store`. Every change that is made to the password
+    /// list is sent on the returned receiver, so that the UI can update the list it shows.
+    /// # Errors
+    /// Returns an `Err` if the store is poisoned, the directory can't be watched or the thread
+    /// can't be started
+    pub fn start(store: Arc<Mutex<PasswordStore>>) -> Result<(Self, Receiver<PasswordChange>)> {
+        let root = store.lock()?.get_store_path();
+        let (file_sender, file_events) = mpsc::channel();
+        let (change_sender, changes) = mpsc::channel();
+
+        let mut watcher = notify::recommended_watcher(file_sender)?;
+        watcher.watch(&root, RecursiveMode::Recursive)?;
+        let thread = thread::Builder::new()
+            .name("ripasso-watch".to_owned())
+            .spawn(move || run(&store, &root, &file_events, &change_sender))?;
+
+        Ok((
+            Self {
+                watcher: Some(watcher),
+                thread: Some(thread),
+            },
+            changes,
+        ))
+    }
+}
+
+impl Drop for StoreWatcher {
+    fn drop(&mut self) {
+        // the thread ends when the watcher has closed the channel of file events
+        self.watcher.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn run(
+    store: &Mutex<PasswordStore>,
+    root: &Path,
+    file_events: &Receiver<notify::Result<Event>>,
+    changes: &Sender<PasswordChange>,
+) {
+    // the source of a rename and its tracker, until the destination is seen
+    let mut moved_from: Option<(Option<usize>, PathBuf)> = None;
+    loop {
+        let event = match file_events.recv_timeout(RENAME_WAIT) {
+            Ok(Ok(event)) => event,
+            // events were lost, so everything has to be looked at again
+            Ok(Err(_)) => Event::new(EventKind::Any).add_path(root.to_path_buf()),
+            Err(RecvTimeoutError::Timeout) => match moved_from.take() {
+                Some((_, from)) => Event::new(EventKind::Remove(RemoveKind::Any)).add_path(from),
+                None => continue,
+            },
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+
+        let Ok(mut store) = store.lock() else {
+            break;
+        };
+        let mut changed = vec![];
+        match event.kind {
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                if let Some((_, from)) = moved_from.take() {
+                    changed.extend(update(&mut store, root, &from));
+                }
+                moved_from = event
+                    .paths
+                    .first()
+                    .map(|from| (event.tracker(), from.clone()));
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                for to in &event.paths {
+                    match moved_from.take() {
+                        Some((tracker, from))
+                            if tracker.is_some() && tracker == event.tracker() =>
+                        {
+                            changed.extend(rename(&mut store, root, &from, to));
+                        }
+                        other => {
+                            if let Some((_, from)) = other {
+                                changed.extend(update(&mut store, root, &from));
+                            }
+                            changed.extend(update(&mut store, root, to));
+                        }
+                    }
+                }
+            }
+            // the inotify backend has already reported the rename with the source and the
+            // destination, then this does nothing
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+                changed.extend(rename(&mut store, root, &event.paths[0], &event.paths[1]));
+            }
+            EventKind::Any
+            | EventKind::Create(_)
+            | EventKind::Remove(_)
+            | EventKind::Modify(ModifyKind::Name(_)) => {
+                if let Some((_, from)) = moved_from.take() {
+                    changed.extend(update(&mut store, root, &from));
+                }
+                let paths = if event.need_rescan() {
+                    vec![root.to_path_buf()]
+                } else {
+                    event.paths
+                };
+                for path in &paths {
+                    changed.extend(update(&mut store, root, path));
+                }
+            }
+            // changed content doesn't change the list
+            _ => {}
+        }
+        drop(store);
+
+        for change in changed {
+            // nobody listens anymore, so there is no point in going on
+            if changes.send(change).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Makes the password list agree with the files at or below `path`: entries whose file is gone
+/// are removed, and password files that aren't in the list are added.
+fn update(store: &mut PasswordStore, root: &Path, path: &Path) -> Vec<PasswordChange> {
+    let mut changed = vec![];
+    if is_ignored(root, path) {
+        return changed;
+    }
+
+    let extension = store.password_file_extension();
+    store.passwords.retain(|entry| {
+        let gone = entry.path.starts_with(path) && !is_password_file(extension, &entry.path);
+        if gone {
+            changed.push(PasswordChange::Removed(entry.clone()));
+        }
+        !gone
+    });
+
+    for file in password_files(extension, root, path) {
+        if !store.passwords.iter().any(|entry| entry.path == file) {
+            let entry = new_entry(store, root, &file);
+            store.passwords.push(entry.clone());
+            changed.push(PasswordChange::Added(entry));
+        }
+    }
+    changed
+}
+
+/// Moves the entries at or below `from` to the same place below `to`. Entries that aren't
+/// password files anymore are removed, and files that became password files are added.
+fn rename(store: &mut PasswordStore, root: &Path, from: &Path, to: &Path) -> Vec<PasswordChange> {
+    if is_ignored(root, to) {
+        return update(store, root, from);
+    }
+
+    let extension = store.password_file_extension();
+    let mut changed = vec![];
+    let mut i = 0;
+    while i < store.passwords.len() {
+        let Ok(relpath) = store.passwords[i].path.strip_prefix(from) else {
+            i += 1;
+            continue;
+        };
+        let new_path = if relpath.as_os_str().is_empty() {
+            to.to_path_buf()
+        } else {
+            to.join(relpath)
+        };
+        let new_relpath = new_path.strip_prefix(root).map(Path::to_path_buf);
+
+        match new_relpath {
+            Ok(new_relpath) if is_password_file(extension, &new_path) => {
+                let old = store.passwords[i].clone();
+                store.passwords[i] =
+                    PasswordEntry::with_new_name(old.clone(), root, &new_relpath, extension);
+                changed.push(PasswordChange::Renamed {
+                    from: old,
+                    to: store.passwords[i].clone(),
+                });
+                i += 1;
+            }
+            _ => changed.push(PasswordChange::Removed(store.passwords.remove(i))),
+        }
+    }
+
+    changed.extend(update(store, root, to));
+    changed
+}
+
+fn new_entry(store: &PasswordStore, root: &Path, file: &Path) -> PasswordEntry {
+    match store.repo() {
+        Ok(repo) => PasswordEntry::load_from_git(root, file, &repo, store),
+        Err(_) => PasswordEntry::load_from_filesystem(
+            root,
+            file.strip_prefix(root).unwrap_or(file),
+            store.password_file_extension(),
+        ),
+    }
+}
+
+/// The password files, with the file extension `extension`, at or below `path`.
+fn password_files(extension: &str, root: &Path, path: &Path) -> Vec<PathBuf> {
+    if path.is_dir() {
+        let pattern = path.join(format!("**/*.{extension}"));
+        glob::glob(&pattern.to_string_lossy())
+            .map(|files| {
+                files
+                    .filter_map(std::result::Result::ok)
+                    .filter(|file| !is_ignored(root, file) && is_password_file(extension, file))
+                    .collect()
+            })
+            .unwrap_or_default()
+    } else if is_password_file(extension, path) {
+        vec![path.to_path_buf()]
+    } else {
+        vec![]
+    }
+}
+
+fn is_password_file(extension: &str, path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+        && path.is_file()
+}
+
+/// Files outside of the store and in the git repository aren't passwords.
+fn is_ignored(root: &Path, path: &Path) -> bool {
+    match path.strip_prefix(root) {
+        Ok(relpath) => relpath.components().next() == Some(Component::Normal(".git".as_ref())),
+        Err(_) => true,
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/watch.rs"]
+mod watch_tests;
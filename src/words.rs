@@ -14,7 +14,7 @@ pub fn generate_password(number_of_words: isize) -> String {
 
 /// The large wordlist from
 /// <https://www.eff.org/sv/deeplinks/2016/07/new-wordlists-random-passphrases>
-const WORDS: &[&str] = &[
+pub(crate) const WORDS: &[&str] = &[
     "abacus",
     "abdomen",
     "abdominal",
@@ -0,0 +1,280 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use sequoia_openpgp::{
+    cert::ValidCert,
+    packet::UserID,
+    policy::{HashAlgoSecurity, Policy},
+    regex::RegexSet,
+    types::RevocationStatus,
+    Cert,
+};
+
+use crate::signature::OwnerTrustLevel;
+
+/// The trust amount of a fully trusted introducer, and the amount of certifications that a
+/// user id needs to be fully valid, the same as in OpenPGP trust signatures.
+const FULL_TRUST: usize = 120;
+
+/// A way that a cert is trusted to certify other certs, either as a trust root or through a
+/// chain of trust signatures from one. A cert can be an introducer in several ways, with
+/// different scopes.
+#[derive(Clone)]
+struct Introducer {
+    /// how much its certifications count, up to `FULL_TRUST`
+    amount: usize,
+    /// the trust depth, 1 means that its certifications count, and every level above that
+    /// that it can make other certs introducers with trust signatures
+    depth: u8,
+    /// the indices of the trust signatures in the chain whose regular expressions limit which
+    /// user ids the introducer can certify
+    scopes: Vec<usize>,
+}
+
+impl Introducer {
+    /// Returns if `self` is trusted at least as much as `other`, for at least the same user ids.
+    fn covers(&self, other: &Self) -> bool {
+        self.amount >= other.amount
+            && self.depth >= other.depth
+            && self.scopes.iter().all(|scope| other.scopes.contains(scope))
+    }
+}
+
+/// A valid certification of a user id of `target`, made by `certifier`.
+struct Certification {
+    certifier: [u8; 20],
+    target: [u8; 20],
+    user_id: UserID,
+    /// the trust depth and amount of the trust signature, 0 and `FULL_TRUST` for a plain
+    /// certification
+    depth: u8,
+    amount: usize,
+    /// the user ids that the target can certify if this is a trust signature, from its regular
+    /// expressions, `None` if they aren't valid and so match nothing
+    scope: Option<RegexSet>,
+}
+
+/// Returns if all the regular expressions of the trust signatures `scopes` match `user_id`.
+fn in_scope(certifications: &[Certification], scopes: &[usize], user_id: &UserID) -> bool {
+    scopes.iter().all(|i| {
+        certifications[*i]
+            .scope
+            .as_ref()
+            .is_some_and(|scope| scope.matches_userid(user_id))
+    })
+}
+
+/// Returns all the valid certifications that the certs in `key_ring` have made on each other.
+/// Only the certifications made by certs that are alive and not revoked, with keys that are
+/// alive, not revoked and can certify, on user ids that aren't revoked, count.
+fn certifications(
+    key_ring: &HashMap<[u8; 20], Arc<Cert>>,
+    policy: &dyn Policy,
+) -> Vec<Certification> {
+    let certifiers: Vec<([u8; 20], ValidCert)> = key_ring
+        .iter()
+        .filter_map(|(fp, cert)| {
+            let valid = cert.with_policy(policy, None).ok()?;
+            if valid.alive().is_err()
+                || matches!(valid.revocation_status(), RevocationStatus::Revoked(_))
+            {
+                return None;
+            }
+            Some((*fp, valid))
+        })
+        .collect();
+
+    let mut res = vec![];
+    for (target_fp, target) in key_ring {
+        let Ok(valid_target) = target.with_policy(policy, None) else {
+            continue;
+        };
+        for user_id in valid_target.userids().revoked(false) {
+            for sig in user_id.certifications() {
+                if sig.signature_alive(None, Duration::ZERO).is_err()
+                    || policy
+                        .signature(sig, HashAlgoSecurity::CollisionResistance)
+                        .is_err()
+                {
+                    continue;
+                }
+
+                let issuers = sig.get_issuers();
+                for (certifier_fp, certifier) in &certifiers {
+                    if certifier_fp == target_fp {
+                        continue;
+                    }
+                    let verified = certifier
+                        .keys()
+                        .alive()
+                        .revoked(false)
+                        .for_certification()
+                        .filter(|key| {
+                            issuers
+                                .iter()
+                                .any(|issuer| issuer.aliases(key.key().key_handle()))
+                        })
+                        .any(|key| {
+                            sig.verify_userid_binding(
+                                key.key(),
+                                target.primary_key().key(),
+                                user_id.userid(),
+                            )
+                            .is_ok()
+                        });
+                    if verified {
+                        let (depth, amount) = sig
+                            .trust_signature()
+                            .map_or((0, FULL_TRUST), |(depth, amount)| {
+                                (depth, usize::from(amount))
+                            });
+                        res.push(Certification {
+                            certifier: *certifier_fp,
+                            target: *target_fp,
+                            user_id: user_id.userid().clone(),
+                            depth,
+                            amount,
+                            scope: RegexSet::from_signature(sig).ok(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    res
+}
+
+/// Computes how valid each cert in `key_ring` is, from the certifications made by the `roots`
+/// and by the introducers that they have delegated trust to with trust signatures, limited to
+/// the user ids that match the regular expressions of those trust signatures.
+///  * the roots themselves are `Ultimate`
+///  * revoked certs are `Never`
+///  * certs with a user id that is certified with a total amount of at least `FULL_TRUST` are
+///    `Full`, and certs with some certifications that don't add up to that for any user id
+///    are `Marginal`
+///  * all other certs are `Unknown`
+pub(crate) fn web_of_trust(
+    key_ring: &HashMap<[u8; 20], Arc<Cert>>,
+    roots: &[[u8; 20]],
+    policy: &dyn Policy,
+) -> HashMap<[u8; 20], OwnerTrustLevel> {
+    let certifications = certifications(key_ring, policy);
+
+    let mut introducers: HashMap<[u8; 20], Vec<Introducer>> = roots
+        .iter()
+        .map(|root| {
+            (
+                *root,
+                vec![Introducer {
+                    amount: FULL_TRUST,
+                    depth: u8::MAX,
+                    scopes: vec![],
+                }],
+            )
+        })
+        .collect();
+    // follow the trust signatures until no introducer gets more trusted, the depth drops with
+    // every step in a chain and ways that are covered by others are dropped, so this ends
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (index, certification) in certifications.iter().enumerate() {
+            if certification.depth == 0 {
+                continue;
+            }
+            let Some(by) = introducers.get(&certification.certifier) else {
+                continue;
+            };
+            let delegated: Vec<Introducer> = by
+                .iter()
+                .filter(|by| {
+                    by.depth > 0 && in_scope(&certifications, &by.scopes, &certification.user_id)
+                })
+                .map(|by| {
+                    let mut scopes = by.scopes.clone();
+                    let everything = certification
+                        .scope
+                        .as_ref()
+                        .is_some_and(RegexSet::matches_everything);
+                    if !everything && !scopes.contains(&index) {
+                        scopes.push(index);
+                    }
+                    Introducer {
+                        amount: by.amount.min(certification.amount),
+                        depth: certification.depth.min(by.depth - 1),
+                        scopes,
+                    }
+                })
+                .collect();
+
+            let ways = introducers.entry(certification.target).or_default();
+            for introducer in delegated {
+                if !ways.iter().any(|way| way.covers(&introducer)) {
+                    ways.retain(|way| !introducer.covers(way));
+                    ways.push(introducer);
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    // every certifier counts once for each user id, with its best certification
+    let mut amounts: HashMap<([u8; 20], [u8; 20], &UserID), usize> = HashMap::new();
+    for certification in &certifications {
+        let Some(by) = introducers.get(&certification.certifier) else {
+            continue;
+        };
+        let best = by
+            .iter()
+            .filter(|by| {
+                by.depth > 0 && in_scope(&certifications, &by.scopes, &certification.user_id)
+            })
+            .map(|by| by.amount.min(certification.amount))
+            .max();
+        if let Some(best) = best {
+            let amount = amounts
+                .entry((
+                    certification.certifier,
+                    certification.target,
+                    &certification.user_id,
+                ))
+                .or_insert(0);
+            *amount = (*amount).max(best);
+        }
+    }
+    let mut user_id_validity: HashMap<([u8; 20], &UserID), usize> = HashMap::new();
+    for ((_, target, user_id), amount) in amounts {
+        *user_id_validity.entry((target, user_id)).or_insert(0) += amount;
+    }
+    // a cert is as valid as its most valid user id
+    let mut validity: HashMap<[u8; 20], usize> = HashMap::new();
+    for ((target, _), amount) in user_id_validity {
+        let best = validity.entry(target).or_insert(0);
+        *best = (*best).max(amount);
+    }
+
+    key_ring
+        .iter()
+        .map(|(fp, cert)| {
+            let level = if roots.contains(fp) {
+                OwnerTrustLevel::Ultimate
+            } else if matches!(
+                cert.revocation_status(policy, None),
+                RevocationStatus::Revoked(_)
+            ) {
+                OwnerTrustLevel::Never
+            } else {
+                match validity.get(fp).copied().unwrap_or(0) {
+                    0 => OwnerTrustLevel::Unknown,
+                    amount if amount >= FULL_TRUST => OwnerTrustLevel::Full,
+                    _ => OwnerTrustLevel::Marginal,
+                }
+            };
+            (*fp, level)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[path = "tests/wot.rs"]
+mod wot_tests;